@@ -0,0 +1,87 @@
+// Benchmarks of the solvers on jump-heavy signals, whose output is
+// written as many short segments, and of the ways to write those
+// segments into the output.
+
+#[macro_use]
+extern crate criterion;
+extern crate tv1d;
+
+use criterion::{black_box, Criterion};
+use std::iter;
+
+const LEN: usize = 1 << 16;
+
+// A signal that jumps every few samples, so that most segments of the
+// output are short.
+fn jumpy_f64() -> Vec<f64> {
+    (0..LEN).map(|i| ((i * 7919) % 23) as f64 + ((i / 3) % 2) as f64 * 40.0).collect()
+}
+
+fn jumpy_i32() -> Vec<i32> {
+    (0..LEN).map(|i| ((i * 7919) % 23) as i32 + ((i / 3) % 2) as i32 * 40).collect()
+}
+
+// The segments of the output of `condat` on `input`, as lengths and
+// values.
+fn segments(input: &[f64], lambda: f64) -> Vec<(usize, f64)> {
+    let output = tv1d::condat(input, lambda);
+    let mut segments: Vec<(usize, f64)> = Vec::new();
+    for &value in &output {
+        match segments.last_mut() {
+            Some(segment) if segment.1 == value => segment.0 += 1,
+            _ => segments.push((1, value)),
+        }
+    }
+    segments
+}
+
+fn emission(c: &mut Criterion) {
+    let segments = segments(&jumpy_f64(), 1.0);
+    let mut group = c.benchmark_group("emission");
+    group.bench_function("extend_repeat", |b| {
+        b.iter(|| {
+            let mut output = Vec::with_capacity(LEN);
+            for &(len, value) in &segments {
+                output.extend(iter::repeat_n(value, len));
+            }
+            black_box(output)
+        })
+    });
+    group.bench_function("resize", |b| {
+        b.iter(|| {
+            let mut output = Vec::with_capacity(LEN);
+            for &(len, value) in &segments {
+                let end = output.len() + len;
+                output.resize(end, value);
+            }
+            black_box(output)
+        })
+    });
+    group.bench_function("preallocated_fill", |b| {
+        b.iter(|| {
+            let mut output = vec![0.0; LEN];
+            let mut start = 0;
+            for &(len, value) in &segments {
+                output[start..(start + len)].fill(value);
+                start += len;
+            }
+            black_box(output)
+        })
+    });
+    group.finish();
+}
+
+fn solvers(c: &mut Criterion) {
+    let input = jumpy_f64();
+    let input_i32 = jumpy_i32();
+    let path = tv1d::regularization_path(&input[..4096]);
+    let mut group = c.benchmark_group("jumpy");
+    group.bench_function("condat", |b| b.iter(|| tv1d::condat(black_box(&input), 1.0)));
+    group.bench_function("condat_f64", |b| b.iter(|| tv1d::condat_f64(black_box(&input), 1.0)));
+    group.bench_function("condat_saturating", |b| b.iter(|| tv1d::condat_saturating(black_box(&input_i32), 1)));
+    group.bench_function("path_solution", |b| b.iter(|| path.solution(black_box(1.0))));
+    group.finish();
+}
+
+criterion_group!(benches, emission, solvers);
+criterion_main!(benches);
@@ -0,0 +1,22 @@
+// Benchmarks of the taut string algorithm on long inputs, whose
+// auxiliary arrays do not fit in the caches.
+
+#[macro_use]
+extern crate criterion;
+extern crate tv1d;
+
+use criterion::{black_box, Criterion};
+
+const LEN: usize = 10_000_000;
+
+fn long(c: &mut Criterion) {
+    let input: Vec<f64> = (0..LEN).map(|i| ((i / 500) % 4) as f64 * 3.0 + ((i * 7919) % 17) as f64 * 0.1).collect();
+    let mut workspace = tv1d::TautstringWorkspace::with_capacity(LEN);
+    let mut group = c.benchmark_group("long");
+    group.sample_size(10);
+    group.bench_function("tautstring_with", |b| b.iter(|| tv1d::tautstring_with(&mut workspace, black_box(&input), 2.0).len()));
+    group.finish();
+}
+
+criterion_group!(benches, long);
+criterion_main!(benches);
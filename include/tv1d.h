@@ -0,0 +1,42 @@
+/* C interface to the tv1d total variation denoisers, built with the
+ * `ffi` feature of the crate. See `src/ffi.rs` for the documentation
+ * of every function. */
+
+#ifndef TV1D_H
+#define TV1D_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The values were denoised. */
+#define TV1D_OK 0
+/* `input` or `output` is a null pointer, although `length` is not 0. */
+#define TV1D_ERROR_NULL_POINTER 1
+/* The input contains a NaN or infinite value. */
+#define TV1D_ERROR_NON_FINITE_INPUT 2
+/* `lambda` is NaN. */
+#define TV1D_ERROR_NAN_LAMBDA 3
+/* `lambda` is less than 0. */
+#define TV1D_ERROR_NEGATIVE_LAMBDA 4
+/* The denoised output overflowed, although every input value is
+ * finite. `output` may hold the overflowed output. */
+#define TV1D_ERROR_OVERFLOW 5
+/* Any other error, which the functions do not return today. */
+#define TV1D_ERROR_OTHER 6
+
+/* Denoise `length` values from `input` into `output`, which may be
+ * `input` itself, and return `TV1D_OK` or one of the error codes.
+ * On any error but `TV1D_ERROR_OVERFLOW`, `output` is untouched. */
+int tv1d_condat(const double *input, size_t length, double lambda, double *output);
+int tv1d_condat_f32(const float *input, size_t length, float lambda, float *output);
+int tv1d_tautstring(const double *input, size_t length, double lambda, double *output);
+int tv1d_tautstring_f32(const float *input, size_t length, float lambda, float *output);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TV1D_H */
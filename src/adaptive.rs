@@ -0,0 +1,143 @@
+use dp::dynamic_programming_weighted;
+use scalar::TvFloat;
+use utils;
+
+/// Estimates the standard deviation of the noise around each
+/// difference between neighboring input values, for signals whose
+/// noise level changes over time.
+///
+/// The estimate for the difference between the values `i` and
+/// `i + 1` is the noise level estimated by
+/// [`Lambda::NoiseSigmas`](enum.Lambda.html#variant.NoiseSigmas) from
+/// the `window` differences centered on it, shifted to fit within the
+/// input at its ends. The median makes the estimates robust to the
+/// jumps of the signal, as long as they are fewer than half of the
+/// differences in a window. The returned vector is one shorter than
+/// the input, and empty for a single value.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `window` is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![0.0, 0.1, 0.0, 0.1, 0.0, 2.0, 0.0, 2.0, 0.0];
+/// let sigmas = tv1d::local_noise_sigmas(&input, 3);
+/// assert_eq!(sigmas.len(), 8);
+/// assert!(sigmas[0] < sigmas[7]);
+/// ```
+pub fn local_noise_sigmas<T>(input: &[T], window: usize) -> Vec<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(window > 0, "Window must be greater than 0.");
+
+    let differences = input.len() - 1;
+    let window = window.min(differences);
+    (0..differences)
+        .map(|i| {
+            let start = i.saturating_sub(window / 2).min(differences - window);
+            utils::estimate_noise_sigma(&input[start..(start + window + 1)])
+        })
+        .collect()
+}
+
+/// Denoises the input values with a `lambda` that follows the local
+/// noise level, for heteroscedastic signals such as sensor readings
+/// whose noise grows with the measured value.
+///
+/// Each difference between neighboring values is penalized by
+/// `noise_sigmas` times the noise level around it estimated by
+/// [`tv1d::local_noise_sigmas`](../tv1d/fn.local_noise_sigmas.html)
+/// over `window` differences, and the input is denoised with
+/// [`tv1d::dynamic_programming_weighted`](../tv1d/fn.dynamic_programming_weighted.html).
+/// Quiet stretches of the input are thus denoised less than noisy
+/// ones, instead of one `lambda` oversmoothing the former or leaving
+/// noise in the latter.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `window` is `0`, or
+/// `noise_sigmas` is NaN, infinite or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // A quiet step up, then a noisy stretch around a constant level.
+/// let input: Vec<f64> = vec![0.0, 0.01, 0.0, 1.0, 1.01, 1.0, 1.5, 0.5, 1.5, 0.5, 1.5];
+///
+/// let denoised = tv1d::denoise_adaptive(&input, 2.0, 4);
+/// assert!(denoised[2] < 0.1 && denoised[3] > 0.9);
+/// assert!((denoised[10] - denoised[6]).abs() < 0.5);
+/// ```
+pub fn denoise_adaptive<T>(input: &[T], noise_sigmas: T, window: usize) -> Vec<T>
+    where T: TvFloat
+{
+    let lambdas: Vec<T> = local_noise_sigmas(input, window).into_iter().map(|sigma| noise_sigmas * sigma).collect();
+    dynamic_programming_weighted(input, &lambdas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat_relative;
+    use Lambda;
+
+    fn noise(i: usize) -> f64 {
+        ((i * 7919) % 13) as f64 / 13.0 - 0.5
+    }
+
+    #[test]
+    fn local_noise_sigmas_test_heteroscedastic() {
+        let input: Vec<f64> = (0..400).map(|i| if i < 200 { 0.1 } else { 2.0 } * noise(i)).collect();
+        let sigmas = local_noise_sigmas(&input, 21);
+        assert_eq!(sigmas.len(), 399);
+        let global = utils::estimate_noise_sigma(&input[..201]);
+        assert!((sigmas[50] - global).abs() < 0.5 * global);
+        assert!(sigmas[350] > 10.0 * sigmas[50]);
+    }
+
+    #[test]
+    fn local_noise_sigmas_test_window_longer_than_input() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4];
+        let sigma = utils::estimate_noise_sigma(&input);
+        assert_eq!(local_noise_sigmas(&input, 100), vec![sigma; 4]);
+        assert_eq!(local_noise_sigmas(&[1.0], 3), Vec::<f64>::new());
+    }
+
+    #[test]
+    fn denoise_adaptive_test_matches_global_noise() {
+        let input: Vec<f64> = (0..100).map(|i| ((i / 25) % 2) as f64 + 0.2 * noise(i)).collect();
+        let output = denoise_adaptive(&input, 2.0, input.len());
+        let expected = condat_relative(&input, Lambda::NoiseSigmas(2.0));
+        for (value, expected) in output.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn denoise_adaptive_test_heteroscedastic() {
+        // Steps of the same height, one in a quiet and one in a noisy
+        // stretch.
+        let input: Vec<f64> = (0..400)
+            .map(|i| ((i / 50) % 2) as f64 + if i < 200 { 0.05 } else { 0.5 } * noise(i))
+            .collect();
+        let output = denoise_adaptive(&input, 1.0, 31);
+        let quiet_error = output[..200].iter().map(|&value| (value - value.round()).abs()).fold(0.0, f64::max);
+        assert!(quiet_error < 0.05);
+        // The noisy stretch is denoised more than the quiet one.
+        let residual = |range: std::ops::Range<usize>| range.map(|i| (input[i] - output[i]).abs()).sum::<f64>();
+        assert!(residual(200..400) > 5.0 * residual(0..200));
+    }
+
+    #[test]
+    #[should_panic]
+    fn local_noise_sigmas_test_zero_window() {
+        local_noise_sigmas(&[1.0, 2.0], 0);
+    }
+}
@@ -0,0 +1,144 @@
+use hull::{Buffers, Compensated, TautstringStorage, Vertex};
+use scalar::{TvFloat, TvScalar};
+use {condat_in_place, find_taut_string};
+
+/// Fixed-size scratch storage for
+/// [`tv1d::tautstring_array`](../tv1d/fn.tautstring_array.html),
+/// which denoises arrays of fewer than `M` values without allocating.
+///
+/// The workspace holds `7 * M` values of `T` and `3 * M` indices, so
+/// it can be large for the stack of a microcontroller, and may be
+/// kept in a `static` instead.
+#[derive(Clone, Debug)]
+pub struct TautstringArrayWorkspace<T, const M: usize> {
+    low: [Vertex<Compensated<T>, T>; M],
+    up: [Vertex<Compensated<T>, T>; M],
+    knots: [(usize, T); M],
+}
+
+impl<T: TvFloat, const M: usize> TautstringArrayWorkspace<T, M> {
+    /// Creates a workspace for arrays of fewer than `M` values.
+    pub fn new() -> TautstringArrayWorkspace<T, M> {
+        TautstringArrayWorkspace {
+            low: [Vertex::new(0, Compensated::new(T::zero())); M],
+            up: [Vertex::new(0, Compensated::new(T::zero())); M],
+            knots: [(0, T::zero()); M],
+        }
+    }
+}
+
+impl<T: TvFloat, const M: usize> Default for TautstringArrayWorkspace<T, M> {
+    fn default() -> TautstringArrayWorkspace<T, M> {
+        TautstringArrayWorkspace::new()
+    }
+}
+
+impl<T: TvFloat, const M: usize> TautstringStorage<T> for TautstringArrayWorkspace<T, M> {
+    fn buffers(&mut self, width: usize) -> Buffers<'_, T> {
+        // Every other element is written before it is read.
+        self.low[0] = Vertex::new(0, Compensated::new(T::zero()));
+        self.up[0] = Vertex::new(0, Compensated::new(T::zero()));
+        self.knots[0] = (0, T::zero());
+        (&mut self.low[..width], &mut self.up[..width], &mut self.knots[..width])
+    }
+}
+
+/// Denoises an array of input values with the same algorithm as
+/// [`tv1d::condat`](../tv1d/fn.condat.html), without any heap
+/// allocation, for firmware with only a stack.
+///
+/// # Panics
+/// Panics if `N` is `0`, or `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = [1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(tv1d::condat_array(&input, 10.0), [3.0; 5]);
+/// ```
+pub fn condat_array<T, const N: usize>(input: &[T; N], lambda: T) -> [T; N]
+    where T: TvScalar
+{
+    let mut output = *input;
+    condat_in_place(&mut output, lambda);
+    output
+}
+
+/// Denoises an array of input values with the same algorithm as
+/// [`tv1d::tautstring`](../tv1d/fn.tautstring.html), using the
+/// fixed-size scratch storage of `workspace`, without any heap
+/// allocation.
+///
+/// The workspace must hold more than `N` values, which is checked at
+/// compile time.
+///
+/// # Panics
+/// Panics if `N` is `0`, or `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let mut workspace = tv1d::TautstringArrayWorkspace::<f64, 6>::new();
+/// let input = [100.0, 200.0, 300.0, 400.0, 500.0];
+/// assert_eq!(tv1d::tautstring_array(&input, 10.0, &mut workspace),
+///            [110.0, 200.0, 300.0, 400.0, 490.0]);
+/// ```
+///
+/// A workspace that is too small does not compile:
+///
+/// ```compile_fail
+/// use tv1d;
+///
+/// let mut workspace = tv1d::TautstringArrayWorkspace::<f64, 5>::new();
+/// tv1d::tautstring_array(&[1.0, 2.0, 3.0, 4.0, 5.0], 10.0, &mut workspace);
+/// ```
+pub fn tautstring_array<T, const N: usize, const M: usize>(input: &[T; N],
+                                                           lambda: T,
+                                                           workspace: &mut TautstringArrayWorkspace<T, M>)
+                                                           -> [T; N]
+    where T: TvFloat
+{
+    const {
+        assert!(M > N, "The workspace must hold more values than the input.");
+    }
+
+    let mut output = *input;
+    find_taut_string(&mut output, lambda, workspace, &mut ());
+    output
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use {condat, tautstring};
+
+    #[test]
+    fn array_test_matches_solvers() {
+        let input = [111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        let mut workspace = TautstringArrayWorkspace::<f64, 16>::new();
+        for &lambda in &[0.0, 1.0, 50.0, 500.0, f64::INFINITY] {
+            assert_eq!(condat_array(&input, lambda).to_vec(), condat(&input, lambda));
+            assert_eq!(tautstring_array(&input, lambda, &mut workspace).to_vec(), tautstring(&input, lambda));
+        }
+        // The workspace is reused for shorter inputs.
+        let short = [4.0, 1.0, 4.0];
+        assert_eq!(tautstring_array(&short, 1.0, &mut workspace).to_vec(), tautstring(&short, 1.0));
+    }
+
+    #[test]
+    fn condat_array_test_integers() {
+        let input: [i32; 5] = [1, 2, 3, 4, 5];
+        assert_eq!(condat_array(&input, 10).to_vec(), condat(&input, 10));
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_array_test_empty_input() {
+        let input: [f64; 0] = [];
+        condat_array(&input, 1.0);
+    }
+}
@@ -0,0 +1,208 @@
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use condat_in_place;
+use scalar::TvScalar;
+use utils;
+
+/// Denoises every lane along `axis` of an array of any rank, stored in
+/// `values` with the given `shape` and `strides`, in place, with
+/// [`tv1d::condat`](../tv1d/fn.condat.html).
+///
+/// The value at index `[i_0, i_1, ...]` of the array is
+/// `values[i_0 * strides[0] + i_1 * strides[1] + ...]`, as in the
+/// views of `ndarray` and NumPy, with the strides counted in values.
+/// A lane is the `shape[axis]` values whose indices differ only along
+/// `axis`, e.g. a row of a 2D array stored row by row for an `axis` of
+/// `1`, and a column for an `axis` of `0`. Views that skip values or
+/// are transposed need no copy. If the lanes of a view share values,
+/// e.g. with a stride of `0`, every lane is denoised from the original
+/// values, and a shared value is set to its output in the last lane.
+///
+/// With the `rayon` feature, the lanes are denoised in parallel over
+/// rayon's thread pool.
+///
+/// # Panics
+/// Panics if `shape` and `strides` have different lengths, `axis` is
+/// not less than their length, an index of the array is out of the
+/// bounds of `values`, or `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // A 2x5 array, stored row by row.
+/// let mut values = vec![1.0, 2.0, 3.0, 4.0, 5.0,
+///                       5.0, 5.0, 5.0, 5.0, 9.0];
+///
+/// tv1d::condat_axis(&mut values, &[2, 5], &[5, 1], 1, 10.0);
+/// assert_eq!(values, vec![3.0, 3.0, 3.0, 3.0, 3.0,
+///                         5.8, 5.8, 5.8, 5.8, 5.8]);
+///
+/// // The columns of the same array.
+/// let mut values = vec![1.0, 2.0, 3.0, 4.0, 5.0,
+///                       5.0, 5.0, 5.0, 5.0, 9.0];
+/// tv1d::condat_axis(&mut values, &[2, 5], &[5, 1], 0, 10.0);
+/// assert_eq!(values, vec![3.0, 3.5, 4.0, 4.5, 7.0,
+///                         3.0, 3.5, 4.0, 4.5, 7.0]);
+/// ```
+pub fn condat_axis<T>(values: &mut [T], shape: &[usize], strides: &[usize], axis: usize, lambda: T)
+    where T: TvScalar
+{
+    assert!(shape.len() == strides.len(),
+            "Shape and strides must have the same length.");
+    assert!(axis < shape.len(),
+            "Axis must be less than the number of dimensions.");
+    assert!(!utils::is_nan_lambda(&lambda),
+            "Lambda must not be NaN.");
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    if shape.contains(&0) {
+        return;
+    }
+    let last = shape.iter().zip(strides.iter()).map(|(&length, &stride)| (length - 1) * stride).sum::<usize>();
+    assert!(last < values.len(),
+            "Array indices must be within the bounds of the values.");
+
+    let length = shape[axis];
+    let stride = strides[axis];
+    if length < 2 {
+        return;
+    }
+
+    // The offsets of the first value of every lane, iterating over the
+    // indices of the other axes like an odometer.
+    let mut starts = Vec::new();
+    let mut index = vec![0; shape.len()];
+    let mut start = 0;
+    'lanes: loop {
+        starts.push(start);
+        for k in (0..shape.len()).rev() {
+            if k == axis {
+                continue;
+            }
+            if index[k] + 1 < shape[k] {
+                index[k] += 1;
+                start += strides[k];
+                continue 'lanes;
+            }
+            start -= index[k] * strides[k];
+            index[k] = 0;
+        }
+        break;
+    }
+
+    #[cfg(feature = "rayon")]
+    let lanes = starts.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let lanes = starts.iter();
+
+    let source: &[T] = values;
+    let denoised: Vec<Vec<T>> = lanes.map(|&start| {
+            let mut lane: Vec<T> = source[start..].iter().step_by(stride).take(length).cloned().collect();
+            condat_in_place(&mut lane, lambda);
+            lane
+        })
+        .collect();
+
+    for (&start, lane) in starts.iter().zip(denoised.iter()) {
+        for (value, &denoised) in values[start..].iter_mut().step_by(stride).zip(lane.iter()) {
+            *value = denoised;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    fn sample(n: usize) -> Vec<f64> {
+        (0..n).map(|i| ((i * 7919) % 23) as f64 * 0.5 + (i % 7) as f64).collect()
+    }
+
+    #[test]
+    fn condat_axis_test_3d() {
+        let shape = [3, 4, 5];
+        let strides = [20, 5, 1];
+        let input = sample(60);
+        for axis in 0..3 {
+            let mut values = input.clone();
+            condat_axis(&mut values, &shape, &strides, axis, 2.0);
+            for (i, &value) in values.iter().enumerate() {
+                // The lane through `i` along `axis`.
+                let position = (i / strides[axis]) % shape[axis];
+                let first = i - position * strides[axis];
+                let lane: Vec<f64> = (0..shape[axis]).map(|k| input[first + k * strides[axis]]).collect();
+                assert_eq!(value, condat(&lane, 2.0)[position]);
+            }
+        }
+    }
+
+    #[test]
+    fn condat_axis_test_transposed_view() {
+        // The same 4x6 array, stored row by row and column by column.
+        let rows = sample(24);
+        let mut columns = vec![0.0; 24];
+        for i in 0..4 {
+            for j in 0..6 {
+                columns[j * 4 + i] = rows[i * 6 + j];
+            }
+        }
+        for axis in 0..2 {
+            let mut by_rows = rows.clone();
+            let mut by_columns = columns.clone();
+            condat_axis(&mut by_rows, &[4, 6], &[6, 1], axis, 1.5);
+            condat_axis(&mut by_columns, &[4, 6], &[1, 4], axis, 1.5);
+            for i in 0..4 {
+                for j in 0..6 {
+                    assert_eq!(by_rows[i * 6 + j], by_columns[j * 4 + i]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn condat_axis_test_sparse_view() {
+        // Every other value of every other row of a 4x6 array, leaving
+        // the rest untouched.
+        let input = sample(24);
+        let mut values = input.clone();
+        condat_axis(&mut values, &[2, 3], &[12, 2], 1, 100.0);
+        for i in 0..24 {
+            if (i / 6) % 2 == 0 && i % 2 == 0 {
+                let first = i - i % 6;
+                let mean = (input[first] + input[first + 2] + input[first + 4]) / 3.0;
+                assert!((values[i] - mean).abs() < 1e-12);
+            } else {
+                assert_eq!(values[i], input[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn condat_axis_test_empty_and_integer() {
+        let mut values: Vec<f64> = vec![];
+        condat_axis(&mut values, &[0, 3], &[3, 1], 1, 1.0);
+
+        let mut values = vec![1, 5, 3, 9];
+        condat_axis(&mut values, &[2, 2], &[2, 1], 0, 100);
+        assert_eq!(values, vec![2, 7, 2, 7]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_axis_test_out_of_bounds() {
+        let mut values = vec![1.0; 5];
+        condat_axis(&mut values, &[2, 3], &[3, 1], 0, 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_axis_test_invalid_axis() {
+        let mut values = vec![1.0; 6];
+        condat_axis(&mut values, &[2, 3], &[3, 1], 2, 1.0);
+    }
+}
@@ -0,0 +1,221 @@
+use condat;
+use scalar::TvFloat;
+
+/// The result of
+/// [`tv1d::baseline_tv`](../tv1d/fn.baseline_tv.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BaselineFit<T> {
+    /// The slowly varying baseline, which carries the mean of the
+    /// input.
+    pub baseline: Vec<T>,
+    /// The piecewise constant steps on top of the baseline, with a mean
+    /// of `0`.
+    pub steps: Vec<T>,
+    /// The number of iterations run.
+    pub iterations: usize,
+}
+
+/// Separates the input values into a slowly varying baseline and
+/// piecewise constant steps, for traces with a drifting baseline such
+/// as spectra or electrophysiology recordings.
+///
+/// The baseline `b` and the steps `s` minimize
+/// `0.5 * sum((input - b - s)^2) + 0.5 * smoothness * sum((b[i - 1] -
+/// 2 * b[i] + b[i + 1])^2) + lambda * sum(abs(s[i + 1] - s[i]))`,
+/// i.e. the baseline is penalized quadratically for its curvature,
+/// like a Whittaker smoother, and the steps for their total variation.
+/// The two are solved for alternately, the steps with
+/// [`tv1d::condat`](../tv1d/fn.condat.html) given the baseline, and
+/// the baseline by a banded linear solve given the steps, starting from
+/// the smoothed input, until the baseline changes by less than `1e-9`
+/// times the range of the input or `max_iterations` is reached.
+///
+/// Larger `smoothness` keeps the baseline straighter, leaving more of
+/// the input to the steps, and larger `lambda` leaves fewer steps.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `lambda` is NaN or less
+/// than `0`, or `smoothness` is NaN, infinite or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // A step of `2.0` on top of a slow drift.
+/// let input: Vec<f64> = (0..200).map(|i| (i as f64 / 60.0).sin() + if i < 100 { 0.0 } else { 2.0 }).collect();
+///
+/// let fit = tv1d::baseline_tv(&input, 0.5, 1e5, 1000);
+/// assert!(fit.iterations < 1000);
+/// assert!((fit.steps[100] - fit.steps[99] - 2.0).abs() < 0.2);
+/// assert!((fit.baseline[100] - fit.baseline[99]).abs() < 0.01);
+/// ```
+pub fn baseline_tv<T>(input: &[T], lambda: T, smoothness: T, max_iterations: usize) -> BaselineFit<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(smoothness.is_finite() && smoothness >= T::zero(),
+            "Smoothness must be finite and greater than or equal to 0.");
+
+    let smoother = Smoother::new(input.len(), smoothness);
+    let (min, max) = input.iter().fold((input[0], input[0]), |(min, max), &value| (min.min(value), max.max(value)));
+    let tolerance = T::from_f64(1e-9).expect("Unable to convert f64 to TvScalar.") * (max - min);
+
+    let mut fit = BaselineFit {
+        baseline: smoother.smooth(input.to_vec()),
+        steps: vec![T::zero(); input.len()],
+        iterations: 0,
+    };
+    while fit.iterations < max_iterations {
+        let residual: Vec<T> = input.iter().zip(fit.baseline.iter()).map(|(&value, &baseline)| value - baseline).collect();
+        fit.steps = condat(&residual, lambda);
+
+        let residual: Vec<T> = input.iter().zip(fit.steps.iter()).map(|(&value, &step)| value - step).collect();
+        let baseline = smoother.smooth(residual);
+        let change = baseline.iter().zip(fit.baseline.iter()).fold(T::zero(), |change, (&new, &old)| change.max((new - old).abs()));
+        fit.baseline = baseline;
+        fit.iterations += 1;
+
+        if change <= tolerance {
+            break;
+        }
+    }
+    fit
+}
+
+// The Cholesky factor of `I + smoothness * D' * D`, where `D` takes the
+// second differences, which is banded with two diagonals below the
+// main one.
+struct Smoother<T> {
+    diagonal: Vec<T>,
+    // `first[i]` and `second[i]` are the entries of row `i` one and two
+    // columns left of the diagonal.
+    first: Vec<T>,
+    second: Vec<T>,
+}
+
+impl<T> Smoother<T>
+    where T: TvFloat
+{
+    fn new(width: usize, smoothness: T) -> Smoother<T> {
+        let mut diagonal = vec![T::one(); width];
+        let mut first = vec![T::zero(); width];
+        let mut second = vec![T::zero(); width];
+
+        let two = T::one() + T::one();
+        let coefficients = [T::one(), -two, T::one()];
+        for row in 0..width.saturating_sub(2) {
+            for a in 0..3 {
+                diagonal[row + a] += smoothness * coefficients[a] * coefficients[a];
+                for b in 0..a {
+                    let entry = smoothness * coefficients[a] * coefficients[b];
+                    if a - b == 1 {
+                        first[row + a] += entry;
+                    } else {
+                        second[row + a] += entry;
+                    }
+                }
+            }
+        }
+
+        for i in 0..width {
+            if i >= 2 {
+                second[i] = second[i] / diagonal[i - 2];
+            }
+            if i >= 1 {
+                let above = if i >= 2 { second[i] * first[i - 1] } else { T::zero() };
+                first[i] = (first[i] - above) / diagonal[i - 1];
+            }
+            diagonal[i] = (diagonal[i] - first[i] * first[i] - second[i] * second[i]).sqrt();
+        }
+        Smoother { diagonal, first, second }
+    }
+
+    // Solves `(I + smoothness * D' * D) * x = values` in place.
+    fn smooth(&self, mut values: Vec<T>) -> Vec<T> {
+        let width = values.len();
+        for i in 0..width {
+            if i >= 1 {
+                values[i] = values[i] - self.first[i] * values[i - 1];
+            }
+            if i >= 2 {
+                values[i] = values[i] - self.second[i] * values[i - 2];
+            }
+            values[i] = values[i] / self.diagonal[i];
+        }
+        for i in (0..width).rev() {
+            if i + 1 < width {
+                values[i] = values[i] - self.first[i + 1] * values[i + 1];
+            }
+            if i + 2 < width {
+                values[i] = values[i] - self.second[i + 2] * values[i + 2];
+            }
+            values[i] = values[i] / self.diagonal[i];
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smoother_test_solves_system() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let smoothness = 3.0;
+        let output = Smoother::new(input.len(), smoothness).smooth(input.clone());
+        // Multiply back by `I + smoothness * D' * D`.
+        let mut product = output.clone();
+        for row in 0..(output.len() - 2) {
+            let curvature = output[row] - 2.0 * output[row + 1] + output[row + 2];
+            product[row] += smoothness * curvature;
+            product[row + 1] -= 2.0 * smoothness * curvature;
+            product[row + 2] += smoothness * curvature;
+        }
+        for (value, expected) in product.iter().zip(input.iter()) {
+            assert!((value - expected).abs() < 1e-9);
+        }
+        // Lines are not penalized.
+        let line: Vec<f64> = (0..10).map(|i| 2.0 * i as f64 - 3.0).collect();
+        for (value, expected) in Smoother::new(10, 1e3).smooth(line.clone()).iter().zip(line.iter()) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn baseline_tv_test_fixed_point() {
+        let input: Vec<f64> = (0..300)
+            .map(|i| 0.001 * (i as f64 - 150.0).powi(2) / 30.0 + ((i / 60) % 2) as f64 + ((i * 7919) % 13) as f64 * 0.02)
+            .collect();
+        let fit = baseline_tv(&input, 1.0, 1e5, 1000);
+        assert!(fit.iterations < 1000);
+
+        let residual: Vec<f64> = input.iter().zip(fit.baseline.iter()).map(|(value, baseline)| value - baseline).collect();
+        for (value, expected) in fit.steps.iter().zip(condat(&residual, 1.0).iter()) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+        let mean = fit.steps.iter().sum::<f64>() / 300.0;
+        assert!(mean.abs() < 1e-6);
+    }
+
+    #[test]
+    fn baseline_tv_test_recovers_steps() {
+        let input: Vec<f64> = (0..400).map(|i| (i as f64 / 100.0).cos() * 3.0 + if (i / 100) % 2 == 0 { 0.0 } else { 1.5 }).collect();
+        let fit = baseline_tv(&input, 0.5, 1e5, 1000);
+        for &jump in &[100, 200, 300] {
+            assert!((fit.steps[jump] - fit.steps[jump - 1]).abs() > 1.2);
+            assert!((fit.baseline[jump] - fit.baseline[jump - 1]).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn baseline_tv_test_short_input() {
+        let fit = baseline_tv(&[1.0, 3.0], 0.5, 10.0, 10);
+        assert_eq!(fit.steps, vec![0.0, 0.0]);
+        assert_eq!(fit.baseline, vec![1.0, 3.0]);
+    }
+}
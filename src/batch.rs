@@ -0,0 +1,127 @@
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use condat;
+use progress::{Progress, Tracker};
+use scalar::TvScalar;
+
+/// Denoises each of many inputs independently with
+/// [`tv1d::condat`](../tv1d/fn.condat.html), e.g. a collection of
+/// profiles of different lengths.
+///
+/// With the `rayon` feature, the inputs are denoised in parallel over
+/// rayon's thread pool.
+///
+/// # Panics
+/// Panics if any input's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let inputs = vec![vec![1.0, 2.0, 3.0, 4.0, 5.0], vec![4.0, 2.0]];
+/// assert_eq!(tv1d::condat_batch(&inputs, 10.0),
+///            vec![vec![3.0, 3.0, 3.0, 3.0, 3.0], vec![3.0, 3.0]]);
+/// ```
+pub fn condat_batch<T, I>(inputs: &[I], lambda: T) -> Vec<Vec<T>>
+    where T: TvScalar,
+          I: AsRef<[T]> + Sync
+{
+    condat_batch_with_progress(inputs, lambda, |_| ())
+}
+
+/// Denoises each of many inputs independently like
+/// [`tv1d::condat_batch`](../tv1d/fn.condat_batch.html), calling
+/// `progress` after each input is denoised.
+///
+/// The progress counts the samples of the inputs denoised so far. With
+/// the `rayon` feature, `progress` is called from rayon's threads, and
+/// the calls may arrive out of order.
+///
+/// # Panics
+/// Panics if any input's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use std::sync::Mutex;
+///
+/// let inputs = vec![vec![1.0, 2.0, 3.0, 4.0, 5.0], vec![4.0, 2.0]];
+/// let fractions = Mutex::new(Vec::new());
+/// tv1d::condat_batch_with_progress(&inputs, 10.0, |progress| {
+///     fractions.lock().unwrap().push(progress.fraction);
+/// });
+/// let fractions = fractions.into_inner().unwrap();
+/// assert_eq!(fractions.len(), 2);
+/// assert!(fractions.contains(&1.0));
+/// ```
+pub fn condat_batch_with_progress<T, I, F>(inputs: &[I], lambda: T, progress: F) -> Vec<Vec<T>>
+    where T: TvScalar,
+          I: AsRef<[T]> + Sync,
+          F: Fn(Progress) + Sync
+{
+    let total = inputs.iter().map(|input| input.as_ref().len()).sum();
+    let tracker = Tracker::new(total, &progress);
+
+    #[cfg(feature = "rayon")]
+    let inputs = inputs.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let inputs = inputs.iter();
+
+    inputs.map(|input| {
+            let output = condat(input.as_ref(), lambda);
+            tracker.advance(output.len());
+            output
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn condat_batch_test_matches_condat() {
+        let inputs: Vec<Vec<f64>> = (1..50)
+            .map(|len| (0..len).map(|i| ((i * 7919) % 13) as f64).collect())
+            .collect();
+        let outputs = condat_batch(&inputs, 2.0);
+        assert_eq!(outputs.len(), inputs.len());
+        for (input, output) in inputs.iter().zip(outputs.iter()) {
+            assert_eq!(output, &condat(input, 2.0));
+        }
+    }
+
+    #[test]
+    fn condat_batch_test_slices() {
+        let input = [1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let outputs = condat_batch(&[&input[..4], &input[4..]], 1.0);
+        assert_eq!(outputs, vec![condat(&input[..4], 1.0), condat(&input[4..], 1.0)]);
+    }
+
+    #[test]
+    fn condat_batch_with_progress_test_counts_samples() {
+        let inputs: Vec<Vec<f64>> = (1..20).map(|len| vec![1.0; len]).collect();
+        let samples = AtomicUsize::new(0);
+        let calls = AtomicUsize::new(0);
+        let outputs = condat_batch_with_progress(&inputs, 2.0, |progress| {
+            samples.fetch_max(progress.samples, Ordering::Relaxed);
+            calls.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(outputs, condat_batch(&inputs, 2.0));
+        assert_eq!(samples.into_inner(), (1..20).sum::<usize>());
+        assert_eq!(calls.into_inner(), inputs.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_batch_test_empty_input() {
+        let inputs: Vec<Vec<f64>> = vec![vec![1.0], vec![]];
+        condat_batch(&inputs, 1.0);
+    }
+}
@@ -0,0 +1,197 @@
+use condat;
+use progress::Progress;
+use scalar::TvFloat;
+use utils;
+
+/// The result of
+/// [`tv1d::bayesian_tv`](../tv1d/fn.bayesian_tv.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BayesianFit<T> {
+    /// The maximum a posteriori denoised output.
+    pub output: Vec<T>,
+    /// The estimated `lambda`, i.e. `noise_variance / increment_scale`.
+    pub lambda: T,
+    /// The estimated variance of the Gaussian noise.
+    pub noise_variance: T,
+    /// The estimated scale of the Laplace prior on the differences
+    /// between consecutive values.
+    pub increment_scale: T,
+    /// The number of iterations run.
+    pub iterations: usize,
+}
+
+/// Denoises the input values while estimating `lambda` from the data.
+///
+/// Total variation denoising is the maximum a posteriori (MAP)
+/// estimate of a signal observed with Gaussian noise of variance
+/// `noise_variance`, whose differences between consecutive values
+/// follow a Laplace prior of scale `increment_scale`, with `lambda =
+/// noise_variance / increment_scale`. This function alternates
+/// between computing the MAP fit for the current `lambda` and
+/// re-estimating both hyperparameters from that fit, in the manner
+/// of expectation maximization, until `lambda` stops changing or
+/// `max_iterations` is reached.
+///
+/// The iterations start from a noise level estimated from the median
+/// absolute difference of the input, which is robust to jumps. The
+/// noise variance is re-estimated from the residuals, divided by the
+/// degrees of freedom left over by the fit.
+///
+/// # Panics
+/// Panics if input vector's length is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![0.1, -0.2, 0.0, 0.2, -0.1, 4.1, 3.8, 4.0, 4.2, 3.9];
+///
+/// let fit = tv1d::bayesian_tv(&input, 100);
+/// assert!(fit.lambda > 0.0);
+/// assert!(fit.noise_variance > 0.0);
+/// assert_eq!(fit.output, tv1d::condat(&input, fit.lambda));
+/// ```
+pub fn bayesian_tv<T>(input: &[T], max_iterations: usize) -> BayesianFit<T>
+    where T: TvFloat
+{
+    bayesian_tv_with_progress(input, max_iterations, |_| ())
+}
+
+/// Denoises the input values while estimating `lambda` like
+/// [`tv1d::bayesian_tv`](../tv1d/fn.bayesian_tv.html), calling
+/// `progress` after each iteration.
+///
+/// The progress counts the samples denoised over all iterations so
+/// far, and its fraction is the fraction of `max_iterations` run. As
+/// the iterations can converge before `max_iterations`, `progress` is
+/// called a last time with a fraction of `1` when done.
+///
+/// # Panics
+/// Panics if input vector's length is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use std::cell::RefCell;
+///
+/// let input = vec![0.1, -0.2, 0.0, 0.2, -0.1, 4.1, 3.8, 4.0, 4.2, 3.9];
+/// let fractions = RefCell::new(Vec::new());
+/// let fit = tv1d::bayesian_tv_with_progress(&input, 100, |progress| {
+///     fractions.borrow_mut().push(progress.fraction);
+/// });
+/// let fractions = fractions.into_inner();
+/// assert_eq!(fractions.len(), fit.iterations + 1);
+/// assert_eq!(fractions.last(), Some(&1.0));
+/// ```
+pub fn bayesian_tv_with_progress<T, F>(input: &[T], max_iterations: usize, progress: F) -> BayesianFit<T>
+    where T: TvFloat,
+          F: Fn(Progress)
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    let tolerance = T::from_f64(1e-6).expect("Unable to convert f64 to TvScalar.");
+
+    let sigma = utils::estimate_noise_sigma(input);
+    let mut fit = BayesianFit {
+        output: input.to_vec(),
+        lambda: T::zero(),
+        noise_variance: sigma * sigma,
+        increment_scale: mean_absolute_increment(input),
+        iterations: 0,
+    };
+
+    while fit.iterations < max_iterations && fit.increment_scale > T::zero() {
+        let lambda = fit.noise_variance / fit.increment_scale;
+        let converged = (lambda - fit.lambda).abs() <= tolerance * lambda;
+
+        fit.lambda = lambda;
+        fit.output = condat(input, lambda);
+        fit.noise_variance = noise_variance(input, &fit.output).unwrap_or(fit.noise_variance);
+        fit.increment_scale = mean_absolute_increment(&fit.output);
+        fit.iterations += 1;
+        progress(Progress {
+            samples: fit.iterations * input.len(),
+            fraction: fit.iterations as f64 / max_iterations as f64,
+        });
+
+        if converged {
+            break;
+        }
+    }
+    progress(Progress {
+        samples: fit.iterations * input.len(),
+        fraction: 1.0,
+    });
+    fit
+}
+
+// Maximum likelihood estimate of the Laplace scale of the increments.
+fn mean_absolute_increment<T>(values: &[T]) -> T
+    where T: TvFloat
+{
+    if values.len() < 2 {
+        return T::zero();
+    }
+    let increments: Vec<T> = values.windows(2).map(|pair| (pair[1] - pair[0]).abs()).collect();
+    utils::mean(&increments)
+}
+
+// Estimate of the Gaussian noise variance, corrected for the degrees
+// of freedom of the fit, which are its number of segments. Without
+// the correction, the estimate collapses towards `lambda = 0`, where
+// the residuals vanish.
+fn noise_variance<T>(input: &[T], output: &[T]) -> Option<T>
+    where T: TvFloat
+{
+    let degrees_of_freedom = input.len() - utils::count_segments(output);
+    if degrees_of_freedom == 0 {
+        return None;
+    }
+    let residual_sum = input.iter()
+        .zip(output.iter())
+        .fold(T::zero(), |sum, (&x, &y)| sum + (x - y) * (x - y));
+    Some(residual_sum /
+         T::from_usize(degrees_of_freedom).expect("Unable to convert usize to TvScalar."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bayesian_tv_test_fixed_point() {
+        let input: Vec<f64> = vec![0.1, -0.2, 0.0, 0.2, -0.1, 4.1, 3.8, 4.0, 4.2, 3.9, 1.1, 0.8, 1.0];
+        let fit = bayesian_tv(&input, 1000);
+        assert!(fit.iterations < 1000);
+        assert_eq!(fit.output, condat(&input, fit.lambda));
+        let lambda = fit.noise_variance / fit.increment_scale;
+        assert!((lambda - fit.lambda).abs() <= 0.0001 * lambda);
+    }
+
+    #[test]
+    fn bayesian_tv_test_constant_input() {
+        let input = vec![2.0, 2.0, 2.0, 2.0];
+        let fit = bayesian_tv(&input, 10);
+        assert_eq!(fit.output, input);
+        assert_eq!(fit.iterations, 0);
+    }
+
+    #[test]
+    fn bayesian_tv_test_zero_iterations() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let fit = bayesian_tv(&input, 0);
+        assert_eq!(fit.output, input);
+        assert_eq!(fit.lambda, 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn bayesian_tv_test_empty_input() {
+        let input: Vec<f64> = vec![];
+        bayesian_tv(&input, 10);
+    }
+}
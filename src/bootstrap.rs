@@ -0,0 +1,153 @@
+use std::cmp;
+use std::thread;
+
+use condat;
+use rng::Rng;
+use scalar::TvFloat;
+use utils;
+
+/// Pointwise confidence bands around a denoised output, returned by
+/// [`tv1d::bootstrap_bands`](../tv1d/fn.bootstrap_bands.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ConfidenceBands<T> {
+    /// The denoised output of the input itself.
+    pub output: Vec<T>,
+    /// The lower end of the band at each position.
+    pub lower: Vec<T>,
+    /// The upper end of the band at each position.
+    pub upper: Vec<T>,
+}
+
+/// Computes pointwise confidence bands for the output of
+/// [`tv1d::condat`](../tv1d/fn.condat.html) by residual bootstrap.
+///
+/// The input is denoised once, and the residuals between the input
+/// and the output are resampled with replacement and added back onto
+/// the output `num_resamples` times. Each resampled signal is
+/// denoised again, and the bands contain the fraction `level` of the
+/// resampled outputs at each position, e.g. `0.95`.
+///
+/// The resamples are denoised in parallel over the available threads.
+/// The `seed` makes the bands reproducible, regardless of the number
+/// of threads.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `lambda` is less than `0`,
+/// `num_resamples` is `0`, or `level` is not between `0` and `1`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![0.1, -0.2, 0.0, 0.2, -0.1, 4.1, 3.8, 4.0, 4.2, 3.9];
+///
+/// let bands = tv1d::bootstrap_bands(&input, 0.5, 200, 0.9, 7);
+/// for i in 0..input.len() {
+///     assert!(bands.lower[i] <= bands.upper[i]);
+/// }
+/// ```
+pub fn bootstrap_bands<T>(input: &[T],
+                          lambda: T,
+                          num_resamples: usize,
+                          level: f64,
+                          seed: u64)
+                          -> ConfidenceBands<T>
+    where T: TvFloat
+{
+    assert!(num_resamples > 0,
+            "Number of resamples must be greater than 0.");
+
+    assert!((0.0..=1.0).contains(&level),
+            "Confidence level must be between 0 and 1.");
+
+    let output = condat(input, lambda);
+    let residuals: Vec<T> = input.iter().zip(output.iter()).map(|(&x, &y)| x - y).collect();
+
+    // Each resample gets its own seed so that the result does not
+    // depend on how resamples are distributed over threads.
+    let mut rng = Rng::new(seed);
+    let seeds: Vec<u64> = (0..num_resamples).map(|_| rng.next_u64()).collect();
+
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let chunk_size = num_resamples.div_ceil(threads);
+    let resampled: Vec<Vec<T>> = thread::scope(|scope| {
+        let handles: Vec<_> = seeds.chunks(chunk_size)
+            .map(|seeds| {
+                let (output, residuals) = (&output, &residuals);
+                scope.spawn(move || {
+                    seeds.iter().map(|&seed| refit(output, residuals, lambda, seed)).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles.into_iter()
+            .flat_map(|handle| handle.join().expect("Bootstrap thread panicked."))
+            .collect()
+    });
+
+    let tail = (1.0 - level) / 2.0;
+    let mut lower = Vec::with_capacity(input.len());
+    let mut upper = Vec::with_capacity(input.len());
+    for i in 0..input.len() {
+        let mut values: Vec<T> = resampled.iter().map(|sample| sample[i]).collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(cmp::Ordering::Equal));
+        lower.push(utils::quantile(&values, tail));
+        upper.push(utils::quantile(&values, 1.0 - tail));
+    }
+
+    ConfidenceBands {
+        output,
+        lower,
+        upper,
+    }
+}
+
+// Denoises the output plus residuals resampled with replacement.
+fn refit<T>(output: &[T], residuals: &[T], lambda: T, seed: u64) -> Vec<T>
+    where T: TvFloat
+{
+    let mut rng = Rng::new(seed);
+    let resampled: Vec<T> = output.iter()
+        .map(|&value| value + residuals[rng.below(residuals.len())])
+        .collect();
+    condat(&resampled, lambda)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bootstrap_bands_test_reproducible() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let first = bootstrap_bands(&input, 1.0, 50, 0.9, 3);
+        let second = bootstrap_bands(&input, 1.0, 50, 0.9, 3);
+        assert_eq!(first, second);
+        assert_eq!(first.output, condat(&input, 1.0));
+    }
+
+    #[test]
+    fn bootstrap_bands_test_zero_level() {
+        let input: Vec<f64> = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        let bands = bootstrap_bands(&input, 5.0, 101, 0.0, 11);
+        for i in 0..input.len() {
+            assert!((bands.lower[i] - bands.upper[i]).abs() <= 0.000001);
+        }
+    }
+
+    #[test]
+    fn bootstrap_bands_test_exact_fit() {
+        let input = vec![2.0, 2.0, 5.0, 5.0];
+        let bands = bootstrap_bands(&input, 0.0, 20, 0.95, 0);
+        assert_eq!(bands.lower, input);
+        assert_eq!(bands.upper, input);
+    }
+
+    #[test]
+    #[should_panic]
+    fn bootstrap_bands_test_zero_resamples() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        bootstrap_bands(&input, 1.0, 0, 0.9, 0);
+    }
+}
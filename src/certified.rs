@@ -0,0 +1,263 @@
+use std::ops;
+
+use condat;
+use utils;
+
+/// The result of
+/// [`tv1d::condat_certified`](../tv1d/fn.condat_certified.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct CertifiedOutput {
+    /// The denoised output.
+    pub output: Vec<f64>,
+    /// A guaranteed lower bound of the exact solution at each
+    /// position.
+    pub lower: Vec<f64>,
+    /// A guaranteed upper bound of the exact solution at each
+    /// position.
+    pub upper: Vec<f64>,
+    /// A guaranteed upper bound of the Euclidean distance between the
+    /// output and the exact solution.
+    pub radius: f64,
+}
+
+/// Denoises the input values with
+/// [`tv1d::condat`](../tv1d/fn.condat.html), and returns a guaranteed
+/// enclosure of the exact solution at each position along with the
+/// output.
+///
+/// The objective of total variation denoising is strongly convex, so
+/// the distance between any output and the exact solution is bounded
+/// by the square root of twice the duality gap of the output. The gap
+/// is computed against a dual point built from the residuals of the
+/// output, in interval arithmetic rounding every operation outward,
+/// so the bound holds despite the rounding errors of both the solver
+/// and the certification. The enclosures are `output ± radius`, and
+/// their width grows with the square root of the rounding error of
+/// the objective. For an infinite `lambda`, the mean of the input is
+/// enclosed directly instead.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, any input value is not
+/// finite, or `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// let certified = tv1d::condat_certified(&input, 10.0);
+/// for i in 0..input.len() {
+///     assert!(certified.lower[i] <= 3.0 && 3.0 <= certified.upper[i]);
+/// }
+/// assert!(certified.radius < 1e-6);
+/// ```
+pub fn condat_certified(input: &[f64], lambda: f64) -> CertifiedOutput {
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(input.iter().all(|value| value.is_finite()),
+            "Input values must be finite.");
+
+    assert!(!utils::is_nan_lambda(&lambda),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= 0.0,
+            "Lambda must be greater than or equal to 0.");
+
+    let output = condat(input, lambda);
+    if utils::is_infinite_lambda(lambda) {
+        return certified_mean(input, output);
+    }
+
+    let radius = distance_bound(input, &output, lambda);
+    let lower = output.iter().map(|&value| (value - radius).next_down()).collect();
+    let upper = output.iter().map(|&value| (value + radius).next_up()).collect();
+    CertifiedOutput {
+        output,
+        lower,
+        upper,
+        radius,
+    }
+}
+
+// Encloses the exact solution for an infinite `lambda`, which is the
+// mean of the input, directly, as the duality gap of any output that
+// is not exactly constant is infinite.
+fn certified_mean(input: &[f64], output: Vec<f64>) -> CertifiedOutput {
+    let sum = input.iter().fold(Interval::point(0.0), |sum, &value| sum + Interval::point(value));
+    let mean = sum / input.len() as f64;
+    let distance = output.iter()
+        .fold(0.0f64, |distance, &value| distance.max((value - mean.lo).next_up()).max((mean.hi - value).next_up()));
+    CertifiedOutput {
+        lower: vec![mean.lo; input.len()],
+        upper: vec![mean.hi; input.len()],
+        radius: (distance * (input.len() as f64).sqrt().next_up()).next_up(),
+        output,
+    }
+}
+
+// Returns an upper bound of the distance between `output` and the
+// exact solution, from the duality gap of `output`.
+//
+// The dual point `u` is the running sum of the residuals, clamped to
+// `[-lambda, lambda]` and zero at both ends, so it is feasible however
+// the sum is rounded. It maps to the signal `v` with
+// `v[i] = u[i - 1] - u[i]`, and the dual objective is
+// `<input, v> - ||v||^2 / 2`.
+fn distance_bound(input: &[f64], output: &[f64], lambda: f64) -> f64 {
+    let lambda_interval = Interval::point(lambda);
+
+    let mut primal = Interval::point(0.0);
+    let mut dual = Interval::point(0.0);
+    let mut running_sum = 0.0;
+    let mut previous_u = 0.0;
+    for i in 0..input.len() {
+        let residual = Interval::point(input[i]) - Interval::point(output[i]);
+        primal = primal + residual.square().half();
+        if i + 1 < input.len() {
+            let jump = Interval::point(output[i + 1]) - Interval::point(output[i]);
+            if !jump.is_zero() {
+                primal = primal + lambda_interval * jump.abs();
+            }
+        }
+
+        let u = if i + 1 < input.len() {
+            running_sum -= input[i] - output[i];
+            running_sum.max(-lambda).min(lambda)
+        } else {
+            0.0
+        };
+        let v = Interval::point(previous_u) - Interval::point(u);
+        dual = dual + Interval::point(input[i]) * v - v.square().half();
+        previous_u = u;
+    }
+
+    let gap = (primal - dual).hi.max(0.0);
+    (gap + gap).next_up().sqrt().next_up()
+}
+
+// A closed interval of real numbers, whose operations round their
+// ends outward so that the result contains every exact result of the
+// operation on values in the operands.
+#[derive(Clone, Copy, Debug)]
+struct Interval {
+    lo: f64,
+    hi: f64,
+}
+
+impl Interval {
+    fn point(value: f64) -> Interval {
+        Interval { lo: value, hi: value }
+    }
+
+    fn is_zero(self) -> bool {
+        self.lo == 0.0 && self.hi == 0.0
+    }
+
+    fn abs(self) -> Interval {
+        if self.lo >= 0.0 {
+            self
+        } else if self.hi <= 0.0 {
+            Interval { lo: -self.hi, hi: -self.lo }
+        } else {
+            Interval { lo: 0.0, hi: self.hi.max(-self.lo) }
+        }
+    }
+
+    fn square(self) -> Interval {
+        let abs = self.abs();
+        abs * abs
+    }
+
+    // Halving is exact unless it underflows, which rounds toward
+    // zero at most by the smallest subnormal.
+    fn half(self) -> Interval {
+        Interval {
+            lo: (self.lo * 0.5).next_down(),
+            hi: (self.hi * 0.5).next_up(),
+        }
+    }
+}
+
+impl ops::Add for Interval {
+    type Output = Interval;
+
+    fn add(self, other: Interval) -> Interval {
+        Interval {
+            lo: (self.lo + other.lo).next_down(),
+            hi: (self.hi + other.hi).next_up(),
+        }
+    }
+}
+
+impl ops::Sub for Interval {
+    type Output = Interval;
+
+    fn sub(self, other: Interval) -> Interval {
+        Interval {
+            lo: (self.lo - other.hi).next_down(),
+            hi: (self.hi - other.lo).next_up(),
+        }
+    }
+}
+
+impl ops::Mul for Interval {
+    type Output = Interval;
+
+    fn mul(self, other: Interval) -> Interval {
+        let products = [self.lo * other.lo, self.lo * other.hi, self.hi * other.lo, self.hi * other.hi];
+        let lo = products.iter().fold(f64::INFINITY, |lo, &product| lo.min(product));
+        let hi = products.iter().fold(f64::NEG_INFINITY, |hi, &product| hi.max(product));
+        Interval {
+            lo: lo.next_down(),
+            hi: hi.next_up(),
+        }
+    }
+}
+
+impl ops::Div<f64> for Interval {
+    type Output = Interval;
+
+    // Divides by a positive `divisor`.
+    fn div(self, divisor: f64) -> Interval {
+        Interval {
+            lo: (self.lo / divisor).next_down(),
+            hi: (self.hi / divisor).next_up(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tautstring_double_double;
+
+    #[test]
+    fn condat_certified_test_encloses_exact_solution() {
+        let input = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        for &lambda in &[0.0, 1.0, 50.0, 500.0, f64::INFINITY] {
+            let certified = condat_certified(&input, lambda);
+            assert_eq!(certified.output, condat(&input, lambda));
+            let exact = tautstring_double_double(&input, lambda);
+            for (i, &value) in exact.iter().enumerate() {
+                assert!(certified.lower[i] <= value && value <= certified.upper[i]);
+            }
+            assert!(certified.radius < 1e-4);
+        }
+    }
+
+    #[test]
+    fn condat_certified_test_rejects_wrong_output() {
+        let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let radius = distance_bound(&input, &[3.0, 3.0, 3.0, 3.0, 3.5], 10.0);
+        assert!(radius >= 0.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_certified_test_non_finite_input() {
+        condat_certified(&[1.0, f64::NAN], 1.0);
+    }
+}
@@ -0,0 +1,445 @@
+use std::cmp;
+use std::thread;
+
+use condat;
+use progress::{Progress, Tracker};
+use scalar::TvFloat;
+use utils;
+
+/// The number of chunks of `condat_parallel` with the `deterministic`
+/// feature, so that its output does not depend on the machine.
+#[cfg(feature = "deterministic")]
+const DETERMINISTIC_CHUNKS: usize = 8;
+
+/// The output of
+/// [`tv1d::condat_chunked`](../tv1d/fn.condat_chunked.html), with a
+/// bound on its deviation from the exact output.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ChunkedOutput<T> {
+    /// The stitched denoised output.
+    pub output: Vec<T>,
+    /// A bound on the absolute difference between each value of
+    /// `output` and the output of
+    /// [`tv1d::condat`](../tv1d/fn.condat.html) on the whole input,
+    /// up to rounding.
+    pub deviation: Vec<T>,
+}
+
+impl<T: TvFloat> ChunkedOutput<T> {
+    /// Returns the largest deviation bound over the output.
+    pub fn max_deviation(&self) -> T {
+        self.deviation.iter().fold(T::zero(), |max, &deviation| max.max(deviation))
+    }
+}
+
+/// Denoises a large input in chunks of `chunk_len` values, each
+/// extended by `overlap` values of its neighbours on both sides, and
+/// stitches the chunks together with a provable bound on the deviation
+/// from the output of [`tv1d::condat`](../tv1d/fn.condat.html) on the
+/// whole input.
+///
+/// Restricted to an extended chunk, the exact output is the output of
+/// `condat` on the extended chunk with its first and last value moved
+/// by at most `lambda`, which stands for the pull of the rest of the
+/// input. As total variation denoising is monotone in its input, the
+/// exact output is bounded by the outputs with both values moved down
+/// and up by `lambda`. The stitched output is the midpoint of these
+/// bounds, and its deviation is half their distance. The pull of an
+/// end is shared by the values of the segment it belongs to, so the
+/// deviation shrinks as the segments at the ends of the extended
+/// chunks grow, and a larger `overlap` gives a smaller deviation.
+///
+/// With `parallel`, the chunks are denoised over the available
+/// threads.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `lambda` is NaN or less
+/// than `0`, or `chunk_len` is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input: Vec<f64> = (0..1000).map(|i| if (i / 100) % 2 == 0 { 0.0 } else { 10.0 }).collect();
+/// let chunked = tv1d::condat_chunked(&input, 1.0, 256, 64, true);
+///
+/// let exact = tv1d::condat(&input, 1.0);
+/// for i in 0..input.len() {
+///     assert!((chunked.output[i] - exact[i]).abs() <= chunked.deviation[i] + 1e-9);
+/// }
+/// assert!(chunked.max_deviation() < 0.02);
+/// ```
+pub fn condat_chunked<T>(input: &[T],
+                         lambda: T,
+                         chunk_len: usize,
+                         overlap: usize,
+                         parallel: bool)
+                         -> ChunkedOutput<T>
+    where T: TvFloat
+{
+    condat_chunked_with_progress(input, lambda, chunk_len, overlap, parallel, |_| ())
+}
+
+/// Denoises a large input in chunks like
+/// [`tv1d::condat_chunked`](../tv1d/fn.condat_chunked.html), calling
+/// `progress` after each chunk is denoised.
+///
+/// The progress counts the samples of the chunks denoised so far,
+/// without their overlap. With `parallel`, `progress` is called from
+/// the threads denoising the chunks, and the calls may arrive out of
+/// order.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `lambda` is NaN or less
+/// than `0`, or `chunk_len` is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use std::sync::Mutex;
+///
+/// let input: Vec<f64> = (0..1000).map(|i| if (i / 100) % 2 == 0 { 0.0 } else { 10.0 }).collect();
+/// let samples = Mutex::new(Vec::new());
+/// tv1d::condat_chunked_with_progress(&input, 1.0, 256, 64, false, |progress| {
+///     samples.lock().unwrap().push(progress.samples);
+/// });
+/// assert_eq!(samples.into_inner().unwrap(), vec![256, 512, 768, 1000]);
+/// ```
+pub fn condat_chunked_with_progress<T, F>(input: &[T],
+                                          lambda: T,
+                                          chunk_len: usize,
+                                          overlap: usize,
+                                          parallel: bool,
+                                          progress: F)
+                                          -> ChunkedOutput<T>
+    where T: TvFloat,
+          F: Fn(Progress) + Sync
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(!lambda.is_nan(),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    assert!(chunk_len > 0, "Chunk length must be greater than 0.");
+
+    trace_span!("condat_chunked",
+                len = input.len(),
+                lambda = lambda.to_f64().unwrap_or(f64::NAN),
+                chunk_len,
+                overlap);
+
+    let tracker = Tracker::new(input.len(), &progress);
+    if utils::is_infinite_lambda(lambda) {
+        let output = condat(input, lambda);
+        tracker.advance(input.len());
+        return ChunkedOutput {
+            output,
+            deviation: vec![T::zero(); input.len()],
+        };
+    }
+
+    let starts: Vec<usize> = (0..input.len()).step_by(chunk_len).collect();
+    let threads = if parallel {
+        thread::available_parallelism().map_or(1, |threads| threads.get())
+    } else {
+        1
+    };
+    let per_thread = starts.len().div_ceil(threads);
+    let tracker = &tracker;
+    let chunks: Vec<(Vec<T>, Vec<T>)> = thread::scope(|scope| {
+        let handles: Vec<_> = starts.chunks(per_thread)
+            .map(|starts| {
+                scope.spawn(move || {
+                    starts.iter()
+                        .map(|&start| {
+                            let chunk = denoise_chunk(input, lambda, start, chunk_len, overlap);
+                            tracker.advance(chunk.0.len());
+                            chunk
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles.into_iter()
+            .flat_map(|handle| handle.join().expect("Chunk thread panicked."))
+            .collect()
+    });
+
+    let mut output = Vec::with_capacity(input.len());
+    let mut deviation = Vec::with_capacity(input.len());
+    for (chunk_output, chunk_deviation) in chunks {
+        output.extend(chunk_output);
+        deviation.extend(chunk_deviation);
+    }
+    ChunkedOutput { output, deviation }
+}
+
+/// Denoises a long input with [`tv1d::condat`](../tv1d/fn.condat.html)
+/// in parallel, splitting it into one chunk per available thread, and
+/// returns an output within `tolerance` of the output of `condat` on
+/// the whole input, up to rounding.
+///
+/// Each chunk is denoised like in
+/// [`tv1d::condat_chunked`](../tv1d/fn.condat_chunked.html), with a
+/// bound on its deviation from the exact output. The chunks whose
+/// deviation exceeds `tolerance` are denoised again, in parallel, with
+/// twice the overlap, until every chunk is within `tolerance`. A chunk
+/// extended over the whole input has no deviation, so this always
+/// ends, and a `tolerance` of `0` gives the exact output, although
+/// possibly after denoising most of the input several times.
+///
+/// With the `deterministic` feature, the input is split into a fixed
+/// number of chunks, so that the output is the same on every machine.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `lambda` is NaN or less
+/// than `0`, or `tolerance` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input: Vec<f64> = (0..10000).map(|i| ((i / 700) % 3) as f64 + ((i * 7919) % 13) as f64 * 0.1).collect();
+/// let parallel = tv1d::condat_parallel(&input, 5.0, 1e-6);
+/// assert!(parallel.max_deviation() <= 1e-6);
+///
+/// let exact = tv1d::condat(&input, 5.0);
+/// for i in 0..input.len() {
+///     assert!((parallel.output[i] - exact[i]).abs() <= 1e-6 + 1e-9);
+/// }
+/// ```
+pub fn condat_parallel<T>(input: &[T], lambda: T, tolerance: T) -> ChunkedOutput<T>
+    where T: TvFloat
+{
+    condat_parallel_with_progress(input, lambda, tolerance, |_| ())
+}
+
+/// Denoises a long input in parallel like
+/// [`tv1d::condat_parallel`](../tv1d/fn.condat_parallel.html), calling
+/// `progress` after each round of denoising.
+///
+/// The progress counts the samples of the chunks that are within
+/// `tolerance` so far, so a round whose chunks all need to be denoised
+/// again reports no new samples.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `lambda` is NaN or less
+/// than `0`, or `tolerance` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use std::sync::Mutex;
+///
+/// let input: Vec<f64> = (0..10000).map(|i| ((i / 700) % 3) as f64).collect();
+/// let fraction = Mutex::new(0.0);
+/// tv1d::condat_parallel_with_progress(&input, 5.0, 1e-6, |progress| {
+///     *fraction.lock().unwrap() = progress.fraction;
+/// });
+/// assert_eq!(fraction.into_inner().unwrap(), 1.0);
+/// ```
+pub fn condat_parallel_with_progress<T, F>(input: &[T], lambda: T, tolerance: T, progress: F) -> ChunkedOutput<T>
+    where T: TvFloat,
+          F: Fn(Progress) + Sync
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(!lambda.is_nan(),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    assert!(tolerance >= T::zero(),
+            "Tolerance must be greater than or equal to 0.");
+
+    trace_span!("condat_parallel",
+                len = input.len(),
+                lambda = lambda.to_f64().unwrap_or(f64::NAN),
+                tolerance = tolerance.to_f64().unwrap_or(f64::NAN));
+
+    if utils::is_infinite_lambda(lambda) {
+        return condat_chunked_with_progress(input, lambda, input.len(), 0, false, progress);
+    }
+
+    let tracker = Tracker::new(input.len(), &progress);
+
+    #[cfg(not(feature = "deterministic"))]
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    #[cfg(feature = "deterministic")]
+    let threads = DETERMINISTIC_CHUNKS;
+    let chunk_len = input.len().div_ceil(threads);
+    let starts: Vec<usize> = (0..input.len()).step_by(chunk_len).collect();
+    let mut overlaps = vec![cmp::max(chunk_len / 4, 1); starts.len()];
+    let mut chunks: Vec<Option<(Vec<T>, Vec<T>)>> = vec![None; starts.len()];
+    loop {
+        let pending: Vec<usize> = (0..starts.len()).filter(|&k| chunks[k].is_none()).collect();
+        if pending.is_empty() {
+            break;
+        }
+
+        let denoised: Vec<(Vec<T>, Vec<T>)> = thread::scope(|scope| {
+            let handles: Vec<_> = pending.iter()
+                .map(|&k| {
+                    let (start, overlap) = (starts[k], overlaps[k]);
+                    scope.spawn(move || denoise_chunk(input, lambda, start, chunk_len, overlap))
+                })
+                .collect();
+            handles.into_iter()
+                .map(|handle| handle.join().expect("Chunk thread panicked."))
+                .collect()
+        });
+
+        let mut accepted = 0;
+        for (&k, chunk) in pending.iter().zip(denoised) {
+            let covers_input = starts[k] <= overlaps[k] &&
+                               starts[k] + chunk_len + overlaps[k] >= input.len();
+            if covers_input || chunk.1.iter().all(|&deviation| deviation <= tolerance) {
+                accepted += chunk.0.len();
+                chunks[k] = Some(chunk);
+            } else {
+                overlaps[k] *= 2;
+                trace_event!(fallback = "wider_overlap", chunk = k, overlap = overlaps[k], "Denoising a chunk again.");
+            }
+        }
+        tracker.advance(accepted);
+    }
+
+    let mut output = Vec::with_capacity(input.len());
+    let mut deviation = Vec::with_capacity(input.len());
+    for (chunk_output, chunk_deviation) in chunks.into_iter().flatten() {
+        output.extend(chunk_output);
+        deviation.extend(chunk_deviation);
+    }
+    ChunkedOutput { output, deviation }
+}
+
+// Denoises the chunk starting at `start` between its lower and upper
+// bounds, returning their midpoint and half their distance.
+fn denoise_chunk<T>(input: &[T],
+                    lambda: T,
+                    start: usize,
+                    chunk_len: usize,
+                    overlap: usize)
+                    -> (Vec<T>, Vec<T>)
+    where T: TvFloat
+{
+    let end = cmp::min(start + chunk_len, input.len());
+    let window_start = start.saturating_sub(overlap);
+    let window_end = cmp::min(end + overlap, input.len());
+
+    // Only the ends that are not the ends of the whole input are
+    // pulled by the rest of the input.
+    let bound = |shift: T| {
+        let mut window = input[window_start..window_end].to_vec();
+        if window_start > 0 {
+            window[0] += shift;
+        }
+        if window_end < input.len() {
+            let last = window.len() - 1;
+            window[last] += shift;
+        }
+        condat(&window, lambda)
+    };
+    let lower = bound(-lambda);
+    let upper = bound(lambda);
+
+    let two = T::one() + T::one();
+    (start..end)
+        .map(|i| i - window_start)
+        .map(|i| ((lower[i] + upper[i]) / two, (upper[i] - lower[i]) / two))
+        .unzip()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn signal() -> Vec<f64> {
+        (0..500).map(|i| ((i / 40) % 3) as f64 * 5.0 + ((i * 7919) % 13) as f64 * 0.4).collect()
+    }
+
+    #[test]
+    fn condat_chunked_test_bound_holds() {
+        let input = signal();
+        for &lambda in &[0.0, 1.0, 10.0, 100.0] {
+            let exact = condat(&input, lambda);
+            for &(chunk_len, overlap) in &[(1, 0), (50, 0), (64, 16), (100, 200)] {
+                let chunked = condat_chunked(&input, lambda, chunk_len, overlap, false);
+                assert_eq!(chunked.output.len(), input.len());
+                for (i, &value) in exact.iter().enumerate() {
+                    assert!((chunked.output[i] - value).abs() <= chunked.deviation[i] + 0.000001);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn condat_chunked_test_single_chunk_is_exact() {
+        let input = signal();
+        let chunked = condat_chunked(&input, 5.0, input.len(), 0, false);
+        assert_eq!(chunked.output, condat(&input, 5.0));
+        assert_eq!(chunked.max_deviation(), 0.0);
+    }
+
+    #[test]
+    fn condat_chunked_test_overlap_shrinks_deviation() {
+        let input = signal();
+        let narrow = condat_chunked(&input, 5.0, 64, 4, false).max_deviation();
+        let wide = condat_chunked(&input, 5.0, 64, 128, false).max_deviation();
+        assert!(wide < narrow);
+    }
+
+    #[test]
+    fn condat_chunked_test_parallel() {
+        let input = signal();
+        assert_eq!(condat_chunked(&input, 5.0, 16, 32, true),
+                   condat_chunked(&input, 5.0, 16, 32, false));
+    }
+
+    #[test]
+    fn condat_parallel_test_within_tolerance() {
+        let input = signal();
+        for &lambda in &[0.0, 1.0, 10.0, 100.0, f64::INFINITY] {
+            let exact = condat(&input, lambda);
+            for &tolerance in &[0.0, 0.001, 0.1] {
+                let parallel = condat_parallel(&input, lambda, tolerance);
+                assert!(parallel.max_deviation() <= tolerance);
+                for (i, &value) in exact.iter().enumerate() {
+                    assert!((parallel.output[i] - value).abs() <= tolerance + 0.000001);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn condat_chunked_with_progress_test_parallel_counts_samples() {
+        let input = signal();
+        let calls = AtomicUsize::new(0);
+        let samples = AtomicUsize::new(0);
+        let chunked = condat_chunked_with_progress(&input, 5.0, 16, 32, true, |progress| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            samples.fetch_max(progress.samples, Ordering::Relaxed);
+        });
+        assert_eq!(chunked, condat_chunked(&input, 5.0, 16, 32, false));
+        assert_eq!(calls.into_inner(), input.len().div_ceil(16));
+        assert_eq!(samples.into_inner(), input.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_parallel_test_negative_tolerance() {
+        condat_parallel(&[1.0, 2.0], 1.0, -1.0);
+    }
+}
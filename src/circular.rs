@@ -0,0 +1,108 @@
+use std::f64::consts;
+
+use condat;
+use scalar::TvFloat;
+
+/// Denoises angles in radians, such as a phase time series, like
+/// [`tv1d::condat`](../tv1d/fn.condat.html), measuring the
+/// differences between neighboring values modulo `2π`.
+///
+/// A phase that wraps around from `π` to `-π` is on the circle only a
+/// small step, but to `condat` a jump of almost `2π`. The input is
+/// therefore unwrapped first, taking the shortest way around the
+/// circle between neighboring values, then denoised, and the output
+/// wrapped back into `[-π, π)`. This minimizes the total variation
+/// on the circle as long as every jump of the output is shorter than
+/// `π`, which holds for small enough `lambda` or jumps of the input.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use std::f64::consts::PI;
+/// use tv1d;
+///
+/// // A phase close to `π`, wrapping around to `-π` and back.
+/// let input = vec![PI - 0.01, -PI + 0.01, PI - 0.03, -PI + 0.03];
+///
+/// let denoised = tv1d::condat_circular(&input, 1.0);
+/// for value in denoised {
+///     assert!((value.abs() - PI).abs() < 1e-9);
+/// }
+/// ```
+pub fn condat_circular<T>(input: &[T], lambda: T) -> Vec<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    let mut unwrapped = Vec::with_capacity(input.len());
+    let mut previous = input[0];
+    unwrapped.push(previous);
+    for window in input.windows(2) {
+        previous += wrap(window[1] - window[0]);
+        unwrapped.push(previous);
+    }
+
+    condat(&unwrapped, lambda).into_iter().map(wrap).collect()
+}
+
+// Returns `angle` modulo `2π` in `[-π, π)`.
+fn wrap<T: TvFloat>(angle: T) -> T {
+    let pi = T::from_f64(consts::PI).expect("Unable to convert f64 to TvScalar.");
+    let tau = pi + pi;
+    angle - tau * ((angle + pi) / tau).floor()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn distance(a: f64, b: f64) -> f64 {
+        wrap(a - b).abs()
+    }
+
+    #[test]
+    fn condat_circular_test_matches_condat_without_wraps() {
+        let input = vec![0.1, 0.3, 0.2, 1.4, 1.5, 1.3, -0.6, -0.5];
+        for &lambda in &[0.0, 0.05, 0.5, 10.0] {
+            let output = condat_circular(&input, lambda);
+            let expected = condat(&input, lambda);
+            for (value, expected) in output.iter().zip(expected.iter()) {
+                assert!((value - expected).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn condat_circular_test_wraps_around() {
+        // A noisy phase close to `π`, then a jump to `π / 2`.
+        let input = vec![3.1, -3.1, 3.12, -3.13, 3.13, 1.6, 1.55, 1.58, 1.57];
+        let output = condat_circular(&input, 0.2);
+        for &value in &output {
+            assert!((-PI..PI).contains(&value));
+        }
+        for &value in &output[..5] {
+            assert!(distance(value, PI) < 0.1);
+        }
+        for &value in &output[5..] {
+            assert!(distance(value, PI / 2.0) < 0.1);
+        }
+        // Without unwrapping, the wraps are denoised as jumps, and
+        // averaged away from `π`.
+        assert!(distance(condat_circular(&input[..5], 1.0)[0], PI) < 0.05);
+        assert!(distance(condat(&input[..5], 1.0)[0], PI) > 1.0);
+    }
+
+    #[test]
+    fn condat_circular_test_input_outside_of_range() {
+        let input = vec![0.1 + 2.0 * PI, 0.1, 0.1 - 4.0 * PI];
+        for value in condat_circular(&input, 0.0) {
+            assert!((value - 0.1).abs() < 1e-12);
+        }
+    }
+}
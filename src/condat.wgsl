@@ -0,0 +1,110 @@
+// Condat's algorithm, as in `condat_in_place`, run by one invocation
+// per signal. The signals are concatenated in `values`, and signal `s`
+// occupies `values[offsets[s]..offsets[s + 1]]`, which is denoised in
+// place.
+//
+// WGSL cannot share the Rust loop of `Condat` in `segments.rs`, so this
+// is a port of it, step for step. Keep the two in sync; the tests of
+// `GpuDenoiser` compare their outputs.
+
+struct Params {
+    lambda: f32,
+    count: u32,
+    stride: u32,
+    padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> offsets: array<u32>;
+@group(0) @binding(2) var<storage, read_write> values: array<f32>;
+
+fn fill(start: u32, end: u32, value: f32) {
+    for (var i = start; i < end; i++) {
+        values[i] = value;
+    }
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let signal = id.y * params.stride + id.x;
+    if (signal >= params.count) {
+        return;
+    }
+    let base = offsets[signal];
+    let last = offsets[signal + 1u] - 1u;
+
+    let lambda = params.lambda;
+    let twolambda = 2.0 * lambda;
+    let minlambda = -lambda;
+
+    var current_input_index = base;
+    var segment_start = base;
+    var umin = lambda;
+    var umax = minlambda;
+    var segment_lower_bound = values[base] - lambda;
+    var segment_upper_bound = values[base] + lambda;
+    var kplus = base;
+    var kminus = base;
+
+    loop {
+        if (current_input_index == last) {
+            if (umin < 0.0) {
+                fill(segment_start, kminus + 1u, segment_lower_bound);
+                segment_start = kminus + 1u;
+                current_input_index = segment_start;
+                kminus = segment_start;
+                segment_lower_bound = values[kminus];
+                umin = lambda;
+                umax = segment_lower_bound + umin - segment_upper_bound;
+            } else if (umax > 0.0) {
+                fill(segment_start, kplus + 1u, segment_upper_bound);
+                segment_start = kplus + 1u;
+                current_input_index = segment_start;
+                kplus = segment_start;
+                segment_upper_bound = values[kplus];
+                umax = minlambda;
+                umin = segment_upper_bound + umax - segment_lower_bound;
+            } else {
+                segment_lower_bound += umin / f32(current_input_index - segment_start + 1u);
+                fill(segment_start, current_input_index + 1u, segment_lower_bound);
+                return;
+            }
+        } else {
+            umin += values[current_input_index + 1u] - segment_lower_bound;
+            umax += values[current_input_index + 1u] - segment_upper_bound;
+            if (umin < minlambda) {
+                fill(segment_start, kminus + 1u, segment_lower_bound);
+                segment_start = kminus + 1u;
+                current_input_index = segment_start;
+                kminus = segment_start;
+                kplus = segment_start;
+                segment_lower_bound = values[kplus];
+                segment_upper_bound = segment_lower_bound + twolambda;
+                umin = lambda;
+                umax = minlambda;
+            } else if (umax > lambda) {
+                fill(segment_start, kplus + 1u, segment_upper_bound);
+                segment_start = kplus + 1u;
+                current_input_index = segment_start;
+                kminus = segment_start;
+                kplus = segment_start;
+                segment_upper_bound = values[kplus];
+                segment_lower_bound = segment_upper_bound - twolambda;
+                umin = lambda;
+                umax = minlambda;
+            } else {
+                current_input_index += 1u;
+                if (umin >= lambda) {
+                    kminus = current_input_index;
+                    segment_lower_bound += (umin - lambda) / f32(kminus - segment_start + 1u);
+                    umin = lambda;
+                }
+                if (umax <= minlambda) {
+                    kplus = current_input_index;
+                    segment_upper_bound += (umax + lambda) / f32(kplus - segment_start + 1u);
+                    umax = minlambda;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// Checks of the output of the solvers against an independent solver,
+// used with the `cross-check` feature in debug builds to catch
+// regressions and numerical divergences while developing solvers.
+
+use dp::dynamic_programming_unchecked;
+use find_segments;
+use scalar::{TvFloat, TvScalar};
+use utils;
+
+/// The solver that an output is checked against.
+#[derive(Clone, Copy, Debug)]
+pub enum Reference {
+    Condat,
+    DynamicProgramming,
+}
+
+/// Asserts that `output`, the output of the solver `name` on `input`
+/// with `lambda`, agrees with the output of `reference` within the
+/// square root of the machine epsilon of `T`, relative to the largest
+/// absolute input value.
+///
+/// The reference runs on `f64`, so that it does not share the rounding
+/// errors of the checked solver on narrower types, and on the input
+/// divided by `utils::overflow_scale`, so that it does not overflow on
+/// values close to the largest finite value. It runs without being
+/// checked itself.
+pub fn check<T: TvFloat>(name: &str, reference: Reference, input: &[T], lambda: T, output: &[T]) {
+    compare(name, reference, input, lambda, output, T::epsilon());
+}
+
+/// Like `check`, for the solvers generic over `TvScalar`. Only the
+/// floating point types are checked, as the integers truncate the
+/// value of each segment.
+pub fn check_scalar<T: TvScalar>(name: &str, reference: Reference, input: &[T], lambda: T, output: &[T]) {
+    // Unlike the floating point types, the integers convert `0.5` to
+    // `0`.
+    if T::from_f64(0.5) == Some(T::zero()) {
+        return;
+    }
+    let two = T::one() + T::one();
+    let mut epsilon = T::one();
+    while T::one() + epsilon / two != T::one() {
+        epsilon = epsilon / two;
+    }
+    compare(name, reference, input, lambda, output, epsilon);
+}
+
+fn compare<T: TvScalar>(name: &str, reference: Reference, input: &[T], lambda: T, output: &[T], epsilon: T) {
+    let to_f64 = |value: T| value.to_f64().expect("Float values convert to f64.");
+    let input: Vec<f64> = input.iter().map(|&value| to_f64(value)).collect();
+    let lambda = to_f64(lambda);
+    let reference_scale = utils::overflow_scale(&input, lambda);
+    let mut scaled: Vec<f64> = input.iter().map(|&value| value / reference_scale).collect();
+    let solve = || {
+        let expected = match reference {
+            Reference::Condat => {
+                find_segments(&mut scaled, lambda / reference_scale, &mut ());
+                scaled
+            }
+            Reference::DynamicProgramming => dynamic_programming_unchecked(&scaled, lambda / reference_scale),
+        };
+        expected.into_iter().map(|value| value * reference_scale).collect::<Vec<f64>>()
+    };
+    // The reference is not part of the traced solve.
+    #[cfg(feature = "tracing")]
+    let expected = ::tracing::subscriber::with_default(::tracing::subscriber::NoSubscriber::default(), solve);
+    #[cfg(not(feature = "tracing"))]
+    let expected = solve();
+    let scale = input.iter().fold(1.0f64, |scale, value| scale.max(value.abs()));
+    let tolerance = to_f64(epsilon).sqrt() * scale;
+    for (index, (&value, &expected)) in output.iter().zip(expected.iter()).enumerate() {
+        let value = to_f64(value);
+        assert!((value - expected).abs() <= tolerance,
+                "Cross-check of {} against {:?} failed at index {} with lambda {}: {} != {}.",
+                name,
+                reference,
+                index,
+                lambda,
+                value,
+                expected);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_test_agreeing_output() {
+        let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        check("test", Reference::Condat, &input, 10.0, &[3.0; 5]);
+        check("test", Reference::DynamicProgramming, &input, 10.0, &[3.0; 5]);
+    }
+
+    #[test]
+    fn check_scalar_test_integers() {
+        // The integers are not checked, as they truncate.
+        check_scalar("test", Reference::Condat, &[1, 2, 3, 4, 6], 10, &[3; 5]);
+        check_scalar("test", Reference::Condat, &[1.0, 2.0, 3.0, 4.0, 6.0], 10.0, &[3.2; 5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn check_scalar_test_disagreeing_output() {
+        check_scalar("test", Reference::DynamicProgramming, &[1.0, 2.0, 3.0, 4.0, 6.0], 10.0, &[3.0; 5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn check_test_disagreeing_output() {
+        let input = vec![1.0f32, 2.0, 3.0, 4.0, 5.0];
+        check("test", Reference::Condat, &input, 10.0, &[3.0, 3.0, 3.0, 3.0, 3.1]);
+    }
+}
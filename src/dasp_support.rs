@@ -0,0 +1,120 @@
+use dasp::{Frame, Sample};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use condat_in_place;
+
+/// Denoises each channel of a buffer of `dasp` frames independently
+/// with [`tv1d::condat`](../tv1d/fn.condat.html), e.g. the left and
+/// right channels of a buffer of stereo `[f32; 2]` frames, and returns
+/// the denoised frames.
+///
+/// Any `dasp` sample type is a mono frame too, so a buffer of `i16` or
+/// `f32` samples can be denoised the same way. The samples are
+/// converted to `f64` with `dasp`'s conversions, i.e. to amplitudes
+/// between `-1` and `1` whatever the sample type, so `lambda` is in
+/// the same unit for every sample format, and converted back to the
+/// sample type of the frames after denoising.
+///
+/// With the `rayon` feature, the channels are denoised in parallel
+/// over rayon's thread pool.
+///
+/// # Panics
+/// Panics if `frames` is empty, or `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate tv1d;
+///
+/// // A stereo buffer, with a ramp on the left channel and a constant
+/// // on the right one.
+/// let frames: Vec<[i16; 2]> = vec![[1000, -4000], [2000, -4000], [3000, -4000],
+///                                  [4000, -4000], [5000, -4000]];
+///
+/// assert_eq!(tv1d::denoise_frames(&frames, 1.0),
+///            vec![[3000, -4000]; 5]);
+/// ```
+pub fn denoise_frames<F>(frames: &[F], lambda: f64) -> Vec<F>
+    where F: Frame + Sync,
+          F::Sample: Sync
+{
+    assert!(!frames.is_empty(),
+            "Input list should have at least one value.");
+
+    #[cfg(feature = "rayon")]
+    let offsets = (0..F::CHANNELS).into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let offsets = 0..F::CHANNELS;
+
+    let denoised: Vec<Vec<f64>> = offsets.map(|offset| {
+            let mut channel: Vec<f64> = frames.iter().map(|frame| to_f64(*frame.channel(offset).expect("Channel must be within the frame."))).collect();
+            condat_in_place(&mut channel, lambda);
+            channel
+        })
+        .collect();
+
+    (0..frames.len()).map(|i| F::from_fn(|offset| from_f64(denoised[offset][i]))).collect()
+}
+
+fn to_f64<S>(sample: S) -> f64
+    where S: Sample
+{
+    sample.to_float_sample().to_sample()
+}
+
+fn from_f64<S>(value: f64) -> S
+    where S: Sample
+{
+    <S::Float as Sample>::from_sample(value).to_sample()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    #[test]
+    fn denoise_frames_test_matches_condat() {
+        let left = vec![0.1, 0.21, 0.52, 0.82, 0.14, 0.52, 0.62, 0.11];
+        let right = vec![-0.3, 0.02, 0.41, 0.77, -0.5, 0.25, 0.12, 0.9];
+        let frames: Vec<[f64; 2]> = left.iter().zip(right.iter()).map(|(&l, &r)| [l, r]).collect();
+        let denoised = denoise_frames(&frames, 0.2);
+        let expected_left = condat(&left, 0.2);
+        let expected_right = condat(&right, 0.2);
+        for (i, frame) in denoised.iter().enumerate() {
+            assert_eq!(*frame, [expected_left[i], expected_right[i]]);
+        }
+    }
+
+    #[test]
+    fn denoise_frames_test_sample_formats() {
+        // The same signal as `f32`, `i16` and `u8` samples gives the
+        // same output for the same `lambda`, up to the resolution of the
+        // sample format.
+        let input: Vec<f32> = vec![0.0, 0.5, 0.25, -0.5, -0.5, 0.75, 0.5, 0.5];
+        let expected = denoise_frames(&input, 0.125);
+
+        let samples: Vec<i16> = input.iter().map(|&value| value.to_sample()).collect();
+        for (value, &expected) in denoise_frames(&samples, 0.125).into_iter().zip(expected.iter()) {
+            assert!((value.to_sample::<f32>() - expected).abs() <= 1.0 / 32768.0);
+        }
+
+        let samples: Vec<u8> = input.iter().map(|&value| value.to_sample()).collect();
+        for (value, &expected) in denoise_frames(&samples, 0.125).into_iter().zip(expected.iter()) {
+            assert!((value.to_sample::<f32>() - expected).abs() <= 1.0 / 128.0);
+        }
+    }
+
+    #[test]
+    fn denoise_frames_test_zero_lambda() {
+        let frames: Vec<[i32; 3]> = vec![[i32::MIN, 0, i32::MAX], [5, -7, 1 << 20], [-3, 12, 1]];
+        assert_eq!(denoise_frames(&frames, 0.0), frames);
+    }
+
+    #[test]
+    #[should_panic]
+    fn denoise_frames_test_empty() {
+        denoise_frames::<[f32; 2]>(&[], 1.0);
+    }
+}
@@ -0,0 +1,639 @@
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::str;
+
+use condat;
+use dp::dynamic_programming;
+use path::regularization_path;
+use error::TvError;
+use forecast::SegmentedModel;
+use resample::{Interpolation, UniformGrid};
+use scalar::TvFloat;
+use tautstring;
+use utils;
+
+/// A total variation denoising algorithm.
+pub trait Denoiser<T> {
+    /// Denoises the input values with `lambda`.
+    ///
+    /// # Panics
+    /// Panics if input vector's length is `0`, or `lambda` is NaN or
+    /// less than `0`.
+    fn denoise(&self, input: &[T], lambda: T) -> Vec<T>;
+}
+
+/// The algorithms of this crate, for choosing one at runtime, e.g.
+/// from a configuration file.
+///
+/// All algorithms compute the same output up to rounding, but differ
+/// in speed depending on the input. An `Algorithm` parses from and
+/// displays as its lowercase name: `condat`, `tautstring`, `path` or
+/// `dp`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::Denoiser;
+///
+/// let algorithm: tv1d::Algorithm = "tautstring".parse().unwrap();
+/// assert_eq!(algorithm, tv1d::Algorithm::Tautstring);
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(algorithm.denoise(&input, 10.0), vec![3.0, 3.0, 3.0, 3.0, 3.0]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Algorithm {
+    /// Condat's direct algorithm, see
+    /// [`tv1d::condat`](../tv1d/fn.condat.html).
+    Condat,
+    /// The taut string algorithm, see
+    /// [`tv1d::tautstring`](../tv1d/fn.tautstring.html).
+    Tautstring,
+    /// The solution at `lambda` of the regularization path, see
+    /// [`tv1d::regularization_path`](../tv1d/fn.regularization_path.html).
+    Path,
+    /// The dynamic programming algorithm, whose run time is linear in
+    /// the worst case, see
+    /// [`tv1d::dynamic_programming`](../tv1d/fn.dynamic_programming.html).
+    DynamicProgramming,
+}
+
+impl Algorithm {
+    /// Returns the lowercase name of the algorithm.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Algorithm::Condat => "condat",
+            Algorithm::Tautstring => "tautstring",
+            Algorithm::Path => "path",
+            Algorithm::DynamicProgramming => "dp",
+        }
+    }
+}
+
+impl<T> Denoiser<T> for Algorithm
+    where T: TvFloat
+{
+    fn denoise(&self, input: &[T], lambda: T) -> Vec<T> {
+        match *self {
+            Algorithm::Condat => condat(input, lambda),
+            Algorithm::Tautstring => tautstring(input, lambda),
+            Algorithm::Path => regularization_path(input).solution(lambda),
+            Algorithm::DynamicProgramming => dynamic_programming(input, lambda),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl str::FromStr for Algorithm {
+    type Err = ParseAlgorithmError;
+
+    fn from_str(name: &str) -> Result<Algorithm, ParseAlgorithmError> {
+        [Algorithm::Condat, Algorithm::Tautstring, Algorithm::Path, Algorithm::DynamicProgramming]
+            .iter()
+            .find(|algorithm| algorithm.name().eq_ignore_ascii_case(name.trim()))
+            .cloned()
+            .ok_or(ParseAlgorithmError)
+    }
+}
+
+/// The error returned when parsing an unknown
+/// [`Algorithm`](enum.Algorithm.html) name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ParseAlgorithmError;
+
+impl fmt::Display for ParseAlgorithmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "algorithm should be one of `condat`, `tautstring`, `path` or `dp`")
+    }
+}
+
+impl error::Error for ParseAlgorithmError {}
+
+/// How a [`TvDenoiser`](struct.TvDenoiser.html) treats the ends of
+/// the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Boundary {
+    /// The first and the last value each have a single neighbor, as
+    /// in [`tv1d::condat`](../tv1d/fn.condat.html).
+    Free,
+    /// The input is one period of a periodic signal, so the last value
+    /// is also a neighbor of the first, and the difference between
+    /// them is penalized like the others.
+    Periodic,
+}
+
+/// A denoiser configured once with its options and reused for many
+/// inputs.
+///
+/// The options are set builder-style on the value returned by
+/// [`new`](struct.TvDenoiser.html#method.new), then checked by
+/// [`build`](struct.TvDenoiser.html#method.build). Unset options keep
+/// their defaults: a `lambda` of `0`, which leaves the input
+/// unchanged, `Algorithm::Condat`, `Boundary::Free` and a
+/// `min_segment_len` of `1`.
+///
+/// With the `serde` feature, a deserialized denoiser is checked like
+/// one from `build`, and an invalid configuration fails to
+/// deserialize.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let denoiser = tv1d::TvDenoiser::new()
+///     .lambda(10.0)
+///     .algorithm(tv1d::Algorithm::Tautstring)
+///     .build()
+///     .unwrap();
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(denoiser.denoise(&input), vec![3.0, 3.0, 3.0, 3.0, 3.0]);
+///
+/// let invalid = tv1d::TvDenoiser::new().lambda(-1.0).build();
+/// assert_eq!(invalid.unwrap_err(), tv1d::TvError::NegativeLambda);
+///
+/// // The last value is a neighbor of the first.
+/// let denoiser = tv1d::TvDenoiser::new()
+///     .lambda(1.0)
+///     .boundary(tv1d::Boundary::Periodic)
+///     .build()
+///     .unwrap();
+///
+/// let input = vec![9.0, 1.0, 1.0, 1.0, 1.0, 5.0];
+/// assert_eq!(denoiser.denoise(&input), vec![7.0, 1.5, 1.5, 1.5, 1.5, 5.0]);
+///
+/// // Segments shorter than 2 values are merged into a neighbor.
+/// let denoiser = tv1d::TvDenoiser::new().min_segment_len(2).build().unwrap();
+///
+/// let input = vec![1.0, 1.0, 7.0, 4.0, 4.0, 4.0, 4.0];
+/// assert_eq!(denoiser.denoise(&input), vec![1.0, 1.0, 4.6, 4.6, 4.6, 4.6, 4.6]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde",
+           serde(try_from = "TvDenoiserConfig<T>",
+                 bound(deserialize = "T: TvFloat + ::serde::Deserialize<'de>")))]
+pub struct TvDenoiser<T> {
+    lambda: T,
+    algorithm: Algorithm,
+    boundary: Boundary,
+    min_segment_len: usize,
+}
+
+// The fields of a serialized `TvDenoiser`, which are only turned into
+// one through `build`.
+#[cfg(feature = "serde")]
+#[derive(::serde::Deserialize)]
+struct TvDenoiserConfig<T> {
+    lambda: T,
+    algorithm: Algorithm,
+    boundary: Boundary,
+    min_segment_len: usize,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<TvDenoiserConfig<T>> for TvDenoiser<T>
+    where T: TvFloat
+{
+    type Error = TvError;
+
+    fn try_from(config: TvDenoiserConfig<T>) -> Result<TvDenoiser<T>, TvError> {
+        TvDenoiser::new()
+            .lambda(config.lambda)
+            .algorithm(config.algorithm)
+            .boundary(config.boundary)
+            .min_segment_len(config.min_segment_len)
+            .build()
+    }
+}
+
+impl<T> TvDenoiser<T>
+    where T: TvFloat
+{
+    /// Creates a denoiser with the default options.
+    pub fn new() -> TvDenoiser<T> {
+        TvDenoiser {
+            lambda: T::zero(),
+            algorithm: Algorithm::Condat,
+            boundary: Boundary::Free,
+            min_segment_len: 1,
+        }
+    }
+
+    /// Sets the `lambda` to denoise with.
+    pub fn lambda(self, lambda: T) -> TvDenoiser<T> {
+        TvDenoiser { lambda, ..self }
+    }
+
+    /// Sets the algorithm to denoise with.
+    pub fn algorithm(self, algorithm: Algorithm) -> TvDenoiser<T> {
+        TvDenoiser { algorithm, ..self }
+    }
+
+    /// Sets how the ends of the input are treated.
+    ///
+    /// With `Boundary::Periodic`, the algorithm denoises the input
+    /// with the first and the last value shifted towards each other by
+    /// a penalty between `-lambda` and `lambda` on the difference
+    /// between them, which is found by bisection such that the two
+    /// denoised ends agree with it. This runs the algorithm about as
+    /// many times as `T` has bits of precision.
+    pub fn boundary(self, boundary: Boundary) -> TvDenoiser<T> {
+        TvDenoiser { boundary, ..self }
+    }
+
+    /// Sets the minimum number of values of each constant segment of
+    /// the output.
+    ///
+    /// After denoising, each shorter segment, from left to right, is
+    /// merged into the neighboring segment whose value is closer, and
+    /// the merged segment takes the mean of their values weighted by
+    /// their lengths, which keeps the sum of the output. The output is
+    /// then no longer the exact minimizer for `lambda`. An output
+    /// shorter than `min_segment_len` becomes a single segment.
+    pub fn min_segment_len(self, min_segment_len: usize) -> TvDenoiser<T> {
+        TvDenoiser { min_segment_len, ..self }
+    }
+
+    /// Checks the options and returns the configured denoiser.
+    ///
+    /// # Errors
+    /// Returns `TvError::NanLambda` if `lambda` is NaN,
+    /// `TvError::NegativeLambda` if `lambda` is less than `0`, and
+    /// `TvError::ZeroMinSegmentLen` if `min_segment_len` is `0`.
+    pub fn build(self) -> Result<TvDenoiser<T>, TvError> {
+        if self.lambda.is_nan() {
+            return Err(TvError::NanLambda);
+        }
+        if self.lambda < T::zero() {
+            return Err(TvError::NegativeLambda);
+        }
+        if self.min_segment_len == 0 {
+            return Err(TvError::ZeroMinSegmentLen);
+        }
+        Ok(self)
+    }
+
+    /// Returns the `lambda` the denoiser denoises with.
+    pub fn get_lambda(&self) -> T {
+        self.lambda
+    }
+
+    /// Returns the algorithm the denoiser denoises with.
+    pub fn get_algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Returns how the denoiser treats the ends of the input.
+    pub fn get_boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// Returns the minimum number of values of each segment of the
+    /// output.
+    pub fn get_min_segment_len(&self) -> usize {
+        self.min_segment_len
+    }
+
+    /// Denoises the input values with the configured options.
+    ///
+    /// # Panics
+    /// Panics if input vector's length is `0`, or `lambda` is NaN or
+    /// less than `0`, which [`build`](struct.TvDenoiser.html#method.build)
+    /// rules out.
+    pub fn denoise(&self, input: &[T]) -> Vec<T> {
+        let mut output = match self.boundary {
+            Boundary::Free => self.algorithm.denoise(input, self.lambda),
+            Boundary::Periodic => self.denoise_periodic(input),
+        };
+        if self.min_segment_len > 1 {
+            merge_short_segments(&mut output, self.min_segment_len);
+        }
+        output
+    }
+
+    // The penalty `c` on the difference between the last and the first
+    // value, between `-lambda` and `lambda`, adds to the first value and
+    // subtracts from the last, after which the ends are only tied to
+    // their other neighbor. The difference between the denoised ends
+    // grows with `c`, and is `0` at the periodic solution unless `c` is
+    // at a bound.
+    fn denoise_periodic(&self, input: &[T]) -> Vec<T> {
+        let last = input.len() - 1;
+        if last == 0 || self.lambda == T::zero() || utils::is_infinite_lambda(self.lambda) {
+            return self.algorithm.denoise(input, self.lambda);
+        }
+
+        let mut shifted = input.to_vec();
+        let mut denoise = |penalty: T| {
+            shifted[0] = input[0] + penalty;
+            shifted[last] = input[last] - penalty;
+            let output = self.algorithm.denoise(&shifted, self.lambda);
+            let difference = output[0] - output[last];
+            (output, difference)
+        };
+
+        let (mut low, mut high) = (-self.lambda, self.lambda);
+        let (output, difference) = denoise(high);
+        if difference <= T::zero() {
+            return output;
+        }
+        let (output, difference) = denoise(low);
+        if difference >= T::zero() {
+            return output;
+        }
+        let two = T::one() + T::one();
+        let mut output = output;
+        while high - low > T::epsilon() * self.lambda {
+            let middle = (low + high) / two;
+            let (middle_output, difference) = denoise(middle);
+            output = middle_output;
+            if difference == T::zero() {
+                break;
+            } else if difference < T::zero() {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        output
+    }
+
+    /// Denoises unevenly spaced `(timestamp, value)` pairs by
+    /// resampling them onto `grid`, denoising the resampled values with
+    /// the configured options, and reading the denoised values off at
+    /// the timestamps of the input, both according to `interpolation`.
+    ///
+    /// Both directions repeat the end values outside the known
+    /// samples, as described for
+    /// [`UniformGrid`](struct.UniformGrid.html).
+    ///
+    /// # Panics
+    /// Panics if input vector's length is `0`, its timestamps are not
+    /// strictly increasing, or the grid is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tv1d;
+    /// use tv1d::{Interpolation, UniformGrid};
+    ///
+    /// let input = vec![(0.0, 1.0), (0.9, 2.0), (2.1, 5.0), (3.0, 6.0)];
+    /// let grid = UniformGrid::covering(&[0.0, 3.0], 1.0);
+    ///
+    /// // Resampled to `[1.0, 2.0, 5.0, 6.0]` at `[0.0, 1.0, 2.0, 3.0]`.
+    /// let denoiser = tv1d::TvDenoiser::new().lambda(1.0).build().unwrap();
+    /// let denoised = denoiser.denoise_resampled(&input, &grid, Interpolation::Nearest);
+    /// assert_eq!(denoised, vec![2.0, 2.0, 5.0, 5.0]);
+    /// ```
+    pub fn denoise_resampled(&self, input: &[(T, T)], grid: &UniformGrid<T>, interpolation: Interpolation) -> Vec<T> {
+        let denoised = self.denoise(&grid.resample(input, interpolation));
+        let timestamps: Vec<T> = input.iter().map(|&(timestamp, _)| timestamp).collect();
+        grid.sample(&denoised, &timestamps, interpolation)
+    }
+
+    /// Denoises the input values with the configured options and
+    /// returns the [`SegmentedModel`](struct.SegmentedModel.html) of
+    /// the output, for forecasting.
+    ///
+    /// # Panics
+    /// Panics if input vector's length is `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tv1d;
+    /// use tv1d::Extrapolation;
+    ///
+    /// let denoiser = tv1d::TvDenoiser::new().lambda(3.0).build().unwrap();
+    /// let model = denoiser.fit(&[1.0, 1.0, 1.0, 9.0, 9.0, 9.0]);
+    /// assert_eq!(model.breakpoints(), &[3]);
+    /// assert_eq!(model.predict(2, Extrapolation::Level), vec![8.0, 8.0]);
+    /// ```
+    pub fn fit(&self, input: &[T]) -> SegmentedModel<T> {
+        SegmentedModel::new(input, self.denoise(input))
+    }
+}
+
+// Merges each segment of the output shorter than `min_len`, from left
+// to right, into the neighboring segment whose value is closer, with
+// the mean of their values weighted by their lengths.
+fn merge_short_segments<T>(output: &mut [T], min_len: usize)
+    where T: TvFloat
+{
+    let mut segments: Vec<(usize, T)> = Vec::new();
+    let mut start = 0;
+    while start < output.len() {
+        let len = output[start..].iter().position(|value| *value != output[start]).unwrap_or(output.len() - start);
+        segments.push((len, output[start]));
+        start += len;
+    }
+
+    let merge = |(len, value): (usize, T), (other_len, other_value): (usize, T)| {
+        let weight = |len| T::from_usize(len).expect("Unable to convert usize to TvScalar.");
+        (len + other_len, (value * weight(len) + other_value * weight(other_len)) / weight(len + other_len))
+    };
+    // `merged` holds the segments of at least `min_len` values, and
+    // `pending` a short segment being merged into the next one.
+    let mut merged: Vec<(usize, T)> = Vec::with_capacity(segments.len());
+    let mut pending = None;
+    for i in 0..segments.len() {
+        let segment = match pending.take() {
+            Some(short) => merge(short, segments[i]),
+            None => segments[i],
+        };
+        if segment.0 >= min_len {
+            merged.push(segment);
+            continue;
+        }
+        let closer_to_previous = match (merged.last(), segments.get(i + 1)) {
+            (Some(previous), Some(next)) => (previous.1 - segment.1).abs() <= (next.1 - segment.1).abs(),
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if closer_to_previous {
+            let previous = merged.last_mut().expect("A previous segment is closer.");
+            *previous = merge(*previous, segment);
+        } else if i + 1 < segments.len() {
+            pending = Some(segment);
+        } else {
+            merged.push(segment);
+        }
+    }
+
+    let mut start = 0;
+    for (len, value) in merged {
+        output[start..(start + len)].fill(value);
+        start += len;
+    }
+}
+
+impl<T> Default for TvDenoiser<T>
+    where T: TvFloat
+{
+    fn default() -> TvDenoiser<T> {
+        TvDenoiser::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graph::denoise_graph;
+
+    #[test]
+    fn algorithm_test_agree() {
+        let input: Vec<f64> = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        for &lambda in &[0.0, 1.0, 50.0, 1000.0] {
+            let output_expected = condat(&input, lambda);
+            for algorithm in &[Algorithm::Tautstring, Algorithm::Path, Algorithm::DynamicProgramming] {
+                let output = algorithm.denoise(&input, lambda);
+                for i in 0..input.len() {
+                    assert!((output[i] - output_expected[i]).abs() <= 0.000001);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn algorithm_test_parse() {
+        for algorithm in &[Algorithm::Condat, Algorithm::Tautstring, Algorithm::Path, Algorithm::DynamicProgramming] {
+            assert_eq!(algorithm.to_string().parse(), Ok(*algorithm));
+        }
+        assert_eq!(" Condat\n".parse(), Ok(Algorithm::Condat));
+        assert_eq!("fista".parse::<Algorithm>(), Err(ParseAlgorithmError));
+    }
+
+    #[test]
+    #[should_panic]
+    fn algorithm_test_path_empty_input() {
+        let input: Vec<f64> = vec![];
+        Algorithm::Path.denoise(&input, 1.0);
+    }
+
+    #[test]
+    fn tv_denoiser_test_matches_algorithm() {
+        let input: Vec<f64> = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        for algorithm in &[Algorithm::Condat, Algorithm::Tautstring, Algorithm::Path] {
+            let denoiser = TvDenoiser::new().lambda(3.0).algorithm(*algorithm).build().unwrap();
+            assert_eq!(denoiser.get_lambda(), 3.0);
+            assert_eq!(denoiser.get_algorithm(), *algorithm);
+            assert_eq!(denoiser.denoise(&input), algorithm.denoise(&input, 3.0));
+        }
+    }
+
+    #[test]
+    fn tv_denoiser_test_defaults() {
+        let input: Vec<f64> = vec![1.0, 2.1, 5.2, 8.2];
+        let denoiser = TvDenoiser::default().build().unwrap();
+        assert_eq!(denoiser.get_algorithm(), Algorithm::Condat);
+        assert_eq!(denoiser.denoise(&input), input);
+    }
+
+    #[test]
+    fn tv_denoiser_test_denoise_resampled() {
+        // Evenly spaced input on the grid is denoised as it is.
+        let values = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let input: Vec<(f64, f64)> = values.iter().enumerate().map(|(i, &value)| (i as f64 * 2.0, value)).collect();
+        let grid = UniformGrid::covering(&[0.0, 14.0], 2.0);
+        let denoiser = TvDenoiser::new().lambda(1.5).build().unwrap();
+        for &interpolation in &[Interpolation::Nearest, Interpolation::Linear] {
+            assert_eq!(denoiser.denoise_resampled(&input, &grid, interpolation), denoiser.denoise(&values));
+        }
+    }
+
+    #[test]
+    fn tv_denoiser_test_invalid_lambda() {
+        assert_eq!(TvDenoiser::new().lambda(-1.0).build(), Err(TvError::NegativeLambda));
+        assert_eq!(TvDenoiser::new().lambda(f64::NAN).build().unwrap_err(), TvError::NanLambda);
+        assert_eq!(TvDenoiser::new().lambda(1.0).min_segment_len(0).build(), Err(TvError::ZeroMinSegmentLen));
+    }
+
+    #[test]
+    fn tv_denoiser_test_periodic_boundary() {
+        let input: Vec<f64> = vec![9.0, 1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let edges: Vec<(usize, usize)> = (0..input.len()).map(|i| (i, (i + 1) % input.len())).collect();
+        for &lambda in &[0.0, 0.5, 2.0, 5.0, 100.0] {
+            let expected = denoise_graph(&input, &edges, lambda, 1e-12, 100_000).output;
+            for algorithm in &[Algorithm::Condat, Algorithm::Tautstring, Algorithm::DynamicProgramming] {
+                let denoiser = TvDenoiser::new()
+                    .lambda(lambda)
+                    .algorithm(*algorithm)
+                    .boundary(Boundary::Periodic)
+                    .build()
+                    .unwrap();
+                assert_eq!(denoiser.get_boundary(), Boundary::Periodic);
+                let output = denoiser.denoise(&input);
+                for i in 0..input.len() {
+                    assert!((output[i] - expected[i]).abs() <= 1e-6);
+                }
+            }
+        }
+        let denoiser = TvDenoiser::new().lambda(1.0).boundary(Boundary::Periodic).build().unwrap();
+        assert_eq!(denoiser.denoise(&[4.0]), vec![4.0]);
+    }
+
+    #[test]
+    fn tv_denoiser_test_min_segment_len() {
+        let input: Vec<f64> = vec![1.0, 1.0, 1.0, 8.0, 3.0, 3.0, 3.0, 3.0, 0.0];
+        let denoiser = TvDenoiser::new().min_segment_len(3).build().unwrap();
+        assert_eq!(denoiser.get_min_segment_len(), 3);
+        // The `8.0` is closer to the `3.0`s than to the `1.0`s, and the
+        // last `0.0` has no next segment.
+        let merged = 20.0 / 6.0;
+        assert_eq!(denoiser.denoise(&input), vec![1.0, 1.0, 1.0, merged, merged, merged, merged, merged, merged]);
+
+        // Short segments at the start are merged into the next ones.
+        let denoiser = TvDenoiser::new().min_segment_len(4).build().unwrap();
+        assert_eq!(denoiser.denoise(&[0.0, 2.0, 4.0, 6.0, 6.0, 6.0, 6.0]), vec![30.0 / 7.0; 7]);
+        assert_eq!(denoiser.denoise(&[1.0, 2.0, 3.0]), vec![2.0; 3]);
+
+        let input: Vec<f64> = (0..200).map(|i| ((i * 7919) % 13) as f64).collect();
+        for &min_segment_len in &[1, 2, 5, 17] {
+            let denoiser = TvDenoiser::new().lambda(2.0).min_segment_len(min_segment_len).build().unwrap();
+            let output = denoiser.denoise(&input);
+            let mut start = 0;
+            while start < output.len() {
+                let len = output[start..].iter().position(|&value| value != output[start]).unwrap_or(output.len() - start);
+                assert!(len >= min_segment_len);
+                start += len;
+            }
+            let sum: f64 = output.iter().sum();
+            assert!((sum - input.iter().sum::<f64>()).abs() < 1e-9);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn tv_denoiser_test_serde_round_trip() {
+        use serde_json;
+
+        let denoiser = TvDenoiser::new().lambda(2.5).algorithm(Algorithm::Tautstring).build().unwrap();
+        let json = serde_json::to_string(&denoiser).unwrap();
+        assert_eq!(json, r#"{"lambda":2.5,"algorithm":"Tautstring","boundary":"Free","min_segment_len":1}"#);
+        assert_eq!(serde_json::from_str::<TvDenoiser<f64>>(&json).unwrap(), denoiser);
+
+        let json = r#"{"lambda":2.5,"algorithm":"Condat","boundary":"Free","min_segment_len":0}"#;
+        let error = serde_json::from_str::<TvDenoiser<f64>>(json).unwrap_err();
+        assert_eq!(error.to_string(), TvError::ZeroMinSegmentLen.to_string());
+        let json = r#"{"lambda":-1.0,"algorithm":"Condat","boundary":"Free","min_segment_len":1}"#;
+        assert!(serde_json::from_str::<TvDenoiser<f64>>(json).is_err());
+
+        let error = TvError::LengthMismatch { input: 4, output: 3 };
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(json, r#"{"LengthMismatch":{"input":4,"output":3}}"#);
+        assert_eq!(serde_json::from_str::<TvError>(&json).unwrap(), error);
+    }
+}
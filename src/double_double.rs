@@ -0,0 +1,254 @@
+use std::ops;
+
+use hull::{self, HullSum};
+use utils;
+
+/// An extended precision number represented as the unevaluated sum of
+/// two `f64`s, a double-double, with about 106 bits of significand.
+///
+/// The low part is at most half an ulp of the high part, so the
+/// values are ordered by their high part first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+impl DoubleDouble {
+    /// Returns the double-double equal to `value`.
+    pub fn from_f64(value: f64) -> DoubleDouble {
+        DoubleDouble { hi: value, lo: 0.0 }
+    }
+
+    /// Returns the `f64` closest to the double-double.
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    /// Returns the high and the low part of the double-double.
+    pub fn parts(self) -> (f64, f64) {
+        (self.hi, self.lo)
+    }
+
+    // Returns the double-double of `hi + lo` where `|hi| >= |lo|`.
+    fn normalized(hi: f64, lo: f64) -> DoubleDouble {
+        let sum = hi + lo;
+        DoubleDouble {
+            hi: sum,
+            lo: lo - (sum - hi),
+        }
+    }
+
+    // Returns the quotient by `divisor`, refined once with the exact
+    // remainder of the first quotient.
+    fn div_f64(self, divisor: f64) -> DoubleDouble {
+        let quotient = self.hi / divisor;
+        let (product, product_error) = two_product(quotient, divisor);
+        let remainder = self - DoubleDouble::normalized(product, product_error);
+        DoubleDouble::normalized(quotient, remainder.hi / divisor)
+    }
+}
+
+// Returns the rounded product of `a` and `b` together with its rounding
+// error, i.e. Dekker's TwoProduct, so that the product plus the error is
+// exactly `a * b` unless it underflows. It avoids a fused multiply-add,
+// which the `deterministic` feature rules out.
+//
+// The halves of a factor above `2^996` can overflow, so such a factor
+// is scaled down by `2^28` first, along with the product if it is that
+// large too, and otherwise the other factor is scaled up to keep the
+// product, all of which is exact. An infinite or NaN product has no
+// error to speak of, and its negation is returned like a fused
+// multiply-add would.
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let product = a * b;
+    if !product.is_finite() {
+        return (product, -product);
+    }
+    if product.abs() > SPLIT_THRESHOLD {
+        let (product, error) = if a.abs() >= b.abs() {
+            two_product(a / SPLIT_SCALE, b)
+        } else {
+            two_product(a, b / SPLIT_SCALE)
+        };
+        return (product * SPLIT_SCALE, error * SPLIT_SCALE);
+    }
+    if a.abs() > SPLIT_THRESHOLD {
+        return two_product(a / SPLIT_SCALE, b * SPLIT_SCALE);
+    }
+    if b.abs() > SPLIT_THRESHOLD {
+        return two_product(a * SPLIT_SCALE, b / SPLIT_SCALE);
+    }
+    let (a_hi, a_lo) = split(a);
+    let (b_hi, b_lo) = split(b);
+    let error = ((a_hi * b_hi - product) + a_hi * b_lo + a_lo * b_hi) + a_lo * b_lo;
+    (product, error)
+}
+
+// `2^996`, above which multiplying by `SPLITTER` can overflow, and
+// `2^28`, by which such values are scaled down.
+const SPLIT_THRESHOLD: f64 = 6.696928794914171e299;
+const SPLIT_SCALE: f64 = 268_435_456.0;
+// `2^27 + 1`.
+const SPLITTER: f64 = 134_217_729.0;
+
+// Splits `value`, at most `2^996` in magnitude, into two halves of at
+// most 26 significant bits whose sum is exactly `value`, i.e.
+// Veltkamp's split.
+fn split(value: f64) -> (f64, f64) {
+    let scaled = SPLITTER * value;
+    let hi = scaled - (scaled - value);
+    (hi, value - hi)
+}
+
+impl ops::Add for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn add(self, other: DoubleDouble) -> DoubleDouble {
+        let (sum, error) = utils::two_sum(self.hi, other.hi);
+        DoubleDouble::normalized(sum, error + self.lo + other.lo)
+    }
+}
+
+impl ops::Sub for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn sub(self, other: DoubleDouble) -> DoubleDouble {
+        self + -other
+    }
+}
+
+impl ops::Neg for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn neg(self) -> DoubleDouble {
+        DoubleDouble {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl HullSum for DoubleDouble {
+    type Slope = DoubleDouble;
+
+    fn from_value(value: f64) -> DoubleDouble {
+        DoubleDouble::from_f64(value)
+    }
+
+    fn value(self) -> DoubleDouble {
+        self
+    }
+
+    fn slope(self, len: usize) -> DoubleDouble {
+        self.div_f64(len as f64)
+    }
+}
+
+/// Denoises `f64` input values with the taut string algorithm of
+/// [`tv1d::tautstring`](../tv1d/fn.tautstring.html), accumulating the
+/// sums and the slopes of the taut string in
+/// [`DoubleDouble`](struct.DoubleDouble.html).
+///
+/// The sums since the last knot of `tv1d::tautstring` are exact to
+/// about the precision of `f64` relative to their magnitude, which
+/// loses the small variations of inputs with an extreme dynamic
+/// range, such as faint sources next to bright ones in astronomical
+/// photometry. Double-double sums keep them until the output is
+/// rounded back to `f64`, at a few times the cost.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1e20, 1.0, -1e20, 1.0];
+/// let output = tv1d::tautstring_double_double(&input, 1e21);
+/// assert_eq!(output, vec![0.5; 4]);
+/// ```
+pub fn tautstring_double_double(input: &[f64], lambda: f64) -> Vec<f64> {
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(!utils::is_nan_lambda(&lambda),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= 0.0,
+            "Lambda must be greater than or equal to 0.");
+
+    if utils::is_infinite_lambda(lambda) {
+        let sum = input.iter().fold(DoubleDouble::default(), |sum, &value| sum + DoubleDouble::from_f64(value));
+        return vec![sum.div_f64(input.len() as f64).to_f64(); input.len()];
+    }
+
+    let mut knots = Vec::new();
+    let samples = input.iter().map(|&value| Ok(DoubleDouble::from_f64(value)));
+    let Ok(()) = hull::find_knots(samples, DoubleDouble::from_f64(lambda), &mut knots);
+
+    let mut output = vec![0.0; input.len()];
+    let mut start = 0;
+    for (index, value) in knots {
+        utils::fill(&mut output[start..index], value.to_f64());
+        start = index;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tautstring;
+
+    #[test]
+    fn double_double_test_keeps_small_values() {
+        let sum = DoubleDouble::from_f64(1e20) + DoubleDouble::from_f64(1.0);
+        assert_eq!(sum.parts(), (1e20, 1.0));
+        assert_eq!((sum - DoubleDouble::from_f64(1e20)).to_f64(), 1.0);
+        assert_eq!(DoubleDouble::from_f64(1.0).div_f64(3.0).to_f64(), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn two_product_test_matches_fused_multiply_add() {
+        let values = [1.0 / 3.0, -0.1, 7.0, 1e-300, 3.0e150, -1.7e300, f64::MAX];
+        for &a in &values {
+            for &b in &values {
+                let (product, error) = two_product(a, b);
+                assert_eq!(product, a * b);
+                if product.is_finite() && product.abs() > 1e-280 {
+                    assert_eq!(error, a.mul_add(b, -product), "{} * {}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tautstring_double_double_test_matches_tautstring() {
+        let input = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        for &lambda in &[0.0, 1.0, 50.0, 500.0, f64::INFINITY] {
+            let output = tautstring_double_double(&input, lambda);
+            let output_expected = tautstring(&input, lambda);
+            for (value, expected) in output.iter().zip(output_expected.iter()) {
+                assert!((value - expected).abs() <= 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn tautstring_double_double_test_extreme_dynamic_range() {
+        let input = vec![1e20, 3.0, -1e20, 1.0, 2.0, 6.0];
+        let output = tautstring_double_double(&input, 1e21);
+        assert!(output.iter().all(|&value| value == 2.0));
+        let output = tautstring_double_double(&input, f64::INFINITY);
+        assert!(output.iter().all(|&value| value == 2.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn tautstring_double_double_test_empty_input() {
+        tautstring_double_double(&[], 1.0);
+    }
+}
@@ -0,0 +1,333 @@
+#[cfg(all(feature = "cross-check", debug_assertions))]
+use cross_check;
+use scalar::TvFloat;
+use utils;
+
+/// Denoises the input values with the dynamic programming algorithm
+/// by Johnson N. in 2013 in the paper ["A Dynamic Programming
+/// Algorithm for the Fused Lasso and L0-Segmentation"](https://doi.org/10.1080/10618600.2012.681238).
+///
+/// Unlike [`tv1d::condat`](../tv1d/fn.condat.html), whose run time
+/// can grow quadratically on adversarial inputs, this algorithm's run
+/// time is linear in the length of the input in the worst case, which
+/// makes it the choice when latency bounds matter more than the
+/// average speed. It passes forward over the input, keeping the
+/// derivative of the cost of each value of the last sample as a
+/// piecewise linear function, whose knots are added and removed at the
+/// ends only, and then backward to read off the output.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(tv1d::dynamic_programming(&input, 10.0), vec![3.0, 3.0, 3.0, 3.0, 3.0]);
+///
+/// let input = vec![100.0, 200.0, 300.0, 400.0, 500.0];
+/// assert_eq!(tv1d::dynamic_programming(&input, 10.0), vec![110.0, 200.0, 300.0, 400.0, 490.0]);
+/// ```
+pub fn dynamic_programming<T>(input: &[T], lambda: T) -> Vec<T>
+    where T: TvFloat
+{
+    let output = dynamic_programming_unchecked(input, lambda);
+
+    #[cfg(all(feature = "cross-check", debug_assertions))]
+    cross_check::check("dynamic_programming", cross_check::Reference::Condat, input, lambda, &output);
+
+    output
+}
+
+// Denoises the input like `dynamic_programming`, without the
+// cross-check, which also runs this as its reference.
+pub(crate) fn dynamic_programming_unchecked<T>(input: &[T], lambda: T) -> Vec<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(!utils::is_nan_lambda(&lambda),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    trace_span!("dynamic_programming", len = input.len(), lambda = lambda.to_f64().unwrap_or(f64::NAN));
+
+    let output = if utils::is_infinite_lambda(lambda) {
+        trace_event!(fallback = "infinite_lambda", "Returning the mean.");
+        vec![utils::mean(input); input.len()]
+    } else if input.len() == 1 || lambda == T::zero() {
+        trace_event!(fallback = "zero_lambda_or_single_value", "Returning the input.");
+        input.to_vec()
+    } else {
+        solve(input, |_| lambda)
+    };
+    trace_event!(segments = utils::count_segments(&output), "Denoised.");
+    output
+}
+
+/// Denoises the input values with the same algorithm as
+/// [`tv1d::dynamic_programming`](../tv1d/fn.dynamic_programming.html),
+/// penalizing the difference between the values `i` and `i + 1` by
+/// `lambdas[i]` instead of a single `lambda`.
+///
+/// Larger `lambdas` tie neighboring values more tightly together, and
+/// a `lambda` of `0` leaves them free to jump. With every `lambdas[i]`
+/// equal to `lambda`, the output is that of `dynamic_programming`.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `lambdas` is not one
+/// shorter than the input, or any of `lambdas` is NaN, infinite or
+/// less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // The jump between the second and the third value is free.
+/// let input = vec![1.0, 2.0, 5.0, 6.0];
+/// let denoised = tv1d::dynamic_programming_weighted(&input, &[10.0, 0.0, 10.0]);
+/// assert_eq!(denoised, vec![1.5, 1.5, 5.5, 5.5]);
+/// ```
+pub fn dynamic_programming_weighted<T>(input: &[T], lambdas: &[T]) -> Vec<T>
+    where T: TvFloat
+{
+    assert!(lambdas.len() + 1 == input.len() || input.is_empty(),
+            "Lambdas must be one shorter than the input.");
+
+    dynamic_programming_indexed(input, |i| lambdas[i])
+}
+
+/// Denoises the input values with the same algorithm as
+/// [`tv1d::dynamic_programming`](../tv1d/fn.dynamic_programming.html),
+/// penalizing the difference between the values `i` and `i + 1` by
+/// `lambda(i)`.
+///
+/// Unlike
+/// [`tv1d::dynamic_programming_weighted`](../tv1d/fn.dynamic_programming_weighted.html),
+/// no vector of penalties is needed, e.g. to relax the penalty near
+/// known event times. `lambda` is called once for each difference, in
+/// increasing order of `i`.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` returns NaN,
+/// infinity or a value less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // Allow a jump after the event at index `2`.
+/// let input = vec![1.0, 2.0, 3.0, 7.0, 8.0, 9.0];
+/// let denoised = tv1d::dynamic_programming_indexed(&input, |i| if i == 2 { 0.0 } else { 10.0 });
+/// assert_eq!(denoised, vec![2.0, 2.0, 2.0, 8.0, 8.0, 8.0]);
+/// ```
+pub fn dynamic_programming_indexed<T, L>(input: &[T], lambda: L) -> Vec<T>
+    where T: TvFloat,
+          L: Fn(usize) -> T
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    trace_span!("dynamic_programming_indexed", len = input.len());
+
+    let output = if input.len() == 1 {
+        trace_event!(fallback = "single_value", "Returning the input.");
+        input.to_vec()
+    } else {
+        solve(input, |i| {
+            let lambda = lambda(i);
+            assert!(!lambda.is_nan(),
+                    "Lambda must not be NaN.");
+
+            assert!(lambda.is_finite(),
+                    "Lambda must be finite.");
+
+            assert!(lambda >= T::zero(),
+                    "Lambda must be greater than or equal to 0.");
+            lambda
+        })
+    };
+    trace_event!(segments = utils::count_segments(&output), "Denoised.");
+    output
+}
+
+// Solves for the penalty `lambdas(i)` of the difference between the
+// values `i` and `i + 1`, which must be finite, for at least two
+// values.
+fn solve<T, L>(input: &[T], lambdas: L) -> Vec<T>
+    where T: TvFloat,
+          L: Fn(usize) -> T
+{
+    let width = input.len();
+
+    // The knots `x` of the derivative, with the change `a` of its
+    // slope and `b` of its intercept at each knot, grow from the
+    // middle of the arrays towards both ends, between `l` and `r`.
+    let mut x = vec![T::zero(); 2 * width];
+    let mut a = vec![T::zero(); 2 * width];
+    let mut b = vec![T::zero(); 2 * width];
+
+    // `minus` and `plus` store, for each sample, the values of the
+    // next sample below and above which it stops following it.
+    let mut minus = vec![T::zero(); width - 1];
+    let mut plus = vec![T::zero(); width - 1];
+
+    let lambda = lambdas(0);
+    minus[0] = input[0] - lambda;
+    plus[0] = input[0] + lambda;
+    let mut l = width - 1;
+    let mut r = width;
+    x[l] = minus[0];
+    x[r] = plus[0];
+    a[l] = T::one();
+    b[l] = lambda - input[0];
+    a[r] = -T::one();
+    b[r] = input[0] + lambda;
+    let (mut a_first, mut b_first) = (T::one(), -lambda - input[1]);
+    let (mut a_last, mut b_last) = (-T::one(), input[1] - lambda);
+
+    for k in 1..(width - 1) {
+        let lambda = lambdas(k);
+
+        // Step up from the lowest knot until the derivative is
+        // greater than `-lambda`.
+        let (mut a_lo, mut b_lo) = (a_first, b_first);
+        let mut lo = l;
+        while lo <= r && a_lo * x[lo] + b_lo <= -lambda {
+            a_lo += a[lo];
+            b_lo += b[lo];
+            lo += 1;
+        }
+
+        // Step down from the highest knot until the derivative is
+        // less than `lambda`.
+        let (mut a_hi, mut b_hi) = (a_last, b_last);
+        let mut hi = r;
+        while hi >= lo && -a_hi * x[hi] - b_hi >= lambda {
+            a_hi += a[hi];
+            b_hi += b[hi];
+            hi -= 1;
+        }
+
+        // The knots below `lo` and above `hi` are replaced by the
+        // knots where the derivative reaches `-lambda` and `lambda`.
+        minus[k] = (-lambda - b_lo) / a_lo;
+        l = lo - 1;
+        x[l] = minus[k];
+        plus[k] = (lambda + b_hi) / -a_hi;
+        r = hi + 1;
+        x[r] = plus[k];
+
+        a[l] = a_lo;
+        b[l] = b_lo + lambda;
+        a[r] = a_hi;
+        b[r] = b_hi + lambda;
+        a_first = T::one();
+        b_first = -lambda - input[k + 1];
+        a_last = -T::one();
+        b_last = input[k + 1] - lambda;
+    }
+
+    // The last sample is where the derivative is zero.
+    let (mut a_lo, mut b_lo) = (a_first, b_first);
+    let mut lo = l;
+    while lo <= r && a_lo * x[lo] + b_lo <= T::zero() {
+        a_lo += a[lo];
+        b_lo += b[lo];
+        lo += 1;
+    }
+
+    let mut output = vec![T::zero(); width];
+    output[width - 1] = -b_lo / a_lo;
+    for k in (0..(width - 1)).rev() {
+        output[k] = output[k + 1].max(minus[k]).min(plus[k]);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    #[test]
+    fn dynamic_programming_test_matches_condat() {
+        let inputs = vec![vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1],
+                          vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1],
+                          (0..2000).map(|i| ((i / 70) % 4) as f64 * 3.0 + ((i * 7919) % 17) as f64 * 0.1).collect()];
+        for input in &inputs {
+            for &lambda in &[0.0, 0.5, 2.0, 50.0, 1e6, f64::INFINITY] {
+                let output = dynamic_programming(input, lambda);
+                let output_expected = condat(input, lambda);
+                for (value, expected) in output.iter().zip(output_expected.iter()) {
+                    assert!((value - expected).abs() <= 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dynamic_programming_weighted_test_matches_unweighted() {
+        let input: Vec<f64> = (0..500).map(|i| ((i / 40) % 3) as f64 * 2.0 + ((i * 7919) % 13) as f64 * 0.1).collect();
+        for &lambda in &[0.0, 0.3, 4.0, 1e6] {
+            let output = dynamic_programming_weighted(&input, &vec![lambda; input.len() - 1]);
+            let output_expected = dynamic_programming(&input, lambda);
+            for (value, expected) in output.iter().zip(output_expected.iter()) {
+                assert!((value - expected).abs() <= 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn dynamic_programming_weighted_test_free_difference() {
+        // A difference with a `lambda` of `0` splits the input into
+        // two independent halves.
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let lambdas = vec![1.0, 1.0, 1.0, 0.0, 2.0, 2.0, 2.0];
+        let mut output_expected = dynamic_programming(&input[..4], 1.0);
+        output_expected.extend(dynamic_programming(&input[4..], 2.0));
+        let output = dynamic_programming_weighted(&input, &lambdas);
+        for (value, expected) in output.iter().zip(output_expected.iter()) {
+            assert!((value - expected).abs() <= 1e-9);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn dynamic_programming_weighted_test_wrong_length() {
+        dynamic_programming_weighted(&[1.0, 2.0, 3.0], &[1.0]);
+    }
+
+    #[test]
+    fn dynamic_programming_indexed_test_matches_weighted() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let lambdas: Vec<f64> = (0..7).map(|i| i as f64 * 0.5).collect();
+        assert_eq!(dynamic_programming_indexed(&input, |i| i as f64 * 0.5),
+                   dynamic_programming_weighted(&input, &lambdas));
+    }
+
+    #[test]
+    #[should_panic]
+    fn dynamic_programming_indexed_test_negative_lambda() {
+        dynamic_programming_indexed(&[1.0, 2.0, 3.0], |i| 1.0 - i as f64 * 2.0);
+    }
+
+    #[test]
+    fn dynamic_programming_test_single_value() {
+        assert_eq!(dynamic_programming(&[4.0], 1.0), vec![4.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn dynamic_programming_test_empty_input() {
+        dynamic_programming::<f64>(&[], 1.0);
+    }
+}
@@ -0,0 +1,182 @@
+use std::ops::Range;
+
+use condat;
+use missing::condat_masked;
+use scalar::TvFloat;
+
+/// How [`tv1d::condat_dropouts`](../tv1d/fn.condat_dropouts.html)
+/// treats the dropouts it detects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum DropoutHandling {
+    /// Leaves the dropouts out of the fidelity term, as missing
+    /// samples of
+    /// [`tv1d::condat_masked`](../tv1d/fn.condat_masked.html).
+    Mask,
+    /// Denoises the input as is, and only reports the dropouts.
+    Flag,
+}
+
+/// The output of
+/// [`tv1d::condat_dropouts`](../tv1d/fn.condat_dropouts.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct DropoutOutput<T> {
+    /// The denoised values.
+    pub output: Vec<T>,
+    /// The index ranges of the detected dropouts, in increasing order.
+    pub dropouts: Vec<Range<usize>>,
+}
+
+/// Detects sensor dropouts, where a stuck sensor repeats the same
+/// value exactly.
+///
+/// A run of at least `min_run` consecutive equal values is a dropout.
+/// The first value of the run is the last genuine reading, so the
+/// returned range starts at the second one. Noise makes exact repeats
+/// unlikely in a working sensor, but a noiseless or coarsely
+/// quantized signal has genuine plateaus too, which `min_run` should
+/// be large enough to tell apart. NaN values are never equal, and
+/// never part of a dropout.
+///
+/// # Panics
+/// Panics if `min_run` is less than `2`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 1.2, 3.0, 3.0, 3.0, 3.0, 0.9, 1.1, 1.1];
+/// assert_eq!(tv1d::detect_dropouts(&input, 3), vec![3..6]);
+/// assert_eq!(tv1d::detect_dropouts(&input, 2), vec![3..6, 8..9]);
+/// ```
+pub fn detect_dropouts<T>(input: &[T], min_run: usize) -> Vec<Range<usize>>
+    where T: PartialEq
+{
+    assert!(min_run >= 2,
+            "Minimum run must be at least 2.");
+
+    let mut dropouts = Vec::new();
+    let mut start = 0;
+    for end in 1..input.len() + 1 {
+        if end == input.len() || input[end] != input[start] {
+            if end - start >= min_run {
+                dropouts.push(start + 1..end);
+            }
+            start = end;
+        }
+    }
+    dropouts
+}
+
+/// Denoises the input values with
+/// [`tv1d::condat`](../tv1d/fn.condat.html), detecting dropouts with
+/// [`tv1d::detect_dropouts`](../tv1d/fn.detect_dropouts.html).
+///
+/// Without detection, a dropout looks to `condat` like a genuine
+/// plateau, and pulls the output towards the stuck value. With
+/// `DropoutHandling::Mask`, the dropouts are treated as missing
+/// samples, and the output is bridged linearly over them from the
+/// denoised values on either side. With `DropoutHandling::Flag`, the
+/// output is that of `condat`, and the dropouts are only reported.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `min_run` is less than
+/// `2`, or `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // The sensor gets stuck at `2.0` while the signal rises to `8.0`.
+/// let input = vec![2.0, 2.0, 2.0, 2.0, 8.0, 8.0];
+///
+/// let masked = tv1d::condat_dropouts(&input, 0.0, 3, tv1d::DropoutHandling::Mask);
+/// assert_eq!(masked.output, vec![2.0, 3.5, 5.0, 6.5, 8.0, 8.0]);
+/// assert_eq!(masked.dropouts, vec![1..4]);
+///
+/// let flagged = tv1d::condat_dropouts(&input, 0.0, 3, tv1d::DropoutHandling::Flag);
+/// assert_eq!(flagged.output, input);
+/// ```
+pub fn condat_dropouts<T>(input: &[T], lambda: T, min_run: usize, handling: DropoutHandling) -> DropoutOutput<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    let dropouts = detect_dropouts(input, min_run);
+    let output = match handling {
+        DropoutHandling::Mask => {
+            // The first value of every run is kept, so at least one
+            // sample is observed.
+            let mut missing = vec![false; input.len()];
+            for range in &dropouts {
+                for flag in &mut missing[range.clone()] {
+                    *flag = true;
+                }
+            }
+            condat_masked(input, &missing, lambda)
+        }
+        DropoutHandling::Flag => condat(input, lambda),
+    };
+
+    DropoutOutput {
+        output,
+        dropouts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_dropouts_test_runs() {
+        assert!(detect_dropouts::<f64>(&[], 2).is_empty());
+        assert!(detect_dropouts(&[1.0], 2).is_empty());
+        assert_eq!(detect_dropouts(&[4, 4, 4, 4], 4), vec![1..4]);
+        assert!(detect_dropouts(&[4, 4, 4, 4], 5).is_empty());
+        assert_eq!(detect_dropouts(&[1, 1, 2, 3, 3, 3], 2), vec![1..2, 4..6]);
+    }
+
+    #[test]
+    fn detect_dropouts_test_nan() {
+        let nan = f64::NAN;
+        assert!(detect_dropouts(&[nan, nan, nan], 2).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn detect_dropouts_test_min_run() {
+        detect_dropouts(&[1.0, 1.0], 1);
+    }
+
+    #[test]
+    fn condat_dropouts_test_matches_masked() {
+        let input = vec![1.0, 2.1, 5.2, 5.2, 5.2, 5.2, 6.2, 10.1];
+        let missing = vec![false, false, false, true, true, true, false, false];
+        let masked = condat_dropouts(&input, 1.0, 4, DropoutHandling::Mask);
+        assert_eq!(masked.output, condat_masked(&input, &missing, 1.0));
+        assert_eq!(masked.dropouts, vec![3..6]);
+    }
+
+    #[test]
+    fn condat_dropouts_test_without_dropouts() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        for &handling in &[DropoutHandling::Mask, DropoutHandling::Flag] {
+            let output = condat_dropouts(&input, 3.0, 2, handling);
+            assert_eq!(output.output, condat(&input, 3.0));
+            assert!(output.dropouts.is_empty());
+        }
+    }
+
+    #[test]
+    fn condat_dropouts_test_constant() {
+        let input = vec![3.0; 5];
+        let output = condat_dropouts(&input, 1.0, 2, DropoutHandling::Mask);
+        assert_eq!(output.output, input);
+        assert_eq!(output.dropouts, vec![1..5]);
+    }
+}
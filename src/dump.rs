@@ -0,0 +1,241 @@
+use std::io::{self, Write};
+
+use observer::{Bounds, Observer};
+use scalar::{TvFloat, TvScalar};
+use workspace::TautstringWorkspace;
+use {find_segments, find_taut_string};
+
+// Writes each state as a line of JSON, keeping the first error, after
+// which nothing more is written.
+struct JsonLines<W> {
+    writer: W,
+    result: io::Result<()>,
+}
+
+impl<W: Write> JsonLines<W> {
+    fn write(&mut self, step: &str, fields: &[(&str, Field)]) {
+        if self.result.is_err() {
+            return;
+        }
+        let mut line = format!("{{\"step\":\"{}\"", step);
+        for &(name, ref value) in fields {
+            line.push_str(&format!(",\"{}\":", name));
+            match *value {
+                Field::Index(index) => line.push_str(&index.to_string()),
+                Field::Value(Some(value)) if value.is_finite() => line.push_str(&value.to_string()),
+                // JSON has no infinite or NaN numbers.
+                Field::Value(_) => line.push_str("null"),
+                Field::Text(text) => line.push_str(&format!("\"{}\"", text)),
+            }
+        }
+        line.push_str("}\n");
+        self.result = self.writer.write_all(line.as_bytes());
+    }
+}
+
+enum Field<'a> {
+    Index(usize),
+    Value(Option<f64>),
+    Text(&'a str),
+}
+
+impl<T: TvScalar, W: Write> Observer<T> for JsonLines<W> {
+    fn bounds(&mut self, bounds: Bounds<T>) {
+        self.write("bounds",
+                   &[("index", Field::Index(bounds.index)),
+                     ("segment_start", Field::Index(bounds.segment_start)),
+                     ("kminus", Field::Index(bounds.kminus)),
+                     ("kplus", Field::Index(bounds.kplus)),
+                     ("umin", Field::Value(bounds.umin.to_f64())),
+                     ("umax", Field::Value(bounds.umax.to_f64())),
+                     ("lower", Field::Value(bounds.lower.to_f64())),
+                     ("upper", Field::Value(bounds.upper.to_f64()))]);
+    }
+
+    fn segment(&mut self, start: usize, end: usize, value: T) {
+        self.write("segment",
+                   &[("start", Field::Index(start)),
+                     ("end", Field::Index(end)),
+                     ("value", Field::Value(value.to_f64()))]);
+    }
+
+    fn hulls(&mut self, index: usize, lower: usize, upper: usize) {
+        self.write("hulls",
+                   &[("index", Field::Index(index)),
+                     ("lower", Field::Index(lower)),
+                     ("upper", Field::Index(upper))]);
+    }
+
+    fn knot(&mut self, index: usize, value: T) {
+        self.write("knot",
+                   &[("index", Field::Index(index)), ("value", Field::Value(value.to_f64()))]);
+    }
+
+    fn shortcut(&mut self, reason: &str) {
+        self.write("shortcut", &[("reason", Field::Text(reason))]);
+    }
+}
+
+/// Denoises the input values with
+/// [`tv1d::condat`](../tv1d/fn.condat.html), writing the evolution of
+/// its internal state to `writer` for debugging.
+///
+/// Each state is written as a line of JSON with a `step` field:
+///
+/// - `bounds`: the state before the value after `index` is inspected,
+///   with the start of the current segment `segment_start`, the last
+///   positions `kminus` and `kplus` where `umin` and `umax` were
+///   clamped, `umin` and `umax`, and the bounds `lower` and `upper` of
+///   the value of the segment.
+/// - `segment`: a segment of the output from `start` to `end`
+///   exclusive written with `value`. Segments may be written again
+///   when the algorithm backtracks.
+/// - `shortcut`: the output is found without running the algorithm,
+///   for `reason`.
+///
+/// Values that are not finite are written as `null`.
+///
+/// # Errors
+/// Returns the first error writing to `writer`. The denoising runs to
+/// the end regardless.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let mut dump = Vec::new();
+/// let output = tv1d::condat_with_dump(&[1.0, 5.0], 1.0, &mut dump).unwrap();
+/// assert_eq!(output, vec![2.0, 4.0]);
+///
+/// let dump = String::from_utf8(dump).unwrap();
+/// assert_eq!(dump.lines().next(),
+///            Some(r#"{"step":"bounds","index":0,"segment_start":0,"kminus":0,"kplus":0,"umin":1,"umax":-1,"lower":0,"upper":2}"#));
+/// assert_eq!(dump.lines().last(), Some(r#"{"step":"segment","start":1,"end":2,"value":4}"#));
+/// ```
+pub fn condat_with_dump<T, W>(input: &[T], lambda: T, writer: W) -> io::Result<Vec<T>>
+    where T: TvScalar,
+          W: Write
+{
+    let mut output = input.to_vec();
+    let mut dump = JsonLines {
+        writer,
+        result: Ok(()),
+    };
+    find_segments(&mut output, lambda, &mut dump);
+    dump.result.map(|()| output)
+}
+
+/// Denoises the input values with
+/// [`tv1d::tautstring`](../tv1d/fn.tautstring.html), writing the
+/// evolution of its internal state to `writer` for debugging.
+///
+/// Each state is written as a line of JSON with a `step` field:
+///
+/// - `hulls`: the number of vertices `lower` and `upper` of the convex
+///   hulls of the lower and the upper bound after the running sum
+///   reaches `index`.
+/// - `knot`: a knot of the taut string at `index`, with the `value` of
+///   the output since the previous knot.
+/// - `shortcut`: the output is found without running the algorithm,
+///   for `reason`.
+///
+/// Values that are not finite are written as `null`.
+///
+/// # Errors
+/// Returns the first error writing to `writer`. The denoising runs to
+/// the end regardless.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let mut dump = Vec::new();
+/// let output = tv1d::tautstring_with_dump(&[1.0, 5.0], 1.0, &mut dump).unwrap();
+/// assert_eq!(output, vec![2.0, 4.0]);
+///
+/// let dump = String::from_utf8(dump).unwrap();
+/// let knots: Vec<&str> = dump.lines().filter(|line| line.contains("\"knot\"")).collect();
+/// assert_eq!(knots, vec![r#"{"step":"knot","index":1,"value":2}"#,
+///                        r#"{"step":"knot","index":2,"value":4}"#]);
+/// ```
+pub fn tautstring_with_dump<T, W>(input: &[T], lambda: T, writer: W) -> io::Result<Vec<T>>
+    where T: TvFloat,
+          W: Write
+{
+    let mut output = input.to_vec();
+    let mut dump = JsonLines {
+        writer,
+        result: Ok(()),
+    };
+    find_taut_string(&mut output, lambda, &mut TautstringWorkspace::new(), &mut dump);
+    dump.result.map(|()| output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {condat, tautstring};
+
+    // A writer that fails after `capacity` bytes.
+    struct Full {
+        capacity: usize,
+    }
+
+    impl Write for Full {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            if bytes.len() > self.capacity {
+                return Err(io::Error::new(io::ErrorKind::WriteZero, "full"));
+            }
+            self.capacity -= bytes.len();
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn with_dump_test_matches_solvers() {
+        let input = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        for &lambda in &[0.0, 1.0, 50.0, 500.0, f64::INFINITY] {
+            let mut dump = Vec::new();
+            assert_eq!(condat_with_dump(&input, lambda, &mut dump).unwrap(), condat(&input, lambda));
+            assert!(!dump.is_empty());
+            let mut dump = Vec::new();
+            assert_eq!(tautstring_with_dump(&input, lambda, &mut dump).unwrap(), tautstring(&input, lambda));
+            assert!(!dump.is_empty());
+        }
+    }
+
+    #[test]
+    fn condat_with_dump_test_segments_cover_output() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let mut dump = Vec::new();
+        condat_with_dump(&input, 1.0, &mut dump).unwrap();
+        let last_end = String::from_utf8(dump)
+            .unwrap()
+            .lines()
+            .filter(|line| line.starts_with("{\"step\":\"segment\""))
+            .map(|line| line.split(",\"end\":").nth(1).unwrap().split(',').next().unwrap().to_string())
+            .next_back();
+        assert_eq!(last_end, Some(input.len().to_string()));
+    }
+
+    #[test]
+    fn condat_with_dump_test_write_error() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let error = condat_with_dump(&input, 1.0, Full { capacity: 100 }).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+    }
+}
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use condat_in_place;
+use fixed::GUARD_BITS;
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// Denoises durations, such as latency measurements, with
+/// [`tv1d::condat`](../tv1d/fn.condat.html).
+///
+/// The durations are denoised in nanoseconds as `i128`, which
+/// represents every `Duration` exactly, unlike `f64`, which loses
+/// precision beyond about 104 days. As in
+/// [`tv1d::condat_fixed`](../tv1d/fn.condat_fixed.html), the
+/// nanoseconds are widened with 16 guard bits, so that the value of
+/// each segment of an input of length `n` is within `0.5 + n * 2^-16`
+/// nanoseconds of the value found in exact arithmetic, and rounded to
+/// the nearest nanosecond.
+///
+/// # Panics
+/// Panics if input vector's length is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use tv1d;
+///
+/// let latencies: Vec<Duration> = [10, 12, 11, 50, 52, 51].iter().map(|&ms| Duration::from_millis(ms)).collect();
+/// let denoised = tv1d::condat_duration(&latencies, Duration::from_millis(3));
+/// assert_eq!(denoised[..3], [Duration::from_millis(12); 3]);
+/// assert_eq!(denoised[3..], [Duration::from_millis(50); 3]);
+/// ```
+pub fn condat_duration(input: &[Duration], lambda: Duration) -> Vec<Duration> {
+    let mut values: Vec<i128> = input.iter().map(|&duration| to_nanos(duration) << GUARD_BITS).collect();
+    condat_in_place(&mut values, to_nanos(lambda) << GUARD_BITS);
+
+    let half = 1 << (GUARD_BITS - 1);
+    values.into_iter().map(|value| from_nanos((value + half) >> GUARD_BITS)).collect()
+}
+
+fn to_nanos(duration: Duration) -> i128 {
+    i128::from(duration.as_secs()) * NANOS_PER_SEC + i128::from(duration.subsec_nanos())
+}
+
+// The output lies between the smallest and the largest input up to
+// rounding, which is clamped to the range of `Duration`.
+fn from_nanos(nanos: i128) -> Duration {
+    let nanos = nanos.clamp(0, to_nanos(Duration::MAX));
+    Duration::new((nanos / NANOS_PER_SEC) as u64, (nanos % NANOS_PER_SEC) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    #[test]
+    fn condat_duration_test_matches_condat() {
+        let input: Vec<f64> = (0..300).map(|i| (((i / 30) % 3) * 40_000 + (i * 7919) % 997) as f64).collect();
+        let durations: Vec<Duration> = input.iter().map(|&nanos| Duration::from_nanos(nanos as u64)).collect();
+        for &lambda in &[0, 10, 1000, 100_000] {
+            let expected = condat(&input, lambda as f64);
+            let output = condat_duration(&durations, Duration::from_nanos(lambda));
+            for (value, expected) in output.iter().zip(expected.iter()) {
+                assert!((value.as_nanos() as f64 - expected).abs() <= 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn condat_duration_test_exact() {
+        // One nanosecond apart after more than a century, which `f64`
+        // cannot tell apart.
+        let base = Duration::from_secs(4_000_000_000);
+        let input = vec![base, base + Duration::from_nanos(1), base + Duration::from_nanos(2)];
+        assert_eq!(condat_duration(&input, Duration::ZERO), input);
+        assert_eq!(condat_duration(&input, Duration::from_secs(1)), vec![base + Duration::from_nanos(1); 3]);
+
+        let extreme = vec![Duration::MAX, Duration::ZERO, Duration::MAX];
+        assert_eq!(condat_duration(&extreme, Duration::ZERO), extreme);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_duration_test_empty_input() {
+        condat_duration(&[], Duration::from_secs(1));
+    }
+}
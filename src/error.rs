@@ -0,0 +1,60 @@
+use std::error;
+use std::fmt;
+
+/// The error returned by the fallible functions of this crate, such
+/// as [`tv1d::try_condat`](../tv1d/fn.try_condat.html), instead of
+/// panicking.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum TvError {
+    /// The input contains a NaN or infinite value.
+    NonFiniteInput {
+        /// The index of the first offending value in the input.
+        index: usize,
+        /// The offending value.
+        value: f64,
+    },
+    /// The denoised output overflowed, although every input value is
+    /// finite, e.g. the mean of values close to the largest finite
+    /// value.
+    Overflow {
+        /// The index of the first output value that overflowed.
+        index: usize,
+        /// The overflowed value, infinite or NaN.
+        value: f64,
+    },
+    /// The output buffer has a different length than the input.
+    LengthMismatch {
+        /// The length of the input.
+        input: usize,
+        /// The length of the output buffer.
+        output: usize,
+    },
+    /// The minimum segment length of a
+    /// [`TvDenoiser`](struct.TvDenoiser.html) is `0`, although every
+    /// segment has at least one value.
+    ZeroMinSegmentLen,
+    /// `lambda` is less than `0`.
+    NegativeLambda,
+    /// `lambda` is NaN.
+    NanLambda,
+}
+
+impl fmt::Display for TvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TvError::NonFiniteInput { index, value } => {
+                write!(f, "input list should only contain finite values, found {} at index {}", value, index)
+            }
+            TvError::Overflow { index, value } => write!(f, "output overflowed to {} at index {}", value, index),
+            TvError::LengthMismatch { input, output } => {
+                write!(f, "output of length {} should have the input's length {}", output, input)
+            }
+            TvError::ZeroMinSegmentLen => write!(f, "minimum segment length must be at least 1"),
+            TvError::NegativeLambda => write!(f, "lambda must be greater than or equal to 0"),
+            TvError::NanLambda => write!(f, "lambda must not be NaN"),
+        }
+    }
+}
+
+impl error::Error for TvError {}
@@ -0,0 +1,198 @@
+use std::ops;
+use std::vec;
+
+use condat;
+use condat_in_place;
+use scalar::TvScalar;
+
+/// Extension methods on slices, and through them on vectors, for
+/// chaining total variation denoising in data-wrangling code.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d::TvDenoiseExt;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(input.tv_denoise(1.0), vec![2.0, 2.0, 3.0, 4.0, 4.0]);
+/// assert_eq!(input.tv_segments(1.0), vec![(0..2, 2.0), (2..3, 3.0), (3..5, 4.0)]);
+/// ```
+pub trait TvDenoiseExt<T> {
+    /// Denoises the values with
+    /// [`tv1d::condat`](../tv1d/fn.condat.html).
+    ///
+    /// # Panics
+    /// Panics if the length is `0`, or `lambda` is NaN or less than `0`.
+    fn tv_denoise(&self, lambda: T) -> Vec<T>;
+
+    /// Denoises the values with
+    /// [`tv1d::condat`](../tv1d/fn.condat.html) and returns the
+    /// constant runs of the output as index ranges with their values.
+    ///
+    /// # Panics
+    /// Panics if the length is `0`, or `lambda` is NaN or less than `0`.
+    fn tv_segments(&self, lambda: T) -> Vec<(ops::Range<usize>, T)>;
+}
+
+impl<T> TvDenoiseExt<T> for [T]
+    where T: TvScalar
+{
+    fn tv_denoise(&self, lambda: T) -> Vec<T> {
+        condat(self, lambda)
+    }
+
+    fn tv_segments(&self, lambda: T) -> Vec<(ops::Range<usize>, T)> {
+        let output = condat(self, lambda);
+        let mut segments = Vec::new();
+        let mut start = 0;
+        for end in 1..(output.len() + 1) {
+            if end == output.len() || output[end] != output[start] {
+                segments.push((start..end, output[start]));
+                start = end;
+            }
+        }
+        segments
+    }
+}
+
+/// An adapter denoising the values of any iterator, so that the crate
+/// composes with iterator-based pipelines.
+///
+/// Total variation denoising is not local, as a value at the end of
+/// the input can move every output value, so the values are collected
+/// before they are denoised.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d::TvIteratorExt;
+///
+/// let output: Vec<f64> = (1..6)
+///     .map(|x| x as f64)
+///     .denoise(1.0)
+///     .map(|x| x * 10.0)
+///     .collect();
+/// assert_eq!(output, vec![20.0, 20.0, 30.0, 40.0, 40.0]);
+/// ```
+pub trait TvIteratorExt<T>: Iterator<Item = T> + Sized {
+    /// Collects the values and denoises them with
+    /// [`tv1d::condat`](../tv1d/fn.condat.html), returning an iterator
+    /// over the denoised values. An empty iterator yields no values.
+    ///
+    /// # Panics
+    /// Panics if the iterator yields any value and `lambda` is NaN or
+    /// less than `0`.
+    fn denoise(self, lambda: T) -> vec::IntoIter<T>;
+}
+
+impl<T, I> TvIteratorExt<T> for I
+    where I: Iterator<Item = T>,
+          T: TvScalar
+{
+    fn denoise(self, lambda: T) -> vec::IntoIter<T> {
+        let mut values: Vec<T> = self.collect();
+        if !values.is_empty() {
+            condat_in_place(&mut values, lambda);
+        }
+        values.into_iter()
+    }
+}
+
+/// Denoises values from a fallible source, such as values parsed from
+/// a file, with [`tv1d::condat`](../tv1d/fn.condat.html), returning
+/// the first error of the source instead.
+///
+/// The source is not consumed past its first error. An empty source
+/// yields an empty output.
+///
+/// # Panics
+/// Panics if the source yields any value and `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let output = tv1d::condat_results("1 2 3 4 5".split(' ').map(|x| x.parse::<f64>()), 10.0);
+/// assert_eq!(output, Ok(vec![3.0, 3.0, 3.0, 3.0, 3.0]));
+///
+/// let output = tv1d::condat_results("1 2 x 4 5".split(' ').map(|x| x.parse::<f64>()), 10.0);
+/// assert!(output.is_err());
+/// ```
+pub fn condat_results<T, E, I>(values: I, lambda: T) -> Result<Vec<T>, E>
+    where I: IntoIterator<Item = Result<T, E>>,
+          T: TvScalar
+{
+    let mut values = values.into_iter().collect::<Result<Vec<T>, E>>()?;
+    if !values.is_empty() {
+        condat_in_place(&mut values, lambda);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tv_denoise_test_matches_condat() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        assert_eq!(input.tv_denoise(3.0), condat(&input, 3.0));
+        assert_eq!(input[2..].tv_denoise(3.0), condat(&input[2..], 3.0));
+    }
+
+    #[test]
+    fn tv_segments_test_cover_output() {
+        let input = vec![111, 422, 145, 248, 871, 675, 436, 310];
+        let output = condat(&input, 50);
+        let segments = input.tv_segments(50);
+        assert_eq!(segments[0].0.start, 0);
+        assert_eq!(segments[segments.len() - 1].0.end, input.len());
+        for pair in segments.windows(2) {
+            assert_eq!(pair[0].0.end, pair[1].0.start);
+            assert!(pair[0].1 != pair[1].1);
+        }
+        for (range, value) in segments {
+            assert!(output[range].iter().all(|&x| x == value));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn tv_segments_test_empty_input() {
+        let input: Vec<f64> = vec![];
+        input.tv_segments(1.0);
+    }
+
+    #[test]
+    fn denoise_iterator_test_matches_condat() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let output: Vec<f64> = input.iter().cloned().denoise(3.0).collect();
+        assert_eq!(output, condat(&input, 3.0));
+    }
+
+    #[test]
+    fn denoise_iterator_test_empty() {
+        assert_eq!(Vec::<f64>::new().into_iter().denoise(1.0).count(), 0);
+    }
+
+    #[test]
+    fn condat_results_test_matches_condat() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let results: Vec<Result<f64, ()>> = input.iter().map(|&x| Ok(x)).collect();
+        assert_eq!(condat_results(results, 3.0), Ok(condat(&input, 3.0)));
+        assert_eq!(condat_results(Vec::<Result<f64, ()>>::new(), 3.0), Ok(vec![]));
+    }
+
+    #[test]
+    fn condat_results_test_first_error() {
+        let mut consumed = 0;
+        let results = (0..10).map(|i| {
+            consumed += 1;
+            if i < 3 { Ok(i as f64) } else { Err(i) }
+        });
+        assert_eq!(condat_results(results, 1.0), Err(3));
+        assert_eq!(consumed, 4);
+    }
+}
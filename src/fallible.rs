@@ -0,0 +1,464 @@
+use std::f64;
+
+use condat;
+use condat_in_place;
+use error::TvError;
+use scalar::TvFloat;
+use tautstring;
+use utils;
+
+/// Denoises the input values like
+/// [`tv1d::condat`](../tv1d/fn.condat.html), but returns an error
+/// instead of panicking on invalid arguments.
+///
+/// An empty input yields an empty output, so that arbitrary windows
+/// of a signal can be denoised without checking their length first.
+///
+/// # Errors
+/// Returns `TvError::NonFiniteInput` with the index of the first NaN
+/// or infinite value in the input, `TvError::Overflow` if the output
+/// overflows, `TvError::NanLambda` if `lambda` is NaN, and
+/// `TvError::NegativeLambda` if `lambda` is less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(tv1d::try_condat(&input, 10.0), Ok(vec![3.0, 3.0, 3.0, 3.0, 3.0]));
+/// assert_eq!(tv1d::try_condat(&input, -1.0), Err(tv1d::TvError::NegativeLambda));
+/// assert_eq!(tv1d::try_condat(&[], 1.0), Ok(vec![]));
+/// ```
+pub fn try_condat<T>(input: &[T], lambda: T) -> Result<Vec<T>, TvError>
+    where T: TvFloat
+{
+    try_condat_with_policy(input, lambda, NonFinitePolicy::Error)
+}
+
+/// Denoises the input values like
+/// [`tv1d::condat_into`](../tv1d/fn.condat_into.html), writing the
+/// denoised output into `output`, but returns an error instead of
+/// panicking on invalid arguments.
+///
+/// All of the algorithm's state lives in `output` and on the stack,
+/// so this function never allocates on the heap, neither on success
+/// nor on error. This makes it suitable for firmware without a heap
+/// allocator. An empty input with an empty output is left untouched.
+///
+/// # Errors
+/// Returns `TvError::LengthMismatch` if `output` has a different
+/// length than the input, `TvError::NonFiniteInput` with the index of
+/// the first NaN or infinite value in the input, `TvError::NanLambda`
+/// if `lambda` is NaN, and `TvError::NegativeLambda` if `lambda` is
+/// less than `0`, leaving `output` untouched. Returns
+/// `TvError::Overflow` if the denoised output written to `output`
+/// overflows.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = [1.0, 2.0, 3.0, 4.0, 5.0];
+/// let mut output = [0.0; 5];
+/// assert_eq!(tv1d::try_condat_into(&input, 10.0, &mut output), Ok(()));
+/// assert_eq!(output, [3.0, 3.0, 3.0, 3.0, 3.0]);
+///
+/// let mut short = [0.0; 4];
+/// assert_eq!(tv1d::try_condat_into(&input, 10.0, &mut short),
+///            Err(tv1d::TvError::LengthMismatch { input: 5, output: 4 }));
+/// ```
+pub fn try_condat_into<T>(input: &[T], lambda: T, output: &mut [T]) -> Result<(), TvError>
+    where T: TvFloat
+{
+    if input.len() != output.len() {
+        return Err(TvError::LengthMismatch {
+            input: input.len(),
+            output: output.len(),
+        });
+    }
+    validate_lambda(lambda)?;
+    if let Some(index) = input.iter().position(|value| !value.is_finite()) {
+        return Err(non_finite_input(input, index));
+    }
+    if input.is_empty() {
+        return Ok(());
+    }
+
+    output.copy_from_slice(input);
+    condat_in_place(output, lambda);
+    check_output(output)
+}
+
+/// Denoises the input values like
+/// [`tv1d::tautstring`](../tv1d/fn.tautstring.html), but returns an
+/// error instead of panicking on invalid arguments.
+///
+/// An empty input yields an empty output, so that arbitrary windows
+/// of a signal can be denoised without checking their length first.
+///
+/// # Errors
+/// Returns `TvError::NonFiniteInput` with the index of the first NaN
+/// or infinite value in the input, `TvError::Overflow` if the output
+/// overflows, `TvError::NanLambda` if `lambda` is NaN, and
+/// `TvError::NegativeLambda` if `lambda` is less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(tv1d::try_tautstring(&input, 0.0), Ok(input.clone()));
+///
+/// match tv1d::try_tautstring(&[1.0, 2.0, std::f64::INFINITY], 1.0) {
+///     Err(tv1d::TvError::NonFiniteInput { index, .. }) => assert_eq!(index, 2),
+///     _ => panic!("Expected an error for the infinite value."),
+/// }
+/// ```
+pub fn try_tautstring<T>(input: &[T], lambda: T) -> Result<Vec<T>, TvError>
+    where T: TvFloat
+{
+    try_tautstring_with_policy(input, lambda, NonFinitePolicy::Error)
+}
+
+/// Denoises the input values like
+/// [`tv1d::try_tautstring`](../tv1d/fn.try_tautstring.html).
+///
+/// The taut string only sums the input values since its last knot, so
+/// its precision does not degrade with the length of the input, and
+/// there is no running sum whose precision needs to be checked. The
+/// two return the same result. Use `tv1d::try_tautstring` instead.
+///
+/// # Errors
+/// Returns the same errors as
+/// [`tv1d::try_tautstring`](../tv1d/fn.try_tautstring.html).
+///
+/// # Examples
+///
+/// ```
+/// #![allow(deprecated)]
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(tv1d::tautstring_checked(&input, 1.0), tv1d::try_tautstring(&input, 1.0));
+/// ```
+#[deprecated(note = "`tv1d::try_tautstring` returns the same result, use it instead")]
+pub fn tautstring_checked<T>(input: &[T], lambda: T) -> Result<Vec<T>, TvError>
+    where T: TvFloat
+{
+    try_tautstring(input, lambda)
+}
+
+/// What to do with NaN and infinite values in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum NonFinitePolicy {
+    /// Return `TvError::NonFiniteInput`.
+    Error,
+    /// Denoise the finite values as if the non-finite values were not
+    /// there, and fill the output at the non-finite positions by
+    /// linear interpolation between the neighbouring denoised values.
+    SkipAndInterpolate,
+    /// Denoise each run of finite values independently, as if the
+    /// non-finite values split the input into separate signals, and
+    /// keep the non-finite values as they are in the output.
+    PropagateSegmentBreak,
+}
+
+/// Denoises the input values like
+/// [`tv1d::try_condat`](../tv1d/fn.try_condat.html), handling NaN and
+/// infinite values in the input according to `policy`.
+///
+/// # Errors
+/// Returns `TvError::NonFiniteInput` with the index of the first NaN
+/// or infinite value in the input if `policy` is
+/// `NonFinitePolicy::Error`, or if the input contains no finite values and `policy` is
+/// `NonFinitePolicy::SkipAndInterpolate`. Returns
+/// `TvError::Overflow` if the output at a finite input value
+/// overflows, `TvError::NanLambda` if `lambda` is NaN, and
+/// `TvError::NegativeLambda` if `lambda` is less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::NonFinitePolicy;
+///
+/// let input = vec![1.0, std::f64::NAN, 3.0, 3.0];
+///
+/// let interpolated = tv1d::try_condat_with_policy(&input, 0.0,
+///                                                 NonFinitePolicy::SkipAndInterpolate);
+/// assert_eq!(interpolated, Ok(vec![1.0, 2.0, 3.0, 3.0]));
+///
+/// let split = tv1d::try_condat_with_policy(&input, 0.0,
+///                                          NonFinitePolicy::PropagateSegmentBreak).unwrap();
+/// assert!(split[1].is_nan());
+/// ```
+pub fn try_condat_with_policy<T>(input: &[T],
+                                 lambda: T,
+                                 policy: NonFinitePolicy)
+                                 -> Result<Vec<T>, TvError>
+    where T: TvFloat
+{
+    with_policy(input, lambda, policy, condat)
+}
+
+/// Denoises the input values like
+/// [`tv1d::try_tautstring`](../tv1d/fn.try_tautstring.html), handling
+/// NaN and infinite values in the input according to `policy`.
+///
+/// # Errors
+/// Returns `TvError::NonFiniteInput` with the index of the first NaN
+/// or infinite value in the input if `policy` is
+/// `NonFinitePolicy::Error`, or if the input contains no finite
+/// values and `policy` is `NonFinitePolicy::SkipAndInterpolate`.
+/// Returns `TvError::Overflow` if the output at a finite input value
+/// overflows, `TvError::NanLambda` if `lambda` is NaN, and
+/// `TvError::NegativeLambda` if `lambda` is less than `0`.
+pub fn try_tautstring_with_policy<T>(input: &[T],
+                                     lambda: T,
+                                     policy: NonFinitePolicy)
+                                     -> Result<Vec<T>, TvError>
+    where T: TvFloat
+{
+    with_policy(input, lambda, policy, tautstring)
+}
+
+// Denoises the input with `denoise` according to `policy`, and
+// checks that the output is finite wherever the input is.
+fn with_policy<T, F>(input: &[T], lambda: T, policy: NonFinitePolicy, denoise: F) -> Result<Vec<T>, TvError>
+    where T: TvFloat,
+          F: Fn(&[T], T) -> Vec<T>
+{
+    let output = denoise_with_policy(input, lambda, policy, denoise)?;
+    for (index, (value, denoised)) in input.iter().zip(output.iter()).enumerate() {
+        if value.is_finite() && !denoised.is_finite() {
+            return Err(overflow(&output, index));
+        }
+    }
+    Ok(output)
+}
+
+fn denoise_with_policy<T, F>(input: &[T], lambda: T, policy: NonFinitePolicy, denoise: F) -> Result<Vec<T>, TvError>
+    where T: TvFloat,
+          F: Fn(&[T], T) -> Vec<T>
+{
+    validate_lambda(lambda)?;
+
+    let first_non_finite = input.iter().position(|value| !value.is_finite());
+    if let (Some(index), NonFinitePolicy::Error) = (first_non_finite, policy) {
+        return Err(non_finite_input(input, index));
+    }
+
+    if first_non_finite.is_none() {
+        if input.is_empty() {
+            return Ok(Vec::new());
+        }
+        return Ok(denoise(input, lambda));
+    }
+
+    trace_event!(fallback = ?policy,
+                 non_finite = input.iter().filter(|value| !value.is_finite()).count(),
+                 "Handling non-finite input values.");
+    match policy {
+        NonFinitePolicy::Error => unreachable!("Non-finite input is rejected above."),
+        NonFinitePolicy::SkipAndInterpolate => {
+            let positions: Vec<usize> = (0..input.len()).filter(|&i| input[i].is_finite()).collect();
+            if positions.is_empty() {
+                return Err(non_finite_input(input, 0));
+            }
+            let values: Vec<T> = positions.iter().map(|&i| input[i]).collect();
+            Ok(utils::interpolate(input.len(), &positions, &denoise(&values, lambda)))
+        }
+        NonFinitePolicy::PropagateSegmentBreak => {
+            let mut output = input.to_vec();
+            let mut start = 0;
+            while start < input.len() {
+                if !input[start].is_finite() {
+                    start += 1;
+                    continue;
+                }
+                let end = input[start..]
+                    .iter()
+                    .position(|value| !value.is_finite())
+                    .map_or(input.len(), |len| start + len);
+                output[start..end].copy_from_slice(&denoise(&input[start..end], lambda));
+                start = end;
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn non_finite_input<T: TvFloat>(input: &[T], index: usize) -> TvError {
+    TvError::NonFiniteInput {
+        index,
+        value: input[index].to_f64().unwrap_or(f64::NAN),
+    }
+}
+
+fn overflow<T: TvFloat>(output: &[T], index: usize) -> TvError {
+    TvError::Overflow {
+        index,
+        value: output[index].to_f64().unwrap_or(f64::NAN),
+    }
+}
+
+// The solvers can overflow on finite values close to the largest
+// finite value, such as in the means of their segments.
+fn check_output<T: TvFloat>(output: &[T]) -> Result<(), TvError> {
+    match output.iter().position(|value| !value.is_finite()) {
+        Some(index) => Err(overflow(output, index)),
+        None => Ok(()),
+    }
+}
+
+fn validate_lambda<T: TvFloat>(lambda: T) -> Result<(), TvError> {
+    if lambda.is_nan() {
+        return Err(TvError::NanLambda);
+    }
+    if lambda < T::zero() {
+        return Err(TvError::NegativeLambda);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_condat_test_matches_condat() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        assert_eq!(try_condat(&input, 3.0), Ok(condat(&input, 3.0)));
+    }
+
+    #[test]
+    fn try_condat_test_errors() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        assert_eq!(try_condat(&[1.0, f64::INFINITY], 1.0),
+                   Err(TvError::NonFiniteInput {
+                       index: 1,
+                       value: f64::INFINITY,
+                   }));
+        assert_eq!(try_condat(&input, -1.0), Err(TvError::NegativeLambda));
+        assert_eq!(try_condat(&input, f64::NEG_INFINITY), Err(TvError::NegativeLambda));
+        assert_eq!(try_condat(&input, f64::NAN), Err(TvError::NanLambda));
+    }
+
+    // The cross-check panics on the overflow, as its reference does not
+    // overflow.
+    #[test]
+    #[cfg(not(all(feature = "cross-check", debug_assertions)))]
+    fn try_condat_test_overflow() {
+        // The mean of these values overflows, although every value is
+        // finite.
+        let input = [f64::MAX, f64::MAX, f64::MAX / 2.0, 1.0];
+        assert_eq!(try_condat(&input, f64::INFINITY), Err(TvError::Overflow { index: 0, value: f64::INFINITY }));
+        let mut output = [0.0; 4];
+        assert_eq!(try_condat_into(&input, f64::INFINITY, &mut output),
+                   Err(TvError::Overflow { index: 0, value: f64::INFINITY }));
+        assert_eq!(try_condat_with_policy(&[f64::NAN, f64::MAX, f64::MAX], f64::INFINITY,
+                                          NonFinitePolicy::PropagateSegmentBreak),
+                   Err(TvError::Overflow { index: 1, value: f64::INFINITY }));
+    }
+
+    #[test]
+    fn try_condat_test_empty_input() {
+        assert_eq!(try_condat::<f64>(&[], 1.0), Ok(vec![]));
+        assert_eq!(try_condat::<f64>(&[], -1.0), Err(TvError::NegativeLambda));
+    }
+
+    #[test]
+    fn try_condat_test_infinite_lambda() {
+        let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(try_condat(&input, f64::INFINITY), Ok(vec![3.0; 5]));
+    }
+
+    #[test]
+    fn try_condat_with_policy_test_skip_and_interpolate() {
+        let nan = f64::NAN;
+        let input = vec![nan, 1.0, nan, nan, 4.0, f64::INFINITY];
+        let output = try_condat_with_policy(&input, 0.0, NonFinitePolicy::SkipAndInterpolate);
+        assert_eq!(output, Ok(vec![1.0, 1.0, 2.0, 3.0, 4.0, 4.0]));
+
+        let output = try_condat_with_policy(&[nan, nan], 0.0, NonFinitePolicy::SkipAndInterpolate);
+        match output {
+            Err(TvError::NonFiniteInput { index: 0, value }) => assert!(value.is_nan()),
+            _ => panic!("Expected an error for input without finite values."),
+        }
+    }
+
+    #[test]
+    fn try_condat_with_policy_test_propagate_segment_break() {
+        let input = vec![1.0, 3.0, f64::NEG_INFINITY, 5.0, 7.0];
+        let output = try_condat_with_policy(&input, 10.0, NonFinitePolicy::PropagateSegmentBreak);
+        assert_eq!(output, Ok(vec![2.0, 2.0, f64::NEG_INFINITY, 6.0, 6.0]));
+    }
+
+    #[test]
+    fn try_condat_with_policy_test_finite_input() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        for &policy in &[NonFinitePolicy::Error,
+                         NonFinitePolicy::SkipAndInterpolate,
+                         NonFinitePolicy::PropagateSegmentBreak] {
+            assert_eq!(try_condat_with_policy(&input, 3.0, policy), Ok(condat(&input, 3.0)));
+        }
+    }
+
+    #[test]
+    fn try_tautstring_test_matches_tautstring() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        assert_eq!(try_tautstring(&input, 3.0), Ok(tautstring(&input, 3.0)));
+    }
+
+    #[test]
+    fn try_tautstring_test_errors() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        assert_eq!(try_tautstring(&[1.0, 2.0, f64::NEG_INFINITY, f64::NAN], 1.0),
+                   Err(TvError::NonFiniteInput {
+                       index: 2,
+                       value: f64::NEG_INFINITY,
+                   }));
+        assert_eq!(try_tautstring(&[f64::MAX, 1.0, f64::MAX], 1e300),
+                   Ok(tautstring(&[f64::MAX, 1.0, f64::MAX], 1e300)));
+        assert_eq!(try_tautstring(&input, f64::NAN), Err(TvError::NanLambda));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn tautstring_checked_test_matches_try_tautstring() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        assert_eq!(tautstring_checked(&input, 3.0), Ok(tautstring(&input, 3.0)));
+        assert_eq!(tautstring_checked(&[1.0, f64::INFINITY], 3.0), try_tautstring(&[1.0, f64::INFINITY], 3.0));
+    }
+
+    #[test]
+    fn try_tautstring_test_empty_input() {
+        assert_eq!(try_tautstring::<f64>(&[], 1.0), Ok(vec![]));
+    }
+
+    #[test]
+    fn try_condat_into_test_matches_condat() {
+        let input = [1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let mut output = [0.0; 8];
+        assert_eq!(try_condat_into(&input, 3.0, &mut output), Ok(()));
+        assert_eq!(&output[..], &condat(&input, 3.0)[..]);
+    }
+
+    #[test]
+    fn try_condat_into_test_errors() {
+        let input = [1.0, 2.1, 5.2, f64::NAN];
+        let mut output = [7.0; 4];
+        assert_eq!(try_condat_into(&input, 1.0, &mut output[..3]),
+                   Err(TvError::LengthMismatch { input: 4, output: 3 }));
+        assert_eq!(try_condat_into(&input[..3], -1.0, &mut output[..3]),
+                   Err(TvError::NegativeLambda));
+        assert!(matches!(try_condat_into(&input, 1.0, &mut output),
+                         Err(TvError::NonFiniteInput { index: 3, .. })));
+        assert_eq!(output, [7.0; 4]);
+        assert_eq!(try_condat_into::<f64>(&[], 1.0, &mut []), Ok(()));
+    }
+}
@@ -0,0 +1,89 @@
+// Non-generic versions of `condat_in_place` for `f32` and `f64`. They
+// run the loop of `tv1d::condat` monomorphized for the float type,
+// whose `TvScalar::from_usize` converts the segment lengths with `as`
+// and cannot fail, so the hot loop is as fast as a copy written for
+// the type and the outputs are identical.
+
+#[cfg(all(feature = "cross-check", debug_assertions))]
+use cross_check;
+use find_segments;
+#[cfg(feature = "tracing")]
+use utils;
+
+macro_rules! condat_float {
+    ($name:ident, $name_in_place:ident, $float:ty) => {
+        /// Denoises the input values with the same algorithm as
+        /// [`tv1d::condat`](../tv1d/fn.condat.html), specialized for
+        #[doc = concat!("`", stringify!($float), "`.")]
+        ///
+        /// The output is identical to the output of `tv1d::condat`.
+        ///
+        /// # Panics
+        /// Panics if input vector's length is `0`, or `lambda` is NaN
+        /// or less than `0`.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use tv1d;
+        ///
+        #[doc = concat!("let input: Vec<", stringify!($float), "> = vec![1.0, 2.0, 3.0, 4.0, 5.0];")]
+        #[doc = concat!("assert_eq!(tv1d::", stringify!($name), "(&input, 10.0), vec![3.0; 5]);")]
+        /// ```
+        pub fn $name(input: &[$float], lambda: $float) -> Vec<$float> {
+            let mut output = input.to_vec();
+            $name_in_place(&mut output, lambda);
+            output
+        }
+
+        /// Denoises the values in place with the same algorithm as
+        #[doc = concat!("[`tv1d::", stringify!($name), "`](../tv1d/fn.", stringify!($name), ".html).")]
+        ///
+        /// # Panics
+        /// Panics if the length of `values` is `0`, or `lambda` is NaN
+        /// or less than `0`.
+        pub fn $name_in_place(values: &mut [$float], lambda: $float) {
+            trace_span!(stringify!($name), len = values.len(), lambda = f64::from(lambda));
+
+            #[cfg(all(feature = "cross-check", debug_assertions))]
+            let input = values.to_vec();
+
+            find_segments(values, lambda, &mut ());
+            trace_event!(segments = utils::count_segments(values), "Denoised.");
+
+            #[cfg(all(feature = "cross-check", debug_assertions))]
+            cross_check::check(stringify!($name), cross_check::Reference::DynamicProgramming, &input, lambda, values);
+        }
+    };
+}
+
+condat_float!(condat_f32, condat_f32_in_place, f32);
+condat_float!(condat_f64, condat_f64_in_place, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    #[test]
+    fn condat_f64_test_matches_condat() {
+        let input: Vec<f64> = (0..2000).map(|i| ((i / 70) % 4) as f64 * 3.0 + ((i * 7919) % 17) as f64 * 0.1).collect();
+        for &lambda in &[0.0, 0.5, 2.0, 50.0, 1e6, f64::INFINITY] {
+            assert_eq!(condat_f64(&input, lambda), condat(&input, lambda));
+        }
+    }
+
+    #[test]
+    fn condat_f32_test_matches_condat() {
+        let input: Vec<f32> = (0..2000).map(|i| ((i / 70) % 4) as f32 * 3.0 + ((i * 7919) % 17) as f32 * 0.1).collect();
+        for &lambda in &[0.0, 0.5, 2.0, 50.0, 1e6, f32::INFINITY] {
+            assert_eq!(condat_f32(&input, lambda), condat(&input, lambda));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_f64_test_empty_input() {
+        condat_f64(&[], 1.0);
+    }
+}
@@ -0,0 +1,168 @@
+//! A C interface to the denoisers, for C and C++ code linking the
+//! crate built as a `cdylib`, e.g. with
+//! `cargo rustc --release --features ffi --crate-type cdylib`. The
+//! declarations are in `include/tv1d.h`.
+//!
+//! Every function denoises `length` values read from `input` into the
+//! `length` values at `output`, which may be the same pointer as
+//! `input` to denoise in place, and returns `TV1D_OK` on success or one
+//! of the other `TV1D_ERROR_*` codes, leaving `output` untouched, on
+//! invalid arguments. The functions never unwind into C.
+
+use std::os::raw::c_int;
+use std::ptr;
+use std::slice;
+
+use error::TvError;
+use fallible::{try_condat_into, try_tautstring};
+use scalar::TvFloat;
+
+/// The values were denoised.
+pub const TV1D_OK: c_int = 0;
+/// `input` or `output` is a null pointer, although `length` is not `0`.
+pub const TV1D_ERROR_NULL_POINTER: c_int = 1;
+/// The input contains a NaN or infinite value.
+pub const TV1D_ERROR_NON_FINITE_INPUT: c_int = 2;
+/// `lambda` is NaN.
+pub const TV1D_ERROR_NAN_LAMBDA: c_int = 3;
+/// `lambda` is less than `0`.
+pub const TV1D_ERROR_NEGATIVE_LAMBDA: c_int = 4;
+/// The denoised output overflowed, although every input value is
+/// finite. `output` may hold the overflowed output.
+pub const TV1D_ERROR_OVERFLOW: c_int = 5;
+/// Any other error, which the functions of this module do not return
+/// today.
+pub const TV1D_ERROR_OTHER: c_int = 6;
+
+/// Denoises `length` `double` values with
+/// [`tv1d::condat`](../fn.condat.html), without allocating unless
+/// denoising in place.
+///
+/// # Safety
+/// Unless `length` is `0`, `input` must point to `length` readable
+/// values and `output` to `length` writable values, which do not
+/// overlap unless `output` is `input`.
+#[no_mangle]
+pub unsafe extern "C" fn tv1d_condat(input: *const f64, length: usize, lambda: f64, output: *mut f64) -> c_int {
+    denoise(input, length, lambda, output, try_condat_into)
+}
+
+/// Denoises `length` `float` values with
+/// [`tv1d::condat`](../fn.condat.html), like
+/// [`tv1d_condat`](fn.tv1d_condat.html).
+///
+/// # Safety
+/// Unless `length` is `0`, `input` must point to `length` readable
+/// values and `output` to `length` writable values, which do not
+/// overlap unless `output` is `input`.
+#[no_mangle]
+pub unsafe extern "C" fn tv1d_condat_f32(input: *const f32, length: usize, lambda: f32, output: *mut f32) -> c_int {
+    denoise(input, length, lambda, output, try_condat_into)
+}
+
+/// Denoises `length` `double` values with
+/// [`tv1d::tautstring`](../fn.tautstring.html).
+///
+/// # Safety
+/// Unless `length` is `0`, `input` must point to `length` readable
+/// values and `output` to `length` writable values, which do not
+/// overlap unless `output` is `input`.
+#[no_mangle]
+pub unsafe extern "C" fn tv1d_tautstring(input: *const f64, length: usize, lambda: f64, output: *mut f64) -> c_int {
+    denoise(input, length, lambda, output, try_tautstring_into)
+}
+
+/// Denoises `length` `float` values with
+/// [`tv1d::tautstring`](../fn.tautstring.html), like
+/// [`tv1d_tautstring`](fn.tv1d_tautstring.html).
+///
+/// # Safety
+/// Unless `length` is `0`, `input` must point to `length` readable
+/// values and `output` to `length` writable values, which do not
+/// overlap unless `output` is `input`.
+#[no_mangle]
+pub unsafe extern "C" fn tv1d_tautstring_f32(input: *const f32, length: usize, lambda: f32, output: *mut f32) -> c_int {
+    denoise(input, length, lambda, output, try_tautstring_into)
+}
+
+fn try_tautstring_into<T: TvFloat>(input: &[T], lambda: T, output: &mut [T]) -> Result<(), TvError> {
+    output.copy_from_slice(&try_tautstring(input, lambda)?);
+    Ok(())
+}
+
+unsafe fn denoise<T, F>(input: *const T, length: usize, lambda: T, output: *mut T, solve: F) -> c_int
+    where T: TvFloat,
+          F: Fn(&[T], T, &mut [T]) -> Result<(), TvError>
+{
+    let result = if length == 0 {
+        solve(&[], lambda, &mut [])
+    } else if input.is_null() || output.is_null() {
+        return TV1D_ERROR_NULL_POINTER;
+    } else if ptr::eq(input, output) {
+        // Denoising in place; the input is copied first, as it must not
+        // be borrowed while the output is written.
+        let copy = slice::from_raw_parts(input, length).to_vec();
+        solve(&copy, lambda, slice::from_raw_parts_mut(output, length))
+    } else {
+        solve(slice::from_raw_parts(input, length), lambda, slice::from_raw_parts_mut(output, length))
+    };
+
+    match result {
+        Ok(()) => TV1D_OK,
+        Err(TvError::NonFiniteInput { .. }) => TV1D_ERROR_NON_FINITE_INPUT,
+        Err(TvError::NanLambda) => TV1D_ERROR_NAN_LAMBDA,
+        Err(TvError::NegativeLambda) => TV1D_ERROR_NEGATIVE_LAMBDA,
+        Err(TvError::Overflow { .. }) => TV1D_ERROR_OVERFLOW,
+        Err(TvError::LengthMismatch { .. }) | Err(TvError::ZeroMinSegmentLen) => TV1D_ERROR_OTHER,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {condat, tautstring};
+
+    #[test]
+    fn tv1d_condat_test_matches_condat() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let mut output = vec![0.0; input.len()];
+        assert_eq!(unsafe { tv1d_condat(input.as_ptr(), input.len(), 1.5, output.as_mut_ptr()) }, TV1D_OK);
+        assert_eq!(output, condat(&input, 1.5));
+
+        let input: Vec<f32> = input.iter().map(|&value| value as f32).collect();
+        let mut output = vec![0.0; input.len()];
+        assert_eq!(unsafe { tv1d_tautstring_f32(input.as_ptr(), input.len(), 1.5, output.as_mut_ptr()) }, TV1D_OK);
+        assert_eq!(output, tautstring(&input, 1.5));
+    }
+
+    #[test]
+    fn tv1d_condat_test_in_place() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let mut values = input.clone();
+        assert_eq!(unsafe { tv1d_tautstring(values.as_ptr(), values.len(), 1.5, values.as_mut_ptr()) }, TV1D_OK);
+        assert_eq!(values, tautstring(&input, 1.5));
+
+        let mut values: Vec<f32> = input.iter().map(|&value| value as f32).collect();
+        let expected = condat(&values, 1.5);
+        assert_eq!(unsafe { tv1d_condat_f32(values.as_ptr(), values.len(), 1.5, values.as_mut_ptr()) }, TV1D_OK);
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn tv1d_condat_test_errors() {
+        let input = [1.0, f64::NAN, 3.0];
+        let mut output = vec![0.0; 3];
+        assert_eq!(unsafe { tv1d_condat(input.as_ptr(), 3, 1.0, output.as_mut_ptr()) }, TV1D_ERROR_NON_FINITE_INPUT);
+        assert_eq!(output, vec![0.0; 3]);
+        assert_eq!(unsafe { tv1d_condat(input.as_ptr(), 1, -1.0, output.as_mut_ptr()) }, TV1D_ERROR_NEGATIVE_LAMBDA);
+        assert_eq!(unsafe { tv1d_tautstring(input.as_ptr(), 1, f64::NAN, output.as_mut_ptr()) },
+                   TV1D_ERROR_NAN_LAMBDA);
+        assert_eq!(unsafe { tv1d_tautstring(input.as_ptr(), 3, 1.0, ptr::null_mut()) }, TV1D_ERROR_NULL_POINTER);
+    }
+
+    #[test]
+    fn tv1d_condat_test_empty_input() {
+        assert_eq!(unsafe { tv1d_condat(ptr::null(), 0, 1.0, ptr::null_mut()) }, TV1D_OK);
+        assert_eq!(unsafe { tv1d_condat(ptr::null(), 0, -1.0, ptr::null_mut()) }, TV1D_ERROR_NEGATIVE_LAMBDA);
+    }
+}
@@ -0,0 +1,517 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops;
+use std::path::{Path, PathBuf};
+
+use lazy::{condat_lazy, CondatState};
+use scalar::TvFloat;
+use segments::Condat;
+
+/// A sample of a raw capture file, stored as little-endian bytes
+/// without any header or separator.
+pub trait RawSample: TvFloat + ops::AddAssign<Self> {
+    /// The number of bytes of a sample.
+    const SIZE: usize;
+
+    /// Reads a sample from its `SIZE` little-endian bytes.
+    fn read_le(bytes: &[u8]) -> Self;
+
+    /// Writes the sample as `SIZE` little-endian bytes.
+    fn write_le(self, bytes: &mut [u8]);
+}
+
+impl RawSample for f32 {
+    const SIZE: usize = 4;
+
+    fn read_le(bytes: &[u8]) -> f32 {
+        let mut le = [0; 4];
+        le.copy_from_slice(bytes);
+        f32::from_le_bytes(le)
+    }
+
+    fn write_le(self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl RawSample for f64 {
+    const SIZE: usize = 8;
+
+    fn read_le(bytes: &[u8]) -> f64 {
+        let mut le = [0; 8];
+        le.copy_from_slice(bytes);
+        f64::from_le_bytes(le)
+    }
+
+    fn write_le(self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_le_bytes());
+    }
+}
+
+/// Denoises a raw capture file with
+/// [`tv1d::condat`](../tv1d/fn.condat.html), reading it in chunks of
+/// `chunk_len` samples, and writes the denoised samples to `output` in
+/// the same format.
+///
+/// After every chunk, the denoised samples written so far are synced
+/// to disk and a checkpoint is written to `checkpoint`, holding the
+/// offset of the first sample not yet written and the state of the
+/// [`CondatState`](struct.CondatState.html) denoising the file. If
+/// `checkpoint` exists when the function is called, for example after
+/// a crash, denoising resumes from it instead of from the start of
+/// the file. The checkpoint is removed once the whole file is
+/// denoised.
+///
+/// Only the chunk and the samples of the current segment are held in
+/// memory, so files much larger than memory can be denoised.
+///
+/// # Errors
+/// Returns an error if reading the input, writing the output or
+/// writing the checkpoint fails, if the input ends in the middle of a
+/// sample, or if the checkpoint is invalid or was written with a
+/// different `lambda`.
+///
+/// # Panics
+/// Panics if `lambda` is NaN or less than `0`, or `chunk_len` is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let directory = std::env::temp_dir();
+/// let input = directory.join("tv1d_doc_input.raw");
+/// let output = directory.join("tv1d_doc_output.raw");
+/// let checkpoint = directory.join("tv1d_doc_output.checkpoint");
+///
+/// let samples = [1.0f64, 2.0, 3.0, 4.0, 5.0];
+/// let bytes: Vec<u8> = samples.iter().flat_map(|x| x.to_le_bytes().to_vec()).collect();
+/// std::fs::write(&input, bytes).unwrap();
+///
+/// tv1d::denoise_file(&input, &output, &checkpoint, 10.0f64, 2).unwrap();
+/// assert_eq!(std::fs::read(&output).unwrap(),
+///            [3.0f64; 5].iter().flat_map(|x| x.to_le_bytes().to_vec()).collect::<Vec<_>>());
+/// assert!(!checkpoint.exists());
+/// ```
+pub fn denoise_file<T, I, O, C>(input: I,
+                                output: O,
+                                checkpoint: C,
+                                lambda: T,
+                                chunk_len: usize)
+                                -> io::Result<()>
+    where T: RawSample,
+          I: AsRef<Path>,
+          O: AsRef<Path>,
+          C: AsRef<Path>
+{
+    denoise_file_chunks(input.as_ref(),
+                        output.as_ref(),
+                        checkpoint.as_ref(),
+                        lambda,
+                        chunk_len,
+                        None)
+}
+
+// Denoises the file like `denoise_file`, but stops without finishing
+// after `max_chunks` chunks if given, as if the process crashed.
+fn denoise_file_chunks<T: RawSample>(input: &Path,
+                                     output: &Path,
+                                     checkpoint: &Path,
+                                     lambda: T,
+                                     chunk_len: usize,
+                                     max_chunks: Option<usize>)
+                                     -> io::Result<()> {
+    assert!(chunk_len > 0, "Chunk length must be greater than 0.");
+
+    let mut state = CondatState::new(lambda);
+    let mut position = 0;
+    if let Some((restored, start)) = read_checkpoint(checkpoint, lambda)? {
+        state = restored;
+        position = start;
+    }
+
+    let mut input = File::open(input)?;
+    input.seek(SeekFrom::Start((position * T::SIZE) as u64))?;
+    let mut output = OpenOptions::new().write(true).create(true).truncate(false).open(output)?;
+    // Drop any samples written after the checkpoint.
+    output.set_len((position * T::SIZE) as u64)?;
+    output.seek(SeekFrom::End(0))?;
+
+    let mut bytes = vec![0; chunk_len * T::SIZE];
+    let mut chunk = Vec::with_capacity(chunk_len);
+    let mut output_bytes = Vec::with_capacity(chunk_len * T::SIZE);
+    let mut chunks = 0;
+    loop {
+        let len = read_full(&mut input, &mut bytes)?;
+        if !len.is_multiple_of(T::SIZE) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      "input file ends in the middle of a sample"));
+        }
+        chunk.clear();
+        chunk.extend(bytes[..len].chunks(T::SIZE).map(T::read_le));
+        position += chunk.len();
+
+        write_samples(&mut output, state.feed(&chunk), &mut output_bytes)?;
+        output.sync_data()?;
+        write_checkpoint(checkpoint, &state, position - state.pending())?;
+
+        chunks += 1;
+        if max_chunks == Some(chunks) {
+            return Ok(());
+        }
+        if len < bytes.len() {
+            break;
+        }
+    }
+
+    write_samples(&mut output, state.finish(), &mut output_bytes)?;
+    output.sync_data()?;
+    fs::remove_file(checkpoint)
+}
+
+// The number of samples encoded at once when writing to a writer.
+pub(crate) const WRITE_CHUNK_LEN: usize = 8192;
+
+/// Denoises the input values with
+/// [`tv1d::condat`](../tv1d/fn.condat.html) and writes the denoised
+/// output to `writer` as raw little-endian samples.
+///
+/// The output is produced lazily and written in small chunks, so it
+/// is never held in memory as a whole. Together with a memory-mapped
+/// input file, this denoises signals larger than memory.
+///
+/// # Errors
+/// Returns an error if writing to `writer` fails.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = [1.0f32, 2.0, 3.0, 4.0, 5.0];
+/// let mut output = Vec::new();
+/// tv1d::denoise_to_writer(&input, 10.0, &mut output).unwrap();
+/// assert_eq!(output, [3.0f32; 5].iter().flat_map(|x| x.to_le_bytes().to_vec()).collect::<Vec<_>>());
+/// ```
+pub fn denoise_to_writer<T, W>(input: &[T], lambda: T, mut writer: W) -> io::Result<()>
+    where T: RawSample,
+          W: Write
+{
+    let mut samples = condat_lazy(input, lambda);
+    let mut bytes = Vec::with_capacity(WRITE_CHUNK_LEN * T::SIZE);
+    while samples.len() > 0 {
+        write_samples(&mut writer, samples.by_ref().take(WRITE_CHUNK_LEN), &mut bytes)?;
+    }
+    writer.flush()
+}
+
+/// Denoises raw little-endian samples read from `reader` with
+/// [`tv1d::condat`](../tv1d/fn.condat.html) and writes the denoised
+/// output to `writer` in the same format.
+///
+/// The input is read in chunks of `chunk_len` samples, and only the
+/// chunk and the samples of the current segment are held in memory.
+/// An empty input yields an empty output.
+///
+/// # Errors
+/// Returns an error if reading from `reader` or writing to `writer`
+/// fails, or if the input ends in the middle of a sample.
+///
+/// # Panics
+/// Panics if `lambda` is NaN or less than `0`, or `chunk_len` is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input: Vec<u8> = [1.0f64, 2.0, 3.0, 4.0, 5.0].iter().flat_map(|x| x.to_le_bytes().to_vec()).collect();
+/// let mut output = Vec::new();
+/// tv1d::denoise_reader(&input[..], &mut output, 10.0f64, 2).unwrap();
+/// assert_eq!(output, [3.0f64; 5].iter().flat_map(|x| x.to_le_bytes().to_vec()).collect::<Vec<_>>());
+/// ```
+pub fn denoise_reader<T, R, W>(mut reader: R, mut writer: W, lambda: T, chunk_len: usize) -> io::Result<()>
+    where T: RawSample,
+          R: Read,
+          W: Write
+{
+    assert!(chunk_len > 0, "Chunk length must be greater than 0.");
+
+    let mut state = CondatState::new(lambda);
+    let mut bytes = vec![0; chunk_len * T::SIZE];
+    let mut chunk = Vec::with_capacity(chunk_len);
+    let mut output_bytes = Vec::with_capacity(chunk_len * T::SIZE);
+    loop {
+        let len = read_full(&mut reader, &mut bytes)?;
+        if !len.is_multiple_of(T::SIZE) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      "input ends in the middle of a sample"));
+        }
+        chunk.clear();
+        chunk.extend(bytes[..len].chunks(T::SIZE).map(T::read_le));
+        write_samples(&mut writer, state.feed(&chunk), &mut output_bytes)?;
+        if len < bytes.len() {
+            break;
+        }
+    }
+    write_samples(&mut writer, state.finish(), &mut output_bytes)?;
+    writer.flush()
+}
+
+// Reads until `bytes` is full or the end of the file, returning the
+// number of bytes read.
+pub(crate) fn read_full<R: Read>(reader: &mut R, bytes: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < bytes.len() {
+        match reader.read(&mut bytes[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(len)
+}
+
+pub(crate) fn write_samples<T, W, S>(writer: &mut W, samples: S, bytes: &mut Vec<u8>) -> io::Result<()>
+    where T: RawSample,
+          W: Write,
+          S: Iterator<Item = T>
+{
+    bytes.clear();
+    for sample in samples {
+        let len = bytes.len();
+        bytes.resize(len + T::SIZE, 0);
+        sample.write_le(&mut bytes[len..]);
+    }
+    writer.write_all(bytes)
+}
+
+const CHECKPOINT_MAGIC: &[u8] = b"TV1DCKPT";
+
+// Writes the offset of the first sample not yet written, where the
+// state's buffer starts, and the state of the algorithm. The buffer
+// itself is read again from the input on resume. The checkpoint is
+// written to a temporary file first, so that a crash never leaves a
+// partial checkpoint behind.
+fn write_checkpoint<T: RawSample>(path: &Path, state: &CondatState<T>, start: usize) -> io::Result<()> {
+    let mut bytes = CHECKPOINT_MAGIC.to_vec();
+    bytes.push(T::SIZE as u8);
+    bytes.extend_from_slice(&(start as u64).to_le_bytes());
+    let mut values = vec![state.lambda];
+    match state.condat {
+        Some(ref condat) => {
+            bytes.push(1);
+            for &index in &[condat.current_input_index, condat.kplus, condat.kminus] {
+                bytes.extend_from_slice(&(index as u64).to_le_bytes());
+            }
+            values.extend_from_slice(&[condat.umin,
+                                       condat.umax,
+                                       condat.segment_lower_bound,
+                                       condat.segment_upper_bound]);
+        }
+        None => bytes.push(0),
+    }
+    for value in values {
+        let len = bytes.len();
+        bytes.resize(len + T::SIZE, 0);
+        value.write_le(&mut bytes[len..]);
+    }
+
+    let temporary = temporary_path(path);
+    {
+        let mut file = File::create(&temporary)?;
+        file.write_all(&bytes)?;
+        file.sync_data()?;
+    }
+    fs::rename(temporary, path)
+}
+
+// Reads a checkpoint written by `write_checkpoint`, returning the
+// restored state and the offset of the first sample not yet written,
+// or `None` if there is no checkpoint.
+fn read_checkpoint<T: RawSample>(path: &Path, lambda: T) -> io::Result<Option<(CondatState<T>, usize)>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error),
+    };
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid checkpoint");
+
+    let mut reader = CheckpointReader { bytes: &bytes };
+    if reader.take(CHECKPOINT_MAGIC.len()).ok_or_else(invalid)? != CHECKPOINT_MAGIC ||
+       reader.take(1).ok_or_else(invalid)? != [T::SIZE as u8] {
+        return Err(invalid());
+    }
+    let start = reader.index().ok_or_else(invalid)?;
+    let has_condat = reader.take(1).ok_or_else(invalid)?[0] == 1;
+    let mut indices = [0; 3];
+    if has_condat {
+        for index in &mut indices {
+            *index = reader.index().ok_or_else(invalid)?;
+        }
+    }
+    let checkpoint_lambda: T = reader.value().ok_or_else(invalid)?;
+    if checkpoint_lambda != lambda {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                  "checkpoint was written with a different lambda"));
+    }
+
+    let mut state = CondatState::new(lambda);
+    if has_condat {
+        state.condat = Some(Condat {
+            lambda,
+            current_input_index: indices[0],
+            segment_start: 0,
+            umin: reader.value().ok_or_else(invalid)?,
+            umax: reader.value().ok_or_else(invalid)?,
+            segment_lower_bound: reader.value().ok_or_else(invalid)?,
+            segment_upper_bound: reader.value().ok_or_else(invalid)?,
+            kplus: indices[1],
+            kminus: indices[2],
+        });
+    }
+    Ok(Some((state, start)))
+}
+
+struct CheckpointReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> CheckpointReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn index(&mut self) -> Option<usize> {
+        let mut le = [0; 8];
+        le.copy_from_slice(self.take(8)?);
+        Some(u64::from_le_bytes(le) as usize)
+    }
+
+    fn value<T: RawSample>(&mut self) -> Option<T> {
+        self.take(T::SIZE).map(T::read_le)
+    }
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut temporary = OsString::from(path.as_os_str());
+    temporary.push(".tmp");
+    PathBuf::from(temporary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    use std::env;
+
+    fn paths(name: &str) -> (PathBuf, PathBuf, PathBuf) {
+        let directory = env::temp_dir();
+        (directory.join(format!("tv1d_{}_input.raw", name)),
+         directory.join(format!("tv1d_{}_output.raw", name)),
+         directory.join(format!("tv1d_{}_output.checkpoint", name)))
+    }
+
+    fn write_raw(path: &Path, samples: &[f64]) {
+        let mut bytes = Vec::new();
+        write_samples(&mut bytes, samples.iter().cloned(), &mut Vec::new()).unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    fn read_raw(path: &Path) -> Vec<f64> {
+        fs::read(path).unwrap().chunks(8).map(f64::read_le).collect()
+    }
+
+    fn signal() -> Vec<f64> {
+        (0..1000).map(|i| ((i / 100) % 3) as f64 * 10.0 + ((i * 7919) % 13) as f64 * 0.3).collect()
+    }
+
+    #[test]
+    fn denoise_file_test_matches_condat() {
+        let (input, output, checkpoint) = paths("matches_condat");
+        let samples = signal();
+        write_raw(&input, &samples);
+        for &chunk_len in &[1, 7, 1000, 4096] {
+            denoise_file(&input, &output, &checkpoint, 2.0, chunk_len).unwrap();
+            assert_eq!(read_raw(&output), condat(&samples, 2.0));
+            assert!(!checkpoint.exists());
+        }
+    }
+
+    #[test]
+    fn denoise_file_test_resume() {
+        let (input, output, checkpoint) = paths("resume");
+        let samples = signal();
+        write_raw(&input, &samples);
+        for &lambda in &[0.0, 2.0, 1000.0, f64::INFINITY] {
+            denoise_file_chunks(&input, &output, &checkpoint, lambda, 64, Some(5)).unwrap();
+            assert!(checkpoint.exists());
+            // Samples written after the checkpoint are dropped on resume.
+            OpenOptions::new().append(true).open(&output).unwrap().write_all(&[1, 2, 3]).unwrap();
+            denoise_file_chunks(&input, &output, &checkpoint, lambda, 64, Some(3)).unwrap();
+
+            denoise_file(&input, &output, &checkpoint, lambda, 64).unwrap();
+            // `condat` returns the mean for a `lambda` of at least
+            // `lambda_max`, which rounds differently.
+            let denoised = read_raw(&output);
+            assert_eq!(denoised.len(), samples.len());
+            for (value, expected) in denoised.iter().zip(condat(&samples, lambda).iter()) {
+                assert!((value - expected).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn denoise_file_test_errors() {
+        let (input, output, checkpoint) = paths("errors");
+        fs::write(&input, [0; 12]).unwrap();
+        let error = denoise_file(&input, &output, &checkpoint, 1.0f64, 4).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        write_raw(&input, &signal());
+        denoise_file_chunks(&input, &output, &checkpoint, 1.0f64, 64, Some(1)).unwrap();
+        let error = denoise_file(&input, &output, &checkpoint, 2.0f64, 64).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        fs::remove_file(&checkpoint).unwrap();
+    }
+
+    #[test]
+    fn denoise_to_writer_test_matches_condat() {
+        let samples = signal();
+        let mut bytes = Vec::new();
+        denoise_to_writer(&samples, 2.0, &mut bytes).unwrap();
+        let output: Vec<f64> = bytes.chunks(8).map(f64::read_le).collect();
+        assert_eq!(output, condat(&samples, 2.0));
+    }
+
+    #[test]
+    fn denoise_reader_test_matches_condat() {
+        let samples: Vec<f32> = signal().iter().map(|&x| x as f32).collect();
+        let mut input = Vec::new();
+        write_samples(&mut input, samples.iter().cloned(), &mut Vec::new()).unwrap();
+        for &chunk_len in &[1, 7, 1000, 4096] {
+            let mut bytes = Vec::new();
+            denoise_reader(&input[..], &mut bytes, 2.0f32, chunk_len).unwrap();
+            let output: Vec<f32> = bytes.chunks(4).map(f32::read_le).collect();
+            assert_eq!(output, condat(&samples, 2.0));
+        }
+
+        let mut bytes = Vec::new();
+        denoise_reader(&[][..], &mut bytes, 2.0f32, 16).unwrap();
+        assert!(bytes.is_empty());
+        let error = denoise_reader(&input[..3], &mut bytes, 2.0f32, 16).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
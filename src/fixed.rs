@@ -0,0 +1,385 @@
+use std::cmp;
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+use std::ops;
+
+use condat_in_place;
+use scalar::{private, TvScalar};
+
+/// The number of bits below the least significant bit of the fixed
+/// point format kept by the intermediate values of `condat_fixed`.
+pub(crate) const GUARD_BITS: u32 = 16;
+
+/// The largest `lambda` of
+/// [`tv1d::condat_fixed`](../tv1d/fn.condat_fixed.html), in least
+/// significant bits, with which its intermediate values cannot
+/// overflow.
+pub const MAX_FIXED_LAMBDA: u64 = 1 << 44;
+
+/// A fixed point number format that
+/// [`tv1d::condat_fixed`](../tv1d/fn.condat_fixed.html) can denoise.
+pub trait FixedPoint: Copy + Send + Sync + 'static {
+    /// The number of fractional bits of the format.
+    const FRACTIONAL_BITS: u32;
+
+    /// Returns the underlying integer of the value, i.e. the value in
+    /// units of its least significant bit.
+    fn to_bits(self) -> i64;
+
+    /// Returns the value whose underlying integer is `bits`, saturated
+    /// to the range of the format.
+    fn from_bits(bits: i64) -> Self;
+}
+
+macro_rules! q_format {
+    ($name:ident, $integer:ty, $bits:expr) => {
+        #[doc = concat!("A signed fixed point number with ", stringify!($bits), " fractional bits stored in an")]
+        #[doc = concat!("`", stringify!($integer), "`, representing values in `[-1, 1)`.")]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(pub $integer);
+
+        impl $name {
+            /// Returns the value nearest to `value`, saturated to
+            /// `[-1, 1)`.
+            pub fn from_f64(value: f64) -> $name {
+                $name::from_bits((value * (1u64 << $bits) as f64).round() as i64)
+            }
+
+            /// Returns the value as an `f64`, which represents it
+            /// exactly.
+            pub fn to_f64(self) -> f64 {
+                f64::from(self.0) / (1u64 << $bits) as f64
+            }
+        }
+
+        impl FixedPoint for $name {
+            const FRACTIONAL_BITS: u32 = $bits;
+
+            fn to_bits(self) -> i64 {
+                i64::from(self.0)
+            }
+
+            fn from_bits(bits: i64) -> $name {
+                $name(bits.clamp(i64::from(<$integer>::MIN), i64::from(<$integer>::MAX)) as $integer)
+            }
+        }
+    };
+}
+
+q_format!(Q15, i16, 15);
+q_format!(Q31, i32, 31);
+
+/// A value of the fixed point format `Q` widened to an `i64` with 16
+/// guard bits below the least significant bit of `Q`, the scalar in
+/// which [`tv1d::condat_fixed`](../tv1d/fn.condat_fixed.html)
+/// denoises.
+///
+/// `Fixed` is a [`TvScalar`](trait.TvScalar.html), so the generic
+/// solvers can denoise fixed point values in integer arithmetic only.
+/// Like the integers, its divisions truncate, here to a multiple of
+/// `2^-16` least significant bits of `Q`, and its sums overflow the
+/// `i64` for values and `lambda` beyond about `2^44` least significant
+/// bits.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::{Fixed, Q15};
+///
+/// let input: Vec<Fixed<Q15>> = [0.25, 0.5, 0.75].iter().map(|&x| Fixed::new(Q15::from_f64(x))).collect();
+/// let output = tv1d::condat(&input, Fixed::new(Q15::from_f64(0.5)));
+/// assert_eq!(output[0].round(), Q15::from_f64(0.5));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Fixed<Q> {
+    bits: i64,
+    format: PhantomData<Q>,
+}
+
+impl<Q: FixedPoint> Fixed<Q> {
+    // The number of fractional bits of the widened value.
+    const SHIFT: u32 = Q::FRACTIONAL_BITS + GUARD_BITS;
+
+    /// Returns `value` widened exactly.
+    pub fn new(value: Q) -> Fixed<Q> {
+        Fixed::from_bits(value.to_bits() << GUARD_BITS)
+    }
+
+    /// Returns the value whose underlying integer, in units of `2^-16`
+    /// least significant bits of `Q`, is `bits`.
+    pub fn from_bits(bits: i64) -> Fixed<Q> {
+        Fixed {
+            bits,
+            format: PhantomData,
+        }
+    }
+
+    /// Returns the underlying integer of the value, in units of
+    /// `2^-16` least significant bits of `Q`.
+    pub fn to_bits(self) -> i64 {
+        self.bits
+    }
+
+    /// Returns the nearest value of `Q`, saturated to its range.
+    pub fn round(self) -> Q {
+        let half = 1 << (GUARD_BITS - 1);
+        Q::from_bits((self.bits + half) >> GUARD_BITS)
+    }
+}
+
+impl<Q> PartialEq for Fixed<Q> {
+    fn eq(&self, other: &Fixed<Q>) -> bool {
+        self.bits == other.bits
+    }
+}
+
+impl<Q> Eq for Fixed<Q> {}
+
+impl<Q> PartialOrd for Fixed<Q> {
+    fn partial_cmp(&self, other: &Fixed<Q>) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<Q> Ord for Fixed<Q> {
+    fn cmp(&self, other: &Fixed<Q>) -> cmp::Ordering {
+        self.bits.cmp(&other.bits)
+    }
+}
+
+impl<Q: FixedPoint> ops::Add for Fixed<Q> {
+    type Output = Fixed<Q>;
+
+    fn add(self, other: Fixed<Q>) -> Fixed<Q> {
+        Fixed::from_bits(self.bits + other.bits)
+    }
+}
+
+impl<Q: FixedPoint> ops::Sub for Fixed<Q> {
+    type Output = Fixed<Q>;
+
+    fn sub(self, other: Fixed<Q>) -> Fixed<Q> {
+        Fixed::from_bits(self.bits - other.bits)
+    }
+}
+
+impl<Q: FixedPoint> ops::Mul for Fixed<Q> {
+    type Output = Fixed<Q>;
+
+    fn mul(self, other: Fixed<Q>) -> Fixed<Q> {
+        Fixed::from_bits(((i128::from(self.bits) * i128::from(other.bits)) >> Fixed::<Q>::SHIFT) as i64)
+    }
+}
+
+impl<Q: FixedPoint> ops::Div for Fixed<Q> {
+    type Output = Fixed<Q>;
+
+    fn div(self, other: Fixed<Q>) -> Fixed<Q> {
+        Fixed::from_bits(((i128::from(self.bits) << Fixed::<Q>::SHIFT) / i128::from(other.bits)) as i64)
+    }
+}
+
+impl<Q: FixedPoint> ops::Neg for Fixed<Q> {
+    type Output = Fixed<Q>;
+
+    fn neg(self) -> Fixed<Q> {
+        Fixed::from_bits(-self.bits)
+    }
+}
+
+impl<Q: FixedPoint> ops::AddAssign for Fixed<Q> {
+    fn add_assign(&mut self, other: Fixed<Q>) {
+        self.bits += other.bits;
+    }
+}
+
+impl<Q: FixedPoint> ops::SubAssign for Fixed<Q> {
+    fn sub_assign(&mut self, other: Fixed<Q>) {
+        self.bits -= other.bits;
+    }
+}
+
+impl<Q> private::Sealed for Fixed<Q> {}
+
+impl<Q: FixedPoint> TvScalar for Fixed<Q> {
+    fn zero() -> Fixed<Q> {
+        Fixed::from_bits(0)
+    }
+
+    fn one() -> Fixed<Q> {
+        Fixed::from_bits(1 << Fixed::<Q>::SHIFT)
+    }
+
+    fn from_usize(value: usize) -> Option<Fixed<Q>> {
+        i64::try_from(value).ok()
+            .and_then(|value| value.checked_mul(1 << Fixed::<Q>::SHIFT))
+            .map(Fixed::from_bits)
+    }
+
+    fn from_f64(value: f64) -> Option<Fixed<Q>> {
+        // `2^63` is exact in `f64`, and every smaller value truncates
+        // into the range of `i64`.
+        let bits = value * (1u64 << Fixed::<Q>::SHIFT) as f64;
+        if bits.abs() < (1u64 << 63) as f64 {
+            Some(Fixed::from_bits(bits as i64))
+        } else {
+            None
+        }
+    }
+
+    fn to_f64(self) -> Option<f64> {
+        Some(self.bits as f64 / (1u64 << Fixed::<Q>::SHIFT) as f64)
+    }
+
+    fn try_add(self, other: Fixed<Q>) -> Option<Fixed<Q>> {
+        self.bits.checked_add(other.bits).map(Fixed::from_bits)
+    }
+
+    fn try_sub(self, other: Fixed<Q>) -> Option<Fixed<Q>> {
+        self.bits.checked_sub(other.bits).map(Fixed::from_bits)
+    }
+
+    // Like the integers, counts beyond the range of the value divide in
+    // `i128`.
+    fn div_count(self, count: usize) -> Fixed<Q> {
+        Fixed::from_bits((i128::from(self.bits) / count as i128) as i64)
+    }
+
+    fn mean(values: &[Fixed<Q>]) -> Fixed<Q> {
+        let sum = values.iter().fold(0i128, |sum, value| sum + i128::from(value.bits));
+        Fixed::from_bits((sum / values.len() as i128) as i64)
+    }
+}
+
+/// Denoises fixed point input values, such as [`Q15`](struct.Q15.html)
+/// and [`Q31`](struct.Q31.html) samples, with
+/// [`tv1d::condat`](../tv1d/fn.condat.html) in integer arithmetic
+/// only, for targets without a floating point unit.
+///
+/// `lambda` is given in units of the least significant bit of `Q`, so
+/// that it can exceed the range of `Q`.
+///
+/// The values are widened to [`Fixed`](struct.Fixed.html), an `i64`
+/// with 16 guard bits below the least significant bit of `Q`, and
+/// denoised by `condat` on `Fixed`, whose divisions truncate. Each
+/// update of the value of a segment then errs by less than `2^-16` of
+/// the least significant bit, so after the at most `n` updates of a
+/// segment of an input of length `n`, and the final rounding to the
+/// nearest value of `Q`, the value of each segment is within
+/// `0.5 + n * 2^-16` least significant bits of the value found by the
+/// algorithm in exact arithmetic. For inputs of up to `2^15` values,
+/// this is less than one least significant bit. The rounding errors can
+/// also move a jump by one position when the exact output is within the
+/// error of a tie.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is greater than
+/// [`MAX_FIXED_LAMBDA`](constant.MAX_FIXED_LAMBDA.html).
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::Q15;
+///
+/// let input: Vec<Q15> = [0.1, 0.12, 0.09, 0.6, 0.62, 0.61].iter().map(|&x| Q15::from_f64(x)).collect();
+/// // A `lambda` of 0.05.
+/// let output = tv1d::condat_fixed(&input, 1638);
+///
+/// let expected = tv1d::condat(&[0.1, 0.12, 0.09, 0.6, 0.62, 0.61], 0.05);
+/// for (value, expected) in output.iter().zip(expected.iter()) {
+///     assert!((value.to_f64() - expected).abs() < 0.001);
+/// }
+/// ```
+pub fn condat_fixed<Q: FixedPoint>(input: &[Q], lambda: u64) -> Vec<Q> {
+    assert!(lambda <= MAX_FIXED_LAMBDA,
+            "Lambda must be at most MAX_FIXED_LAMBDA.");
+
+    let mut values: Vec<Fixed<Q>> = input.iter().map(|&value| Fixed::new(value)).collect();
+    condat_in_place(&mut values, Fixed::from_bits((lambda as i64) << GUARD_BITS));
+    values.into_iter().map(Fixed::round).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    fn signal() -> Vec<f64> {
+        (0..2000).map(|i| ((i / 70) % 4) as f64 * 0.2 - 0.4 + ((i * 7919) % 17) as f64 * 0.003).collect()
+    }
+
+    #[test]
+    fn condat_fixed_test_q15_within_one_lsb() {
+        let input: Vec<Q15> = signal().iter().map(|&value| Q15::from_f64(value)).collect();
+        let input_f64: Vec<f64> = input.iter().map(|value| value.to_f64()).collect();
+        for &lambda in &[0, 1, 100, 1000, 100_000, MAX_FIXED_LAMBDA] {
+            let output = condat_fixed(&input, lambda);
+            let expected = condat(&input_f64, lambda as f64 / (1 << 15) as f64);
+            for (value, expected) in output.iter().zip(expected.iter()) {
+                assert!((value.to_f64() - expected).abs() <= 1.0 / (1 << 15) as f64);
+            }
+        }
+    }
+
+    #[test]
+    fn condat_fixed_test_q31_within_one_lsb() {
+        let input: Vec<Q31> = signal().iter().map(|&value| Q31::from_f64(value)).collect();
+        let input_f64: Vec<f64> = input.iter().map(|value| value.to_f64()).collect();
+        for &lambda in &[0, 1, 1 << 16, 1 << 26, MAX_FIXED_LAMBDA] {
+            let output = condat_fixed(&input, lambda);
+            let expected = condat(&input_f64, lambda as f64 / (1u64 << 31) as f64);
+            for (value, expected) in output.iter().zip(expected.iter()) {
+                // `f64` itself errs by more than the least significant
+                // bit of `Q31` on long segments.
+                assert!((value.to_f64() - expected).abs() <= 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn condat_fixed_test_extreme_values() {
+        let input = vec![Q15(i16::MIN), Q15(i16::MAX), Q15(i16::MIN), Q15(i16::MAX)];
+        assert_eq!(condat_fixed(&input, 0), input);
+        // The mean is `-0.5` least significant bits, a tie between the
+        // two nearest values.
+        let output = condat_fixed(&input, MAX_FIXED_LAMBDA);
+        assert!(output == vec![Q15(-1); 4] || output == vec![Q15(0); 4]);
+    }
+
+    #[test]
+    fn fixed_test_matches_condat_fixed() {
+        let input: Vec<Q15> = signal().iter().map(|&value| Q15::from_f64(value)).collect();
+        let fixed: Vec<Fixed<Q15>> = input.iter().map(|&value| Fixed::new(value)).collect();
+        let lambda = Fixed::new(Q15::from_f64(0.05));
+        let output: Vec<Q15> = condat(&fixed, lambda).into_iter().map(Fixed::round).collect();
+        assert_eq!(output, condat_fixed(&input, 1638));
+    }
+
+    #[test]
+    fn fixed_test_arithmetic() {
+        let half = Fixed::new(Q31::from_f64(0.5));
+        assert_eq!(half + half, Fixed::one());
+        assert_eq!(half * half, Fixed::new(Q31::from_f64(0.25)));
+        assert_eq!(half / (Fixed::one() + Fixed::one()), Fixed::new(Q31::from_f64(0.25)));
+        assert_eq!(half.div_count(2), Fixed::new(Q31::from_f64(0.25)));
+        assert_eq!(Fixed::<Q15>::from_usize(3), Fixed::from_f64(3.0));
+        assert_eq!(Fixed::<Q15>::from_f64(0.75).and_then(TvScalar::to_f64), Some(0.75));
+        assert_eq!(Fixed::<Q31>::from_f64(1e10), None);
+        assert_eq!(Fixed::new(Q15(-3)).round(), Q15(-3));
+    }
+
+    #[test]
+    fn q15_test_conversions() {
+        assert_eq!(Q15::from_f64(0.5), Q15(1 << 14));
+        assert_eq!(Q15::from_f64(2.0), Q15(i16::MAX));
+        assert_eq!(Q15::from_f64(-2.0).to_f64(), -1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_fixed_test_lambda_too_large() {
+        condat_fixed(&[Q15(0)], MAX_FIXED_LAMBDA + 1);
+    }
+}
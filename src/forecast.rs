@@ -0,0 +1,230 @@
+use std::ops::Range;
+
+use condat;
+use scalar::TvFloat;
+
+/// How [`SegmentedModel::predict`](struct.SegmentedModel.html#method.predict)
+/// continues the last segment past the end of the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Extrapolation {
+    /// Repeats the value of the last segment.
+    Level,
+    /// Continues the least squares line through the input values of the
+    /// last segment, anchored at the value of the segment at its
+    /// center.
+    Trend,
+}
+
+/// A piecewise constant model of a signal, made of the segments of a
+/// denoised output, which forecasts the signal by continuing its last
+/// segment.
+///
+/// The model is built from an input and its denoised output, such as
+/// that of [`tv1d::condat`](../tv1d/fn.condat.html) or
+/// [`TvDenoiser::fit`](struct.TvDenoiser.html#method.fit). The
+/// segments are the runs of equal output values, and the breakpoints
+/// the indices where a new segment starts.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::{Extrapolation, SegmentedModel};
+///
+/// let input = vec![1.0, 1.2, 0.8, 5.0, 6.0, 7.0];
+/// let model = SegmentedModel::fit(&input, 1.0);
+///
+/// // The denoised output is `[1.33.., 1.33.., 1.33.., 5.0, 6.0, 6.0]`.
+/// assert_eq!(model.breakpoints(), &[3, 4]);
+/// assert_eq!(model.predict(2, Extrapolation::Level), vec![6.0, 6.0]);
+///
+/// // The input rises by `1.0` per sample over the last segment.
+/// assert_eq!(model.last_trend(), 1.0);
+/// assert_eq!(model.predict(2, Extrapolation::Trend), vec![7.5, 8.5]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SegmentedModel<T> {
+    fitted: Vec<T>,
+    breakpoints: Vec<usize>,
+    slope: T,
+}
+
+impl<T> SegmentedModel<T>
+    where T: TvFloat
+{
+    /// Creates the model of `input` from its denoised `output`.
+    ///
+    /// # Panics
+    /// Panics if input vector's length is `0`, or `input` and `output`
+    /// have different lengths.
+    pub fn new(input: &[T], output: Vec<T>) -> SegmentedModel<T> {
+        assert!(!input.is_empty(),
+                "Input list should have at least one value.");
+        assert!(input.len() == output.len(),
+                "Input and output must have the same length.");
+
+        let breakpoints: Vec<usize> = (1..output.len()).filter(|&i| output[i] != output[i - 1]).collect();
+
+        // The least squares slope over the last segment, with the
+        // indices centered on the segment.
+        let start = breakpoints.last().cloned().unwrap_or(0);
+        let last = &input[start..];
+        let center = T::from_usize(last.len() - 1).expect("Unable to convert usize to TvScalar.") / (T::one() + T::one());
+        let mut covariance = T::zero();
+        let mut variance = T::zero();
+        for (i, &value) in last.iter().enumerate() {
+            let x = T::from_usize(i).expect("Unable to convert usize to TvScalar.") - center;
+            covariance += x * value;
+            variance += x * x;
+        }
+        let slope = if variance > T::zero() {
+            covariance / variance
+        } else {
+            T::zero()
+        };
+
+        SegmentedModel {
+            fitted: output,
+            breakpoints,
+            slope,
+        }
+    }
+
+    /// Denoises the input values with
+    /// [`tv1d::condat`](../tv1d/fn.condat.html) and creates the model
+    /// of the output.
+    ///
+    /// # Panics
+    /// Panics if input vector's length is `0`, or `lambda` is NaN or
+    /// less than `0`.
+    pub fn fit(input: &[T], lambda: T) -> SegmentedModel<T> {
+        SegmentedModel::new(input, condat(input, lambda))
+    }
+
+    /// Returns the denoised output the model was created from.
+    pub fn fitted(&self) -> &[T] {
+        &self.fitted
+    }
+
+    /// Returns the indices where a new segment starts, in increasing
+    /// order. The first segment starts at `0`, which is not included.
+    pub fn breakpoints(&self) -> &[usize] {
+        &self.breakpoints
+    }
+
+    /// Returns the segments as index ranges with their values.
+    pub fn segments(&self) -> Vec<(Range<usize>, T)> {
+        let mut starts = vec![0];
+        starts.extend_from_slice(&self.breakpoints);
+        starts.iter()
+            .enumerate()
+            .map(|(k, &start)| {
+                let end = starts.get(k + 1).cloned().unwrap_or(self.fitted.len());
+                (start..end, self.fitted[start])
+            })
+            .collect()
+    }
+
+    /// Returns the value of the last segment.
+    pub fn last_level(&self) -> T {
+        self.fitted[self.fitted.len() - 1]
+    }
+
+    /// Returns the slope per sample of the least squares line through
+    /// the input values of the last segment, which is `0` if the
+    /// segment has a single value.
+    pub fn last_trend(&self) -> T {
+        self.slope
+    }
+
+    /// Forecasts the `horizon` values following the input.
+    ///
+    /// With `Extrapolation::Trend`, the forecast at index `n + h` of an
+    /// input of length `n` is the value of the last segment plus the
+    /// slope times the distance of `n + h` from the center of the
+    /// segment.
+    pub fn predict(&self, horizon: usize, extrapolation: Extrapolation) -> Vec<T> {
+        let level = self.last_level();
+        match extrapolation {
+            Extrapolation::Level => vec![level; horizon],
+            Extrapolation::Trend => {
+                let start = self.breakpoints.last().cloned().unwrap_or(0);
+                let length = self.fitted.len() - start;
+                let two = T::one() + T::one();
+                let center = T::from_usize(length - 1).expect("Unable to convert usize to TvScalar.") / two;
+                (0..horizon)
+                    .map(|h| {
+                        let x = T::from_usize(length + h).expect("Unable to convert usize to TvScalar.");
+                        level + self.slope * (x - center)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segmented_model_test_segments() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let output = condat(&input, 1.0);
+        let model = SegmentedModel::fit(&input, 1.0);
+        assert_eq!(model.fitted(), &output[..]);
+        let segments = model.segments();
+        assert_eq!(segments.len(), model.breakpoints().len() + 1);
+        for (range, value) in segments {
+            for &fitted in &output[range] {
+                assert_eq!(fitted, value);
+            }
+        }
+        for &breakpoint in model.breakpoints() {
+            assert!(output[breakpoint] != output[breakpoint - 1]);
+        }
+    }
+
+    #[test]
+    fn segmented_model_test_single_segment() {
+        let model = SegmentedModel::fit(&[4.0], 1.0);
+        assert!(model.breakpoints().is_empty());
+        assert_eq!(model.segments(), vec![(0..1, 4.0)]);
+        assert_eq!(model.last_trend(), 0.0);
+        assert_eq!(model.predict(3, Extrapolation::Trend), vec![4.0; 3]);
+        assert!(model.predict(0, Extrapolation::Level).is_empty());
+    }
+
+    #[test]
+    fn segmented_model_test_trend() {
+        // A line within the last segment is continued exactly.
+        let input = vec![9.0, 9.0, 0.0, 0.5, 1.0, 1.5, 2.0];
+        let output = vec![9.0, 9.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+        let model = SegmentedModel::new(&input, output);
+        assert_eq!(model.breakpoints(), &[2]);
+        assert_eq!(model.last_trend(), 0.5);
+        assert_eq!(model.predict(3, Extrapolation::Trend), vec![2.5, 3.0, 3.5]);
+        assert_eq!(model.predict(3, Extrapolation::Level), vec![1.0; 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn segmented_model_test_length_mismatch() {
+        SegmentedModel::new(&[1.0, 2.0], vec![1.0]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn segmented_model_test_serde_round_trip() {
+        use serde_json;
+
+        let input = vec![9.0, 9.0, 0.0, 0.5, 1.0, 1.5, 2.0];
+        let model = SegmentedModel::new(&input, vec![9.0, 9.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+        let json = serde_json::to_string(&model).unwrap();
+        let restored: SegmentedModel<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, model);
+        assert_eq!(restored.predict(3, Extrapolation::Trend), vec![2.5, 3.0, 3.5]);
+    }
+}
@@ -0,0 +1,161 @@
+use scalar::TvFloat;
+
+/// An online approximation of total variation denoising for
+/// non-stationary, unbounded streams, which gradually forgets old
+/// samples.
+///
+/// Each pushed sample immediately yields the current segment's level,
+/// a mean of the segment's samples in which each older sample is
+/// weighted down by the forgetting factor. Like
+/// [`tv1d::condat`](../tv1d/fn.condat.html), a new segment starts
+/// when the running sum of the residuals from the level exceeds
+/// `lambda` in magnitude, except that the residuals are forgotten
+/// too, so that slow drifts do not add up to a jump.
+///
+/// A forgetting factor of `1` forgets nothing, while a factor `f`
+/// below `1` lets the current level depend on roughly the last
+/// `1 / (1 - f)` samples. Unlike `condat`, segments never start
+/// before the sample that triggered them, so the output only
+/// approximates total variation denoising.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let mut smoother = tv1d::ForgettingTv::new(2.0, 0.9);
+/// for _ in 0..20 {
+///     assert_eq!(smoother.push(1.0), 1.0);
+/// }
+/// // A single outlier is smoothed over, but a lasting step is followed.
+/// assert!(smoother.push(2.0) < 1.2);
+/// let output: Vec<f64> = (0..10).map(|_| smoother.push(10.0)).collect();
+/// assert_eq!(output[9], 10.0);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ForgettingTv<T> {
+    lambda: T,
+    forgetting: T,
+    // The forgotten sums of the current segment's samples, of their
+    // weights and of their residuals from the level.
+    sum: T,
+    weight: T,
+    residual: T,
+}
+
+impl<T: TvFloat> ForgettingTv<T> {
+    /// Creates a smoother for `lambda`, forgetting samples by the
+    /// factor `forgetting` at each new sample.
+    ///
+    /// # Panics
+    /// Panics if `lambda` is NaN or less than `0`, or `forgetting` is
+    /// not greater than `0` and less than or equal to `1`.
+    pub fn new(lambda: T, forgetting: T) -> ForgettingTv<T> {
+        assert!(!lambda.is_nan(),
+                "Lambda must not be NaN.");
+
+        assert!(lambda >= T::zero(),
+                "Lambda must be greater than or equal to 0.");
+
+        assert!(forgetting > T::zero() && forgetting <= T::one(),
+                "Forgetting factor must be greater than 0 and at most 1.");
+
+        ForgettingTv {
+            lambda,
+            forgetting,
+            sum: T::zero(),
+            weight: T::zero(),
+            residual: T::zero(),
+        }
+    }
+
+    /// Returns the level of the current segment, or `None` if no
+    /// sample was pushed since the smoother was created or reset.
+    pub fn level(&self) -> Option<T> {
+        if self.weight > T::zero() {
+            Some(self.sum / self.weight)
+        } else {
+            None
+        }
+    }
+
+    /// Adds a sample to the stream, and returns the denoised sample.
+    pub fn push(&mut self, sample: T) -> T {
+        let jump = match self.level() {
+            Some(level) => {
+                self.residual = self.forgetting * self.residual + (sample - level);
+                self.residual.abs() > self.lambda
+            }
+            None => true,
+        };
+        if jump {
+            self.sum = sample;
+            self.weight = T::one();
+            self.residual = T::zero();
+        } else {
+            self.sum = self.forgetting * self.sum + sample;
+            self.weight = self.forgetting * self.weight + T::one();
+        }
+        self.sum / self.weight
+    }
+
+    /// Forgets every sample pushed so far.
+    pub fn reset(&mut self) {
+        self.sum = T::zero();
+        self.weight = T::zero();
+        self.residual = T::zero();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forgetting_tv_test_steps() {
+        let mut smoother = ForgettingTv::new(3.0, 0.95);
+        let input: Vec<f64> = (0..200).map(|i| if (i / 50) % 2 == 0 { 0.0 } else { 10.0 }).collect();
+        let output: Vec<f64> = input.iter().map(|&x| smoother.push(x)).collect();
+        // Each step is followed within one sample of the jump.
+        for i in 0..input.len() {
+            if i % 50 != 0 || i == 0 {
+                assert!((output[i] - input[i]).abs() <= 0.000001);
+            }
+        }
+    }
+
+    #[test]
+    fn forgetting_tv_test_smooths_noise() {
+        let mut smoother = ForgettingTv::new(2.0, 1.0);
+        let input: Vec<f64> = (0..100).map(|i| if i % 2 == 0 { 4.5 } else { 5.5 }).collect();
+        let output: Vec<f64> = input.iter().map(|&x| smoother.push(x)).collect();
+        assert!((output[99] - 5.0).abs() <= 0.000001);
+        assert_eq!(smoother.level(), Some(output[99]));
+    }
+
+    #[test]
+    fn forgetting_tv_test_forgets_drift() {
+        // Without forgetting, the residuals of a slow drift add up to
+        // a jump, while with forgetting the level follows it.
+        let input: Vec<f64> = (0..1000).map(|i| i as f64 * 0.01).collect();
+        let mut smoother = ForgettingTv::new(1.0, 0.5);
+        let output: Vec<f64> = input.iter().map(|&x| smoother.push(x)).collect();
+        assert!((output[999] - input[999]).abs() <= 0.1);
+    }
+
+    #[test]
+    fn forgetting_tv_test_reset() {
+        let mut smoother = ForgettingTv::new(1.0, 0.9);
+        smoother.push(1.0);
+        smoother.reset();
+        assert_eq!(smoother.level(), None);
+        assert_eq!(smoother.push(5.0), 5.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn forgetting_tv_test_zero_forgetting() {
+        ForgettingTv::new(1.0, 0.0);
+    }
+}
@@ -0,0 +1,328 @@
+use std::borrow::Cow;
+use std::cmp;
+use std::error;
+use std::fmt;
+use std::sync::mpsc;
+
+use pollster;
+use wgpu;
+
+use condat;
+use utils;
+
+// The number of invocations in a workgroup, as declared by the shader.
+const WORKGROUP_SIZE: usize = 64;
+
+/// The error returned when denoising on the GPU with
+/// [`tv1d::GpuDenoiser`](../tv1d/struct.GpuDenoiser.html) fails.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum GpuError {
+    /// No GPU adapter is available.
+    NoAdapter,
+    /// The adapter failed to create a device, for the given reason.
+    Device(String),
+    /// Reading the denoised output back from the GPU failed.
+    ReadBack,
+}
+
+impl fmt::Display for GpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GpuError::NoAdapter => write!(f, "no GPU adapter is available"),
+            GpuError::Device(ref reason) => write!(f, "unable to create a GPU device: {}", reason),
+            GpuError::ReadBack => write!(f, "unable to read the denoised output back from the GPU"),
+        }
+    }
+}
+
+impl error::Error for GpuError {}
+
+/// Denoises batches of many short signals with Condat's algorithm of
+/// [`tv1d::condat`](../tv1d/fn.condat.html) on the GPU, where each
+/// signal is denoised by its own invocation of a compute shader.
+///
+/// Creating a `GpuDenoiser` sets up the GPU device and compiles the
+/// shader, so it should be reused between batches. The signals of a
+/// batch are uploaded together, in as few dispatches as the device's
+/// buffer size limits allow. Signals too long for a single buffer are
+/// denoised on the CPU instead.
+///
+/// The GPU computes in `f32` and may round differently than the CPU,
+/// so the output can differ from `tv1d::condat` in the last bits.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // A GPU is not available on every machine.
+/// if let Ok(gpu) = tv1d::GpuDenoiser::new() {
+///     let inputs = vec![vec![1.0f32, 2.0, 3.0, 4.0, 5.0], vec![4.0, 2.0]];
+///     assert_eq!(gpu.condat_batch(&inputs, 10.0).unwrap(),
+///                vec![vec![3.0, 3.0, 3.0, 3.0, 3.0], vec![3.0, 3.0]]);
+/// }
+/// ```
+pub struct GpuDenoiser {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    adapter: String,
+}
+
+impl GpuDenoiser {
+    /// Sets up the default GPU adapter.
+    ///
+    /// # Errors
+    /// Returns an error if no GPU adapter is available or it fails to
+    /// create a device.
+    pub fn new() -> Result<GpuDenoiser, GpuError> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .map_err(|_| GpuError::NoAdapter)?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+                // The largest buffers the adapter supports fit the
+                // most signals in a dispatch.
+                required_limits: adapter.limits(),
+                ..Default::default()
+            }))
+            .map_err(|error| GpuError::Device(error.to_string()))?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("tv1d condat"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("condat.wgsl"))),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("tv1d condat"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Ok(GpuDenoiser {
+            device,
+            queue,
+            pipeline,
+            adapter: adapter.get_info().name,
+        })
+    }
+
+    /// Denoises each of the inputs independently, like
+    /// [`tv1d::condat_batch`](../tv1d/fn.condat_batch.html).
+    ///
+    /// # Errors
+    /// Returns an error if reading the output back from the GPU fails.
+    ///
+    /// # Panics
+    /// Panics if any input's length is `0`, or `lambda` is NaN or less
+    /// than `0`.
+    pub fn condat_batch<I: AsRef<[f32]>>(&self, inputs: &[I], lambda: f32) -> Result<Vec<Vec<f32>>, GpuError> {
+        check_arguments(inputs, lambda);
+
+        if utils::is_infinite_lambda(lambda) {
+            return Ok(inputs.iter().map(|input| condat(input.as_ref(), lambda)).collect());
+        }
+
+        let limits = self.device.limits();
+        let max_len = (cmp::min(limits.max_storage_buffer_binding_size, limits.max_buffer_size) / 4) as usize;
+
+        let mut outputs = Vec::with_capacity(inputs.len());
+        let mut start = 0;
+        while start < inputs.len() {
+            let len = inputs[start].as_ref().len();
+            if len > max_len {
+                outputs.push(condat(inputs[start].as_ref(), lambda));
+                start += 1;
+                continue;
+            }
+
+            // Take as many of the following signals as fit in a
+            // buffer together.
+            let mut end = start + 1;
+            let mut total_len = len;
+            while end < inputs.len() && total_len + inputs[end].as_ref().len() <= max_len {
+                total_len += inputs[end].as_ref().len();
+                end += 1;
+            }
+            outputs.extend(self.dispatch(&inputs[start..end], total_len, lambda)?);
+            start = end;
+        }
+        Ok(outputs)
+    }
+
+    // Denoises the inputs, which have `total_len` values together, in
+    // a single dispatch.
+    fn dispatch<I: AsRef<[f32]>>(&self, inputs: &[I], total_len: usize, lambda: f32) -> Result<Vec<Vec<f32>>, GpuError> {
+        let mut offsets = Vec::with_capacity(4 * (inputs.len() + 1));
+        let mut values = Vec::with_capacity(4 * total_len);
+        let mut offset = 0u32;
+        offsets.extend_from_slice(&offset.to_le_bytes());
+        for input in inputs {
+            for value in input.as_ref() {
+                values.extend_from_slice(&value.to_le_bytes());
+            }
+            offset += input.as_ref().len() as u32;
+            offsets.extend_from_slice(&offset.to_le_bytes());
+        }
+
+        // Dispatch the workgroups in rows, as there can only be so
+        // many in each dimension.
+        let groups = inputs.len().div_ceil(WORKGROUP_SIZE);
+        let columns = cmp::min(groups, self.device.limits().max_compute_workgroups_per_dimension as usize);
+        let rows = groups.div_ceil(columns);
+        let mut params = Vec::with_capacity(16);
+        params.extend_from_slice(&lambda.to_le_bytes());
+        params.extend_from_slice(&(inputs.len() as u32).to_le_bytes());
+        params.extend_from_slice(&((columns * WORKGROUP_SIZE) as u32).to_le_bytes());
+        params.extend_from_slice(&0u32.to_le_bytes());
+
+        let params_buffer = self.buffer(&params, wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST);
+        let offsets_buffer = self.buffer(&offsets, wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST);
+        let values_buffer = self.buffer(&values,
+                                        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST |
+                                        wgpu::BufferUsages::COPY_SRC);
+        let read_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: values.len() as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                           binding: 0,
+                           resource: params_buffer.as_entire_binding(),
+                       },
+                       wgpu::BindGroupEntry {
+                           binding: 1,
+                           resource: offsets_buffer.as_entire_binding(),
+                       },
+                       wgpu::BindGroupEntry {
+                           binding: 2,
+                           resource: values_buffer.as_entire_binding(),
+                       }],
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(columns as u32, rows as u32, 1);
+        }
+        encoder.copy_buffer_to_buffer(&values_buffer, 0, &read_buffer, 0, values.len() as u64);
+        self.queue.submit(Some(encoder.finish()));
+
+        let (sender, receiver) = mpsc::channel();
+        read_buffer.map_async(wgpu::MapMode::Read, .., move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::wait_indefinitely()).map_err(|_| GpuError::ReadBack)?;
+        match receiver.recv() {
+            Ok(Ok(())) => {}
+            _ => return Err(GpuError::ReadBack),
+        }
+
+        let outputs = {
+            let bytes = read_buffer.get_mapped_range(..).map_err(|_| GpuError::ReadBack)?;
+            let mut values = bytes.chunks(4).map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+            inputs.iter().map(|input| values.by_ref().take(input.as_ref().len()).collect()).collect()
+        };
+        read_buffer.unmap();
+        Ok(outputs)
+    }
+
+    // Creates a buffer holding `bytes`.
+    fn buffer(&self, bytes: &[u8], usage: wgpu::BufferUsages) -> wgpu::Buffer {
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: bytes.len() as u64,
+            usage,
+            mapped_at_creation: false,
+        });
+        self.queue.write_buffer(&buffer, 0, bytes);
+        buffer
+    }
+}
+
+// Panics on the arguments that `GpuDenoiser::condat_batch` rejects,
+// which does not need a GPU.
+fn check_arguments<I: AsRef<[f32]>>(inputs: &[I], lambda: f32) {
+    assert!(inputs.iter().all(|input| !input.as_ref().is_empty()),
+            "Input list should have at least one value.");
+
+    assert!(!lambda.is_nan(),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= 0.0,
+            "Lambda must be greater than or equal to 0.");
+}
+
+impl fmt::Debug for GpuDenoiser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GpuDenoiser").field("adapter", &self.adapter).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gpu_denoiser_test_matches_condat() {
+        let gpu = match GpuDenoiser::new() {
+            Ok(gpu) => gpu,
+            // Nothing to test without a GPU.
+            Err(_) => return,
+        };
+        let inputs: Vec<Vec<f32>> = (1..300)
+            .map(|len| (0..len).map(|i| ((i * 7919 + len) % 23) as f32 * 0.5 + ((i / 10) % 3) as f32 * 4.0).collect())
+            .collect();
+        for &lambda in &[0.0, 0.5, 3.0, 100.0, f32::INFINITY] {
+            let outputs = gpu.condat_batch(&inputs, lambda).unwrap();
+            assert_eq!(outputs.len(), inputs.len());
+            for (input, output) in inputs.iter().zip(outputs.iter()) {
+                let output_expected = condat(input, lambda);
+                assert_eq!(output.len(), input.len());
+                for (value, expected) in output.iter().zip(output_expected.iter()) {
+                    assert!((value - expected).abs() <= 0.0001 * expected.abs().max(1.0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gpu_denoiser_test_empty_batch() {
+        if let Ok(gpu) = GpuDenoiser::new() {
+            assert!(gpu.condat_batch::<Vec<f32>>(&[], 1.0).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Input list should have at least one value.")]
+    fn gpu_denoiser_test_empty_input() {
+        let inputs: [Vec<f32>; 1] = [vec![]];
+        match GpuDenoiser::new() {
+            Ok(gpu) => {
+                gpu.condat_batch(&inputs, 1.0).unwrap();
+            }
+            // Without a GPU, only the arguments are checked.
+            Err(_) => check_arguments(&inputs, 1.0),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Lambda must be greater than or equal to 0.")]
+    fn gpu_denoiser_test_negative_lambda() {
+        let inputs = [vec![1.0f32, 2.0]];
+        match GpuDenoiser::new() {
+            Ok(gpu) => {
+                gpu.condat_batch(&inputs, -1.0).unwrap();
+            }
+            Err(_) => check_arguments(&inputs, -1.0),
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use condat_in_place;
+use scalar::TvFloat;
+use utils;
+
+/// The result of [`tv1d::denoise_graph`](../tv1d/fn.denoise_graph.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct GraphOutput<T> {
+    /// The denoised value of each node.
+    pub output: Vec<T>,
+    /// The number of iterations run.
+    pub iterations: usize,
+}
+
+/// Denoises values on the nodes of a sparse graph, given as a list of
+/// edges between node indices, penalizing the absolute difference
+/// across every edge.
+///
+/// The output approximately minimizes
+/// `0.5 * sum((x[i] - input[i])^2) + lambda * sum(abs(x[a] - x[b]))`,
+/// where the second sum runs over the edges `(a, b)`. The edges are
+/// decomposed into chains, walks along unused edges, which may pass the
+/// same node several times and so cover cycles too. Each chain keeps
+/// its own copy of the values of its nodes, and ADMM with a penalty
+/// parameter of `1` alternates between denoising every copy with
+/// [`tv1d::condat`](../tv1d/fn.condat.html) and averaging the copies
+/// of each node with its input value, until the copies agree with the
+/// output and change by at most `tolerance` from one iteration to the
+/// next, or after `max_iterations`. Unlike the exact solvers of the
+/// crate, the output is only as accurate as `tolerance` allows, and
+/// ADMM may need many iterations to reach a small one.
+///
+/// Nodes without edges keep their input value, and an edge from a node
+/// to itself is ignored. An edge listed twice is penalized twice.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, an edge has a node index
+/// out of bounds, `tolerance` is NaN or less than `0`, or `lambda` is
+/// NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // A triangle of noisy values and a fourth node hanging off it.
+/// let input: Vec<f64> = vec![1.0, 1.2, 0.8, 5.0];
+/// let edges = vec![(0, 1), (1, 2), (2, 0), (2, 3)];
+///
+/// let denoised = tv1d::denoise_graph(&input, &edges, 0.2, 1e-9, 10_000);
+/// assert!(denoised.iterations < 10_000);
+/// for &value in &denoised.output[..3] {
+///     assert!((value - 1.0666666666).abs() < 1e-6);
+/// }
+/// assert!((denoised.output[3] - 4.8).abs() < 1e-6);
+/// ```
+pub fn denoise_graph<T>(input: &[T], edges: &[(usize, usize)], lambda: T, tolerance: T, max_iterations: usize) -> GraphOutput<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+    assert!(edges.iter().all(|&(a, b)| a < input.len() && b < input.len()),
+            "Edges must join nodes within the bounds of the input.");
+    assert!(!tolerance.is_nan() && tolerance >= T::zero(),
+            "Tolerance must be greater than or equal to 0.");
+    assert!(!utils::is_nan_lambda(&lambda),
+            "Lambda must not be NaN.");
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    let chains = decompose(input.len(), edges);
+    let mut output = input.to_vec();
+    if chains.is_empty() || lambda == T::zero() {
+        return GraphOutput {
+            output,
+            iterations: 0,
+        };
+    }
+
+    let mut copies = vec![0; input.len()];
+    for &node in chains.iter().flatten() {
+        copies[node] += 1;
+    }
+
+    // The copies of the values of the nodes of each chain, and their
+    // scaled dual variables.
+    let mut values: Vec<Vec<T>> = chains.iter().map(|chain| chain.iter().map(|&node| input[node]).collect()).collect();
+    let mut duals: Vec<Vec<T>> = chains.iter().map(|chain| vec![T::zero(); chain.len()]).collect();
+    let mut sums = vec![T::zero(); input.len()];
+    let mut denoised = Vec::new();
+
+    let mut iterations = 0;
+    while iterations < max_iterations {
+        // Average the copies of each node, shifted by their duals, with
+        // its input value.
+        for sum in sums.iter_mut() {
+            *sum = T::zero();
+        }
+        for (chain, (values, duals)) in chains.iter().zip(values.iter().zip(duals.iter())) {
+            for (k, &node) in chain.iter().enumerate() {
+                sums[node] += values[k] - duals[k];
+            }
+        }
+        for node in 0..input.len() {
+            if copies[node] > 0 {
+                let count = T::from_usize(copies[node]).expect("Unable to convert usize to TvScalar.");
+                output[node] = (input[node] + sums[node]) / (T::one() + count);
+            }
+        }
+
+        // Denoise the copies of every chain, and update the duals.
+        let mut change = T::zero();
+        let mut residual = T::zero();
+        for (chain, (values, duals)) in chains.iter().zip(values.iter_mut().zip(duals.iter_mut())) {
+            denoised.clear();
+            denoised.extend(chain.iter().zip(duals.iter()).map(|(&node, &dual)| output[node] + dual));
+            condat_in_place(&mut denoised, lambda);
+            for (k, &node) in chain.iter().enumerate() {
+                change = change.max((denoised[k] - values[k]).abs());
+                residual = residual.max((output[node] - denoised[k]).abs());
+                duals[k] += output[node] - denoised[k];
+                values[k] = denoised[k];
+            }
+        }
+
+        iterations += 1;
+        if change <= tolerance && residual <= tolerance {
+            break;
+        }
+    }
+
+    GraphOutput {
+        output,
+        iterations,
+    }
+}
+
+// Decomposes the edges into chains of nodes, each edge joining two
+// consecutive nodes of exactly one chain. Every chain is extended
+// along unused edges from both of its ends for as long as possible.
+fn decompose(nodes: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut adjacent = vec![Vec::new(); nodes];
+    for (id, &(a, b)) in edges.iter().enumerate() {
+        if a != b {
+            adjacent[a].push((b, id));
+            adjacent[b].push((a, id));
+        }
+    }
+
+    let mut used = vec![false; edges.len()];
+    // The number of edges of each node already looked at.
+    let mut visited = vec![0; nodes];
+    let mut next_edge = |node: usize, used: &mut Vec<bool>| {
+        while visited[node] < adjacent[node].len() {
+            let (neighbor, id) = adjacent[node][visited[node]];
+            visited[node] += 1;
+            if !used[id] {
+                used[id] = true;
+                return Some(neighbor);
+            }
+        }
+        None
+    };
+
+    let mut chains = Vec::new();
+    for (id, &(a, b)) in edges.iter().enumerate() {
+        if a == b || used[id] {
+            continue;
+        }
+        used[id] = true;
+
+        let mut backward = vec![a];
+        while let Some(node) = next_edge(backward[backward.len() - 1], &mut used) {
+            backward.push(node);
+        }
+        let mut chain = vec![b];
+        while let Some(node) = next_edge(chain[chain.len() - 1], &mut used) {
+            chain.push(node);
+        }
+        backward.reverse();
+        backward.extend(chain);
+        chains.push(backward);
+    }
+    chains
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+    use tree::denoise_tree;
+    use tv2d::condat_2d_accurate;
+
+    fn assert_close(output: &[f64], expected: &[f64]) {
+        for (value, expected) in output.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn denoise_graph_test_decompose() {
+        let edges = vec![(0, 1), (3, 3), (1, 2), (2, 0), (4, 5), (2, 4)];
+        let chains = decompose(6, &edges);
+        let mut covered = Vec::new();
+        for chain in &chains {
+            for pair in chain.windows(2) {
+                let id = edges.iter().position(|&(a, b)| (a, b) == (pair[0], pair[1]) || (b, a) == (pair[0], pair[1])).unwrap();
+                covered.push(id);
+            }
+        }
+        covered.sort();
+        assert_eq!(covered, vec![0, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn denoise_graph_test_chain_and_tree() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let chain: Vec<(usize, usize)> = (1..input.len()).map(|i| (i - 1, i)).collect();
+        let denoised = denoise_graph(&input, &chain, 1.5, 1e-10, 100_000);
+        // A single chain is solved exactly in the second iteration.
+        assert_eq!(denoised.iterations, 2);
+        assert_close(&denoised.output, &condat(&input, 1.5));
+
+        let parents = vec![None, Some(0), Some(0), Some(1), Some(1), Some(2), Some(5), Some(5)];
+        let edges: Vec<(usize, usize)> = (1..input.len()).map(|i| (parents[i].unwrap(), i)).collect();
+        let denoised = denoise_graph(&input, &edges, 1.5, 1e-10, 100_000);
+        assert!(denoised.iterations < 100_000);
+        assert_close(&denoised.output, &denoise_tree(&input, &parents, 1.5));
+    }
+
+    #[test]
+    fn denoise_graph_test_grid() {
+        let input: Vec<f64> = (0..30).map(|i| ((i % 6) / 3 * 4 + (i / 6) / 2 * 3) as f64 + ((i * 7919) % 11) as f64 * 0.2).collect();
+        let mut edges = Vec::new();
+        for i in 0..30 {
+            if i % 6 < 5 {
+                edges.push((i, i + 1));
+            }
+            if i + 6 < 30 {
+                edges.push((i, i + 6));
+            }
+        }
+        let denoised = denoise_graph(&input, &edges, 0.5, 1e-10, 100_000);
+        assert!(denoised.iterations < 100_000);
+        assert_close(&denoised.output, &condat_2d_accurate(&input, 6, 0.5, 1e-8, 100_000).output);
+    }
+
+    #[test]
+    fn denoise_graph_test_without_edges() {
+        let input = vec![1.0, 2.0, 3.0];
+        assert_eq!(denoise_graph(&input, &[(1, 1)], 1.0, 0.0, 10).output, input);
+        assert_eq!(denoise_graph(&input, &[(0, 2)], 0.0, 0.0, 10).output, input);
+    }
+
+    #[test]
+    #[should_panic]
+    fn denoise_graph_test_edge_out_of_bounds() {
+        denoise_graph(&[1.0, 2.0], &[(0, 2)], 1.0, 0.0, 10);
+    }
+}
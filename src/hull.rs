@@ -0,0 +1,233 @@
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+use std::ops;
+
+use scalar::TvFloat;
+use utils;
+
+/// A difference between two points of a bound of the taut string, as
+/// accumulated by the vertices of its hulls.
+pub(crate) trait HullSum: Copy + ops::Add<Output = Self> + ops::Neg<Output = Self> {
+    /// The slope of a hull's edge.
+    type Slope: Copy + PartialOrd;
+
+    /// Returns the sum of `value` alone.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    fn from_value(value: f64) -> Self;
+
+    /// Returns the slope of an edge of length `1` with the difference.
+    fn value(self) -> Self::Slope;
+
+    /// Returns the slope of an edge of length `len` with the
+    /// difference.
+    fn slope(self, len: usize) -> Self::Slope;
+}
+
+/// A sum of floating point values together with the rounding error of
+/// the additions, carried along with `two_sum`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Compensated<T> {
+    pub(crate) sum: T,
+    pub(crate) error: T,
+}
+
+impl<T: TvFloat> Compensated<T> {
+    /// Returns the sum of `value` alone.
+    pub(crate) fn new(value: T) -> Compensated<T> {
+        Compensated {
+            sum: value,
+            error: T::zero(),
+        }
+    }
+}
+
+impl<T: TvFloat> ops::Add for Compensated<T> {
+    type Output = Compensated<T>;
+
+    fn add(self, other: Compensated<T>) -> Compensated<T> {
+        let (sum, rounding) = utils::two_sum(self.sum, other.sum);
+        Compensated {
+            sum,
+            error: self.error + (other.error + rounding),
+        }
+    }
+}
+
+impl<T: TvFloat> ops::Neg for Compensated<T> {
+    type Output = Compensated<T>;
+
+    fn neg(self) -> Compensated<T> {
+        Compensated {
+            sum: -self.sum,
+            error: -self.error,
+        }
+    }
+}
+
+impl<T: TvFloat> HullSum for Compensated<T> {
+    type Slope = T;
+
+    fn from_value(value: f64) -> Compensated<T> {
+        Compensated::new(T::from_f64(value).expect("Unable to convert f64 to TvScalar."))
+    }
+
+    fn value(self) -> T {
+        self.sum + self.error
+    }
+
+    fn slope(self, len: usize) -> T {
+        (self.sum + self.error) / T::from_usize(len).expect("Unable to convert usize to TvScalar.")
+    }
+}
+
+/// A vertex of the convex hull of the lower or the upper bound of the
+/// taut string since the last knot, with the difference between the
+/// bound at the vertex and at the previous vertex, and the slope of
+/// the hull's edge ending at the vertex, of the slope type `V` of the
+/// difference `S`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Vertex<S, V> {
+    pub(crate) index: usize,
+    pub(crate) delta: S,
+    pub(crate) slope: V,
+}
+
+impl<S: HullSum> Vertex<S, S::Slope> {
+    /// Creates the vertex at `index` of an edge of length `1` and
+    /// difference `delta`.
+    pub(crate) fn new(index: usize, delta: S) -> Vertex<S, S::Slope> {
+        Vertex {
+            index,
+            delta,
+            slope: delta.value(),
+        }
+    }
+
+    /// Adds `delta` to the vertex's difference, which then spans from
+    /// `start` to `index`.
+    pub(crate) fn extend(&mut self, index: usize, start: usize, delta: S) {
+        self.index = index;
+        self.delta = self.delta + delta;
+        self.slope = self.delta.slope(index - start);
+    }
+}
+
+// The auxiliary arrays of the taut string algorithm: the vertices of
+// the lower and the upper hull, and the knots.
+pub(crate) type Buffers<'a, T> = (&'a mut [Vertex<Compensated<T>, T>],
+                                  &'a mut [Vertex<Compensated<T>, T>],
+                                  &'a mut [(usize, T)]);
+
+pub(crate) trait TautstringStorage<T> {
+    // Returns the arrays, of at least `width` elements each, with the
+    // first vertex of each hull and the first knot at index `0`.
+    fn buffers(&mut self, width: usize) -> Buffers<'_, T>;
+}
+
+/// Where the knots of the taut string are written, in order, as they
+/// become final.
+#[cfg(feature = "std")]
+pub(crate) trait Knots<V> {
+    /// The error of writing a knot.
+    type Error;
+
+    /// Writes the knot at `index`, with the denoised value of the
+    /// segment ending at it.
+    fn push(&mut self, index: usize, value: V) -> Result<(), Self::Error>;
+}
+
+#[cfg(feature = "std")]
+impl<V> Knots<V> for Vec<(usize, V)> {
+    type Error = ::std::convert::Infallible;
+
+    fn push(&mut self, index: usize, value: V) -> Result<(), Self::Error> {
+        Vec::push(self, (index, value));
+        Ok(())
+    }
+}
+
+/// Finds the knots of the taut string of the samples with the
+/// algorithm of `tautstring`, where the hulls are double-ended queues
+/// of their vertices since the last knot instead of stacks in a
+/// workspace, so the memory use is bounded by the length of the
+/// longest segment. The hulls' first vertex stands for the last knot.
+///
+/// The samples are read one at a time, and the knots after the
+/// implicit first one at `0` are written to `knots`.
+#[cfg(feature = "std")]
+pub(crate) fn find_knots<S, I, K>(samples: I, lambda: S, knots: &mut K) -> Result<(), K::Error>
+    where S: HullSum,
+          I: Iterator<Item = Result<S, K::Error>>,
+          K: Knots<S::Slope>
+{
+    let base = |sum: S| {
+        Vertex {
+            index: 0,
+            delta: S::from_value(0.0),
+            slope: sum.value(),
+        }
+    };
+    let infinity = S::from_value(f64::INFINITY);
+    let mut low = VecDeque::new();
+    let mut up = VecDeque::new();
+    low.push_back(base(infinity));
+    up.push_back(base(-infinity));
+    let mut knot_index = 0;
+
+    let mut samples = samples.peekable();
+    let mut i = 0;
+    while let Some(sample) = samples.next() {
+        let sample = sample?;
+        i += 1;
+
+        // The bounds start and end at the running sums themselves,
+        // and are `lambda` below and above them in between.
+        let mut step_low = Vertex::new(i, sample);
+        let mut step_up = step_low;
+        if i == 1 {
+            step_low.extend(i, i - 1, -lambda);
+            step_up.extend(i, i - 1, lambda);
+        }
+        if samples.peek().is_none() {
+            step_low.extend(i, i - 1, lambda);
+            step_up.extend(i, i - 1, -lambda);
+        }
+
+        low.push_back(step_low);
+        while low.len() > 2 && low[low.len() - 2].slope <= low[low.len() - 1].slope {
+            let top = low.pop_back().expect("The lower hull has more than two vertices.");
+            let start = if low.len() > 2 { low[low.len() - 2].index } else { knot_index };
+            low.back_mut().expect("The lower hull has more than two vertices.").extend(i, start, top.delta);
+        }
+
+        up.push_back(step_up);
+        while up.len() > 2 && up[up.len() - 2].slope >= up[up.len() - 1].slope {
+            let top = up.pop_back().expect("The upper hull has more than two vertices.");
+            let start = if up.len() > 2 { up[up.len() - 2].index } else { knot_index };
+            up.back_mut().expect("The upper hull has more than two vertices.").extend(i, start, top.delta);
+        }
+
+        while low.len() == 2 && up.len() > 2 && low[1].slope >= up[1].slope {
+            // The first vertex of the upper hull becomes a knot, from
+            // which the lower hull's only edge now starts.
+            let knot = up[1];
+            knot_index = knot.index;
+            knots.push(knot_index, knot.slope)?;
+            up.pop_front();
+            low[1].extend(i, knot_index, -knot.delta);
+        }
+        while up.len() == 2 && low.len() > 2 && up[1].slope <= low[1].slope {
+            let knot = low[1];
+            knot_index = knot.index;
+            knots.push(knot_index, knot.slope)?;
+            low.pop_front();
+            up[1].extend(i, knot_index, -knot.delta);
+        }
+    }
+
+    // The remaining vertices of the lower hull are the last knots.
+    for vertex in low.iter().skip(1) {
+        knots.push(vertex.index, vertex.slope)?;
+    }
+    Ok(())
+}
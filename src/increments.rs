@@ -0,0 +1,88 @@
+use condat;
+use scalar::TvFloat;
+use utils;
+
+/// Denoises the first differences of the input values with
+/// [`tv1d::condat`](../tv1d/fn.condat.html) and integrates them back,
+/// for signals whose noise lives on the increments, such as step
+/// counters and cumulative meters.
+///
+/// The denoised differences are piecewise constant, so the output is
+/// piecewise linear: a constant rate of increase between the times
+/// the rate changes. The integrated output is shifted to have the
+/// same mean as the input, which fits it to the input best, rather
+/// than started at the first, noisy, input value.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // A meter counting up by about 2 per sample, then by about 5.
+/// let input = vec![0.0, 2.0, 3.0, 6.0, 8.0, 13.0, 18.0, 24.0, 28.0];
+///
+/// let denoised = tv1d::condat_increments(&input, 10.0);
+/// let rates: Vec<f64> = denoised.windows(2).map(|pair| pair[1] - pair[0]).collect();
+/// assert!(rates.iter().all(|rate| (rate - 3.5).abs() < 1e-9));
+///
+/// let denoised = tv1d::condat_increments(&input, 1.0);
+/// assert!(denoised[1] - denoised[0] < 3.0 && denoised[8] - denoised[7] > 4.0);
+/// ```
+pub fn condat_increments<T>(input: &[T], lambda: T) -> Vec<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    if input.len() == 1 {
+        return input.to_vec();
+    }
+
+    let differences: Vec<T> = input.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    let mut output = Vec::with_capacity(input.len());
+    output.push(T::zero());
+    for difference in condat(&differences, lambda) {
+        let last = output[output.len() - 1];
+        output.push(last + difference);
+    }
+
+    let offset = utils::mean(input) - utils::mean(&output);
+    output.into_iter().map(|value| value + offset).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condat_increments_test_zero_lambda() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        for (value, expected) in condat_increments(&input, 0.0).iter().zip(input.iter()) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        assert_eq!(condat_increments(&[4.0], 1.0), vec![4.0]);
+    }
+
+    #[test]
+    fn condat_increments_test_piecewise_linear() {
+        // A counter whose rate changes from 1 to 3 halfway, with noise
+        // on each increment.
+        let mut input = vec![0.0];
+        for i in 0..200 {
+            let rate = if i < 100 { 1.0 } else { 3.0 };
+            let last = input[input.len() - 1];
+            input.push(last + rate + ((i * 7919) % 13) as f64 * 0.04 - 0.24);
+        }
+        let output = condat_increments(&input, 5.0);
+        let rates: Vec<f64> = output.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        assert!((rates[50] - 1.0).abs() < 0.1);
+        assert!((rates[150] - 3.0).abs() < 0.1);
+
+        let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+        assert!((mean(&output) - mean(&input)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,110 @@
+use fast::condat_f64_in_place;
+use scalar::TvInteger;
+
+/// How [`tv1d::condat_integer`](../tv1d/fn.condat_integer.html)
+/// rounds the denoised output back to integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Rounding {
+    /// Round to the nearest integer, and halfway cases away from `0`.
+    Nearest,
+    /// Round down, towards negative infinity.
+    Floor,
+    /// Round up, towards positive infinity.
+    Ceil,
+    /// Round towards `0`, i.e. truncate.
+    TowardZero,
+}
+
+impl Rounding {
+    fn round(self, value: f64) -> f64 {
+        match self {
+            Rounding::Nearest => value.round(),
+            Rounding::Floor => value.floor(),
+            Rounding::Ceil => value.ceil(),
+            Rounding::TowardZero => value.trunc(),
+        }
+    }
+}
+
+/// Denoises integer input values, such as samples from an ADC, with
+/// [`tv1d::condat_f64`](../tv1d/fn.condat_f64.html), and rounds the
+/// output back to integers according to `rounding`.
+///
+/// Unlike [`tv1d::condat`](../tv1d/fn.condat.html) on integers, which
+/// computes in integer arithmetic and so truncates the value of each
+/// segment, this computes in `f64` with a fractional `lambda`, and
+/// only rounds the final output. The output lies between the smallest
+/// and the largest input value, so it always fits in `T`. Integers
+/// beyond `2^53` in magnitude, which `f64` cannot represent exactly,
+/// are rounded to the nearest representable value before denoising.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::Rounding;
+///
+/// let input: Vec<i16> = vec![100, 101, 100, 900, 901, 900];
+/// assert_eq!(tv1d::condat_integer(&input, 2.0, Rounding::Nearest),
+///            vec![101, 101, 101, 900, 900, 900]);
+/// assert_eq!(tv1d::condat_integer(&input, 2.0, Rounding::Floor),
+///            vec![101, 101, 101, 899, 899, 899]);
+/// ```
+pub fn condat_integer<T>(input: &[T], lambda: f64, rounding: Rounding) -> Vec<T>
+    where T: TvInteger
+{
+    let mut values: Vec<f64> = input.iter()
+        .map(|value| value.to_f64().expect("Unable to convert integer to f64."))
+        .collect();
+    condat_f64_in_place(&mut values, lambda);
+    values.into_iter()
+        .map(|value| {
+            let rounded = rounding.round(value);
+            // Only the largest integers of `T` can round past its
+            // bounds, through their conversion to `f64`.
+            T::from_f64(rounded).unwrap_or(if rounded < 0.0 { T::min_value() } else { T::max_value() })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    #[test]
+    fn condat_integer_test_matches_condat_f64() {
+        let input: Vec<i32> = (0..500).map(|i| ((i / 40) % 3) * 500 + (i * 7919) % 13).collect();
+        let input_f64: Vec<f64> = input.iter().map(|&value| value as f64).collect();
+        for &lambda in &[0.0, 0.5, 3.0, 100.0, f64::INFINITY] {
+            let expected = condat(&input_f64, lambda);
+            for &rounding in &[Rounding::Nearest, Rounding::Floor, Rounding::Ceil, Rounding::TowardZero] {
+                let output = condat_integer(&input, lambda, rounding);
+                for (&value, &expected) in output.iter().zip(expected.iter()) {
+                    assert_eq!(value as f64, rounding.round(expected));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn condat_integer_test_extreme_values() {
+        let input = vec![i64::MAX, i64::MAX - 1, i64::MIN];
+        let output = condat_integer(&input, 0.0, Rounding::Nearest);
+        assert_eq!(output, vec![i64::MAX, i64::MAX, i64::MIN]);
+
+        let input = vec![u8::MAX, 0, u8::MAX];
+        assert_eq!(condat_integer(&input, 0.0, Rounding::Ceil), input);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_integer_test_empty_input() {
+        condat_integer::<i16>(&[], 1.0, Rounding::Nearest);
+    }
+}
@@ -0,0 +1,193 @@
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use condat_in_place;
+use scalar::TvScalar;
+
+/// Denoises every `stride`-th input value, starting at `offset`, with
+/// [`tv1d::condat`](../tv1d/fn.condat.html), e.g. one channel of an
+/// interleaved buffer, without copying the channel out first.
+///
+/// The denoised channel is returned, with one value per value of the
+/// channel.
+///
+/// # Panics
+/// Panics if `stride` is `0`, `offset` is not less than the input's
+/// length, or `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // Interleaved x, y and z values.
+/// let input = vec![1.0, 0.0, 7.0, 2.0, 0.0, 7.0, 3.0, 0.0, 7.0, 4.0, 0.0, 7.0, 5.0, 0.0, 7.0];
+/// assert_eq!(tv1d::condat_strided(&input, 3, 0, 10.0), vec![3.0, 3.0, 3.0, 3.0, 3.0]);
+/// assert_eq!(tv1d::condat_strided(&input, 3, 2, 10.0), vec![7.0, 7.0, 7.0, 7.0, 7.0]);
+/// ```
+pub fn condat_strided<T>(input: &[T], stride: usize, offset: usize, lambda: T) -> Vec<T>
+    where T: TvScalar
+{
+    assert!(stride > 0, "Stride must be greater than 0.");
+
+    assert!(offset < input.len(),
+            "Offset must be less than the input's length.");
+
+    let mut output: Vec<T> = input[offset..].iter().step_by(stride).cloned().collect();
+    condat_in_place(&mut output, lambda);
+    output
+}
+
+/// Denoises each channel of an interleaved buffer independently with
+/// [`tv1d::condat`](../tv1d/fn.condat.html), e.g. the left and right
+/// channels of stereo audio, and returns the denoised channels
+/// interleaved the same way. For a 2D array stored row by row, the
+/// channels are its columns.
+///
+/// With the `rayon` feature, the channels are denoised in parallel
+/// over rayon's thread pool.
+///
+/// # Panics
+/// Panics if input vector's length is `0` or not a multiple of
+/// `channels`, `channels` is `0`, or `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 10.0, 2.0, 10.0, 3.0, 10.0, 4.0, 10.0, 5.0, 10.0];
+/// assert_eq!(tv1d::denoise_interleaved(&input, 2, 10.0),
+///            vec![3.0, 10.0, 3.0, 10.0, 3.0, 10.0, 3.0, 10.0, 3.0, 10.0]);
+/// ```
+pub fn denoise_interleaved<T>(input: &[T], channels: usize, lambda: T) -> Vec<T>
+    where T: TvScalar
+{
+    assert!(channels > 0, "Number of channels must be greater than 0.");
+
+    denoise_channels(input, channels, |_| lambda)
+}
+
+/// Denoises each channel of an interleaved buffer independently like
+/// [`tv1d::denoise_interleaved`](../tv1d/fn.denoise_interleaved.html),
+/// but with its own `lambda`, for channels with different noise
+/// levels. There are as many channels as values in `lambdas`.
+///
+/// # Panics
+/// Panics if input vector's length is `0` or not a multiple of the
+/// number of channels, `lambdas` is empty, or any `lambda` is NaN or
+/// less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 1.0, 2.0, 2.0, 3.0, 3.0, 4.0, 4.0, 5.0, 5.0];
+/// assert_eq!(tv1d::denoise_interleaved_lambdas(&input, &[10.0, 0.0]),
+///            vec![3.0, 1.0, 3.0, 2.0, 3.0, 3.0, 3.0, 4.0, 3.0, 5.0]);
+/// ```
+pub fn denoise_interleaved_lambdas<T>(input: &[T], lambdas: &[T]) -> Vec<T>
+    where T: TvScalar
+{
+    assert!(!lambdas.is_empty(), "Number of channels must be greater than 0.");
+
+    denoise_channels(input, lambdas.len(), |channel| lambdas[channel])
+}
+
+fn denoise_channels<T, F>(input: &[T], channels: usize, lambda: F) -> Vec<T>
+    where T: TvScalar,
+          F: Fn(usize) -> T + Sync
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(input.len().is_multiple_of(channels),
+            "Input length must be a multiple of the number of channels.");
+
+    #[cfg(feature = "rayon")]
+    let offsets = (0..channels).into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let offsets = 0..channels;
+
+    let denoised: Vec<Vec<T>> = offsets.map(|offset| {
+            let mut channel: Vec<T> = input[offset..].iter().step_by(channels).cloned().collect();
+            condat_in_place(&mut channel, lambda(offset));
+            channel
+        })
+        .collect();
+
+    let mut output = input.to_vec();
+    for (offset, channel) in denoised.iter().enumerate() {
+        for (value, &denoised) in output[offset..].iter_mut().step_by(channels).zip(channel.iter()) {
+            *value = denoised;
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    #[test]
+    fn condat_strided_test_matches_condat() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        for stride in 1..4 {
+            for offset in 0..stride {
+                let channel: Vec<f64> = input.iter().skip(offset).step_by(stride).cloned().collect();
+                assert_eq!(condat_strided(&input, stride, offset, 2.0), condat(&channel, 2.0));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_strided_test_zero_stride() {
+        condat_strided(&[1.0, 2.0], 0, 0, 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_strided_test_offset_out_of_bounds() {
+        condat_strided(&[1.0, 2.0], 3, 2, 1.0);
+    }
+
+    #[test]
+    fn denoise_interleaved_test_matches_strided() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1, 3.3, 0.2, 4.1, 7.7];
+        for &channels in &[1, 2, 3, 4, 6, 12] {
+            let output = denoise_interleaved(&input, channels, 2.0);
+            for offset in 0..channels {
+                let channel: Vec<f64> = output.iter().skip(offset).step_by(channels).cloned().collect();
+                assert_eq!(channel, condat_strided(&input, channels, offset, 2.0));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn denoise_interleaved_test_partial_frame() {
+        denoise_interleaved(&[1.0, 2.0, 3.0], 2, 1.0);
+    }
+
+    #[test]
+    fn denoise_interleaved_lambdas_test_per_channel() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1, 3.3, 0.2, 4.1, 7.7];
+        let lambdas = [0.5, 2.0, 100.0];
+        let output = denoise_interleaved_lambdas(&input, &lambdas);
+        for (offset, &lambda) in lambdas.iter().enumerate() {
+            let channel: Vec<f64> = output.iter().skip(offset).step_by(3).cloned().collect();
+            assert_eq!(channel, condat_strided(&input, 3, offset, lambda));
+        }
+        assert_eq!(denoise_interleaved_lambdas(&input, &[2.0, 2.0]),
+                   denoise_interleaved(&input, 2, 2.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn denoise_interleaved_lambdas_test_negative_lambda() {
+        denoise_interleaved_lambdas(&[1.0, 2.0, 3.0, 4.0], &[1.0, -1.0]);
+    }
+}
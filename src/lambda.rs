@@ -0,0 +1,288 @@
+use condat;
+use scalar::TvFloat;
+use utils;
+
+/// Returns the smallest `lambda` for which total variation denoising
+/// of `input` produces a constant output, i.e. the mean of the input.
+///
+/// Any `lambda` greater than or equal to this value yields the same
+/// output, so `lambda_max` is a natural upper end for searches and
+/// grids over `lambda`.
+///
+/// # Panics
+/// Panics if input vector's length is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// let lambda_max = tv1d::lambda_max(&input);
+/// assert_eq!(lambda_max, 3.0);
+/// assert_eq!(tv1d::condat(&input, lambda_max), vec![3.0; 5]);
+/// ```
+pub fn lambda_max<T>(input: &[T]) -> T
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    let mean = utils::mean(input);
+
+    // The dual variable of the constant solution is the running sum
+    // of the residuals, and `lambda` must bound it everywhere.
+    let mut running_sum = T::zero();
+    let mut lambda_max = T::zero();
+    for &value in &input[..input.len() - 1] {
+        running_sum = running_sum + value - mean;
+        lambda_max = lambda_max.max(running_sum.abs());
+    }
+    lambda_max
+}
+
+/// Searches for a `lambda` whose denoised output consists of
+/// (approximately) `target_segments` constant segments, and returns
+/// that `lambda` together with the denoised output.
+///
+/// The number of segments decreases monotonically as `lambda` grows,
+/// so the search bisects the interval between `0` and
+/// [`tv1d::lambda_max`](../tv1d/fn.lambda_max.html). When no `lambda`
+/// produces exactly `target_segments` segments, the returned fit is
+/// the one whose segment count is closest to the target.
+///
+/// # Panics
+/// Panics if input vector's length is `0` or `target_segments` is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 1.2, 0.9, 5.0, 5.1, 4.8, 9.0, 9.2, 8.9];
+///
+/// let (lambda, denoised) = tv1d::lambda_for_segments(&input, 3);
+/// assert!(lambda > 0.0);
+/// assert_eq!(denoised[0], denoised[2]);
+/// assert!(denoised[2] < denoised[3]);
+/// assert!(denoised[5] < denoised[6]);
+/// ```
+pub fn lambda_for_segments<T>(input: &[T], target_segments: usize) -> (T, Vec<T>)
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(target_segments > 0,
+            "Target number of segments must be greater than 0.");
+
+    let two = T::one() + T::one();
+
+    let mut low = T::zero();
+    let mut high = lambda_max(input);
+
+    let mut best_lambda = low;
+    let mut best_output = condat(input, low);
+    let mut best_distance = utils::count_segments(&best_output).abs_diff(target_segments);
+
+    // Bisection on `lambda` stops once the segment count matches or
+    // the interval can no longer be halved.
+    let mut middle = (low + high) / two;
+    while best_distance > 0 && low < middle && middle < high {
+        let output = condat(input, middle);
+        let segments = utils::count_segments(&output);
+
+        let current_distance = segments.abs_diff(target_segments);
+        if current_distance < best_distance {
+            best_lambda = middle;
+            best_output = output;
+            best_distance = current_distance;
+        }
+
+        if segments > target_segments {
+            low = middle;
+        } else {
+            high = middle;
+        }
+        middle = (low + high) / two;
+    }
+    (best_lambda, best_output)
+}
+
+/// A `lambda` relative to the input, so that the same value suits
+/// signals of different magnitudes, e.g. in configuration files.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::Lambda;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(Lambda::Absolute(1.5).resolve(&input), 1.5);
+/// assert_eq!(Lambda::FractionOfMax(0.5).resolve(&input), 1.5);
+/// assert_eq!(Lambda::PerUnitTime { lambda: 0.01, sample_rate: 100.0 }.resolve(&input), 1.0);
+/// assert_eq!(tv1d::condat_relative(&input, Lambda::FractionOfMax(1.0)), vec![3.0; 5]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Lambda<T> {
+    /// The `lambda` itself, in the units of the input.
+    Absolute(T),
+    /// A fraction of [`tv1d::lambda_max`](../tv1d/fn.lambda_max.html)
+    /// of the input, so that `1` and above denoise it to a constant.
+    FractionOfMax(T),
+    /// A multiple of the standard deviation of the noise in the input,
+    /// estimated from the median absolute difference of neighboring
+    /// values, which is robust to the jumps of the signal.
+    NoiseSigmas(T),
+    /// A `lambda` per unit of time, e.g. a second, for samples taken
+    /// `sample_rate` times per unit of time.
+    ///
+    /// The squared error of each sample stands for `1 / sample_rate` of
+    /// the time, while the total variation does not depend on the rate,
+    /// so this resolves to `lambda * sample_rate`. The same `lambda`
+    /// then smooths a signal recorded at 100 Hz and at 1 kHz alike.
+    PerUnitTime {
+        /// The `lambda` in the units of the input times the unit of time.
+        lambda: T,
+        /// The number of samples per unit of time.
+        sample_rate: T,
+    },
+}
+
+impl<T: TvFloat> Lambda<T> {
+    /// Returns the absolute `lambda` for `input`.
+    ///
+    /// # Panics
+    /// Panics if input vector's length is `0`.
+    pub fn resolve(self, input: &[T]) -> T {
+        assert!(!input.is_empty(),
+                "Input list should have at least one value.");
+
+        match self {
+            Lambda::Absolute(lambda) => lambda,
+            Lambda::FractionOfMax(fraction) => fraction * lambda_max(input),
+            Lambda::NoiseSigmas(sigmas) => sigmas * utils::estimate_noise_sigma(input),
+            Lambda::PerUnitTime { lambda, sample_rate } => lambda * sample_rate,
+        }
+    }
+}
+
+/// Denoises the input values with
+/// [`tv1d::condat`](../tv1d/fn.condat.html), with a `lambda` relative
+/// to the input resolved by
+/// [`Lambda::resolve`](enum.Lambda.html#method.resolve).
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or the resolved `lambda`
+/// is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::Lambda;
+///
+/// let input = vec![1.0, 1.2, 0.9, 5.0, 5.1, 4.8];
+/// let denoised = tv1d::condat_relative(&input, Lambda::NoiseSigmas(1.0));
+/// assert_eq!(denoised[0], denoised[2]);
+/// assert!(denoised[2] < denoised[3]);
+/// ```
+pub fn condat_relative<T>(input: &[T], lambda: Lambda<T>) -> Vec<T>
+    where T: TvFloat
+{
+    condat(input, lambda.resolve(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lambda_max_test_constant_output() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let lambda = lambda_max(&input);
+        assert_eq!(utils::count_segments(&condat(&input, lambda)), 1);
+        assert!(utils::count_segments(&condat(&input, lambda * 0.99)) > 1);
+    }
+
+    #[test]
+    fn lambda_max_test_single_value() {
+        assert_eq!(lambda_max(&[4.0]), 0.0);
+    }
+
+    #[test]
+    fn lambda_for_segments_test_exact_count() {
+        let input = vec![1.0, 1.2, 0.9, 5.0, 5.1, 4.8, 9.0, 9.2, 8.9];
+        for target in 1..4 {
+            let (lambda, output) = lambda_for_segments(&input, target);
+            assert_eq!(utils::count_segments(&output), target);
+            assert_eq!(output, condat(&input, lambda));
+        }
+    }
+
+    #[test]
+    fn lambda_for_segments_test_more_than_input_length() {
+        let input = vec![1.0, 2.1, 5.2, 8.2];
+        let (lambda, output) = lambda_for_segments(&input, 10);
+        assert_eq!(lambda, 0.0);
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    #[should_panic]
+    fn lambda_for_segments_test_zero_target() {
+        let input = vec![1.0, 2.1, 5.2, 8.2];
+        lambda_for_segments(&input, 0);
+    }
+
+    #[test]
+    fn lambda_test_resolve() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        assert_eq!(Lambda::Absolute(2.0).resolve(&input), 2.0);
+        assert_eq!(Lambda::FractionOfMax(0.25).resolve(&input), 0.25 * lambda_max(&input));
+        assert_eq!(Lambda::NoiseSigmas(3.0).resolve(&input), 3.0 * utils::estimate_noise_sigma(&input));
+        assert_eq!(condat_relative(&input, Lambda::FractionOfMax(0.25)),
+                   condat(&input, 0.25 * lambda_max(&input)));
+    }
+
+    #[test]
+    fn lambda_test_scale_invariant() {
+        let input = vec![1.0, 1.2, 0.9, 5.0, 5.1, 4.8, 9.0, 9.2, 8.9];
+        let scaled: Vec<f64> = input.iter().map(|value| value * 1024.0).collect();
+        for &lambda in &[Lambda::FractionOfMax(0.3), Lambda::NoiseSigmas(2.0)] {
+            let expected: Vec<f64> = condat_relative(&input, lambda).iter().map(|value| value * 1024.0).collect();
+            assert_eq!(condat_relative(&scaled, lambda), expected);
+        }
+    }
+
+    #[test]
+    fn lambda_test_per_unit_time() {
+        // The same noisy signal over one second, sampled at 100 Hz and
+        // at 1 kHz, repeating each of the slow samples.
+        let slow: Vec<f64> = (0..100).map(|i| ((i / 20) % 2) as f64 + ((i * 7919) % 11) as f64 * 0.02).collect();
+        let fast: Vec<f64> = slow.iter().flat_map(|&value| vec![value; 10]).collect();
+        let slow_output = condat_relative(&slow, Lambda::PerUnitTime { lambda: 0.002, sample_rate: 100.0 });
+        let fast_output = condat_relative(&fast, Lambda::PerUnitTime { lambda: 0.002, sample_rate: 1000.0 });
+        for (i, value) in fast_output.iter().enumerate() {
+            assert!((value - slow_output[i / 10]).abs() < 1e-9);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn lambda_test_serde_round_trip() {
+        use serde_json;
+
+        let lambda = Lambda::NoiseSigmas(2.5);
+        let json = serde_json::to_string(&lambda).unwrap();
+        assert_eq!(json, r#"{"NoiseSigmas":2.5}"#);
+        assert_eq!(serde_json::from_str::<Lambda<f64>>(&json).unwrap(), lambda);
+
+        let lambda = Lambda::PerUnitTime { lambda: 0.5, sample_rate: 100.0 };
+        let json = serde_json::to_string(&lambda).unwrap();
+        assert_eq!(json, r#"{"PerUnitTime":{"lambda":0.5,"sample_rate":100.0}}"#);
+        assert_eq!(serde_json::from_str::<Lambda<f64>>(&json).unwrap(), lambda);
+    }
+}
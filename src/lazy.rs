@@ -0,0 +1,596 @@
+use std::cmp;
+use std::iter;
+
+use scalar::TvScalar;
+use segments::{Condat, Exact};
+use utils;
+
+/// An iterator over the output of
+/// [`tv1d::condat`](../tv1d/fn.condat.html), created by
+/// [`tv1d::condat_lazy`](../tv1d/fn.condat_lazy.html).
+///
+/// The algorithm runs as the iterator is advanced, one segment at a
+/// time, and the iterator only holds the state of the algorithm and
+/// the value of the current segment.
+#[derive(Clone, Debug)]
+pub struct CondatIter<'a, T: 'a> {
+    input: &'a [T],
+    condat: Condat<T>,
+    // The value of the current segment and how many of its values are
+    // still to be yielded.
+    value: T,
+    remaining: usize,
+    // Set once the last segment has been found.
+    finished: bool,
+}
+
+/// Denoises the input values with the same algorithm as
+/// [`tv1d::condat`](../tv1d/fn.condat.html), but yields the denoised
+/// output lazily, so that it can be written out without ever holding
+/// all of it in memory.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// let mut output = tv1d::condat_lazy(&input, 1.0);
+/// assert_eq!(output.next(), Some(2.0));
+/// assert_eq!(output.collect::<Vec<_>>(), vec![2.0, 3.0, 4.0, 4.0]);
+/// ```
+pub fn condat_lazy<T>(input: &[T], lambda: T) -> CondatIter<'_, T>
+    where T: TvScalar
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(!utils::is_nan_lambda(&lambda),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    let mut iter = CondatIter {
+        input,
+        condat: Condat::new(input[0], lambda, &Exact),
+        value: input[0],
+        remaining: 0,
+        finished: false,
+    };
+    if utils::is_infinite_lambda(lambda) {
+        iter.value = utils::mean(input);
+        iter.remaining = input.len();
+        iter.finished = true;
+    }
+    iter
+}
+
+impl<'a, T> Iterator for CondatIter<'a, T>
+    where T: TvScalar
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while self.remaining == 0 {
+            if self.finished {
+                return None;
+            }
+            let (len, value) = match self.condat.advance(self.input, &Exact, &mut ()) {
+                Some(segment) => segment,
+                None => {
+                    let (len, value, last) = self.condat.finish(self.input, &Exact, &mut ());
+                    self.finished = last;
+                    (len, value)
+                }
+            };
+            self.value = value;
+            self.remaining = len;
+        }
+        self.remaining -= 1;
+        Some(self.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = if self.finished {
+            self.remaining
+        } else {
+            self.remaining + self.input.len() - self.condat.segment_start
+        };
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for CondatIter<'a, T>
+    where T: TvScalar
+{
+}
+
+/// The state of [`tv1d::condat`](../tv1d/fn.condat.html) between
+/// chunks of its input, for denoising inputs too large to hold in
+/// memory at once.
+///
+/// Condat's algorithm finalizes segments as it reads the input: once
+/// a segment is found, no later value can change it. Every
+/// [`feed`](struct.CondatState.html#method.feed) yields the values
+/// of the segments it finalized, and
+/// [`finish`](struct.CondatState.html#method.finish) yields the rest
+/// once the input ends. Together, they yield exactly the output of
+/// `condat` on the concatenation of the chunks.
+///
+/// Only the values of the current segment are kept, so the memory use
+/// is bounded by the length of the longest segment. With an infinite
+/// `lambda`, the whole input is one segment and nothing is yielded
+/// before `finish`.
+///
+/// With the `serde` feature, the state can be serialized between
+/// chunks and restored later, for example across restarts of a
+/// long-running service, without reprocessing the input fed so far.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = [1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+///
+/// let mut state = tv1d::CondatState::new(3.0);
+/// let mut output = Vec::new();
+/// for chunk in input.chunks(3) {
+///     output.extend(state.feed(chunk));
+/// }
+/// output.extend(state.finish());
+/// assert_eq!(output, tv1d::condat(&input, 3.0));
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct CondatState<T> {
+    pub(crate) lambda: T,
+    // The input from the start of the current segment on.
+    pub(crate) buffer: Vec<T>,
+    pub(crate) condat: Option<Condat<T>>,
+    // The lengths and values of the segments finalized by a `feed`.
+    pub(crate) segments: Vec<(usize, T)>,
+}
+
+impl<T> CondatState<T>
+    where T: TvScalar
+{
+    /// Creates the state of denoising an empty input with `lambda`.
+    ///
+    /// # Panics
+    /// Panics if `lambda` is NaN or less than `0`.
+    pub fn new(lambda: T) -> CondatState<T> {
+        assert!(!utils::is_nan_lambda(&lambda),
+                "Lambda must not be NaN.");
+
+        assert!(lambda >= T::zero(),
+                "Lambda must be greater than or equal to 0.");
+
+        CondatState {
+            lambda,
+            buffer: Vec::new(),
+            condat: None,
+            segments: Vec::new(),
+        }
+    }
+
+    /// Returns the number of values fed but not yet yielded.
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Appends a chunk to the input, and returns the denoised values
+    /// that can no longer change.
+    pub fn feed(&mut self, chunk: &[T]) -> impl Iterator<Item = T> + '_ {
+        self.buffer.extend_from_slice(chunk);
+        if self.buffer.is_empty() || utils::is_infinite_lambda(self.lambda) {
+            return expand(self.segments.drain(..));
+        }
+
+        let (first, lambda) = (self.buffer[0], self.lambda);
+        let condat = self.condat.get_or_insert_with(|| Condat::new(first, lambda, &Exact));
+        while let Some(segment) = condat.advance(&self.buffer, &Exact, &mut ()) {
+            self.segments.push(segment);
+        }
+        // Drop the values of the finalized segments.
+        let shift = condat.segment_start;
+        if shift > 0 {
+            condat.rebase(shift);
+            self.buffer.drain(..shift);
+        }
+        expand(self.segments.drain(..))
+    }
+
+    /// Ends the input, and returns the denoised values not yielded by
+    /// [`feed`](struct.CondatState.html#method.feed) yet.
+    pub fn finish(mut self) -> impl Iterator<Item = T> {
+        if self.buffer.is_empty() {
+            return expand(self.segments.into_iter());
+        }
+        if utils::is_infinite_lambda(self.lambda) {
+            self.segments.push((self.buffer.len(), utils::mean(&self.buffer)));
+            return expand(self.segments.into_iter());
+        }
+
+        let mut condat = self.condat.take().expect("A value was fed.");
+        loop {
+            while let Some(segment) = condat.advance(&self.buffer, &Exact, &mut ()) {
+                self.segments.push(segment);
+            }
+            let (len, value, last) = condat.finish(&self.buffer, &Exact, &mut ());
+            self.segments.push((len, value));
+            if last {
+                return expand(self.segments.into_iter());
+            }
+        }
+    }
+}
+
+/// An online version of [`tv1d::condat`](../tv1d/fn.condat.html),
+/// which denoises samples as they arrive.
+///
+/// Every [`push`](struct.OnlineTv.html#method.push) yields the samples
+/// of the segments it finalized, and
+/// [`finish`](struct.OnlineTv.html#method.finish) yields the rest once
+/// the stream ends, like a [`CondatState`](struct.CondatState.html)
+/// fed one sample at a time. Together, they yield exactly the output
+/// of `condat` on all pushed samples.
+///
+/// The latency and memory use are bounded by the length of the
+/// longest segment. With an infinite `lambda`, the whole stream is one
+/// segment and nothing is yielded before `finish`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let mut online = tv1d::OnlineTv::new(1.0);
+/// let mut output = Vec::new();
+/// for &sample in &[1.0, 1.0, 1.0, 9.0, 9.0, 9.0] {
+///     output.extend(online.push(sample));
+/// }
+/// assert_eq!(output, vec![1.0 + 1.0 / 3.0; 3]);
+///
+/// output.extend(online.finish());
+/// assert_eq!(output, tv1d::condat(&[1.0, 1.0, 1.0, 9.0, 9.0, 9.0], 1.0));
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct OnlineTv<T> {
+    state: CondatState<T>,
+}
+
+impl<T> OnlineTv<T>
+    where T: TvScalar
+{
+    /// Creates an online denoiser for `lambda`.
+    ///
+    /// # Panics
+    /// Panics if `lambda` is NaN or less than `0`.
+    pub fn new(lambda: T) -> OnlineTv<T> {
+        OnlineTv { state: CondatState::new(lambda) }
+    }
+
+    /// Returns the number of samples pushed but not yet yielded.
+    pub fn pending(&self) -> usize {
+        self.state.pending()
+    }
+
+    /// Adds a sample to the stream, and returns the denoised samples
+    /// that can no longer change.
+    pub fn push(&mut self, sample: T) -> impl Iterator<Item = T> + '_ {
+        self.state.feed(&[sample])
+    }
+
+    /// Ends the stream, and returns the denoised samples not yet
+    /// yielded by [`push`](struct.OnlineTv.html#method.push).
+    pub fn finish(self) -> impl Iterator<Item = T> {
+        self.state.finish()
+    }
+}
+
+/// An event published by [`RevisingTv`](struct.RevisingTv.html) about
+/// the denoised value of the sample at `index` in the stream.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum StreamEvent<T> {
+    /// A first, provisional value for a sample, which later samples
+    /// may still change.
+    Provisional {
+        /// The index of the sample in the stream.
+        index: usize,
+        /// The provisional value.
+        value: T,
+    },
+    /// A new provisional value for a sample, replacing the value
+    /// previously published for it.
+    Revised {
+        /// The index of the sample in the stream.
+        index: usize,
+        /// The revised value.
+        value: T,
+    },
+    /// The final value of a sample, which no later sample can change.
+    Final {
+        /// The index of the sample in the stream.
+        index: usize,
+        /// The final value.
+        value: T,
+    },
+}
+
+/// A streaming denoiser that publishes provisional values right away
+/// and revises them as later samples arrive, for user interfaces that
+/// show low-latency output that corrects itself.
+///
+/// Like [`OnlineTv`](struct.OnlineTv.html), it denoises the stream
+/// exactly as [`tv1d::condat`](../tv1d/fn.condat.html) would, and
+/// every sample eventually gets a `StreamEvent::Final` event. In the
+/// meantime, the provisional values are the output of `condat` as if
+/// the stream ended with the latest sample, and a sample gets a
+/// `StreamEvent::Revised` event whenever this output changes. Each
+/// [`push`](struct.RevisingTv.html#method.push) takes time linear in
+/// the number of samples not yet final.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::StreamEvent;
+///
+/// let mut revising = tv1d::RevisingTv::new(1.0);
+/// assert_eq!(revising.push(1.0).collect::<Vec<_>>(),
+///            vec![StreamEvent::Provisional { index: 0, value: 1.0 }]);
+/// assert_eq!(revising.push(2.0).collect::<Vec<_>>(),
+///            vec![StreamEvent::Revised { index: 0, value: 1.5 },
+///                 StreamEvent::Provisional { index: 1, value: 1.5 }]);
+/// assert_eq!(revising.finish().collect::<Vec<_>>(),
+///            vec![StreamEvent::Final { index: 0, value: 1.5 },
+///                 StreamEvent::Final { index: 1, value: 1.5 }]);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RevisingTv<T> {
+    online: OnlineTv<T>,
+    // The index in the stream of the first sample not yet final, and
+    // the values published for the samples not yet final.
+    offset: usize,
+    published: Vec<T>,
+    events: Vec<StreamEvent<T>>,
+}
+
+impl<T> RevisingTv<T>
+    where T: TvScalar
+{
+    /// Creates a revising denoiser for `lambda`.
+    ///
+    /// # Panics
+    /// Panics if `lambda` is NaN or less than `0`.
+    pub fn new(lambda: T) -> RevisingTv<T> {
+        RevisingTv {
+            online: OnlineTv::new(lambda),
+            offset: 0,
+            published: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Adds a sample to the stream, and returns the events it causes:
+    /// the final values of the samples it finalized, then the revised
+    /// and provisional values of the samples not yet final.
+    pub fn push(&mut self, sample: T) -> impl Iterator<Item = StreamEvent<T>> + '_ {
+        let offset = self.offset;
+        self.events.extend(self.online
+            .push(sample)
+            .enumerate()
+            .map(|(i, value)| StreamEvent::Final { index: offset + i, value }));
+        let finalized = self.events.len();
+        self.offset += finalized;
+        self.published.drain(..cmp::min(finalized, self.published.len()));
+
+        let offset = self.offset;
+        for (i, value) in self.online.clone().finish().enumerate() {
+            if i == self.published.len() {
+                self.published.push(value);
+                self.events.push(StreamEvent::Provisional { index: offset + i, value });
+            } else if self.published[i] != value {
+                self.published[i] = value;
+                self.events.push(StreamEvent::Revised { index: offset + i, value });
+            }
+        }
+        self.events.drain(..)
+    }
+
+    /// Ends the stream, and returns the final values of the samples
+    /// not yet final.
+    pub fn finish(self) -> impl Iterator<Item = StreamEvent<T>> {
+        let offset = self.offset;
+        self.online
+            .finish()
+            .enumerate()
+            .map(move |(i, value)| StreamEvent::Final { index: offset + i, value })
+    }
+}
+
+// Expands segments, given by their lengths and values, into samples.
+fn expand<T, I>(segments: I) -> impl Iterator<Item = T>
+    where T: Copy,
+          I: Iterator<Item = (usize, T)>
+{
+    segments.flat_map(|(len, value)| iter::repeat_n(value, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    // `condat` returns the mean without running the algorithm for a
+    // `lambda` of at least `lambda_max`, which rounds differently.
+    fn assert_close(output: &[f64], expected: &[f64]) {
+        assert_eq!(output.len(), expected.len());
+        for (value, expected) in output.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn condat_lazy_test_matches_condat() {
+        let inputs = vec![vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1],
+                          vec![111.0, 422.1, 145.2],
+                          vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1],
+                          vec![5.0]];
+        for input in &inputs {
+            for &lambda in &[0.0, 1.0, 5.0, 100.0, 1000.0, f64::INFINITY] {
+                let output: Vec<f64> = condat_lazy(input, lambda).collect();
+                assert_close(&output, &condat(input, lambda));
+            }
+        }
+    }
+
+    #[test]
+    fn condat_lazy_test_integers() {
+        let input = vec![111, 422, 145, 248, 871, 675, 436, 310];
+        let output: Vec<i32> = condat_lazy(&input, 50).collect();
+        assert_eq!(output, condat(&input, 50));
+
+        let input: Vec<i8> = (0..300).map(|i| ((i / 150) * 60 + i % 3) as i8).collect();
+        let output: Vec<i8> = condat_lazy(&input, 5).collect();
+        assert_eq!(output, condat(&input, 5));
+    }
+
+    #[test]
+    fn condat_lazy_test_len() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let mut output = condat_lazy(&input, 3.0);
+        for i in 0..input.len() {
+            assert_eq!(output.len(), input.len() - i);
+            output.next();
+        }
+        assert_eq!(output.len(), 0);
+        assert_eq!(output.next(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_lazy_test_empty_input() {
+        let input: Vec<f64> = vec![];
+        condat_lazy(&input, 1.0);
+    }
+
+    #[test]
+    fn online_tv_test_matches_condat() {
+        let inputs = vec![vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1],
+                          vec![111.0, 422.1, 145.2],
+                          vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1],
+                          vec![5.0]];
+        for input in &inputs {
+            for &lambda in &[0.0, 1.0, 5.0, 100.0, 1000.0, f64::INFINITY] {
+                let mut online = OnlineTv::new(lambda);
+                let mut output = Vec::new();
+                for (i, &sample) in input.iter().enumerate() {
+                    output.extend(online.push(sample));
+                    assert_eq!(output.len() + online.pending(), i + 1);
+                }
+                output.extend(online.finish());
+                assert_close(&output, &condat(input, lambda));
+            }
+        }
+    }
+
+    #[test]
+    fn online_tv_test_bounded_buffer() {
+        let input: Vec<f64> = (0..1000).map(|i| if (i / 10) % 2 == 0 { 0.0 } else { 10.0 }).collect();
+        let mut online = OnlineTv::new(1.0);
+        let mut yielded = 0;
+        for &sample in &input {
+            yielded += online.push(sample).count();
+            assert!(online.pending() <= 30);
+        }
+        assert_eq!(yielded + online.finish().count(), input.len());
+    }
+
+    #[test]
+    fn online_tv_test_empty_stream() {
+        assert_eq!(OnlineTv::new(1.0).finish().count(), 0);
+    }
+
+    // Applies an event to the latest published values, checking that
+    // events arrive in a consistent order.
+    fn apply(latest: &mut [Option<f64>], finalized: &mut [bool], event: StreamEvent<f64>) {
+        match event {
+            StreamEvent::Provisional { index, value } => {
+                assert!(latest[index].is_none());
+                latest[index] = Some(value);
+            }
+            StreamEvent::Revised { index, value } => {
+                assert!(latest[index].is_some() && !finalized[index]);
+                latest[index] = Some(value);
+            }
+            StreamEvent::Final { index, value } => {
+                assert!(!finalized[index]);
+                finalized[index] = true;
+                latest[index] = Some(value);
+            }
+        }
+    }
+
+    #[test]
+    fn revising_tv_test_converges_to_condat() {
+        let input = [111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        for &lambda in &[0.0, 50.0, 1000.0] {
+            let mut revising = RevisingTv::new(lambda);
+            let mut latest = vec![None; input.len()];
+            let mut finalized = vec![false; input.len()];
+            for (i, &sample) in input.iter().enumerate() {
+                for event in revising.push(sample) {
+                    apply(&mut latest, &mut finalized, event);
+                }
+                let provisional: Vec<f64> = latest[..(i + 1)].iter().map(|x| x.unwrap()).collect();
+                assert_close(&provisional, &condat(&input[..(i + 1)], lambda));
+            }
+            for event in revising.finish() {
+                apply(&mut latest, &mut finalized, event);
+            }
+            assert!(finalized.iter().all(|&x| x));
+        }
+    }
+
+    #[test]
+    fn condat_state_test_chunks() {
+        let input: Vec<f64> = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        for &lambda in &[0.0, 50.0, 1000.0, f64::INFINITY] {
+            for size in 1..(input.len() + 1) {
+                let mut state = CondatState::new(lambda);
+                let mut output: Vec<f64> = state.feed(&[]).collect();
+                for chunk in input.chunks(size) {
+                    output.extend(state.feed(chunk));
+                }
+                output.extend(state.finish());
+                assert_eq!(output, condat(&input, lambda));
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn condat_state_test_serde_round_trip() {
+        use serde_json;
+
+        let input: Vec<f64> = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        let mut state = CondatState::new(50.0);
+        let mut output: Vec<f64> = state.feed(&input[..5]).collect();
+
+        let json = serde_json::to_string(&state).unwrap();
+        let mut restored: CondatState<f64> = serde_json::from_str(&json).unwrap();
+        output.extend(restored.feed(&input[5..]));
+        output.extend(restored.finish());
+        assert_eq!(output, condat(&input, 50.0));
+    }
+}
@@ -1,15 +1,286 @@
 //! Total variation denoising algorithms for 1D data.
+//!
+//! Without the default `std` feature, the crate is `no_std` and does
+//! not link `alloc`, so it cannot allocate. Only the solvers working
+//! on caller-provided slices and arrays are available then:
+//! [`condat_in_place`](fn.condat_in_place.html),
+//! [`condat_into`](fn.condat_into.html),
+//! [`condat_array`](fn.condat_array.html) and
+//! [`tautstring_array`](fn.tautstring_array.html).
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![deny(missing_docs)]
 #![deny(missing_debug_implementations)]
 
-extern crate num;
+#[cfg(not(any(feature = "std", test)))]
+extern crate core as std;
+#[cfg(feature = "dasp")]
+extern crate dasp;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
+#[cfg(feature = "ordered-float")]
+extern crate ordered_float;
+#[cfg(feature = "gpu")]
+extern crate pollster;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(feature = "gpu")]
+extern crate wgpu;
 
+#[macro_use]
+mod trace;
+
+#[cfg(feature = "std")]
+mod adaptive;
+mod array;
+#[cfg(feature = "std")]
+mod axis;
+#[cfg(feature = "std")]
+mod baseline;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+mod bayes;
+#[cfg(feature = "std")]
+mod bootstrap;
+#[cfg(feature = "std")]
+mod certified;
+#[cfg(feature = "std")]
+mod chunked;
+#[cfg(feature = "std")]
+mod circular;
+#[cfg(all(feature = "cross-check", debug_assertions))]
+mod cross_check;
+#[cfg(feature = "dasp")]
+mod dasp_support;
+#[cfg(feature = "std")]
+mod denoiser;
+#[cfg(feature = "std")]
+mod double_double;
+#[cfg(feature = "std")]
+mod duration;
+#[cfg(feature = "std")]
+mod dump;
+#[cfg(feature = "std")]
+mod dp;
+#[cfg(feature = "std")]
+mod dropout;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+mod ext;
+#[cfg(feature = "std")]
+mod fallible;
+#[cfg(feature = "std")]
+mod fast;
+#[cfg(feature = "std")]
+mod file;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+mod fixed;
+#[cfg(feature = "std")]
+mod forecast;
+#[cfg(feature = "std")]
+mod forgetting;
+#[cfg(feature = "gpu")]
+mod gpu;
+#[cfg(feature = "std")]
+mod graph;
+#[cfg(feature = "std")]
+mod increments;
+#[cfg(feature = "std")]
+mod integer;
+mod hull;
+#[cfg(feature = "std")]
+mod interleaved;
+#[cfg(feature = "std")]
+mod lambda;
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+mod missing;
+#[cfg(feature = "std")]
+mod mixed;
+#[cfg(feature = "std")]
+mod multiscale;
+#[cfg(feature = "ndarray")]
+mod ndarray_support;
+#[cfg(feature = "std")]
+mod normalize;
+mod observer;
+#[cfg(feature = "std")]
+mod out_of_core;
+#[cfg(feature = "std")]
+mod pareto;
+#[cfg(feature = "std")]
+mod path;
+#[cfg(feature = "std")]
+mod pipeline;
+#[cfg(feature = "std")]
+mod posterior;
+#[cfg(feature = "std")]
+mod progress;
+#[cfg(feature = "std")]
+mod reference;
+#[cfg(feature = "std")]
+mod resample;
+#[cfg(feature = "std")]
+mod rng;
+#[cfg(feature = "std")]
+mod saturating;
+mod scalar;
+mod segments;
+#[cfg(feature = "std")]
+mod seasonal;
+#[cfg(feature = "simd")]
+mod simd;
+#[cfg(feature = "std")]
+mod stats;
 mod utils;
+#[cfg(feature = "std")]
+mod timestamps;
+#[cfg(feature = "std")]
+mod tree;
+#[cfg(feature = "std")]
+mod tv2d;
+#[cfg(feature = "std")]
+mod tv3d;
+#[cfg(feature = "std")]
+mod warm_start;
+#[cfg(feature = "std")]
+mod window;
+#[cfg(feature = "std")]
+mod winsorize;
+#[cfg(feature = "std")]
+mod workspace;
+
+#[cfg(feature = "std")]
+pub use adaptive::{denoise_adaptive, local_noise_sigmas};
+pub use array::{condat_array, tautstring_array, TautstringArrayWorkspace};
+#[cfg(feature = "std")]
+pub use axis::condat_axis;
+#[cfg(feature = "std")]
+pub use baseline::{baseline_tv, BaselineFit};
+#[cfg(feature = "std")]
+pub use batch::{condat_batch, condat_batch_with_progress};
+#[cfg(feature = "std")]
+pub use bayes::{bayesian_tv, bayesian_tv_with_progress, BayesianFit};
+#[cfg(feature = "std")]
+pub use bootstrap::{bootstrap_bands, ConfidenceBands};
+#[cfg(feature = "std")]
+pub use certified::{condat_certified, CertifiedOutput};
+#[cfg(feature = "std")]
+pub use chunked::{condat_chunked, condat_chunked_with_progress, condat_parallel, condat_parallel_with_progress,
+                  ChunkedOutput};
+#[cfg(feature = "std")]
+pub use circular::condat_circular;
+#[cfg(feature = "dasp")]
+pub use dasp_support::denoise_frames;
+#[cfg(feature = "std")]
+pub use denoiser::{Algorithm, Boundary, Denoiser, ParseAlgorithmError, TvDenoiser};
+#[cfg(feature = "std")]
+pub use double_double::{tautstring_double_double, DoubleDouble};
+#[cfg(feature = "std")]
+pub use duration::condat_duration;
+#[cfg(feature = "std")]
+pub use dump::{condat_with_dump, tautstring_with_dump};
+#[cfg(feature = "std")]
+pub use dp::{dynamic_programming, dynamic_programming_indexed, dynamic_programming_weighted};
+#[cfg(feature = "std")]
+pub use dropout::{condat_dropouts, detect_dropouts, DropoutHandling, DropoutOutput};
+#[cfg(feature = "std")]
+pub use error::TvError;
+#[cfg(feature = "std")]
+pub use ext::{condat_results, TvDenoiseExt, TvIteratorExt};
+#[cfg(feature = "std")]
+#[allow(deprecated)]
+pub use fallible::tautstring_checked;
+#[cfg(feature = "std")]
+pub use fallible::{try_condat, try_condat_into, try_condat_with_policy, try_tautstring,
+                   try_tautstring_with_policy, NonFinitePolicy};
+#[cfg(feature = "std")]
+pub use fast::{condat_f32, condat_f32_in_place, condat_f64, condat_f64_in_place};
+#[cfg(feature = "std")]
+pub use file::{denoise_file, denoise_reader, denoise_to_writer, RawSample};
+#[cfg(feature = "std")]
+pub use fixed::{condat_fixed, Fixed, FixedPoint, Q15, Q31, MAX_FIXED_LAMBDA};
+#[cfg(feature = "std")]
+pub use forecast::{Extrapolation, SegmentedModel};
+#[cfg(feature = "std")]
+pub use forgetting::ForgettingTv;
+#[cfg(feature = "gpu")]
+pub use gpu::{GpuDenoiser, GpuError};
+#[cfg(feature = "std")]
+pub use graph::{denoise_graph, GraphOutput};
+#[cfg(feature = "std")]
+pub use increments::condat_increments;
+#[cfg(feature = "std")]
+pub use integer::{condat_integer, Rounding};
+#[cfg(feature = "std")]
+pub use interleaved::{condat_strided, denoise_interleaved, denoise_interleaved_lambdas};
+#[cfg(feature = "std")]
+pub use lambda::{condat_relative, lambda_for_segments, lambda_max, Lambda};
+#[cfg(feature = "std")]
+pub use lazy::{condat_lazy, CondatIter, CondatState, OnlineTv, RevisingTv, StreamEvent};
+#[cfg(feature = "std")]
+pub use missing::{condat_fill_gaps, condat_masked, condat_missing, GapFill};
+#[cfg(feature = "std")]
+pub use mixed::tautstring_mixed;
+#[cfg(feature = "std")]
+pub use multiscale::condat_multiscale;
+#[cfg(feature = "ndarray")]
+pub use ndarray_support::{condat_view, denoise_axis};
+#[cfg(feature = "std")]
+pub use normalize::condat_normalized;
+#[cfg(feature = "std")]
+pub use out_of_core::tautstring_out_of_core;
+#[cfg(feature = "std")]
+pub use pareto::{pareto_corner, pareto_frontier, ParetoPoint};
+#[cfg(feature = "std")]
+pub use path::{condat_multi, regularization_path, Fusion, RegularizationPath};
+#[cfg(feature = "std")]
+pub use pipeline::{DetrendPipeline, Trend};
+#[cfg(feature = "std")]
+pub use posterior::{posterior_samples, PosteriorSamples};
+#[cfg(feature = "std")]
+pub use progress::Progress;
+#[cfg(feature = "std")]
+pub use reference::condat_reference;
+#[cfg(feature = "std")]
+pub use resample::{Interpolation, UniformGrid};
+#[cfg(feature = "std")]
+pub use saturating::{condat_saturating, SaturatingOutput};
+pub use scalar::{TvFloat, TvInteger, TvScalar};
+#[cfg(feature = "std")]
+pub use seasonal::{condat_seasonal, seasonal_decompose, SeasonalDecomposition};
+#[cfg(feature = "std")]
+pub use stats::{condat_with_stats, tautstring_with_stats, SolveStats};
+#[cfg(feature = "std")]
+pub use timestamps::{collapse_duplicates, denoise_timestamped, Aggregation, CollapsedSamples};
+#[cfg(feature = "std")]
+pub use tree::denoise_tree;
+#[cfg(feature = "std")]
+pub use tv2d::{condat_2d, condat_2d_accurate, Tv2dOutput};
+#[cfg(feature = "std")]
+pub use tv3d::{condat_3d, Tv3dOutput};
+#[cfg(feature = "std")]
+pub use warm_start::WarmStartSolver;
+#[cfg(feature = "std")]
+pub use window::WindowedTv;
+#[cfg(feature = "std")]
+pub use winsorize::{condat_winsorized, WinsorizedOutput};
+#[cfg(feature = "std")]
+pub use workspace::{tautstring_with, TautstringWorkspace};
 
-use std::cmp;
-use std::iter;
-use std::ops;
+use hull::{Compensated, TautstringStorage, Vertex};
+use observer::Observer;
 
 /// Denoises the input values based on a tautstring algorithm by
 /// Davies P. and Kovac A. in 2001 in the paper ["Local extremes,
@@ -22,13 +293,12 @@ use std::ops;
 /// assuming the input values as a string of data points, which is
 /// transformed taut.
 ///
-/// Note that this algorithm is based on the running sum of the input
-/// values. Therefore, if the sum of inputs reaches infinity, which is
-/// more likely if the input values are very large or the input has a
-/// large length, this algorithm may not return meaningful denoised
-/// output. Relatedly, the input must be a float, not an integer. If
-/// the input is an integer or large, please consider using
-/// [`tv1d::condat`](../tv1d/fn.condat.html).
+/// The taut string is found from sums of the input values since its
+/// last knot rather than from the running sum of the whole input, so
+/// the precision of the output does not depend on the length of the
+/// input or the magnitude of its running sum. The input must be a
+/// float, not an integer. If the input is an integer, please consider
+/// using [`tv1d::condat`](../tv1d/fn.condat.html).
 ///
 /// A `lambda` value may provide different degrees of denoising for
 /// different inputs, except for `lambda` that is `0`.
@@ -36,10 +306,17 @@ use std::ops;
 /// A positive `lambda` closer to `0` will result in a denoised output
 /// that will more closely resemble the input. As `lambda` increases, the
 /// denoised output values become closer to the average of the input
-/// values.
+/// values. A `lambda` of positive infinity returns the average of the
+/// input values.
+///
+/// A `lambda` of `0`, a constant input and a `lambda` of at least
+/// [`tv1d::lambda_max`](../tv1d/fn.lambda_max.html) return without
+/// running the algorithm, as the output is the input or its mean.
 ///
 /// # Panics
-/// Panics if input vector's length is `0` or `lambda` is less than `0`.
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`. See [`tv1d::try_tautstring`](../tv1d/fn.try_tautstring.html)
+/// for a variant returning an error instead.
 ///
 /// # Examples
 ///
@@ -78,156 +355,262 @@ use std::ops;
 /// let denoised_larger_inputs = tv1d::tautstring(&input, lambda);
 /// assert_eq!(denoised_larger_inputs, vec![110.0, 200.0, 300.0, 400.0, 490.0]);
 /// ```
+#[cfg(feature = "std")]
 pub fn tautstring<T>(input: &[T], lambda: T) -> Vec<T>
-    where T: num::Num + num::FromPrimitive + cmp::PartialOrd
-    + ops::AddAssign<T> + ops::SubAssign<T>  + num::Float + num::ToPrimitive
+    where T: TvFloat
 {
-    assert!(input.len() > 0,
-            "Input list should have at least one value.");
+    let mut output = input.to_vec();
+    tautstring_in_place(&mut output, lambda);
+    output
+}
 
-    assert!(lambda >= num::zero(),
-            "Lambda must be greater than or equal to 0.");
+/// Denoises the values in place with the same algorithm as
+/// [`tv1d::tautstring`](../tv1d/fn.tautstring.html), overwriting them
+/// with the denoised output instead of allocating a new vector.
+///
+/// # Panics
+/// Panics if the length of `values` is `0`, or `lambda` is NaN or
+/// less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let mut values = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// tv1d::tautstring_in_place(&mut values, 10.0);
+/// assert_eq!(values, vec![3.0, 3.0, 3.0, 3.0, 3.0]);
+/// ```
+#[cfg(feature = "std")]
+pub fn tautstring_in_place<T>(values: &mut [T], lambda: T)
+    where T: TvFloat
+{
+    tautstring_in_place_with(values, lambda, &mut TautstringWorkspace::new());
+}
 
-    let mut output = vec![num::zero(); input.len()];
-    let width = input.len() + 1;
+/// Denoises the input values with the same algorithm as
+/// [`tv1d::tautstring`](../tv1d/fn.tautstring.html).
+///
+/// `tv1d::tautstring` carries the rounding error of its sums along
+/// with them, so the two return the same output. Use
+/// `tv1d::tautstring` instead.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// #![allow(deprecated)]
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(tv1d::tautstring_compensated(&input, 1.0), tv1d::tautstring(&input, 1.0));
+/// ```
+#[deprecated(note = "`tv1d::tautstring` returns the same output, use it instead")]
+#[cfg(feature = "std")]
+pub fn tautstring_compensated<T>(input: &[T], lambda: T) -> Vec<T>
+    where T: TvFloat
+{
+    let mut output = input.to_vec();
+    tautstring_in_place_with(&mut output, lambda, &mut TautstringWorkspace::new());
+    output
+}
 
-    // Vectors for keeping track of indices.
-    let mut index = vec![num::zero(); width];
-    let mut index_low = vec![num::zero(); width];
-    let mut index_up = vec![num::zero(); width];
+// Denoises the values in place, keeping the auxiliary arrays in
+// `workspace`.
+//
+// The taut string runs between the lower and the upper bound, which
+// are the running sums of the input shifted down and up by `lambda`.
+// Instead of the running sums themselves, which grow with the length
+// of the input, the convex hulls of the bounds keep the difference
+// between the bounds at each of their vertices and at the previous
+// one, together with the rounding error of that difference. These
+// only span the values since the last knot, so the precision of the
+// output does not depend on the length or the offset of the input.
+#[cfg(feature = "std")]
+fn tautstring_in_place_with<T>(values: &mut [T], lambda: T, workspace: &mut TautstringWorkspace<T>)
+    where T: TvFloat
+{
+    trace_span!("tautstring", len = values.len(), lambda = lambda.to_f64().unwrap_or(f64::NAN));
 
-    // `slope_low` and `slope_up` is used to store the slope between
-    // consecutive input values.
-    let mut slope_low = vec![num::zero(); width];
-    let mut slope_up = vec![num::zero(); width];
+    #[cfg(all(feature = "cross-check", debug_assertions))]
+    let input = values.to_vec();
 
-    // `z` stores either `lower_boundary` or `upper_boundary`
-    // throughout the program, which will be used as the denoised
-    // output at the end of the program.
-    let mut z = vec![num::zero(); width];
+    find_taut_string(values, lambda, workspace, &mut ());
+    trace_event!(segments = utils::count_segments(values), "Denoised.");
 
-    // `lower_bound` and `upper_bound` first stores the
-    // cumulative sums of the input values. This will be used to find
-    // slopes between each input points, and later in the denoising
-    // step will be used as the denoised output.
-    let mut lower_bound = vec![num::zero(); width];
-    let mut upper_bound = vec![num::zero(); width];
+    #[cfg(all(feature = "cross-check", debug_assertions))]
+    cross_check::check("tautstring", cross_check::Reference::DynamicProgramming, &input, lambda, values);
+}
 
-    let mut s_low = num::zero();
-    let mut c_low = 0;
-    let mut s_up = 0;
-    let mut c_up = 0;
-    let mut c = 0;
+fn find_taut_string<T, S, O>(values: &mut [T], lambda: T, storage: &mut S, observer: &mut O)
+    where T: TvFloat,
+          S: TautstringStorage<T>,
+          O: Observer<T>
+{
+    assert!(!values.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(!utils::is_nan_lambda(&lambda),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
 
-    // First define `lower_bound` and `upper_bound` by the
-    // first input value and lambda.
-    lower_bound[1] = input[0] - lambda;
-    upper_bound[1] = input[0] + lambda;
+    // With a `lambda` of `0` the output is the input.
+    if lambda == T::zero() {
+        trace_event!(fallback = "zero_lambda", "Returning the input.");
+        observer.shortcut("zero_lambda");
+        return;
+    }
+
+    // The sums since the last knot can overflow although every value
+    // is finite, so values that large are divided by a power of two
+    // for the sums, and the output is multiplied back.
+    let scale = utils::overflow_scale(values, lambda);
+    if scale != T::one() {
+        trace_event!(scale = scale.to_f64().unwrap_or(f64::NAN), "Scaling the input down.");
+        for value in values.iter_mut() {
+            *value = *value / scale;
+        }
+    }
+    let lambda = lambda / scale;
 
-    // Generic tracker.
-    let mut i = 2;
+    if utils::is_infinite_lambda(lambda) {
+        trace_event!(fallback = "infinite_lambda", "Returning the mean.");
+        observer.shortcut("infinite_lambda");
+        let mean = utils::compensated_mean(values);
+        values.fill(mean * scale);
+        return;
+    }
 
-    // Get culmulative sum of the input values.
-    while i < width {
-        lower_bound[i] = lower_bound[i - 1] + input[i - 1];
-        upper_bound[i] = upper_bound[i - 1] + input[i - 1];
-        i += 1;
+    // Constant outputs need no knots to be found.
+    if let Some(value) = utils::constant_solution(values, lambda, utils::compensated_mean) {
+        trace_event!(fallback = "constant_output", "Returning a constant output.");
+        observer.shortcut("constant_output");
+        values.fill(value * scale);
+        return;
     }
 
-    lower_bound[width - 1] += lambda;
-    upper_bound[width - 1] -= lambda;
+    let width = values.len() + 1;
+    // `low` and `up` store the vertices of the hulls of the lower and
+    // the upper bound, and `knots` the index of each knot of the taut
+    // string with its slope since the previous knot, which is the
+    // denoised output between them.
+    let (low, up, knots) = storage.buffers(width);
 
-    slope_low[0] = num::Float::infinity();
-    slope_up[0] = num::Float::neg_infinity();
+    // The hulls are stacks between `s_low` and `c_low`, and `s_up`
+    // and `c_up`, whose first vertex is the last knot `c`.
+    let mut s_low = 0;
+    let mut c_low = 0;
+    let mut s_up = 0;
+    let mut c_up = 0;
+    let mut c = 0;
 
-    // `z` is first set to be the first lower bound.
-    z[0] = lower_bound[0];
+    low[0].slope = T::infinity();
+    up[0].slope = T::neg_infinity();
 
     for i in 1..width {
+        // The bounds start and end at the running sums themselves,
+        // and are `lambda` below and above them in between.
+        let mut step_low = Vertex::new(i, Compensated::new(values[i - 1]));
+        let mut step_up = step_low;
+        if i == 1 {
+            step_low.extend(i, i - 1, Compensated::new(-lambda));
+            step_up.extend(i, i - 1, Compensated::new(lambda));
+        }
+        if i == width - 1 {
+            step_low.extend(i, i - 1, Compensated::new(lambda));
+            step_up.extend(i, i - 1, Compensated::new(-lambda));
+        }
+
         c_low += 1;
         c_up += 1;
+        low[c_low] = step_low;
+        up[c_up] = step_up;
 
-        index_low[c_low] = i;
-        index_up[c_up] = i;
-        slope_low[c_low] = lower_bound[i] - lower_bound[i - 1];
-
-        while (c_low > s_low + 1) && (slope_low[cmp::max(s_low, c_low - 1)] <= slope_low[c_low]) {
+        while (c_low > s_low + 1) && (low[c_low - 1].slope <= low[c_low].slope) {
+            let top = low[c_low];
             c_low -= 1;
-            index_low[c_low] = i;
-            if c_low > s_low + 1 {
-                slope_low[c_low] = (lower_bound[i] - lower_bound[index_low[c_low - 1]]) /
-                                   num::FromPrimitive::from_usize(i - index_low[c_low - 1])
-                    .expect("Unable to convert usize to num::FromPrimitive.");
-            } else {
-                slope_low[c_low] = (lower_bound[i] - z[c]) /
-                                   num::FromPrimitive::from_usize(i - index[c])
-                    .expect("Unable to convert usize to num::FromPrimitive.");
-            }
+            let start = low[c_low - 1].index;
+            low[c_low].extend(i, start, top.delta);
         }
 
-        slope_up[c_up] = upper_bound[i] - upper_bound[i - 1];
-        while (c_up > s_up + 1) && (slope_up[cmp::max(c_up - 1, s_up)] >= slope_up[c_up]) {
+        while (c_up > s_up + 1) && (up[c_up - 1].slope >= up[c_up].slope) {
+            let top = up[c_up];
             c_up -= 1;
-            index_up[c_up] = i;
-            if c_up > s_up + 1 {
-                slope_up[c_up] = (upper_bound[i] - upper_bound[index_up[c_up - 1]]) /
-                                 num::FromPrimitive::from_usize(i - index_up[c_up - 1])
-                    .expect("Unable to convert usize to num::FromPrimitive.");
-            } else {
-                slope_up[c_up] = (upper_bound[i] - z[c]) /
-                                 num::FromPrimitive::from_usize(i - index[c])
-                    .expect("Unable to convert usize to num::FromPrimitive.");
-            }
+            let start = up[c_up - 1].index;
+            up[c_up].extend(i, start, top.delta);
         }
+
         while (c_low == s_low + 1) && (c_up > s_up + 1) &&
-              (slope_low[c_low] >= slope_up[s_up + 1]) {
+              (low[c_low].slope >= up[s_up + 1].slope) {
+            // The first vertex of the upper hull becomes a knot, from
+            // which the lower hull's only edge now starts.
             c += 1;
             s_up += 1;
-            index[c] = index_up[s_up];
-            z[c] = upper_bound[index[c]];
-            index_low[s_low] = index[c];
-            slope_low[c_low] = (lower_bound[i] - z[c]) /
-                               num::FromPrimitive::from_usize(i - index[c])
-                .expect("Unable to convert usize to num::FromPrimitive.");;
+            let knot = up[s_up];
+            knots[c] = (knot.index, knot.slope * scale);
+            observer.knot(knot.index, knot.slope * scale);
+            low[s_low].index = knot.index;
+            low[c_low].extend(i, knot.index, -knot.delta);
         }
         while (c_up == s_up + 1) && (c_low > s_low + 1) &&
-              (slope_up[c_up] <= slope_low[s_low + 1]) {
+              (up[c_up].slope <= low[s_low + 1].slope) {
             c += 1;
             s_low += 1;
-            index[c] = index_low[s_low];
-            z[c] = lower_bound[index[c]];
-            index_up[s_up] = index[c];
-            slope_up[c_up] = (upper_bound[i] - z[c]) /
-                             num::FromPrimitive::from_usize(i - index[c])
-                .expect("Unable to convert usize to num::FromPrimitive.");;
+            let knot = low[s_low];
+            knots[c] = (knot.index, knot.slope * scale);
+            observer.knot(knot.index, knot.slope * scale);
+            up[s_up].index = knot.index;
+            up[c_up].extend(i, knot.index, -knot.delta);
         }
+        observer.hulls(i, c_low - s_low, c_up - s_up);
     }
 
-    for i in 1..(c_low - s_low + 1) {
-        index[c + i] = index_low[s_low + i];
-        z[c + i] = lower_bound[index[c + i]];
+    // The remaining vertices of the lower hull are the last knots.
+    for (knot, vertex) in knots[(c + 1)..].iter_mut().zip(&low[(s_low + 1)..(c_low + 1)]) {
+        *knot = (vertex.index, vertex.slope * scale);
+        observer.knot(vertex.index, vertex.slope * scale);
     }
     c += c_low - s_low;
 
     // Finally, write the denoised output.
-    let mut output_index = 0;
-    let mut denoised_output;
-    i = 1;
-    while i <= c {
-        denoised_output = (z[i] - z[i - 1]) /
-                          num::FromPrimitive::from_usize(index[i] - index[i - 1])
-            .expect("Unable to convert usize to num::FromPrimitive.");;
-        while output_index < index[i] {
-            output[output_index] = denoised_output;
-            output_index += 1;
-        }
-        i += 1;
+    for k in 1..(c + 1) {
+        utils::fill(&mut values[knots[k - 1].0..knots[k].0], knots[k].1);
     }
+}
+
+/// Denoises the input values with the same algorithm as
+/// [`tv1d::tautstring`](../tv1d/fn.tautstring.html), writing the
+/// denoised output into `output` instead of allocating a new vector.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `output` has a different
+/// length than the input, or `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// let mut output = vec![0.0; 5];
+/// tv1d::tautstring_into(&input, 10.0, &mut output);
+/// assert_eq!(output, vec![3.0, 3.0, 3.0, 3.0, 3.0]);
+/// ```
+#[cfg(feature = "std")]
+pub fn tautstring_into<T>(input: &[T], lambda: T, output: &mut [T])
+    where T: TvFloat
+{
     assert!(input.len() == output.len(),
             "Input and output must have the same length.");
-    output
-}
 
+    output.copy_from_slice(input);
+    tautstring_in_place(output, lambda);
+}
 
 /// Denoises the input values based on a non-iterative algorithm
 /// described by Condat L. in 2013 in the paper ["A Direct Algorithm
@@ -235,17 +618,32 @@ pub fn tautstring<T>(input: &[T], lambda: T) -> Vec<T>
 /// Denoising"](https://hal.archives-ouvertes.fr/hal-00675043v2/document).
 /// While this algorithm's run time scales at worst quadratically with
 /// respect to signal length, it appears faster in practical
-/// situations.
+/// situations. Adversarial inputs, whose segments are found only to be
+/// backtracked over again and again, do reach the quadratic run time;
+/// [`tv1d::dynamic_programming`](../tv1d/fn.dynamic_programming.html)
+/// is linear in the worst case.
 ///
 /// A `lambda` value may provide different degrees of denoising for
 /// different inputs, except for `lambda` that is `0`.
 ///
 /// `lambda` closer to `0` means the denoised output will resemble the
 /// input more. As `lambda` increases, the denoised output values
-/// become closer to the average of the input values.
+/// become closer to the average of the input values. A `lambda` of
+/// positive infinity returns the average of the input values.
+///
+/// A `lambda` of `0` and a constant input return the input, and a
+/// `lambda` of at least
+/// [`tv1d::lambda_max`](../tv1d/fn.lambda_max.html) returns the mean
+/// of the input, without running the algorithm.
+///
+/// [`tv1d::condat_f32`](../tv1d/fn.condat_f32.html) and
+/// [`tv1d::condat_f64`](../tv1d/fn.condat_f64.html) return the same
+/// output without the generic conversions in the inner loop.
 ///
 /// # Panics
-/// Panics if input vector's length is `0` or `lambda` is less than `0`.
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`. See [`tv1d::try_condat`](../tv1d/fn.try_condat.html) for
+/// a variant returning an error instead.
 ///
 /// # Examples
 ///
@@ -284,147 +682,128 @@ pub fn tautstring<T>(input: &[T], lambda: T) -> Vec<T>
 /// let denoised_larger_inputs = tv1d::tautstring(&input, lambda);
 /// assert_eq!(denoised_larger_inputs, vec![110.0, 200.0, 300.0, 400.0, 490.0]);
 /// ```
+#[cfg(feature = "std")]
 pub fn condat<T>(input: &[T], lambda: T) -> Vec<T>
-    where T: num::Num + num::FromPrimitive
-    + cmp::PartialOrd + ops::Neg<Output=T> + ops::AddAssign<T> + Copy
+    where T: TvScalar
+{
+    let mut output = input.to_vec();
+    condat_in_place(&mut output, lambda);
+    output
+}
+
+/// Denoises the values in place with the same algorithm as
+/// [`tv1d::condat`](../tv1d/fn.condat.html), overwriting them with
+/// the denoised output instead of allocating a new vector.
+///
+/// The algorithm only ever writes the output of a segment once it
+/// has read every input value of that segment, so no copy of the
+/// input is needed.
+///
+/// # Panics
+/// Panics if the length of `values` is `0`, or `lambda` is NaN or
+/// less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let mut values = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// tv1d::condat_in_place(&mut values, 10.0);
+/// assert_eq!(values, vec![3.0, 3.0, 3.0, 3.0, 3.0]);
+/// ```
+pub fn condat_in_place<T>(values: &mut [T], lambda: T)
+    where T: TvScalar
+{
+    // `T` may not convert to `f64`, so the span records its type
+    // rather than `lambda`.
+    trace_span!("condat", len = values.len(), scalar = std::any::type_name::<T>());
+
+    #[cfg(all(feature = "cross-check", debug_assertions))]
+    let input = values.to_vec();
+
+    find_segments(values, lambda, &mut ());
+    trace_event!(segments = utils::count_segments(values), "Denoised.");
+
+    #[cfg(all(feature = "cross-check", debug_assertions))]
+    cross_check::check_scalar("condat", cross_check::Reference::DynamicProgramming, &input, lambda, values);
+}
+
+pub(crate) fn find_segments<T, O>(values: &mut [T], lambda: T, observer: &mut O)
+    where T: TvScalar,
+          O: Observer<T>
 {
-    assert!(input.len() > 0,
+    assert!(!values.is_empty(),
             "Input list should have at least one value.");
 
-    assert!(lambda >= num::zero(),
+    assert!(!utils::is_nan_lambda(&lambda),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= T::zero(),
             "Lambda must be greater than or equal to 0.");
 
-    let width = input.len();
-    let mut output = Vec::with_capacity(width);
-
-    // `current_input_index` is the location of the element the
-    // program is currently inspecting.
-    let mut current_input_index = 0;
-
-    // `segment_start` is the index for the beginning of current
-    // segment.
-    let mut segment_start = 0;
-
-    let twolambda = T::from_u8(2).expect("Unable to transform `2` to T.") * lambda;
-    let minlambda = -lambda;
-
-    // `umin` and `umax` are used for keeping track of previous data
-    // points we have seen, and will be used to decide whether
-    // `segment_lower_bound` and `segment_upper_bound` should be
-    // adjusted.
-    let mut umin = lambda;
-    let mut umax = minlambda;
-
-    // `segment_lower_bound` and `segment_upper_bound` are the
-    // Boundaries of the segment's value.
-    let mut segment_lower_bound = input[0] - lambda;
-    let mut segment_upper_bound = input[0] + lambda;
-
-    // The last position where `umax = -lambda`.
-    let mut kplus = 0;
-    // The last position where `umin = lambda`.
-    let mut kminus = 0;
-
-    loop {
-        if current_input_index == (width - 1) {
-            // Reached the end of the input. Now process the last
-            // set of jumps.
-            if umin < num::zero() {
-                // Negative jump is necessary as `segment_lower_bound`
-                // is too high.
-                output.extend(iter::repeat(segment_lower_bound).take(kminus - segment_start + 1));
-                segment_start = kminus + 1;
-                utils::sync_values(segment_start, &mut [&mut current_input_index, &mut kminus]);
-                segment_lower_bound = input[kminus];
-                umin = lambda;
-                umax = segment_lower_bound + umin - segment_upper_bound;
-            } else if umax > num::zero() {
-                // If `segment_upper_bound` is too low, jump up.
-                output.extend(iter::repeat(segment_upper_bound).take(kplus - segment_start + 1));
-                segment_start = kplus + 1;
-                utils::sync_values(segment_start, &mut [&mut current_input_index, &mut kplus]);
-                segment_upper_bound = input[kplus];
-                umax = minlambda;
-                umin = segment_upper_bound + umax - segment_lower_bound;
-            } else {
-                // `segment_lower_bound` and `segment_upper_bound` are
-                // not too high or not too low. Adjust the
-                // `segment_lower_bound` to reflect the difference
-                // between the current input value and value at the
-                // beginning of the segment, and extend the output.
-                segment_lower_bound +=
-                    umin /
-                    num::FromPrimitive::from_usize(current_input_index - segment_start + 1)
-                        .expect("Unable to convert usize to num::FromPrimitive.");
-                output.extend(iter::repeat(segment_lower_bound)
-                    .take(current_input_index - segment_start + 1));
-                assert!(input.len() == output.len(),
-                        "Input and output must have the same length.");
-                return output;
-            }
-        } else {
-            umin += input[current_input_index + 1] - segment_lower_bound;
-            umax += input[current_input_index + 1] - segment_upper_bound;
-            if umin < minlambda {
-                // If next value (`input[current_input_index + 1]`is
-                // much smaller than `segment_lower_bound`, make a
-                // negative jump. Next value becomes the
-                // `segment_lower_bound`, and `segment_upper_bound` is
-                // adjusted accordingly.
-                output.extend(iter::repeat(segment_lower_bound).take(kminus - segment_start + 1));
-                segment_start = kminus + 1;
-                utils::sync_values(segment_start,
-                                   &mut [&mut current_input_index, &mut kminus, &mut kplus]);
-                segment_lower_bound = input[kplus];
-                segment_upper_bound = segment_lower_bound + twolambda;
-                umin = lambda;
-                umax = minlambda;
-            } else if umax > lambda {
-                // If next value (`input[current_input_index + 1]`is
-                // much larger than `segment_upper_bound`, make a
-                // negative jump. Next value becomes the
-                // `segment_upper_bound`, and `segment_lower_bound` is
-                // adjusted accordingly.
-                output.extend(iter::repeat(segment_upper_bound).take(kplus - segment_start + 1));
-                segment_start = kplus + 1;
-                utils::sync_values(segment_start,
-                                   &mut [&mut current_input_index, &mut kminus, &mut kplus]);
-                segment_upper_bound = input[kplus];
-                segment_lower_bound = segment_upper_bound - twolambda;
-                umin = lambda;
-                umax = minlambda;
-            } else {
-                // `segment_upper_bound` and `segment_lower_bound` are
-                // appropriate, and therefore no jump is necessary.
-                current_input_index += 1;
-                if umin >= lambda {
-                    // If `umin` is greater than lambda (threshold),
-                    // adjust `segment_lower_bound` to be a little
-                    // higher.
-                    kminus = current_input_index;
-                    segment_lower_bound += (umin - lambda) /
-                                           num::FromPrimitive::from_usize(kminus - segment_start +
-                                                                          1)
-                        .expect("Unable to convert usize to num::FromPrimitive.");
-                    umin = lambda;
-                }
-                if umax <= minlambda {
-                    // If `umax` is smaller than -lambda (threshold),
-                    // adjust `segment_upper_bound` to be a little
-                    // lower.
-                    kplus = current_input_index;
-                    segment_upper_bound += (umax + lambda) /
-                                           num::FromPrimitive::from_usize(kplus - segment_start +
-                                                                          1)
-                        .expect("Unable to convert usize to num::FromPrimitive.");
-                    umax = minlambda;
-                }
-            }
-        }
+    if utils::is_infinite_lambda(lambda) {
+        trace_event!(fallback = "infinite_lambda", "Returning the mean.");
+        observer.shortcut("infinite_lambda");
+        let mean = utils::mean(values);
+        values.fill(mean);
+        return;
+    }
+
+    // With a `lambda` of `0` or a constant input, the output is the
+    // input.
+    if lambda == T::zero() || utils::is_constant(values) {
+        trace_event!(fallback = "zero_lambda_or_constant_input", "Returning the input.");
+        observer.shortcut("zero_lambda_or_constant_input");
+        return;
+    }
+    if let Some(mean) = utils::constant_solution(values, lambda, utils::mean) {
+        trace_event!(fallback = "constant_output", "Returning the mean.");
+        observer.shortcut("constant_output");
+        values.fill(mean);
+        return;
     }
+    if T::condat_widened(values, lambda) {
+        trace_event!(fallback = "widened", "Denoised in a wider type.");
+        observer.shortcut("widened");
+        return;
+    }
+
+    segments::solve(values, lambda, &segments::Exact, observer);
+}
+
+/// Denoises the input values with the same algorithm as
+/// [`tv1d::condat`](../tv1d/fn.condat.html), writing the denoised
+/// output into `output` instead of allocating a new vector.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `output` has a different
+/// length than the input, or `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// let mut output = vec![0.0; 5];
+/// tv1d::condat_into(&input, 10.0, &mut output);
+/// assert_eq!(output, vec![3.0, 3.0, 3.0, 3.0, 3.0]);
+/// ```
+pub fn condat_into<T>(input: &[T], lambda: T, output: &mut [T])
+    where T: TvScalar
+{
+    assert!(input.len() == output.len(),
+            "Input and output must have the same length.");
+
+    output.copy_from_slice(input);
+    condat_in_place(output, lambda);
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
+// The first tests, kept as they were written, compare against `vec!`s
+// and cast their values, which clippy lints.
+#[allow(clippy::useless_vec, clippy::unnecessary_cast)]
 mod tests {
     use super::*;
 
@@ -483,6 +862,29 @@ mod tests {
         tautstring(&input, -1.0);
     }
 
+    #[test]
+    #[should_panic]
+    fn tautstring_test_nan_lambda() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        tautstring(&input, f64::NAN);
+    }
+
+    #[test]
+    fn tautstring_test_infinite_lambda() {
+        let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let output = tautstring(&input, f64::INFINITY);
+        assert_eq!(output, vec![3.0; 5]);
+    }
+
+    #[test]
+    fn tautstring_test_lambda_max() {
+        let input = vec![0.1, 0.7, 0.2, 0.3, 0.9, 0.4];
+        let lambda_max = lambda::lambda_max(&input);
+        let output = tautstring(&input, lambda_max);
+        assert_eq!(output, vec![utils::compensated_mean(&input); input.len()]);
+        assert_eq!(tautstring(&[0.1; 7], 2.0), vec![0.1; 7]);
+    }
+
     #[test]
     #[should_panic]
     fn tautstring_test_empty_input() {
@@ -490,6 +892,78 @@ mod tests {
         condat(&input, 1.0);
     }
 
+    #[test]
+    fn tautstring_test_long_input_matches_condat() {
+        let input: Vec<f64> = (0..200000).map(|i| ((i / 500) % 4) as f64 * 3.0 + ((i * 7919) % 17) as f64 * 0.1).collect();
+        let output = tautstring(&input, 2.0);
+        let output_expected = condat(&input, 2.0);
+        for (value, expected) in output.iter().zip(output_expected.iter()) {
+            assert!((value - expected).abs() <= 0.000001);
+        }
+    }
+
+    #[test]
+    fn tautstring_test_long_large_input() {
+        // The running sums of this input grow far beyond the precision
+        // of `f32`, but the taut string only depends on local sums.
+        let input: Vec<f32> = (0..1_000_000).map(|i| 1000.0 + ((i / 1000) % 2) as f32 * 10.0 + ((i % 13) * 7 % 13) as f32 * 0.1).collect();
+        let output = tautstring(&input, 5.0);
+        let input_f64: Vec<f64> = input.iter().map(|&value| value as f64).collect();
+        let output_expected = condat(&input_f64, 5.0);
+        for (&value, &expected) in output.iter().zip(output_expected.iter()) {
+            assert!((value as f64 - expected).abs() <= 0.001);
+        }
+    }
+
+    #[test]
+    fn tautstring_test_long_constant_input() {
+        let input = vec![0.1f32; 1_000_000];
+        for &lambda in &[1.0, f32::INFINITY] {
+            let output = tautstring(&input, lambda);
+            assert!(output.iter().all(|&value| (value - 0.1).abs() < 0.0001));
+        }
+    }
+
+    #[test]
+    fn tautstring_test_overflowing_sums() {
+        // The sums of these values overflow, although every value is
+        // finite.
+        let input = vec![1e308, 1e308, -1e308, -1e308];
+        assert_eq!(tautstring(&input, 1.0), input);
+
+        let output = tautstring(&[1e308, 1e308, 1e308, 1.0], 1.0);
+        assert_eq!(output[..3], [1e308; 3]);
+        assert_eq!(output[3], 2.0);
+
+        let input = vec![f64::MAX, f64::MAX, f64::MAX, f64::MAX / 2.0];
+        let output = tautstring(&input, f64::MAX);
+        assert!(output.iter().all(|value| value.is_finite()));
+        assert!(output.windows(2).all(|pair| pair[0] == pair[1]));
+        assert_eq!(tautstring(&input, f64::INFINITY), vec![f64::MAX * 0.875; 4]);
+
+        let input: Vec<f32> = (0..1000).map(|i| if (i / 100) % 2 == 0 { 3e37 } else { -3e37 }).collect();
+        let output = tautstring(&input, 1.0);
+        for (&value, &expected) in output.iter().zip(input.iter()) {
+            assert!((value - expected).abs() <= expected.abs() * 1e-6);
+        }
+    }
+
+    #[test]
+    fn tautstring_in_place_test_matches_tautstring() {
+        let input = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        let mut values = input.clone();
+        tautstring_in_place(&mut values, 5.0);
+        assert_eq!(values, tautstring(&input, 5.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn tautstring_into_test_length_mismatch() {
+        let input = vec![1.0, 2.1, 5.2, 8.2];
+        let mut output = vec![0.0; 3];
+        tautstring_into(&input, 1.0, &mut output);
+    }
+
     #[test]
     fn condat_test_input_output_length() {
         let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
@@ -536,6 +1010,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn condat_in_place_test_matches_condat() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        for &lambda in &[0.0, 1.0, 3.0, 100.0] {
+            let mut values = input.clone();
+            condat_in_place(&mut values, lambda);
+            assert_eq!(values, condat(&input, lambda));
+        }
+    }
+
+    #[test]
+    fn condat_into_test_reused_buffer() {
+        let mut output = vec![0.0; 8];
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        condat_into(&input, 3.0, &mut output);
+        assert_eq!(output, condat(&input, 3.0));
+        let input = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        condat_into(&input, 5.0, &mut output);
+        assert_eq!(output, condat(&input, 5.0));
+    }
+
     #[test]
     #[should_panic]
     fn condat_test_negative_lambda() {
@@ -543,10 +1038,70 @@ mod tests {
         condat(&input, -1.0);
     }
 
+    #[test]
+    #[should_panic]
+    fn condat_test_nan_lambda() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        condat(&input, f64::NAN);
+    }
+
+    #[test]
+    fn condat_test_infinite_lambda() {
+        let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let output = condat(&input, f64::INFINITY);
+        assert_eq!(output, vec![3.0; 5]);
+    }
+
+    #[test]
+    fn condat_test_constant_input() {
+        let input = vec![0.1; 7];
+        assert_eq!(condat(&input, 0.3), input);
+    }
+
+    #[test]
+    fn condat_test_lambda_max() {
+        let input = vec![0.1, 0.7, 0.2, 0.3, 0.9, 0.4];
+        let lambda_max = lambda::lambda_max(&input);
+        assert_eq!(condat(&input, lambda_max), vec![utils::mean(&input); input.len()]);
+        assert_eq!(condat(&input, lambda_max * 2.0), vec![utils::mean(&input); input.len()]);
+        assert!(utils::count_segments(&condat(&input, lambda_max * 0.99)) > 1);
+
+        let input: Vec<i8> = (0..300).map(|i| (i % 3) as i8).collect();
+        assert_eq!(condat(&input, 5), vec![1; 300]);
+    }
+
+    #[test]
+    fn condat_test_narrow_integer_extremes() {
+        // The range of the values and `4 * lambda` overflow `i8`, which
+        // then denoises like the same values in `i32`.
+        let inputs: Vec<Vec<i8>> = vec![vec![0, 1, 0, 1],
+                                        vec![100, -100, 100, -100],
+                                        vec![i8::MIN, i8::MAX, i8::MIN, i8::MAX],
+                                        vec![i8::MAX, i8::MAX, i8::MIN, 0, 3]];
+        for input in &inputs {
+            let wide: Vec<i32> = input.iter().map(|&value| value as i32).collect();
+            for &lambda in &[1, 40, i8::MAX] {
+                let output: Vec<i32> = condat(input, lambda).iter().map(|&value| value as i32).collect();
+                assert_eq!(output, condat(&wide, lambda as i32));
+            }
+        }
+    }
+
     #[test]
     #[should_panic]
     fn condat_test_empty_input() {
         let input = vec![];
         condat(&input, 1.0);
     }
+
+    #[test]
+    fn condat_test_long_narrow_integer_segments() {
+        // Segments longer than `i8::MAX`, whose lengths do not fit in
+        // `i8`, denoise like the same values in `i32`.
+        let input: Vec<i8> = (0..300).map(|i| if i < 200 { (i % 3) as i8 } else { 50 + (i % 2) as i8 }).collect();
+        let wide: Vec<i32> = input.iter().map(|&value| value as i32).collect();
+        let output: Vec<i32> = condat(&input, 5).iter().map(|&value| value as i32).collect();
+        assert_eq!(output, condat(&wide, 5));
+        assert!(output[..190].iter().all(|&value| value == output[0]));
+    }
 }
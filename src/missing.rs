@@ -0,0 +1,179 @@
+use condat;
+use scalar::TvFloat;
+use utils;
+
+/// Denoises the input values like
+/// [`tv1d::condat`](../tv1d/fn.condat.html), treating the values
+/// where `missing` is `true` as missing samples.
+///
+/// Missing samples do not pull the denoised output towards their
+/// value, but they still receive an output value. Since the total
+/// variation across a gap is smallest when the output is monotone
+/// over it, the output at missing samples is linearly interpolated
+/// between the denoised values on either side of the gap, and
+/// repeats the nearest denoised value before the first and after the
+/// last observed sample.
+///
+/// # Panics
+/// Panics if `input` and `missing` have different lengths, if every
+/// sample is missing, or if `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 1000.0, 1000.0, 4.0, 4.0];
+/// let missing = vec![false, true, true, false, false];
+///
+/// let denoised = tv1d::condat_masked(&input, &missing, 0.0);
+/// assert_eq!(denoised, vec![1.0, 2.0, 3.0, 4.0, 4.0]);
+/// ```
+pub fn condat_masked<T>(input: &[T], missing: &[bool], lambda: T) -> Vec<T>
+    where T: TvFloat
+{
+    assert!(input.len() == missing.len(),
+            "Input and mask must have the same length.");
+
+    let positions: Vec<usize> = (0..input.len()).filter(|&i| !missing[i]).collect();
+    assert!(!positions.is_empty(),
+            "Input list should have at least one value that is not missing.");
+
+    let values: Vec<T> = positions.iter().map(|&i| input[i]).collect();
+    utils::interpolate(input.len(), &positions, &condat(&values, lambda))
+}
+
+/// Denoises the input values like
+/// [`tv1d::condat_masked`](../tv1d/fn.condat_masked.html), with
+/// missing samples given as `None`.
+///
+/// # Panics
+/// Panics if every sample is missing, or if `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![Some(1.0), None, None, Some(4.0), Some(4.0)];
+///
+/// let denoised = tv1d::condat_missing(&input, 0.0);
+/// assert_eq!(denoised, vec![1.0, 2.0, 3.0, 4.0, 4.0]);
+/// ```
+pub fn condat_missing<T>(input: &[Option<T>], lambda: T) -> Vec<T>
+    where T: TvFloat
+{
+    let missing: Vec<bool> = input.iter().map(|value| value.is_none()).collect();
+    let values: Vec<T> = input.iter().map(|value| value.unwrap_or_else(T::zero)).collect();
+    condat_masked(&values, &missing, lambda)
+}
+
+/// The result of
+/// [`tv1d::condat_fill_gaps`](../tv1d/fn.condat_fill_gaps.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct GapFill<T> {
+    /// The denoised output, with a value at every position.
+    pub output: Vec<T>,
+    /// The indices of the NaN input values, whose output values were
+    /// imputed rather than denoised.
+    pub imputed: Vec<usize>,
+}
+
+/// Denoises the input values like
+/// [`tv1d::condat`](../tv1d/fn.condat.html), bridging runs of NaN
+/// values with the denoised signal.
+///
+/// NaN values are treated as observations of zero weight, as in
+/// [`tv1d::condat_masked`](../tv1d/fn.condat_masked.html). A run of
+/// NaN values inside a segment takes the value of that segment, and
+/// a run between two segments is bridged linearly. The indices of the
+/// imputed values are returned alongside the output.
+///
+/// # Panics
+/// Panics if every value is NaN, or if `lambda` is NaN or less than
+/// `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let nan = std::f64::NAN;
+/// let input = vec![1.0, 1.0, nan, 1.0, 5.0, nan, nan];
+///
+/// let filled = tv1d::condat_fill_gaps(&input, 0.0);
+/// assert_eq!(filled.output, vec![1.0, 1.0, 1.0, 1.0, 5.0, 5.0, 5.0]);
+/// assert_eq!(filled.imputed, vec![2, 5, 6]);
+/// ```
+pub fn condat_fill_gaps<T>(input: &[T], lambda: T) -> GapFill<T>
+    where T: TvFloat
+{
+    let missing: Vec<bool> = input.iter().map(|value| value.is_nan()).collect();
+    GapFill {
+        output: condat_masked(input, &missing, lambda),
+        imputed: (0..input.len()).filter(|&i| missing[i]).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condat_masked_test_matches_observed() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let missing = vec![false, false, true, false, false, true, false, false];
+        let output = condat_masked(&input, &missing, 3.0);
+        let observed = vec![1.0, 2.1, 8.2, 1.4, 6.2, 10.1];
+        let output_expected = condat(&observed, 3.0);
+        assert_eq!(output.len(), input.len());
+        assert_eq!(output[0], output_expected[0]);
+        assert_eq!(output[3], output_expected[2]);
+        assert_eq!(output[7], output_expected[5]);
+    }
+
+    #[test]
+    fn condat_masked_test_missing_ends() {
+        let input = vec![0.0, 2.0, 3.0, 0.0];
+        let missing = vec![true, false, false, true];
+        assert_eq!(condat_masked(&input, &missing, 0.0), vec![2.0, 2.0, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn condat_missing_test_matches_masked() {
+        let input = vec![Some(1.0), None, Some(5.2), Some(8.2), None, None, Some(6.2)];
+        let values = vec![1.0, 0.0, 5.2, 8.2, 0.0, 0.0, 6.2];
+        let missing = vec![false, true, false, false, true, true, false];
+        assert_eq!(condat_missing(&input, 1.0), condat_masked(&values, &missing, 1.0));
+    }
+
+    #[test]
+    fn condat_fill_gaps_test_no_gaps() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let filled = condat_fill_gaps(&input, 3.0);
+        assert_eq!(filled.output, condat(&input, 3.0));
+        assert!(filled.imputed.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_fill_gaps_test_all_nan() {
+        condat_fill_gaps(&[f64::NAN, f64::NAN], 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_masked_test_all_missing() {
+        let input = vec![1.0, 2.0];
+        condat_masked(&input, &[true, true], 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_masked_test_length_mismatch() {
+        let input = vec![1.0, 2.0];
+        condat_masked(&input, &[false], 1.0);
+    }
+}
@@ -0,0 +1,65 @@
+use tautstring_in_place;
+
+/// Denoises `f32` input values with the taut string algorithm of
+/// [`tv1d::tautstring`](../tv1d/fn.tautstring.html), accumulating the
+/// sums and dividing them in `f64`.
+///
+/// The sums of `tv1d::tautstring` only span the values since the last
+/// knot of the taut string, so `f32` input keeps its precision however
+/// long it is. Computing them in `f64` still rounds the output of
+/// long segments more finely, while the input and the output stay
+/// `f32`. This takes an `f64` copy of the input.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1000.0f32; 1_000_000];
+/// let output = tv1d::tautstring_mixed(&input, 1.0);
+/// assert!(output.iter().all(|&value| value == 1000.0));
+/// ```
+pub fn tautstring_mixed(input: &[f32], lambda: f32) -> Vec<f32> {
+    let mut values: Vec<f64> = input.iter().map(|&value| value as f64).collect();
+    tautstring_in_place(&mut values, lambda as f64);
+    values.iter().map(|&value| value as f32).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {condat, tautstring};
+
+    #[test]
+    fn tautstring_mixed_test_matches_tautstring() {
+        let input = [1.0f32, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        for &lambda in &[0.0, 1.0, 5.0, f32::INFINITY] {
+            let output = tautstring_mixed(&input, lambda);
+            let output_expected = tautstring(&input, lambda);
+            for (value, expected) in output.iter().zip(output_expected.iter()) {
+                assert!((value - expected).abs() <= 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn tautstring_mixed_test_long_input() {
+        let input: Vec<f32> = (0..1_000_000).map(|i| 1000.0 + ((i / 1000) % 2) as f32 * 10.0 + ((i % 13) * 7 % 13) as f32 * 0.1).collect();
+        let output = tautstring_mixed(&input, 5.0);
+        let input_f64: Vec<f64> = input.iter().map(|&value| value as f64).collect();
+        let output_expected = condat(&input_f64, 5.0);
+        for (&value, &expected) in output.iter().zip(output_expected.iter()) {
+            assert!((value as f64 - expected).abs() <= 0.001);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn tautstring_mixed_test_empty_input() {
+        tautstring_mixed(&[], 1.0);
+    }
+}
@@ -0,0 +1,238 @@
+use condat;
+use condat_in_place;
+use scalar::TvFloat;
+use utils;
+
+/// Denoises the input values with the same output as
+/// [`tv1d::condat`](../tv1d/fn.condat.html), guided by a denoised
+/// coarse version of the input, for extremely long, mostly flat
+/// signals.
+///
+/// The means of blocks of `factor` values are denoised first, with
+/// `lambda / factor`, which is cheap. Each jump of the coarse output
+/// proposes to split the input at the most likely change point near
+/// it, where the output would jump too and the pull of either side on
+/// the other is exactly `lambda`. The pieces between the splits are
+/// denoised independently with their ends moved by `lambda`, and a
+/// piece whose output is its mean is recognized in a single pass
+/// without running `condat`, which saves the backtracking of `condat`
+/// over long flat stretches. A split is kept only if the output of its
+/// pieces jumps the proposed way, which proves the stitched output
+/// optimal; otherwise the pieces on either side of it are merged and
+/// denoised again. The output is therefore exact up to rounding.
+///
+/// This pays off when the output has few, long segments, i.e. for a
+/// `lambda` large compared to the noise. For a small `lambda`, many
+/// proposed splits fail, and denoising the merged pieces again can
+/// take about twice as long as `condat`.
+///
+/// A `factor` less than `2`, or more than half the input's length,
+/// denoises the whole input with `condat`.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input: Vec<f64> = (0..100_000).map(|i| ((i / 20_000) % 2) as f64 + ((i * 7919) % 13) as f64 * 0.01).collect();
+///
+/// let denoised = tv1d::condat_multiscale(&input, 10.0, 64);
+/// let exact = tv1d::condat(&input, 10.0);
+/// for (value, expected) in denoised.iter().zip(exact.iter()) {
+///     assert!((value - expected).abs() < 1e-9);
+/// }
+/// ```
+pub fn condat_multiscale<T>(input: &[T], lambda: T, factor: usize) -> Vec<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(!lambda.is_nan(),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    if factor < 2 || input.len() < 2 * factor || lambda == T::zero() || utils::is_infinite_lambda(lambda) {
+        return condat(input, lambda);
+    }
+
+    trace_span!("condat_multiscale",
+                len = input.len(),
+                lambda = lambda.to_f64().unwrap_or(f64::NAN),
+                factor);
+
+    // `splits[i]` splits the input between `splits[i].0` and the next
+    // value, with the pull `splits[i].1` of the left side on the
+    // right. `dirty[i]` marks the pieces to denoise, the first before
+    // the first split and each following one after a split.
+    let mut splits = propose_splits(input, lambda, factor);
+    trace_event!(proposed = splits.len(), "Proposed splits.");
+    let mut dirty = vec![true; splits.len() + 1];
+    let mut output = input.to_vec();
+    loop {
+        for piece in 0..dirty.len() {
+            if !dirty[piece] {
+                continue;
+            }
+            let start = if piece == 0 { 0 } else { splits[piece - 1].0 + 1 };
+            let end = if piece == splits.len() { input.len() } else { splits[piece].0 + 1 };
+            output[start..end].copy_from_slice(&input[start..end]);
+            if piece > 0 {
+                output[start] += splits[piece - 1].1;
+            }
+            if piece < splits.len() {
+                output[end - 1] -= splits[piece].1;
+            }
+            denoise_piece(&mut output[start..end], lambda);
+        }
+
+        // A pull of `-lambda` needs the output to jump up, or not at
+        // all, and `lambda` to jump down.
+        let holds: Vec<bool> = splits.iter()
+            .map(|&(index, pull)| {
+                let jump = output[index + 1] - output[index];
+                if pull < T::zero() { jump >= T::zero() } else { jump <= T::zero() }
+            })
+            .collect();
+        if holds.iter().all(|&holds| holds) {
+            trace_event!(kept = splits.len(), "Denoised.");
+            return output;
+        }
+
+        let mut kept_splits = Vec::with_capacity(splits.len());
+        let mut kept_dirty = vec![false];
+        for (i, &split) in splits.iter().enumerate() {
+            if holds[i] {
+                kept_splits.push(split);
+                kept_dirty.push(false);
+            } else {
+                // The pieces on either side are merged into the last.
+                let last = kept_dirty.len() - 1;
+                kept_dirty[last] = true;
+            }
+        }
+        splits = kept_splits;
+        dirty = kept_dirty;
+    }
+}
+
+// Denoises a piece, which is often flat between two splits. The mean
+// is its output if the running sum of the values minus the mean stays
+// within `lambda`, which is checked in one pass instead of running
+// `condat`.
+fn denoise_piece<T>(values: &mut [T], lambda: T)
+    where T: TvFloat
+{
+    let mean = utils::mean(values);
+    let mut sum = T::zero();
+    let flat = values.iter().all(|&value| {
+        sum += value - mean;
+        sum.abs() <= lambda
+    });
+    if flat {
+        values.fill(mean);
+    } else {
+        condat_in_place(values, lambda);
+    }
+}
+
+// Denoises the block means, and proposes a split for each jump of the
+// coarse output, at the most likely change point within the blocks on
+// either side of the jump, where the running sum of the values minus
+// their mean is furthest from `0` the way of the jump.
+fn propose_splits<T>(input: &[T], lambda: T, factor: usize) -> Vec<(usize, T)>
+    where T: TvFloat
+{
+    let means: Vec<T> = input.chunks(factor).map(utils::mean).collect();
+    let factor_lambda = lambda / T::from_usize(factor).expect("Unable to convert usize to TvScalar.");
+    let coarse = condat(&means, factor_lambda);
+
+    let mut splits: Vec<(usize, T)> = Vec::new();
+    let mut block = 0;
+    while block + 1 < coarse.len() {
+        if coarse[block + 1] == coarse[block] {
+            block += 1;
+            continue;
+        }
+        // A jump within a block shows as two coarse jumps the same way,
+        // into and out of the block, and is proposed once.
+        let up = coarse[block + 1] > coarse[block];
+        let first = block;
+        while block + 2 < coarse.len() && (coarse[block + 2] > coarse[block + 1]) == up &&
+              coarse[block + 2] != coarse[block + 1] {
+            block += 1;
+        }
+        block += 1;
+
+        let start = match splits.last() {
+            Some(&(index, _)) => (index + 1).max(first * factor),
+            None => first * factor,
+        };
+        let end = ((block + 1) * factor).min(input.len());
+        if end - start < 2 {
+            continue;
+        }
+
+        let mean = utils::mean(&input[start..end]);
+        let mut sum = T::zero();
+        let mut best = (start, T::zero());
+        for (i, &value) in input[start..(end - 1)].iter().enumerate() {
+            // Below the mean before an upward jump, above it before a
+            // downward one.
+            sum += if up { mean - value } else { value - mean };
+            if sum > best.1 {
+                best = (start + i, sum);
+            }
+        }
+        splits.push((best.0, if up { -lambda } else { lambda }));
+    }
+    splits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condat_multiscale_test_matches_condat() {
+        let inputs: Vec<Vec<f64>> = vec![(0..5000).map(|i| ((i / 300) % 4) as f64 * 2.0 + ((i * 7919) % 17) as f64 * 0.1).collect(),
+                                         (0..3000).map(|i| ((i * 7919) % 101) as f64).collect(),
+                                         (0..4000).map(|i| (i as f64 / 500.0).sin() * 5.0 + ((i / 37) % 2) as f64).collect()];
+        for input in &inputs {
+            for &lambda in &[0.0, 0.1, 1.0, 10.0, 1000.0, f64::INFINITY] {
+                for &factor in &[1, 2, 8, 64, 4000] {
+                    let output = condat_multiscale(input, lambda, factor);
+                    let expected = condat(input, lambda);
+                    for (value, expected) in output.iter().zip(expected.iter()) {
+                        assert!((value - expected).abs() < 1e-8);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn condat_multiscale_test_keeps_splits() {
+        // Clean steps between blocks split the input at every jump.
+        let input: Vec<f64> = (0..1024).map(|i| ((i / 128) % 2) as f64 * 4.0).collect();
+        let splits = propose_splits(&input, 1.0, 16);
+        assert_eq!(splits.len(), 7);
+        assert_eq!((splits[0].0, splits[0].1), (127, -1.0));
+        let output = condat_multiscale(&input, 1.0, 16);
+        for (value, expected) in output.iter().zip(condat(&input, 1.0).iter()) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_multiscale_test_empty_input() {
+        condat_multiscale::<f64>(&[], 1.0, 8);
+    }
+}
@@ -0,0 +1,120 @@
+use ndarray::{Array, Array1, ArrayBase, ArrayView1, Axis, Data, Dimension};
+
+use axis::condat_axis;
+use condat;
+use scalar::TvScalar;
+
+/// Denoises a one-dimensional `ndarray` view with
+/// [`tv1d::condat`](../tv1d/fn.condat.html), e.g. a row or a column of
+/// a larger array.
+///
+/// Views whose values are not contiguous in memory are copied first.
+///
+/// # Panics
+/// Panics if the view's length is `0`, or `lambda` is NaN or less than
+/// `0`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate ndarray;
+/// extern crate tv1d;
+///
+/// use ndarray::array;
+///
+/// let input = array![[1.0, 10.0], [2.0, 10.0], [3.0, 10.0], [4.0, 10.0], [5.0, 10.0]];
+/// let column = tv1d::condat_view(input.column(0), 10.0);
+/// assert_eq!(column, array![3.0, 3.0, 3.0, 3.0, 3.0]);
+/// ```
+pub fn condat_view<T>(input: ArrayView1<T>, lambda: T) -> Array1<T>
+    where T: TvScalar
+{
+    match input.as_slice() {
+        Some(values) => Array1::from(condat(values, lambda)),
+        None => Array1::from(condat(&input.to_vec(), lambda)),
+    }
+}
+
+/// Denoises every lane along `axis` of an `ndarray` array with
+/// [`tv1d::condat`](../tv1d/fn.condat.html), e.g. every row of an
+/// `Array2` for `Axis(1)` and every column for `Axis(0)`, and returns
+/// the denoised array.
+///
+/// The array may have any number of dimensions and any memory layout.
+/// It is copied into row-major order, whose lanes are denoised with
+/// [`tv1d::condat_axis`](../tv1d/fn.condat_axis.html), in parallel
+/// with the `rayon` feature.
+///
+/// # Panics
+/// Panics if `axis` is not less than the number of dimensions of the
+/// array, or `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate ndarray;
+/// extern crate tv1d;
+///
+/// use ndarray::{array, Axis};
+///
+/// let input = array![[1.0, 2.0, 3.0, 4.0, 5.0],
+///                    [5.0, 5.0, 5.0, 5.0, 9.0]];
+///
+/// assert_eq!(tv1d::denoise_axis(&input, Axis(0), 10.0),
+///            array![[3.0, 3.5, 4.0, 4.5, 7.0],
+///                   [3.0, 3.5, 4.0, 4.5, 7.0]]);
+/// ```
+pub fn denoise_axis<T, S, D>(input: &ArrayBase<S, D>, axis: Axis, lambda: T) -> Array<T, D>
+    where T: TvScalar,
+          S: Data<Elem = T>,
+          D: Dimension
+{
+    let mut output = input.as_standard_layout().into_owned();
+    let shape = output.shape().to_vec();
+    let strides: Vec<usize> = output.strides().iter().map(|&stride| stride as usize).collect();
+    condat_axis(output.as_slice_mut().expect("Standard layout arrays are contiguous."),
+                &shape,
+                &strides,
+                axis.index(),
+                lambda);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{s, Array2, Array3};
+
+    #[test]
+    fn condat_view_test_matches_condat() {
+        let values = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let input = Array2::from_shape_vec((4, 2), values.clone()).unwrap();
+        for column in 0..2 {
+            let expected: Vec<f64> = values.iter().skip(column).step_by(2).cloned().collect();
+            assert_eq!(condat_view(input.column(column), 1.5).to_vec(), condat(&expected, 1.5));
+        }
+        let reversed = input.slice(s![..;-1, 0]);
+        assert_eq!(condat_view(reversed, 1.5).to_vec(), condat(&[6.2, 1.4, 5.2, 1.0][..], 1.5));
+    }
+
+    #[test]
+    fn denoise_axis_test_layouts() {
+        let input = Array3::from_shape_fn((3, 4, 5), |(i, j, k)| ((i * 7 + j * 13 + k * 29) % 11) as f64);
+        let transposed = input.clone().reversed_axes();
+        for axis in 0..3 {
+            let output = denoise_axis(&input, Axis(axis), 2.0);
+            for (lane, expected) in output.lanes(Axis(axis)).into_iter().zip(input.lanes(Axis(axis))) {
+                assert_eq!(lane.to_vec(), condat(&expected.to_vec(), 2.0));
+            }
+            // The same lanes, through an array in column-major order.
+            let output = denoise_axis(&transposed, Axis(2 - axis), 2.0);
+            assert_eq!(output.reversed_axes(), denoise_axis(&input, Axis(axis), 2.0));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn denoise_axis_test_invalid_axis() {
+        denoise_axis(&Array2::<f64>::zeros((2, 3)), Axis(2), 1.0);
+    }
+}
@@ -0,0 +1,93 @@
+use condat;
+use scalar::TvFloat;
+use utils;
+
+/// Denoises the input values with
+/// [`tv1d::condat`](../tv1d/fn.condat.html) after standardizing
+/// them, so that `lambda` has the same effect on signals of any
+/// magnitude.
+///
+/// The input is centered on its median and divided by its median
+/// absolute deviation from the median, scaled to estimate the
+/// standard deviation of normally distributed values, both of which
+/// are robust to outliers. The standardized values are denoised with
+/// `lambda`, and the output is returned in the original scale. The
+/// output of `input * a + b` is therefore that of `input`, times `a`
+/// plus `b`, up to rounding.
+///
+/// If more than half of the values are equal, their deviation is `0`,
+/// and the input is only centered.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let volts = vec![0.010, 0.012, 0.011, 0.050, 0.052, 0.051];
+/// let millivolts: Vec<f64> = volts.iter().map(|volt| volt * 1000.0).collect();
+///
+/// let denoised = tv1d::condat_normalized(&volts, 0.5);
+/// let expected: Vec<f64> = tv1d::condat_normalized(&millivolts, 0.5).iter().map(|mv| mv / 1000.0).collect();
+/// for (value, expected) in denoised.iter().zip(expected.iter()) {
+///     assert!((value - expected).abs() < 1e-12);
+/// }
+/// ```
+pub fn condat_normalized<T>(input: &[T], lambda: T) -> Vec<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    let center = utils::median(input);
+    let deviations: Vec<T> = input.iter().map(|&value| (value - center).abs()).collect();
+    // `0.6745` is the median of the absolute standard normal.
+    let consistency = T::from_f64(0.6745).expect("Unable to convert f64 to TvScalar.");
+    let mut scale = utils::median(&deviations) / consistency;
+    if !scale.is_finite() || scale <= T::zero() {
+        scale = T::one();
+    }
+
+    let standardized: Vec<T> = input.iter().map(|&value| (value - center) / scale).collect();
+    condat(&standardized, lambda).into_iter().map(|value| value * scale + center).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condat_normalized_test_scale_invariant() {
+        let input: Vec<f64> = (0..200).map(|i| ((i / 25) % 3) as f64 + ((i * 7919) % 13) as f64 * 0.05).collect();
+        let expected = condat_normalized(&input, 1.0);
+        for &(a, b) in &[(1e-6, 0.0), (1e6, -3.0), (-2.0, 1e3)] {
+            let transformed: Vec<f64> = input.iter().map(|value| value * a + b).collect();
+            let output = condat_normalized(&transformed, 1.0);
+            for (value, expected) in output.iter().zip(expected.iter()) {
+                assert!((value - (expected * a + b)).abs() <= 1e-9 * (a.abs() + b.abs()));
+            }
+        }
+    }
+
+    #[test]
+    fn condat_normalized_test_matches_condat() {
+        // The median is `3` and the median absolute deviation `1`.
+        let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let scale = 1.0 / 0.6745;
+        let output = condat_normalized(&input, 0.5);
+        let expected = condat(&input, 0.5 * scale);
+        for (value, expected) in output.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn condat_normalized_test_zero_deviation() {
+        let input = vec![2.0, 2.0, 2.0, 9.0];
+        assert_eq!(condat_normalized(&input, 0.0), input);
+        assert_eq!(condat_normalized(&input, 1.0), condat(&input, 1.0));
+    }
+}
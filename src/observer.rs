@@ -0,0 +1,39 @@
+// Receives the intermediate states of the solvers as they run. The
+// methods do nothing by default, so that the solvers observed by `()`
+// compile to the same code as without an observer.
+pub(crate) trait Observer<T> {
+    // The state of `condat` before it inspects the value after
+    // `bounds.index`.
+    fn bounds(&mut self, _bounds: Bounds<T>) {}
+
+    // A segment of the output of `condat`, from `start` to `end`
+    // exclusive, being written with `value`.
+    fn segment(&mut self, _start: usize, _end: usize, _value: T) {}
+
+    // The number of vertices of the lower and the upper hull of the
+    // taut string after it reaches `index`.
+    fn hulls(&mut self, _index: usize, _lower: usize, _upper: usize) {}
+
+    // A knot of the taut string at `index`, with the value of the
+    // output since the previous knot.
+    fn knot(&mut self, _index: usize, _value: T) {}
+
+    // A solver returning without running the algorithm, for `reason`.
+    fn shortcut(&mut self, _reason: &str) {}
+}
+
+impl<T> Observer<T> for () {}
+
+// The state of `condat`, named as in `tv1d::condat_with_dump`, whose
+// JSON lines are the only reader of the fields.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) struct Bounds<T> {
+    pub(crate) index: usize,
+    pub(crate) segment_start: usize,
+    pub(crate) kminus: usize,
+    pub(crate) kplus: usize,
+    pub(crate) umin: T,
+    pub(crate) umax: T,
+    pub(crate) lower: T,
+    pub(crate) upper: T,
+}
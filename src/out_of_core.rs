@@ -0,0 +1,200 @@
+use std::cmp;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::iter;
+
+use file::{read_full, write_samples, RawSample, WRITE_CHUNK_LEN};
+use hull::{self, Compensated, Knots};
+use utils;
+
+/// Denoises raw little-endian samples read from `reader` with the
+/// taut string algorithm of
+/// [`tv1d::tautstring`](../tv1d/fn.tautstring.html), for inputs that
+/// do not fit in memory, and writes the denoised output to `writer`
+/// in the same format.
+///
+/// The taut string is found in a first pass over the input, which
+/// streams the input and writes each knot of the taut string to
+/// `scratch` as soon as it is final. A second pass reads the knots
+/// back from `scratch` and writes the output. Only the hulls of the
+/// bounds since the last knot are held in memory, so the memory use
+/// is bounded by the length of the longest segment.
+/// `scratch` can be a temporary file, and is overwritten from its
+/// start. An empty input yields an empty output.
+///
+/// # Errors
+/// Returns an error if reading from `reader`, writing to `writer` or
+/// using `scratch` fails, or if the input ends in the middle of a
+/// sample.
+///
+/// # Panics
+/// Panics if `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use tv1d;
+///
+/// let input: Vec<u8> = [1.0f64, 2.0, 3.0, 4.0, 5.0].iter().flat_map(|x| x.to_le_bytes().to_vec()).collect();
+/// let mut output = Vec::new();
+/// tv1d::tautstring_out_of_core(&input[..], &mut output, Cursor::new(Vec::new()), 10.0f64).unwrap();
+/// assert_eq!(output, [3.0f64; 5].iter().flat_map(|x| x.to_le_bytes().to_vec()).collect::<Vec<_>>());
+/// ```
+pub fn tautstring_out_of_core<T, R, W, S>(reader: R, writer: W, mut scratch: S, lambda: T) -> io::Result<()>
+    where T: RawSample,
+          R: Read,
+          W: Write,
+          S: Read + Write + Seek
+{
+    assert!(!lambda.is_nan(),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    let mut samples = SampleReader {
+        reader: BufReader::new(reader),
+        bytes: vec![0; T::SIZE],
+    };
+    let mut writer = BufWriter::new(writer);
+
+    if utils::is_infinite_lambda(lambda) {
+        // The output is the mean, which only needs the sum and the
+        // length of the input, so `scratch` is not used.
+        let (mut sum, mut len) = (T::zero(), 0);
+        while let Some(sample) = samples.next_sample()? {
+            sum += sample;
+            len += 1;
+        }
+        if len > 0 {
+            let mean = sum / T::from_usize(len).expect("Unable to convert usize to TvScalar.");
+            let mut bytes = Vec::new();
+            while len > 0 {
+                let chunk_len = cmp::min(len, WRITE_CHUNK_LEN);
+                write_samples(&mut writer, (0..chunk_len).map(|_| mean), &mut bytes)?;
+                len -= chunk_len;
+            }
+        }
+        return writer.flush();
+    }
+
+    scratch.seek(SeekFrom::Start(0))?;
+    let mut knots = KnotWriter {
+        writer: BufWriter::new(&mut scratch),
+        count: 0,
+    };
+    let samples = iter::from_fn(|| samples.next_sample().map(|sample| sample.map(Compensated::new)).transpose());
+    hull::find_knots(samples, Compensated::new(lambda), &mut knots)?;
+    knots.writer.flush()?;
+    let count = knots.count;
+    drop(knots);
+
+    scratch.seek(SeekFrom::Start(0))?;
+    let mut knots = BufReader::new(&mut scratch);
+    let mut bytes = vec![0; 8 + T::SIZE];
+    let mut output_bytes = Vec::new();
+    let mut previous_index = 0;
+    for _ in 0..count {
+        knots.read_exact(&mut bytes)?;
+        let mut le = [0; 8];
+        le.copy_from_slice(&bytes[..8]);
+        let index = u64::from_le_bytes(le) as usize;
+        let value = T::read_le(&bytes[8..]);
+
+        // A segment can be as long as the input, so it is written in
+        // chunks.
+        let mut len = index - previous_index;
+        while len > 0 {
+            let chunk_len = cmp::min(len, WRITE_CHUNK_LEN);
+            write_samples(&mut writer, (0..chunk_len).map(|_| value), &mut output_bytes)?;
+            len -= chunk_len;
+        }
+        previous_index = index;
+    }
+    writer.flush()
+}
+
+// Reads raw samples one at a time.
+struct SampleReader<R> {
+    reader: R,
+    bytes: Vec<u8>,
+}
+
+impl<R: Read> SampleReader<R> {
+    fn next_sample<T: RawSample>(&mut self) -> io::Result<Option<T>> {
+        match read_full(&mut self.reader, &mut self.bytes)? {
+            0 => Ok(None),
+            len if len == self.bytes.len() => Ok(Some(T::read_le(&self.bytes))),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "input ends in the middle of a sample")),
+        }
+    }
+}
+
+// Writes the knots of the taut string, each as its index and the
+// denoised value of the segment ending at it, after the implicit
+// first knot at `0`.
+struct KnotWriter<W> {
+    writer: W,
+    count: usize,
+}
+
+impl<T: RawSample, W: Write> Knots<T> for KnotWriter<W> {
+    type Error = io::Error;
+
+    fn push(&mut self, index: usize, value: T) -> io::Result<()> {
+        let mut bytes = vec![0; T::SIZE];
+        value.write_le(&mut bytes);
+        self.writer.write_all(&(index as u64).to_le_bytes())?;
+        self.writer.write_all(&bytes)?;
+        self.count += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tautstring;
+
+    use std::io::Cursor;
+
+    fn denoise(samples: &[f64], lambda: f64) -> Vec<f64> {
+        let mut input = Vec::new();
+        write_samples(&mut input, samples.iter().cloned(), &mut Vec::new()).unwrap();
+        let mut output = Vec::new();
+        tautstring_out_of_core(&input[..], &mut output, Cursor::new(Vec::new()), lambda).unwrap();
+        output.chunks(8).map(f64::read_le).collect()
+    }
+
+    #[test]
+    fn tautstring_out_of_core_test_matches_tautstring() {
+        let inputs = vec![vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1],
+                          vec![111.0, 422.1, 145.2],
+                          vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1],
+                          vec![5.0],
+                          (0..1000).map(|i| ((i / 40) % 3) as f64 * 5.0 + ((i * 7919) % 13) as f64 * 0.4).collect()];
+        for input in &inputs {
+            for &lambda in &[0.0, 1.0, 5.0, 100.0, 1000.0, f64::INFINITY] {
+                let output = denoise(input, lambda);
+                let output_expected = tautstring(input, lambda);
+                assert_eq!(output.len(), input.len());
+                for i in 0..input.len() {
+                    assert!((output[i] - output_expected[i]).abs() <= 0.000001);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tautstring_out_of_core_test_empty_input() {
+        assert!(denoise(&[], 1.0).is_empty());
+        assert!(denoise(&[], f64::INFINITY).is_empty());
+    }
+
+    #[test]
+    fn tautstring_out_of_core_test_partial_sample() {
+        let error = tautstring_out_of_core(&[0u8; 12][..], Vec::new(), Cursor::new(Vec::new()), 1.0f64)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
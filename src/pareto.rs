@@ -0,0 +1,148 @@
+use path::regularization_path;
+use scalar::TvFloat;
+use utils;
+
+/// A point on the trade-off curve between fidelity to the input and
+/// total variation of the denoised output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ParetoPoint<T> {
+    /// The `lambda` the output was denoised with.
+    pub lambda: T,
+    /// The Euclidean norm of the difference between the input and the
+    /// denoised output.
+    pub residual_norm: T,
+    /// The sum of absolute differences between consecutive values of
+    /// the denoised output.
+    pub total_variation: T,
+}
+
+/// Returns the trade-off between the residual norm and the total
+/// variation of the denoised output for each `lambda` in `lambdas`.
+///
+/// As `lambda` grows, the residual norm increases while the total
+/// variation decreases. The [regularization
+/// path](../tv1d/fn.regularization_path.html) is computed once and
+/// shared between all values of `lambda`.
+///
+/// # Panics
+/// Panics if input vector's length is `0` or any `lambda` is less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+///
+/// let frontier = tv1d::pareto_frontier(&input, &[0.0, 10.0]);
+/// assert_eq!(frontier[0].residual_norm, 0.0);
+/// assert_eq!(frontier[0].total_variation, 4.0);
+/// assert_eq!(frontier[1].total_variation, 0.0);
+/// ```
+pub fn pareto_frontier<T>(input: &[T], lambdas: &[T]) -> Vec<ParetoPoint<T>>
+    where T: TvFloat
+{
+    let path = regularization_path(input);
+    lambdas.iter()
+        .map(|&lambda| {
+            let output = path.solution(lambda);
+            let residual_norm = utils::squared_distance(input, &output).sqrt();
+            let total_variation = output.windows(2)
+                .fold(T::zero(), |sum, pair| sum + (pair[1] - pair[0]).abs());
+            ParetoPoint {
+                lambda,
+                residual_norm,
+                total_variation,
+            }
+        })
+        .collect()
+}
+
+/// Returns the index of the corner of a trade-off curve, i.e. the
+/// point furthest from the straight line between the first and the
+/// last point once both axes are scaled to `[0, 1]`.
+///
+/// The corner is where increasing `lambda` stops removing much total
+/// variation for its cost in fidelity, and is a common automatic
+/// choice of `lambda`. Returns `None` if there are fewer than three
+/// points or the curve is a straight line.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 1.1, 0.9, 1.0, 5.0, 5.1, 4.9, 5.0];
+/// let lambdas: Vec<f64> = (0..40).map(|i| i as f64 * 0.25).collect();
+///
+/// let frontier = tv1d::pareto_frontier(&input, &lambdas);
+/// let corner = tv1d::pareto_corner(&frontier).unwrap();
+/// assert!(corner > 0 && corner < lambdas.len() - 1);
+/// ```
+pub fn pareto_corner<T>(points: &[ParetoPoint<T>]) -> Option<usize>
+    where T: TvFloat
+{
+    if points.len() < 3 {
+        return None;
+    }
+
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let residual_range = last.residual_norm - first.residual_norm;
+    let variation_range = first.total_variation - last.total_variation;
+    if residual_range == T::zero() || variation_range == T::zero() {
+        return None;
+    }
+
+    // Once scaled, the line between the end points is `x + y = 1`,
+    // and the corner is the point furthest below it.
+    let mut corner = None;
+    let mut max_distance = T::zero();
+    for (i, point) in points.iter().enumerate() {
+        let x = (point.residual_norm - first.residual_norm) / residual_range;
+        let y = (point.total_variation - last.total_variation) / variation_range;
+        let distance = T::one() - x - y;
+        if distance > max_distance {
+            max_distance = distance;
+            corner = Some(i);
+        }
+    }
+    corner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pareto_frontier_test_monotone() {
+        let input: Vec<f64> = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        let lambdas: Vec<f64> = (0..50).map(|i| i as f64 * 20.0).collect();
+        let frontier = pareto_frontier(&input, &lambdas);
+        assert_eq!(frontier.len(), lambdas.len());
+        for pair in frontier.windows(2) {
+            assert!(pair[0].residual_norm <= pair[1].residual_norm + 0.000001);
+            assert!(pair[0].total_variation + 0.000001 >= pair[1].total_variation);
+        }
+        assert!(frontier[frontier.len() - 1].total_variation.abs() <= 0.000001);
+    }
+
+    #[test]
+    fn pareto_corner_test_step() {
+        let input: Vec<f64> = vec![1.0, 1.1, 0.9, 1.0, 5.0, 5.1, 4.9, 5.0];
+        let lambdas: Vec<f64> = (0..40).map(|i| i as f64 * 0.25).collect();
+        let frontier = pareto_frontier(&input, &lambdas);
+        let corner = pareto_corner(&frontier).unwrap();
+        // At the corner, the noise is removed but the step is kept.
+        let total_variation = frontier[corner].total_variation;
+        assert!(total_variation > 1.0 && total_variation < 4.1);
+    }
+
+    #[test]
+    fn pareto_corner_test_too_few_points() {
+        let input: Vec<f64> = vec![1.0, 5.0];
+        let frontier = pareto_frontier(&input, &[0.0, 1.0]);
+        assert_eq!(pareto_corner(&frontier), None);
+    }
+}
@@ -0,0 +1,417 @@
+use std::cmp;
+use std::collections::BinaryHeap;
+
+use scalar::TvFloat;
+
+/// A fusion event on the regularization path: at `lambda`, the
+/// samples at `index - 1` and `index` join the same constant segment
+/// and stay joined for every larger `lambda`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Fusion<T> {
+    /// The `lambda` at which the two neighbouring segments fuse.
+    pub lambda: T,
+    /// The index of the first sample to the right of the fused
+    /// boundary.
+    pub index: usize,
+}
+
+/// The entire solution path of total variation denoising as `lambda`
+/// goes from `0` to [`tv1d::lambda_max`](../tv1d/fn.lambda_max.html).
+///
+/// In 1D, segments only ever fuse as `lambda` grows, and each segment
+/// value moves linearly in `lambda` between two fusion events. The
+/// path is therefore fully described by the `lambda` at which each
+/// boundary between neighbouring samples disappears, and the denoised
+/// output for any `lambda` can be read off the path in linear time
+/// without re-running a solver.
+///
+/// Use [`tv1d::regularization_path`](../tv1d/fn.regularization_path.html)
+/// to compute the path.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RegularizationPath<T> {
+    input: Vec<T>,
+    // `signs[i]` is the direction of the jump between `input[i]` and
+    // `input[i + 1]`, which the boundary keeps until it fuses.
+    signs: Vec<T>,
+    // `fusion_lambdas[i]` is the `lambda` at which `input[i]` and
+    // `input[i + 1]` join the same segment.
+    fusion_lambdas: Vec<T>,
+}
+
+impl<T> RegularizationPath<T>
+    where T: TvFloat
+{
+    /// Returns every fusion event on the path, ordered by increasing
+    /// `lambda`.
+    pub fn fusions(&self) -> Vec<Fusion<T>> {
+        let mut fusions: Vec<Fusion<T>> = self.fusion_lambdas
+            .iter()
+            .enumerate()
+            .map(|(i, &lambda)| {
+                Fusion {
+                    lambda,
+                    index: i + 1,
+                }
+            })
+            .collect();
+        fusions.sort_by(|a, b| {
+            a.lambda.partial_cmp(&b.lambda).unwrap_or(cmp::Ordering::Equal).then(a.index.cmp(&b.index))
+        });
+        fusions
+    }
+
+    /// Returns the smallest `lambda` at which the whole output is a
+    /// single constant segment.
+    pub fn lambda_max(&self) -> T {
+        self.fusion_lambdas.iter().fold(T::zero(), |max, &lambda| max.max(lambda))
+    }
+
+    /// Returns the number of constant segments in the denoised output
+    /// for `lambda`.
+    pub fn segment_count(&self, lambda: T) -> usize {
+        1 + self.fusion_lambdas.iter().filter(|&&fusion| fusion > lambda).count()
+    }
+
+    /// Returns the denoised output for `lambda`.
+    ///
+    /// # Panics
+    /// Panics if `lambda` is NaN or less than `0`.
+    pub fn solution(&self, lambda: T) -> Vec<T> {
+        assert!(!lambda.is_nan(),
+                "Lambda must not be NaN.");
+
+        assert!(lambda >= T::zero(),
+                "Lambda must be greater than or equal to 0.");
+
+        let width = self.input.len();
+        let mut output = vec![T::zero(); width];
+        let mut segment_start = 0;
+        for segment_end in 1..(width + 1) {
+            if segment_end < width && self.fusion_lambdas[segment_end - 1] <= lambda {
+                continue;
+            }
+            let value = self.segment_value(segment_start, segment_end, lambda);
+            output[segment_start..segment_end].fill(value);
+            segment_start = segment_end;
+        }
+        output
+    }
+
+    // Value of the segment covering `input[start..end]` at `lambda`,
+    // which is the segment mean shifted by `lambda` towards its
+    // neighbours.
+    fn segment_value(&self, start: usize, end: usize, lambda: T) -> T {
+        let sum = self.input[start..end].iter().fold(T::zero(), |sum, &value| sum + value);
+        let len = T::from_usize(end - start).expect("Unable to convert usize to TvScalar.");
+        let pull = self.pull(start, end);
+        if pull == T::zero() {
+            // Avoids `0 * infinity` for an infinite `lambda`.
+            return sum / len;
+        }
+        (sum - lambda * pull) / len
+    }
+
+    // Net number of neighbours lying below the segment covering
+    // `input[start..end]`, each of which pulls the segment down by
+    // `lambda`.
+    fn pull(&self, start: usize, end: usize) -> T {
+        let mut pull = T::zero();
+        if start > 0 {
+            pull += self.signs[start - 1];
+        }
+        if end < self.input.len() {
+            pull -= self.signs[end - 1];
+        }
+        pull
+    }
+}
+
+/// Computes the entire regularization path of total variation
+/// denoising for `input`, i.e. the denoised output for every `lambda`
+/// at once.
+///
+/// This runs in `O(n log n)` time, after which the output for any
+/// `lambda` is available in linear time through
+/// [`RegularizationPath::solution`](struct.RegularizationPath.html#method.solution).
+/// This is much faster than re-solving for each `lambda` when
+/// exploring many values of `lambda` for model selection.
+///
+/// # Panics
+/// Panics if input vector's length is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// let path = tv1d::regularization_path(&input);
+///
+/// assert_eq!(path.solution(0.0), input);
+/// assert_eq!(path.solution(1.0), vec![2.0, 2.0, 3.0, 4.0, 4.0]);
+/// assert_eq!(path.solution(10.0), vec![3.0, 3.0, 3.0, 3.0, 3.0]);
+/// assert_eq!(path.segment_count(2.5), 3);
+/// ```
+pub fn regularization_path<T>(input: &[T]) -> RegularizationPath<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    let width = input.len();
+    let signs: Vec<T> = input.windows(2).map(|pair| sign(pair[1] - pair[0])).collect();
+    let mut fusion_lambdas = vec![T::infinity(); width - 1];
+
+    // Every sample starts as its own segment. Segments are kept in a
+    // linked list and identified by the index of their first sample.
+    let mut segments: Vec<Segment<T>> = (0..width)
+        .map(|i| {
+            Segment {
+                end: i + 1,
+                previous: i.checked_sub(1),
+                sum: input[i],
+                stamp: 0,
+            }
+        })
+        .collect();
+
+    let path = RegularizationPath {
+        input: input.to_vec(),
+        signs,
+        fusion_lambdas: Vec::new(),
+    };
+
+    let mut events = BinaryHeap::new();
+    for start in 1..width {
+        push_event(&path, &segments, start - 1, start, T::zero(), &mut events);
+    }
+
+    while let Some(Event { lambda, left, right, left_stamp, right_stamp }) = events.pop() {
+        if segments[left].stamp != left_stamp || segments[right].stamp != right_stamp ||
+           segments[left].end != right {
+            // One of the segments has fused with another segment
+            // since the event was scheduled.
+            continue;
+        }
+
+        fusion_lambdas[right - 1] = lambda;
+
+        let right_segment = segments[right];
+        segments[right].stamp += 1;
+        let merged = &mut segments[left];
+        merged.end = right_segment.end;
+        merged.sum += right_segment.sum;
+        merged.stamp += 1;
+        let (previous, end) = (merged.previous, merged.end);
+        if end < width {
+            segments[end].previous = Some(left);
+        }
+
+        if let Some(previous) = previous {
+            push_event(&path, &segments, previous, left, lambda, &mut events);
+        }
+        if end < width {
+            push_event(&path, &segments, left, end, lambda, &mut events);
+        }
+    }
+
+    RegularizationPath { fusion_lambdas, ..path }
+}
+
+/// Denoises the input values for each `lambda` in `lambdas`, returning
+/// one output per `lambda` in the same order.
+///
+/// The output for each `lambda` is the same as calling
+/// [`tv1d::condat`](../tv1d/fn.condat.html), but the work of locating
+/// the segment boundaries is shared between all values of `lambda`
+/// through the [regularization
+/// path](../tv1d/fn.regularization_path.html). This is much faster
+/// than independent calls when evaluating many values of `lambda`.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or any `lambda` is NaN or
+/// less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+///
+/// let outputs = tv1d::condat_multi(&input, &[0.0, 10.0]);
+/// assert_eq!(outputs[0], vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+/// assert_eq!(outputs[1], vec![3.0, 3.0, 3.0, 3.0, 3.0]);
+/// ```
+pub fn condat_multi<T>(input: &[T], lambdas: &[T]) -> Vec<Vec<T>>
+    where T: TvFloat
+{
+    let path = regularization_path(input);
+    lambdas.iter().map(|&lambda| path.solution(lambda)).collect()
+}
+
+fn sign<T: TvFloat>(value: T) -> T {
+    if value > T::zero() {
+        T::one()
+    } else if value < T::zero() {
+        -T::one()
+    } else {
+        T::zero()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Segment<T> {
+    end: usize,
+    previous: Option<usize>,
+    sum: T,
+    // Bumped on every fusion to invalidate events scheduled earlier.
+    stamp: usize,
+}
+
+#[derive(Debug)]
+struct Event<T> {
+    lambda: T,
+    left: usize,
+    right: usize,
+    left_stamp: usize,
+    right_stamp: usize,
+}
+
+impl<T: PartialOrd> PartialEq for Event<T> {
+    fn eq(&self, other: &Event<T>) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl<T: PartialOrd> Eq for Event<T> {}
+
+impl<T: PartialOrd> PartialOrd for Event<T> {
+    fn partial_cmp(&self, other: &Event<T>) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: PartialOrd> Ord for Event<T> {
+    // Reversed so that `BinaryHeap` pops the smallest `lambda` first.
+    fn cmp(&self, other: &Event<T>) -> cmp::Ordering {
+        other.lambda
+            .partial_cmp(&self.lambda)
+            .unwrap_or(cmp::Ordering::Equal)
+            .then(other.left.cmp(&self.left))
+    }
+}
+
+// Schedules the fusion of the neighbouring segments starting at
+// `left` and `right`, if their values are moving towards each other.
+fn push_event<T>(path: &RegularizationPath<T>,
+                 segments: &[Segment<T>],
+                 left: usize,
+                 right: usize,
+                 current_lambda: T,
+                 events: &mut BinaryHeap<Event<T>>)
+    where T: TvFloat
+{
+    let len = |start: usize| {
+        T::from_usize(segments[start].end - start)
+            .expect("Unable to convert usize to TvScalar.")
+    };
+    let (left_len, right_len) = (len(left), len(right));
+
+    // The gap between the segment values is `gap - lambda * closing`.
+    let gap = segments[right].sum / right_len - segments[left].sum / left_len;
+    let closing = path.pull(right, segments[right].end) / right_len -
+                  path.pull(left, segments[left].end) / left_len;
+
+    let lambda = if gap == T::zero() {
+        current_lambda
+    } else if gap * closing > T::zero() {
+        (gap / closing).max(current_lambda)
+    } else {
+        return;
+    };
+
+    events.push(Event {
+        lambda,
+        left,
+        right,
+        left_stamp: segments[left].stamp,
+        right_stamp: segments[right].stamp,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+    use lambda_max;
+
+    #[test]
+    fn regularization_path_test_matches_condat() {
+        let input: Vec<f64> = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let path = regularization_path(&input);
+        for &lambda in &[0.0, 0.3, 1.0, 2.5, 3.0, 7.0, 20.0, 100.0] {
+            let output = path.solution(lambda);
+            let output_expected = condat(&input, lambda);
+            for i in 0..input.len() {
+                assert!((output[i] - output_expected[i]).abs() <= 0.000001);
+            }
+        }
+    }
+
+    #[test]
+    fn regularization_path_test_lambda_max() {
+        let input: Vec<f64> = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        let path = regularization_path(&input);
+        assert!((path.lambda_max() - lambda_max(&input)).abs() <= 0.000001);
+        assert_eq!(path.segment_count(path.lambda_max()), 1);
+    }
+
+    #[test]
+    fn regularization_path_test_fusions_sorted() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let fusions = regularization_path(&input).fusions();
+        assert_eq!(fusions.len(), input.len() - 1);
+        for pair in fusions.windows(2) {
+            assert!(pair[0].lambda <= pair[1].lambda);
+        }
+    }
+
+    #[test]
+    fn regularization_path_test_repeated_values() {
+        let input = vec![3.0, 3.0, 3.0, 1.0, 1.0];
+        let path = regularization_path(&input);
+        assert_eq!(path.segment_count(0.0), 2);
+        assert_eq!(path.solution(0.0), input);
+    }
+
+    #[test]
+    fn condat_multi_test_matches_condat() {
+        let input: Vec<f64> = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        let lambdas = [700.0, 0.0, 5.0, 50.0];
+        let outputs = condat_multi(&input, &lambdas);
+        assert_eq!(outputs.len(), lambdas.len());
+        for (output, &lambda) in outputs.iter().zip(lambdas.iter()) {
+            let output_expected = condat(&input, lambda);
+            for i in 0..input.len() {
+                assert!((output[i] - output_expected[i]).abs() <= 0.000001);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_multi_test_negative_lambda() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        condat_multi(&input, &[1.0, -1.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn regularization_path_test_empty_input() {
+        let input: Vec<f64> = vec![];
+        regularization_path(&input);
+    }
+}
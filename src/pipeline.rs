@@ -0,0 +1,256 @@
+use std::cmp;
+
+use denoiser::TvDenoiser;
+use scalar::TvFloat;
+
+/// A slowly varying trend, fitted to the input by
+/// [`Trend::fit`](enum.Trend.html#method.fit).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Trend {
+    /// The least squares polynomial of the given degree in the index
+    /// of the input. A degree of `0` is the mean of the input, and `1`
+    /// a straight line.
+    Polynomial(usize),
+    /// The mean of the given number of input values centered on each
+    /// value, shortened to fit within the input at its ends.
+    MovingAverage(usize),
+}
+
+impl Trend {
+    /// Returns the trend of the input values, one value per input
+    /// value.
+    ///
+    /// A polynomial of a degree not less than the input's length
+    /// interpolates the input, and is fitted with the degree one less
+    /// than the length instead.
+    ///
+    /// # Panics
+    /// Panics if input vector's length is `0`, or the window of
+    /// `Trend::MovingAverage` is `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tv1d::Trend;
+    ///
+    /// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+    /// assert_eq!(Trend::MovingAverage(3).fit(&input), vec![1.5, 2.0, 3.0, 4.0, 4.5]);
+    /// ```
+    pub fn fit<T>(self, input: &[T]) -> Vec<T>
+        where T: TvFloat
+    {
+        assert!(!input.is_empty(),
+                "Input list should have at least one value.");
+
+        match self {
+            Trend::Polynomial(degree) => polynomial(input, degree.min(input.len() - 1)),
+            Trend::MovingAverage(window) => {
+                assert!(window > 0, "Window must be greater than 0.");
+                moving_average(input, window)
+            }
+        }
+    }
+}
+
+// Fits the least squares polynomial of `degree` by solving its normal
+// equations, with the indices mapped onto `[-1, 1]` to keep them well
+// conditioned.
+fn polynomial<T>(input: &[T], degree: usize) -> Vec<T>
+    where T: TvFloat
+{
+    let size = degree + 1;
+    let two = T::one() + T::one();
+    let last = T::from_usize(cmp::max(input.len() - 1, 1)).expect("Unable to convert usize to TvScalar.");
+    let xs: Vec<T> = (0..input.len())
+        .map(|i| two * T::from_usize(i).expect("Unable to convert usize to TvScalar.") / last - T::one())
+        .collect();
+
+    // The rows of the normal equations, each followed by its right
+    // hand side.
+    let mut rows = vec![vec![T::zero(); size + 1]; size];
+    let mut powers = vec![T::one(); 2 * size - 1];
+    for (&x, &y) in xs.iter().zip(input.iter()) {
+        for k in 1..powers.len() {
+            powers[k] = powers[k - 1] * x;
+        }
+        for (j, row) in rows.iter_mut().enumerate() {
+            for k in 0..size {
+                row[k] += powers[j + k];
+            }
+            row[size] += powers[j] * y;
+        }
+    }
+
+    // Gaussian elimination with partial pivoting.
+    for column in 0..size {
+        let pivot = (column..size)
+            .max_by(|&a, &b| rows[a][column].abs().partial_cmp(&rows[b][column].abs()).unwrap_or(cmp::Ordering::Equal))
+            .expect("The normal equations have at least one row.");
+        rows.swap(column, pivot);
+        let pivot_row = rows[column].clone();
+        for row in rows.iter_mut().skip(column + 1) {
+            let factor = row[column] / pivot_row[column];
+            for k in column..(size + 1) {
+                row[k] -= factor * pivot_row[k];
+            }
+        }
+    }
+    let mut coefficients = vec![T::zero(); size];
+    for j in (0..size).rev() {
+        let mut sum = rows[j][size];
+        for k in (j + 1)..size {
+            sum -= rows[j][k] * coefficients[k];
+        }
+        coefficients[j] = sum / rows[j][j];
+    }
+
+    xs.iter().map(|&x| coefficients.iter().rev().fold(T::zero(), |value, &coefficient| value * x + coefficient)).collect()
+}
+
+fn moving_average<T>(input: &[T], window: usize) -> Vec<T>
+    where T: TvFloat
+{
+    let mut sums = Vec::with_capacity(input.len() + 1);
+    sums.push(T::zero());
+    for &value in input {
+        let sum = sums[sums.len() - 1] + value;
+        sums.push(sum);
+    }
+
+    (0..input.len())
+        .map(|i| {
+            let start = i.saturating_sub((window - 1) / 2);
+            let end = cmp::min(input.len(), i + window / 2 + 1);
+            (sums[end] - sums[start]) / T::from_usize(end - start).expect("Unable to convert usize to TvScalar.")
+        })
+        .collect()
+}
+
+/// Denoises input values on top of a slowly varying trend, by
+/// removing the trend, denoising the residual with a
+/// [`TvDenoiser`](struct.TvDenoiser.html), and adding the trend back.
+///
+/// Total variation denoising models a piecewise constant signal, and
+/// turns a drift into a staircase. Removing the drift first leaves
+/// only the steps to denoise.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::{DetrendPipeline, Trend};
+///
+/// // A pulse of height `1.0` on top of a linear drift of `0.1` per sample.
+/// let input: Vec<f64> = (0..100).map(|i| i as f64 * 0.1 + if i < 25 || i >= 75 { 0.0 } else { 1.0 }).collect();
+///
+/// let denoiser = tv1d::TvDenoiser::new().lambda(0.5).build().unwrap();
+/// let pipeline = DetrendPipeline::new(Trend::Polynomial(1), denoiser);
+/// let denoised = pipeline.denoise(&input);
+/// assert!((denoised[25] - denoised[24] - 1.1).abs() < 0.1);
+/// assert!((denoised[60] - denoised[40] - 2.0).abs() < 1e-9);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "T: TvFloat + ::serde::Deserialize<'de>")))]
+pub struct DetrendPipeline<T> {
+    trend: Trend,
+    denoiser: TvDenoiser<T>,
+}
+
+impl<T> DetrendPipeline<T>
+    where T: TvFloat
+{
+    /// Creates a pipeline removing `trend` and denoising the residual
+    /// with `denoiser`.
+    pub fn new(trend: Trend, denoiser: TvDenoiser<T>) -> DetrendPipeline<T> {
+        DetrendPipeline { trend, denoiser }
+    }
+
+    /// Returns the trend the pipeline removes.
+    pub fn get_trend(&self) -> Trend {
+        self.trend
+    }
+
+    /// Returns the denoiser the pipeline denoises the residual with.
+    pub fn get_denoiser(&self) -> TvDenoiser<T> {
+        self.denoiser
+    }
+
+    /// Denoises the input values.
+    ///
+    /// # Panics
+    /// Panics if input vector's length is `0`, or the window of
+    /// `Trend::MovingAverage` is `0`.
+    pub fn denoise(&self, input: &[T]) -> Vec<T> {
+        let trend = self.trend.fit(input);
+        let residual: Vec<T> = input.iter().zip(trend.iter()).map(|(&value, &trend)| value - trend).collect();
+        self.denoiser.denoise(&residual).into_iter().zip(trend).map(|(value, trend)| value + trend).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    #[test]
+    fn trend_test_polynomial_exact() {
+        let input: Vec<f64> = (0..50).map(|i| 3.0 - 0.5 * i as f64 + 0.02 * (i * i) as f64).collect();
+        for &degree in &[2, 3, 5] {
+            for (value, expected) in Trend::Polynomial(degree).fit(&input).iter().zip(input.iter()) {
+                assert!((value - expected).abs() < 1e-9);
+            }
+        }
+        let mean = input.iter().sum::<f64>() / 50.0;
+        for value in Trend::Polynomial(0).fit(&input) {
+            assert!((value - mean).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn trend_test_polynomial_short_input() {
+        assert_eq!(Trend::Polynomial(3).fit(&[4.0]), vec![4.0]);
+        let fitted = Trend::Polynomial(3).fit(&[1.0, 5.0]);
+        assert!((fitted[0] - 1.0).abs() < 1e-12 && (fitted[1] - 5.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn trend_test_moving_average() {
+        let input = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        assert_eq!(Trend::MovingAverage(1).fit(&input), input);
+        assert_eq!(Trend::MovingAverage(4).fit(&input), vec![2.0, 2.5, 3.5, 4.5, 5.0, 5.5]);
+        assert_eq!(Trend::MovingAverage(100).fit(&input), vec![3.5; 6]);
+    }
+
+    #[test]
+    fn detrend_pipeline_test_constant_trend() {
+        // Removing a constant does not change the denoised output.
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let denoiser = TvDenoiser::new().lambda(2.0).build().unwrap();
+        let pipeline = DetrendPipeline::new(Trend::Polynomial(0), denoiser);
+        assert_eq!(pipeline.get_trend(), Trend::Polynomial(0));
+        for (value, expected) in pipeline.denoise(&input).iter().zip(condat(&input, 2.0).iter()) {
+            assert!((value - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn detrend_pipeline_test_drift() {
+        let input: Vec<f64> = (0..200)
+            .map(|i| 0.05 * i as f64 + if (i / 50) % 2 == 0 { 0.0 } else { 2.0 } + ((i * 7919) % 13) as f64 * 0.02)
+            .collect();
+        let denoiser = TvDenoiser::new().lambda(1.0).build().unwrap();
+        let detrended = DetrendPipeline::new(Trend::Polynomial(1), denoiser).denoise(&input);
+        let plain = denoiser.denoise(&input);
+        // The drift within a step survives only with the trend removed.
+        assert!(detrended[40] - detrended[10] > 1.0);
+        assert!(plain[40] - plain[10] < detrended[40] - detrended[10]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn trend_test_zero_window() {
+        Trend::MovingAverage(0).fit(&[1.0, 2.0]);
+    }
+}
@@ -0,0 +1,179 @@
+use std::cmp;
+
+use condat;
+use rng::Rng;
+use scalar::TvFloat;
+use utils;
+
+/// Samples drawn from the posterior distribution of the denoised
+/// signal by
+/// [`tv1d::posterior_samples`](../tv1d/fn.posterior_samples.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct PosteriorSamples<T> {
+    /// The samples, each of the same length as the input.
+    pub samples: Vec<Vec<T>>,
+    /// The fraction of proposed moves that were accepted, which should
+    /// be neither close to `0` nor to `1` for the chain to mix well.
+    pub acceptance_rate: f64,
+}
+
+impl<T> PosteriorSamples<T>
+    where T: TvFloat
+{
+    /// Returns the posterior mean at each position.
+    pub fn mean(&self) -> Vec<T> {
+        let count = T::from_usize(self.samples.len())
+            .expect("Unable to convert usize to TvScalar.");
+        (0..self.width())
+            .map(|i| self.samples.iter().fold(T::zero(), |sum, sample| sum + sample[i]) / count)
+            .collect()
+    }
+
+    /// Returns the equal-tailed credible interval at each position,
+    /// containing the fraction `level` of the samples, e.g. `0.95`.
+    ///
+    /// # Panics
+    /// Panics if `level` is not between `0` and `1`.
+    pub fn credible_intervals(&self, level: f64) -> Vec<(T, T)> {
+        assert!((0.0..=1.0).contains(&level),
+                "Credible level must be between 0 and 1.");
+
+        let tail = (1.0 - level) / 2.0;
+        (0..self.width())
+            .map(|i| {
+                let mut values: Vec<T> = self.samples.iter().map(|sample| sample[i]).collect();
+                values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(cmp::Ordering::Equal));
+                (utils::quantile(&values, tail), utils::quantile(&values, 1.0 - tail))
+            })
+            .collect()
+    }
+
+    fn width(&self) -> usize {
+        self.samples.first().map_or(0, |sample| sample.len())
+    }
+}
+
+/// Draws `num_samples` samples from the posterior distribution of the
+/// denoised signal by Markov chain Monte Carlo, for reporting
+/// uncertainty next to the denoised output.
+///
+/// The model is the one whose maximum a posteriori estimate is
+/// [`tv1d::condat`](../tv1d/fn.condat.html): the input is the signal
+/// plus Gaussian noise of variance `noise_variance`, and the
+/// differences between consecutive signal values follow a Laplace
+/// prior of scale `noise_variance / lambda`. The
+/// [`tv1d::bayesian_tv`](../tv1d/fn.bayesian_tv.html) function
+/// estimates both parameters from the data.
+///
+/// The chain starts at the maximum a posteriori estimate and runs
+/// `num_samples` sweeps of burn-in. Each sample is then taken after
+/// one sweep of random-walk Metropolis updates of every position in
+/// turn. The `seed` makes the samples reproducible.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `lambda` is less than `0`,
+/// or `noise_variance` is not greater than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![0.1, -0.2, 0.0, 0.2, -0.1, 4.1, 3.8, 4.0, 4.2, 3.9];
+///
+/// let posterior = tv1d::posterior_samples(&input, 0.5, 0.04, 1000, 7);
+/// assert_eq!(posterior.samples.len(), 1000);
+///
+/// let intervals = posterior.credible_intervals(0.95);
+/// assert!(intervals[0].0 < 0.0 && 0.0 < intervals[0].1);
+/// assert!(intervals[9].0 < 4.0 && 4.0 < intervals[9].1);
+/// ```
+pub fn posterior_samples<T>(input: &[T],
+                            lambda: T,
+                            noise_variance: T,
+                            num_samples: usize,
+                            seed: u64)
+                            -> PosteriorSamples<T>
+    where T: TvFloat
+{
+    assert!(noise_variance > T::zero(),
+            "Noise variance must be greater than 0.");
+
+    let mut rng = Rng::new(seed);
+    let mut current = condat(input, lambda);
+    let step = noise_variance.sqrt();
+
+    let mut samples = Vec::with_capacity(num_samples);
+    let mut accepted = 0;
+    let mut proposed = 0;
+    for sweep in 0..(2 * num_samples) {
+        for i in 0..input.len() {
+            let noise = T::from_f64(rng.normal()).expect("Unable to convert f64 to TvScalar.");
+            let proposal = current[i] + step * noise;
+            let log_ratio = (log_density(input, &current, i, current[i], lambda) -
+                             log_density(input, &current, i, proposal, lambda)) /
+                            noise_variance;
+            let uniform = T::from_f64(rng.next_f64()).expect("Unable to convert f64 to TvScalar.");
+            if uniform.ln() < log_ratio {
+                current[i] = proposal;
+                accepted += 1;
+            }
+            proposed += 1;
+        }
+        if sweep >= num_samples {
+            samples.push(current.clone());
+        }
+    }
+
+    PosteriorSamples {
+        samples,
+        acceptance_rate: accepted as f64 / cmp::max(proposed, 1) as f64,
+    }
+}
+
+// Terms of the negative log posterior, times `noise_variance`, that
+// depend on the value at `i`.
+fn log_density<T: TvFloat>(input: &[T], current: &[T], i: usize, value: T, lambda: T) -> T {
+    let two = T::one() + T::one();
+    let mut energy = (value - input[i]) * (value - input[i]) / two;
+    if i > 0 {
+        energy += lambda * (value - current[i - 1]).abs();
+    }
+    if i + 1 < current.len() {
+        energy += lambda * (value - current[i + 1]).abs();
+    }
+    energy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posterior_samples_test_reproducible() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let first = posterior_samples(&input, 1.0, 1.0, 50, 3);
+        let second = posterior_samples(&input, 1.0, 1.0, 50, 3);
+        assert_eq!(first, second);
+        assert!(first.acceptance_rate > 0.0 && first.acceptance_rate < 1.0);
+    }
+
+    #[test]
+    fn posterior_samples_test_intervals_contain_mean() {
+        let input = vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+        let posterior = posterior_samples(&input, 5.0, 100.0, 500, 11);
+        let mean = posterior.mean();
+        let intervals = posterior.credible_intervals(0.9);
+        for i in 0..input.len() {
+            assert!(intervals[i].0 <= mean[i] && mean[i] <= intervals[i].1);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn posterior_samples_test_zero_noise_variance() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        posterior_samples(&input, 1.0, 0.0, 10, 0);
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The progress of a long solve, reported to the callback of the
+/// `_with_progress` variants of the solvers, e.g.
+/// [`tv1d::condat_batch_with_progress`](../tv1d/fn.condat_batch_with_progress.html).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Progress {
+    /// The number of input samples processed so far.
+    pub samples: usize,
+    /// The fraction of the work done so far, between `0` and `1`.
+    pub fraction: f64,
+}
+
+// Counts the samples processed by the threads of a solver, and
+// reports the total to `callback` after each step.
+pub(crate) struct Tracker<'a, F: 'a> {
+    samples: AtomicUsize,
+    total: usize,
+    callback: &'a F,
+}
+
+impl<'a, F: Fn(Progress) + Sync> Tracker<'a, F> {
+    pub(crate) fn new(total: usize, callback: &'a F) -> Tracker<'a, F> {
+        Tracker {
+            samples: AtomicUsize::new(0),
+            total,
+            callback,
+        }
+    }
+
+    pub(crate) fn advance(&self, samples: usize) {
+        let samples = self.samples.fetch_add(samples, Ordering::Relaxed) + samples;
+        (self.callback)(Progress {
+            samples,
+            fraction: samples as f64 / self.total as f64,
+        });
+    }
+}
@@ -0,0 +1,114 @@
+use condat;
+use scalar::TvFloat;
+
+/// Denoises the input values like
+/// [`tv1d::condat`](../tv1d/fn.condat.html), additionally anchoring
+/// the output to a reference signal, such as the profile of the
+/// previous day.
+///
+/// The output minimizes
+/// `0.5 * ||x - input||^2 + mu * ||x - reference||^2 + lambda * TV(x)`.
+/// Completing the square, the two fidelity terms are
+/// `(0.5 + mu) * ||x - z||^2` up to a constant, where
+/// `z = (input + 2 * mu * reference) / (1 + 2 * mu)` is a weighted
+/// mean of the input and the reference, so the output is exactly that
+/// of `condat` on `z` with `lambda / (1 + 2 * mu)`. A `mu` of `0`
+/// ignores the reference, and an infinite `mu` returns it.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `input` and `reference`
+/// have different lengths, `mu` is NaN or less than `0`, or `lambda`
+/// is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let yesterday = vec![1.0, 1.0, 1.0, 5.0, 5.0, 5.0];
+/// let today = vec![3.0, 3.0, 3.0, 3.0, 3.0, 3.0];
+///
+/// // Halfway between today and yesterday.
+/// let anchored = tv1d::condat_reference(&today, &yesterday, 0.5, 0.0);
+/// assert_eq!(anchored, vec![2.0, 2.0, 2.0, 4.0, 4.0, 4.0]);
+///
+/// assert_eq!(tv1d::condat_reference(&today, &yesterday, 0.0, 1.0), tv1d::condat(&today, 1.0));
+/// ```
+pub fn condat_reference<T>(input: &[T], reference: &[T], mu: T, lambda: T) -> Vec<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+    assert!(input.len() == reference.len(),
+            "Input and reference must have the same length.");
+    assert!(!mu.is_nan(),
+            "Mu must not be NaN.");
+    assert!(mu >= T::zero(),
+            "Mu must be greater than or equal to 0.");
+    assert!(!lambda.is_nan(),
+            "Lambda must not be NaN.");
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    if !mu.is_finite() {
+        return reference.to_vec();
+    }
+
+    let weight = mu + mu;
+    let scale = T::one() + weight;
+    let anchored: Vec<T> = input.iter()
+        .zip(reference.iter())
+        .map(|(&value, &anchor)| (value + weight * anchor) / scale)
+        .collect();
+    condat(&anchored, lambda / scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The objective the output minimizes.
+    fn objective(output: &[f64], input: &[f64], reference: &[f64], mu: f64, lambda: f64) -> f64 {
+        let mut value = 0.0;
+        for i in 0..output.len() {
+            value += 0.5 * (output[i] - input[i]).powi(2) + mu * (output[i] - reference[i]).powi(2);
+        }
+        value + lambda * output.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum::<f64>()
+    }
+
+    #[test]
+    fn condat_reference_test_minimizes_objective() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let reference = vec![1.5, 1.5, 6.0, 6.0, 6.0, 2.0, 8.0, 8.0];
+        for &(mu, lambda) in &[(0.1, 0.5), (1.0, 2.0), (5.0, 1.0)] {
+            let output = condat_reference(&input, &reference, mu, lambda);
+            let minimum = objective(&output, &input, &reference, mu, lambda);
+            for i in 0..output.len() {
+                for &delta in &[-1e-3, 1e-3] {
+                    let mut perturbed = output.clone();
+                    perturbed[i] += delta;
+                    assert!(objective(&perturbed, &input, &reference, mu, lambda) >= minimum - 1e-12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn condat_reference_test_infinite_mu() {
+        let input = vec![1.0, 2.0, 3.0];
+        let reference = vec![4.0, 0.0, 4.0];
+        assert_eq!(condat_reference(&input, &reference, f64::INFINITY, 1.0), reference);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_reference_test_negative_mu() {
+        condat_reference(&[1.0, 2.0], &[1.0, 2.0], -1.0, 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_reference_test_length_mismatch() {
+        condat_reference(&[1.0, 2.0], &[1.0], 1.0, 1.0);
+    }
+}
@@ -0,0 +1,216 @@
+use scalar::TvFloat;
+
+/// How values are read off between the samples they are known at, for
+/// [`UniformGrid`](struct.UniformGrid.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Interpolation {
+    /// The value of the nearest sample, or of the earlier one halfway
+    /// between two samples.
+    Nearest,
+    /// The linear interpolation between the two neighboring samples.
+    Linear,
+}
+
+/// Evenly spaced timestamps, `start`, `start + step`, and so on, to
+/// put unevenly spaced samples on before denoising them with a solver
+/// for evenly spaced input, and to map the denoised values back.
+///
+/// Before the first and after the last known sample, both
+/// [`resample`](struct.UniformGrid.html#method.resample) and
+/// [`sample`](struct.UniformGrid.html#method.sample) repeat the value
+/// at that end, so that the ends of the input are neither extrapolated
+/// onto the grid nor the ends of the grid back onto the input.
+/// [`TvDenoiser::denoise_resampled`](struct.TvDenoiser.html#method.denoise_resampled)
+/// does both around denoising.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::{Interpolation, UniformGrid};
+///
+/// let input = vec![(0.0, 1.0), (0.5, 2.0), (2.0, 5.0)];
+/// let grid = UniformGrid::covering(&[0.0, 0.5, 2.0], 1.0);
+/// assert_eq!(grid.timestamps(), vec![0.0, 1.0, 2.0]);
+///
+/// let resampled = grid.resample(&input, Interpolation::Linear);
+/// assert_eq!(resampled, vec![1.0, 3.0, 5.0]);
+/// assert_eq!(grid.sample(&resampled, &[0.5, 3.0], Interpolation::Linear), vec![2.0, 5.0]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct UniformGrid<T> {
+    /// The first timestamp of the grid.
+    pub start: T,
+    /// The time between neighboring timestamps of the grid.
+    pub step: T,
+    /// The number of timestamps of the grid.
+    pub len: usize,
+}
+
+impl<T> UniformGrid<T>
+    where T: TvFloat
+{
+    /// Returns the grid of spacing `step` starting at the smallest of
+    /// `timestamps`, with as few timestamps as reach the largest.
+    ///
+    /// # Panics
+    /// Panics if `timestamps` is empty or has a NaN or infinite value,
+    /// or `step` is not finite and greater than `0`.
+    pub fn covering(timestamps: &[T], step: T) -> UniformGrid<T> {
+        assert!(!timestamps.is_empty(),
+                "Timestamps should have at least one value.");
+
+        assert!(timestamps.iter().all(|timestamp| timestamp.is_finite()),
+                "Timestamps must be finite.");
+
+        assert!(step.is_finite() && step > T::zero(),
+                "Step must be finite and greater than 0.");
+
+        let first = timestamps.iter().fold(timestamps[0], |first, &timestamp| first.min(timestamp));
+        let last = timestamps.iter().fold(timestamps[0], |last, &timestamp| last.max(timestamp));
+        let steps = -((first - last) / step).floor();
+        UniformGrid {
+            start: first,
+            step,
+            len: steps.to_f64().expect("Unable to convert TvScalar to f64.") as usize + 1,
+        }
+    }
+
+    /// Returns the timestamps of the grid.
+    pub fn timestamps(&self) -> Vec<T> {
+        (0..self.len).map(|i| self.timestamp(i)).collect()
+    }
+
+    fn timestamp(&self, i: usize) -> T {
+        self.start + self.step * T::from_usize(i).expect("Unable to convert usize to TvScalar.")
+    }
+
+    /// Returns the values of the `(timestamp, value)` pairs at the
+    /// timestamps of the grid, interpolated according to
+    /// `interpolation`.
+    ///
+    /// # Panics
+    /// Panics if input vector's length is `0`, or its timestamps are
+    /// not strictly increasing.
+    pub fn resample(&self, input: &[(T, T)], interpolation: Interpolation) -> Vec<T> {
+        assert!(!input.is_empty(),
+                "Input list should have at least one value.");
+
+        assert!(input.windows(2).all(|window| window[0].0 < window[1].0),
+                "Timestamps must be strictly increasing.");
+
+        let mut next = 0;
+        (0..self.len)
+            .map(|i| {
+                let timestamp = self.timestamp(i);
+                while next < input.len() && input[next].0 < timestamp {
+                    next += 1;
+                }
+                if next == input.len() {
+                    input[next - 1].1
+                } else if next == 0 || input[next].0 == timestamp {
+                    input[next].1
+                } else {
+                    let (before, after) = (input[next - 1], input[next]);
+                    let weight = (timestamp - before.0) / (after.0 - before.0);
+                    interpolate(before.1, after.1, weight, interpolation)
+                }
+            })
+            .collect()
+    }
+
+    /// Returns `values`, one per timestamp of the grid, at
+    /// `timestamps`, interpolated according to `interpolation`.
+    ///
+    /// # Panics
+    /// Panics if `values` does not have one value per timestamp of the
+    /// grid, the grid is empty, or a timestamp is NaN.
+    pub fn sample(&self, values: &[T], timestamps: &[T], interpolation: Interpolation) -> Vec<T> {
+        assert!(values.len() == self.len,
+                "Values must have one value per timestamp of the grid.");
+
+        assert!(self.len > 0, "Grid must have at least one timestamp.");
+
+        let last = T::from_usize(self.len - 1).expect("Unable to convert usize to TvScalar.");
+        timestamps.iter()
+            .map(|&timestamp| {
+                assert!(!timestamp.is_nan(), "Timestamps must not be NaN.");
+
+                let position = ((timestamp - self.start) / self.step).max(T::zero()).min(last);
+                let below = position.floor();
+                let i = below.to_f64().expect("Unable to convert TvScalar to f64.") as usize;
+                if i + 1 == self.len {
+                    values[i]
+                } else {
+                    interpolate(values[i], values[i + 1], position - below, interpolation)
+                }
+            })
+            .collect()
+    }
+}
+
+// Interpolates between `before` and `after`, at the fraction `weight`
+// of the way from one to the other.
+fn interpolate<T>(before: T, after: T, weight: T, interpolation: Interpolation) -> T
+    where T: TvFloat
+{
+    match interpolation {
+        Interpolation::Nearest => {
+            if weight + weight > T::one() {
+                after
+            } else {
+                before
+            }
+        }
+        Interpolation::Linear => before + (after - before) * weight,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_grid_test_covering() {
+        let grid = UniformGrid::covering(&[3.0, 1.0, 2.5], 0.5);
+        assert_eq!(grid, UniformGrid { start: 1.0, step: 0.5, len: 5 });
+
+        // The grid reaches past the last timestamp.
+        let grid = UniformGrid::covering(&[0.0, 2.5], 1.0);
+        assert_eq!(grid.timestamps(), vec![0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(UniformGrid::covering(&[4.0], 1.0).timestamps(), vec![4.0]);
+    }
+
+    #[test]
+    fn uniform_grid_test_nearest() {
+        let input = vec![(0.0, 1.0), (0.4, 2.0), (2.5, 7.0)];
+        let grid = UniformGrid::covering(&[0.0, 2.5], 1.0);
+        assert_eq!(grid.resample(&input, Interpolation::Nearest), vec![1.0, 2.0, 7.0, 7.0]);
+
+        let values = vec![1.0, 2.0, 3.0, 4.0];
+        assert_eq!(grid.sample(&values, &[-1.0, 0.5, 0.6, 2.9, 9.0], Interpolation::Nearest),
+                   vec![1.0, 1.0, 2.0, 4.0, 4.0]);
+    }
+
+    #[test]
+    fn uniform_grid_test_round_trip() {
+        // Samples on the grid, with some missing, come back unchanged.
+        let input: Vec<(f64, f64)> = [0, 1, 2, 5, 6, 9].iter().map(|&i| (i as f64 * 0.25, (i * i) as f64)).collect();
+        let timestamps: Vec<f64> = input.iter().map(|&(timestamp, _)| timestamp).collect();
+        let grid = UniformGrid::covering(&timestamps, 0.25);
+        assert_eq!(grid.len, 10);
+        for &interpolation in &[Interpolation::Nearest, Interpolation::Linear] {
+            let resampled = grid.resample(&input, interpolation);
+            let values: Vec<f64> = input.iter().map(|&(_, value)| value).collect();
+            assert_eq!(grid.sample(&resampled, &timestamps, interpolation), values);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn uniform_grid_test_unordered_input() {
+        UniformGrid::covering(&[0.0, 2.0], 1.0).resample(&[(1.0, 1.0), (0.0, 2.0)], Interpolation::Linear);
+    }
+}
@@ -0,0 +1,163 @@
+use std::cell::Cell;
+
+use scalar::{TvInteger, TvScalar};
+use segments::{self, Arithmetic};
+
+/// The result of
+/// [`tv1d::condat_saturating`](../tv1d/fn.condat_saturating.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SaturatingOutput<T> {
+    /// The denoised output.
+    pub output: Vec<T>,
+    /// Whether any intermediate value saturated at the bounds of `T`,
+    /// in which case the output is only approximate.
+    pub saturated: bool,
+}
+
+/// Denoises the input values like
+/// [`tv1d::condat`](../tv1d/fn.condat.html) for integer types, with
+/// saturating arithmetic.
+///
+/// With integer types, the intermediate values of the algorithm can
+/// exceed the bounds of `T` on extreme inputs, which panics in debug
+/// builds and wraps around in release builds. This variant instead
+/// clamps every intermediate value to the bounds of `T`, and reports
+/// whether that happened alongside the output.
+///
+/// # Panics
+/// Panics if input vector's length is `0` or `lambda` is less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input: Vec<i32> = vec![1, 2, 3, 4, 5];
+/// let denoised = tv1d::condat_saturating(&input, 10);
+/// assert_eq!(denoised.output, tv1d::condat(&input, 10));
+/// assert!(!denoised.saturated);
+///
+/// let extreme: Vec<i8> = vec![-128, 127, -128, 127];
+/// let denoised = tv1d::condat_saturating(&extreme, 100);
+/// assert!(denoised.saturated);
+/// ```
+pub fn condat_saturating<T>(input: &[T], lambda: T) -> SaturatingOutput<T>
+    where T: TvInteger + TvScalar
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    // Like `tv1d::condat`, a `lambda` of at least `lambda_max` returns
+    // the mean without running the algorithm.
+    if let Some(mean) = constant_solution(input, lambda) {
+        return SaturatingOutput {
+            output: vec![mean; input.len()],
+            saturated: false,
+        };
+    }
+
+    // The loop of `tv1d::condat`, with every addition and subtraction
+    // saturating.
+    let arithmetic = Saturating { saturated: Cell::new(false) };
+    let mut output = input.to_vec();
+    segments::solve(&mut output, lambda, &arithmetic, &mut ());
+    SaturatingOutput {
+        output,
+        saturated: arithmetic.saturated.get(),
+    }
+}
+
+// Returns the mean of the input if it is the output for `lambda`, like
+// `utils::constant_solution`, or `None` if it is not or the running sum
+// of the residuals saturates.
+fn constant_solution<T>(input: &[T], lambda: T) -> Option<T>
+    where T: TvInteger + TvScalar
+{
+    let arithmetic = Saturating { saturated: Cell::new(false) };
+    let mean = T::mean(input);
+    let mut running_sum = T::zero();
+    for &value in &input[..input.len() - 1] {
+        running_sum = arithmetic.add(running_sum, arithmetic.sub(value, mean));
+        if arithmetic.saturated.get() || running_sum > lambda || running_sum < -lambda {
+            return None;
+        }
+    }
+    Some(mean)
+}
+
+// Saturating arithmetic that remembers whether it saturated.
+struct Saturating {
+    saturated: Cell<bool>,
+}
+
+impl Saturating {
+    fn saturate<T: TvInteger>(&self, positive: bool) -> T {
+        self.saturated.set(true);
+        if positive { T::max_value() } else { T::min_value() }
+    }
+}
+
+impl<T> Arithmetic<T> for Saturating
+    where T: TvInteger + TvScalar
+{
+    fn add(&self, a: T, b: T) -> T {
+        match a.checked_add(b) {
+            Some(sum) => sum,
+            None => self.saturate(b > T::zero()),
+        }
+    }
+
+    fn sub(&self, a: T, b: T) -> T {
+        match a.checked_sub(b) {
+            Some(difference) => difference,
+            None => self.saturate(b < T::zero()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    #[test]
+    fn condat_saturating_test_matches_condat() {
+        let input: Vec<i64> = vec![111, 422, 145, 248, 871, 675, 436, 310];
+        for &lambda in &[0, 5, 50, 700] {
+            let denoised = condat_saturating(&input, lambda);
+            assert_eq!(denoised.output, condat(&input, lambda));
+            assert!(!denoised.saturated);
+        }
+    }
+
+    #[test]
+    fn condat_saturating_test_extreme_values() {
+        let input: Vec<i16> = vec![i16::MAX, i16::MIN, i16::MAX, i16::MIN, i16::MAX];
+        let denoised = condat_saturating(&input, i16::MAX);
+        assert_eq!(denoised.output.len(), input.len());
+        assert!(denoised.saturated);
+    }
+
+    #[test]
+    fn condat_saturating_test_long_narrow_segment() {
+        // A flat segment longer than `i8::MAX`, whose length does not
+        // fit in `i8`.
+        let input: Vec<i8> = (0..200).map(|i| (i % 2) as i8).collect();
+        let wide: Vec<i32> = input.iter().map(|&value| value as i32).collect();
+        let denoised = condat_saturating(&input, 5);
+        assert_eq!(denoised.output.iter().map(|&value| value as i32).collect::<Vec<i32>>(),
+                   condat(&wide, 5));
+        assert!(!denoised.saturated);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_saturating_test_negative_lambda() {
+        let input: Vec<i32> = vec![1, 2, 3];
+        condat_saturating(&input, -1);
+    }
+}
@@ -0,0 +1,638 @@
+use std::cmp;
+#[cfg(feature = "std")]
+use std::mem;
+use std::ops;
+
+#[cfg(feature = "ordered-float")]
+use ordered_float::{NotNan, OrderedFloat};
+
+// Keeps the scalar traits from being implemented outside of the crate,
+// so that they can gain methods and supertraits without breaking
+// changes.
+pub(crate) mod private {
+    pub trait Sealed {}
+}
+
+/// A scalar type that the solvers can denoise: `f32`, `f64`, the
+/// signed integers, the widened fixed point values of
+/// [`Fixed`](struct.Fixed.html), and with the `ordered-float`
+/// feature, the `OrderedFloat` and `NotNan` wrappers of `f32` and
+/// `f64`.
+///
+/// The arithmetic of the type is the arithmetic of the solvers, so
+/// the divisions of integer types truncate the value of each segment.
+/// The trait is sealed, and is the only bound of the solvers on their
+/// values.
+pub trait TvScalar: private::Sealed + Copy + cmp::PartialOrd + Send + Sync + 'static
+    + ops::Add<Output = Self> + ops::Sub<Output = Self> + ops::Mul<Output = Self> + ops::Div<Output = Self>
+    + ops::Neg<Output = Self> + ops::AddAssign + ops::SubAssign
+{
+    /// Returns `0`.
+    fn zero() -> Self;
+
+    /// Returns `1`.
+    fn one() -> Self;
+
+    /// Returns `value` as `Self`, or `None` if it is out of range.
+    fn from_usize(value: usize) -> Option<Self>;
+
+    /// Returns `value` as `Self`, rounded as by an `as` cast, or `None`
+    /// if it is out of range.
+    fn from_f64(value: f64) -> Option<Self>;
+
+    /// Returns the value as the nearest `f64`, or `None` if it has
+    /// none.
+    fn to_f64(self) -> Option<f64>;
+
+    /// Returns the sum, or `None` if it overflows the range of an
+    /// integer type. The sums of the floating point types round to
+    /// infinity instead, and are always returned.
+    fn try_add(self, other: Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    /// Returns the difference, or `None` if it overflows the range of
+    /// an integer type, like [`try_add`](#method.try_add).
+    fn try_sub(self, other: Self) -> Option<Self> {
+        Some(self - other)
+    }
+
+    /// Denoises the values with [`tv1d::condat`](../tv1d/fn.condat.html)
+    /// in a wider type and returns `true`, if the bounds and the running
+    /// sums of `condat` could overflow `Self` but not the wider type.
+    /// Returns `false`, leaving the values untouched, otherwise.
+    ///
+    /// Only the integers narrower than `i128` widen, to `i128`, and only
+    /// with the `std` feature, which allocates the wider values.
+    fn condat_widened(_values: &mut [Self], _lambda: Self) -> bool {
+        false
+    }
+
+    /// Returns the value divided by `count`, such as a sum of `count`
+    /// values divided into their mean. The integers divide in `i128`,
+    /// so that counts beyond the range of narrow types such as `i8`
+    /// do not overflow.
+    fn div_count(self, count: usize) -> Self {
+        self / Self::from_usize(count).expect("Unable to convert usize to TvScalar.")
+    }
+
+    /// Returns the arithmetic mean of the values. The integers sum in
+    /// `i128`, so that the sums of narrow types such as `i8` do not
+    /// overflow.
+    fn mean(values: &[Self]) -> Self {
+        values.iter().fold(Self::zero(), |sum, &value| sum + value).div_count(values.len())
+    }
+}
+
+/// A floating point [`TvScalar`](trait.TvScalar.html), such as `f32`,
+/// `f64` or `OrderedFloat<f64>`, for the solvers that need infinities, NaN and the
+/// functions of floating point numbers.
+///
+/// Without the `std` feature, only the functions available without
+/// the standard library are part of the trait. The trait is sealed.
+pub trait TvFloat: TvScalar {
+    /// Returns positive infinity.
+    fn infinity() -> Self;
+
+    /// Returns negative infinity.
+    fn neg_infinity() -> Self;
+
+    /// Returns NaN.
+    fn nan() -> Self;
+
+    /// Returns the difference between `1` and the next larger value.
+    fn epsilon() -> Self;
+
+    /// Returns the largest finite value.
+    fn max_value() -> Self;
+
+    /// Returns the smallest finite value.
+    fn min_value() -> Self;
+
+    /// Returns whether the value is NaN.
+    fn is_nan(self) -> bool;
+
+    /// Returns whether the value is neither infinite nor NaN.
+    fn is_finite(self) -> bool;
+
+    /// Returns whether the value is positive or negative infinity.
+    fn is_infinite(self) -> bool;
+
+    /// Returns the absolute value.
+    fn abs(self) -> Self;
+
+    /// Returns the larger of the value and `other`, ignoring NaN.
+    fn max(self, other: Self) -> Self;
+
+    /// Returns the smaller of the value and `other`, ignoring NaN.
+    fn min(self, other: Self) -> Self;
+
+    /// Returns the square root.
+    #[cfg(feature = "std")]
+    fn sqrt(self) -> Self;
+
+    /// Returns the largest integer less than or equal to the value.
+    #[cfg(feature = "std")]
+    fn floor(self) -> Self;
+
+    /// Returns the nearest integer, rounding halfway cases away from
+    /// `0`.
+    #[cfg(feature = "std")]
+    fn round(self) -> Self;
+
+    /// Returns the natural logarithm.
+    #[cfg(feature = "std")]
+    fn ln(self) -> Self;
+
+    /// Returns `e` to the power of the value.
+    #[cfg(feature = "std")]
+    fn exp(self) -> Self;
+
+    /// Returns the value to the integer power `n`.
+    #[cfg(feature = "std")]
+    fn powi(self, n: i32) -> Self;
+}
+
+/// A primitive integer type, signed or unsigned, for the solvers that
+/// work on integers with the range of the type. The trait is sealed.
+pub trait TvInteger: private::Sealed + Copy + cmp::Ord {
+    /// Returns the smallest value.
+    fn min_value() -> Self;
+
+    /// Returns the largest value.
+    fn max_value() -> Self;
+
+    /// Returns the sum, or `None` on overflow.
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    /// Returns the difference, or `None` on overflow.
+    fn checked_sub(self, other: Self) -> Option<Self>;
+
+    /// Returns the product, or `None` on overflow.
+    fn checked_mul(self, other: Self) -> Option<Self>;
+
+    /// Returns `value` truncated towards `0`, or `None` if it is out of
+    /// range.
+    fn from_f64(value: f64) -> Option<Self>;
+
+    /// Returns the value as the nearest `f64`.
+    fn to_f64(self) -> Option<f64>;
+}
+
+macro_rules! float_scalar {
+    ($float:ident) => {
+        impl private::Sealed for $float {}
+
+        impl TvScalar for $float {
+            fn zero() -> $float {
+                0.0
+            }
+
+            fn one() -> $float {
+                1.0
+            }
+
+            fn from_usize(value: usize) -> Option<$float> {
+                Some(value as $float)
+            }
+
+            fn from_f64(value: f64) -> Option<$float> {
+                Some(value as $float)
+            }
+
+            fn to_f64(self) -> Option<f64> {
+                Some(self as f64)
+            }
+        }
+
+        impl TvFloat for $float {
+            fn infinity() -> $float {
+                $float::INFINITY
+            }
+
+            fn neg_infinity() -> $float {
+                $float::NEG_INFINITY
+            }
+
+            fn nan() -> $float {
+                $float::NAN
+            }
+
+            fn epsilon() -> $float {
+                $float::EPSILON
+            }
+
+            fn max_value() -> $float {
+                $float::MAX
+            }
+
+            fn min_value() -> $float {
+                $float::MIN
+            }
+
+            fn is_nan(self) -> bool {
+                $float::is_nan(self)
+            }
+
+            fn is_finite(self) -> bool {
+                $float::is_finite(self)
+            }
+
+            fn is_infinite(self) -> bool {
+                $float::is_infinite(self)
+            }
+
+            fn abs(self) -> $float {
+                $float::abs(self)
+            }
+
+            fn max(self, other: $float) -> $float {
+                $float::max(self, other)
+            }
+
+            fn min(self, other: $float) -> $float {
+                $float::min(self, other)
+            }
+
+            #[cfg(feature = "std")]
+            fn sqrt(self) -> $float {
+                $float::sqrt(self)
+            }
+
+            #[cfg(feature = "std")]
+            fn floor(self) -> $float {
+                $float::floor(self)
+            }
+
+            #[cfg(feature = "std")]
+            fn round(self) -> $float {
+                $float::round(self)
+            }
+
+            #[cfg(feature = "std")]
+            fn ln(self) -> $float {
+                $float::ln(self)
+            }
+
+            #[cfg(feature = "std")]
+            fn exp(self) -> $float {
+                $float::exp(self)
+            }
+
+            #[cfg(feature = "std")]
+            fn powi(self, n: i32) -> $float {
+                $float::powi(self, n)
+            }
+        }
+    };
+}
+
+float_scalar!(f32);
+float_scalar!(f64);
+
+// The wrappers of the `ordered-float` crate compute like the floats
+// they wrap. `NotNan` panics on arithmetic that results in NaN, and so
+// is not a `TvFloat`, which has NaN.
+#[cfg(feature = "ordered-float")]
+macro_rules! ordered_float_scalar {
+    ($float:ident) => {
+        impl private::Sealed for OrderedFloat<$float> {}
+
+        impl TvScalar for OrderedFloat<$float> {
+            fn zero() -> OrderedFloat<$float> {
+                OrderedFloat(0.0)
+            }
+
+            fn one() -> OrderedFloat<$float> {
+                OrderedFloat(1.0)
+            }
+
+            fn from_usize(value: usize) -> Option<OrderedFloat<$float>> {
+                Some(OrderedFloat(value as $float))
+            }
+
+            fn from_f64(value: f64) -> Option<OrderedFloat<$float>> {
+                Some(OrderedFloat(value as $float))
+            }
+
+            fn to_f64(self) -> Option<f64> {
+                Some(self.0 as f64)
+            }
+        }
+
+        impl TvFloat for OrderedFloat<$float> {
+            fn infinity() -> OrderedFloat<$float> {
+                OrderedFloat($float::INFINITY)
+            }
+
+            fn neg_infinity() -> OrderedFloat<$float> {
+                OrderedFloat($float::NEG_INFINITY)
+            }
+
+            fn nan() -> OrderedFloat<$float> {
+                OrderedFloat($float::NAN)
+            }
+
+            fn epsilon() -> OrderedFloat<$float> {
+                OrderedFloat($float::EPSILON)
+            }
+
+            fn max_value() -> OrderedFloat<$float> {
+                OrderedFloat($float::MAX)
+            }
+
+            fn min_value() -> OrderedFloat<$float> {
+                OrderedFloat($float::MIN)
+            }
+
+            fn is_nan(self) -> bool {
+                self.0.is_nan()
+            }
+
+            fn is_finite(self) -> bool {
+                self.0.is_finite()
+            }
+
+            fn is_infinite(self) -> bool {
+                self.0.is_infinite()
+            }
+
+            fn abs(self) -> OrderedFloat<$float> {
+                OrderedFloat(self.0.abs())
+            }
+
+            fn max(self, other: OrderedFloat<$float>) -> OrderedFloat<$float> {
+                OrderedFloat(self.0.max(other.0))
+            }
+
+            fn min(self, other: OrderedFloat<$float>) -> OrderedFloat<$float> {
+                OrderedFloat(self.0.min(other.0))
+            }
+
+            #[cfg(feature = "std")]
+            fn sqrt(self) -> OrderedFloat<$float> {
+                OrderedFloat(self.0.sqrt())
+            }
+
+            #[cfg(feature = "std")]
+            fn floor(self) -> OrderedFloat<$float> {
+                OrderedFloat(self.0.floor())
+            }
+
+            #[cfg(feature = "std")]
+            fn round(self) -> OrderedFloat<$float> {
+                OrderedFloat(self.0.round())
+            }
+
+            #[cfg(feature = "std")]
+            fn ln(self) -> OrderedFloat<$float> {
+                OrderedFloat(self.0.ln())
+            }
+
+            #[cfg(feature = "std")]
+            fn exp(self) -> OrderedFloat<$float> {
+                OrderedFloat(self.0.exp())
+            }
+
+            #[cfg(feature = "std")]
+            fn powi(self, n: i32) -> OrderedFloat<$float> {
+                OrderedFloat(self.0.powi(n))
+            }
+        }
+
+        impl private::Sealed for NotNan<$float> {}
+
+        impl TvScalar for NotNan<$float> {
+            fn zero() -> NotNan<$float> {
+                NotNan::from(0u8)
+            }
+
+            fn one() -> NotNan<$float> {
+                NotNan::from(1u8)
+            }
+
+            fn from_usize(value: usize) -> Option<NotNan<$float>> {
+                NotNan::new(value as $float).ok()
+            }
+
+            fn from_f64(value: f64) -> Option<NotNan<$float>> {
+                NotNan::new(value as $float).ok()
+            }
+
+            fn to_f64(self) -> Option<f64> {
+                Some(self.into_inner() as f64)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "ordered-float")]
+ordered_float_scalar!(f32);
+#[cfg(feature = "ordered-float")]
+ordered_float_scalar!(f64);
+
+macro_rules! integer {
+    ($integer:ident, $lower:expr) => {
+        impl private::Sealed for $integer {}
+
+        impl TvInteger for $integer {
+            fn min_value() -> $integer {
+                $integer::MIN
+            }
+
+            fn max_value() -> $integer {
+                $integer::MAX
+            }
+
+            fn checked_add(self, other: $integer) -> Option<$integer> {
+                $integer::checked_add(self, other)
+            }
+
+            fn checked_sub(self, other: $integer) -> Option<$integer> {
+                $integer::checked_sub(self, other)
+            }
+
+            fn checked_mul(self, other: $integer) -> Option<$integer> {
+                $integer::checked_mul(self, other)
+            }
+
+            fn from_f64(value: f64) -> Option<$integer> {
+                // The bounds are `0` or powers of two, which `f64`
+                // represents exactly. Values between `lower - 1` and
+                // `lower` truncate to `lower`, but `lower - 1` may round
+                // to `lower` itself.
+                let lower: f64 = $lower;
+                let upper = ($integer::MAX / 2 + 1) as f64 * 2.0;
+                if (value >= lower || value > lower - 1.0) && value < upper {
+                    Some(value as $integer)
+                } else {
+                    None
+                }
+            }
+
+            fn to_f64(self) -> Option<f64> {
+                Some(self as f64)
+            }
+        }
+    };
+}
+
+// Only signed integers are scalars, since the solvers negate values.
+macro_rules! integer_scalar {
+    ($integer:ident) => {
+        integer!($integer, $integer::MIN as f64);
+
+        impl TvScalar for $integer {
+            fn zero() -> $integer {
+                0
+            }
+
+            fn one() -> $integer {
+                1
+            }
+
+            fn from_usize(value: usize) -> Option<$integer> {
+                <$integer as ::std::convert::TryFrom<usize>>::try_from(value).ok()
+            }
+
+            fn from_f64(value: f64) -> Option<$integer> {
+                <$integer as TvInteger>::from_f64(value)
+            }
+
+            fn to_f64(self) -> Option<f64> {
+                Some(self as f64)
+            }
+
+            fn try_add(self, other: $integer) -> Option<$integer> {
+                $integer::checked_add(self, other)
+            }
+
+            fn try_sub(self, other: $integer) -> Option<$integer> {
+                $integer::checked_sub(self, other)
+            }
+
+            // The bounds of `condat` lie within `2 * lambda` of the
+            // values, and its running sums within `4 * lambda` of their
+            // range.
+            #[cfg(feature = "std")]
+            fn condat_widened(values: &mut [$integer], lambda: $integer) -> bool {
+                if mem::size_of::<$integer>() >= mem::size_of::<i128>() {
+                    return false;
+                }
+                let min = values.iter().cloned().min().expect("Input list should have at least one value.");
+                let max = values.iter().cloned().max().expect("Input list should have at least one value.");
+                let fits = lambda.checked_add(lambda).and_then(|two_lambda| {
+                    min.checked_sub(two_lambda)?;
+                    max.checked_add(two_lambda)?;
+                    max.checked_sub(min)?.checked_add(two_lambda.checked_add(two_lambda)?)
+                });
+                if fits.is_some() {
+                    return false;
+                }
+
+                let mut wide: Vec<i128> = values.iter().map(|&value| value as i128).collect();
+                ::find_segments(&mut wide, lambda as i128, &mut ());
+                // The output lies between the smallest and the largest
+                // value, up to the truncation of the divisions.
+                for (value, wide) in values.iter_mut().zip(wide) {
+                    *value = wide.clamp(min as i128, max as i128) as $integer;
+                }
+                true
+            }
+
+            // Every count fits in `i128`, and the quotient is no larger
+            // than the value in magnitude, so it fits back in `Self`.
+            fn div_count(self, count: usize) -> $integer {
+                (self as i128 / count as i128) as $integer
+            }
+
+            // The mean is no larger in magnitude than the largest value,
+            // so it fits back in `Self`.
+            fn mean(values: &[$integer]) -> $integer {
+                let sum = values.iter().fold(0i128, |sum, &value| sum + value as i128);
+                (sum / values.len() as i128) as $integer
+            }
+        }
+    };
+}
+
+integer_scalar!(i8);
+integer_scalar!(i16);
+integer_scalar!(i32);
+integer_scalar!(i64);
+integer_scalar!(i128);
+integer_scalar!(isize);
+integer!(u8, 0.0);
+integer!(u16, 0.0);
+integer!(u32, 0.0);
+integer!(u64, 0.0);
+integer!(u128, 0.0);
+integer!(usize, 0.0);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_test_from_f64_bounds() {
+        assert_eq!(<i8 as TvInteger>::from_f64(-128.9), Some(-128));
+        assert_eq!(<i8 as TvInteger>::from_f64(127.9), Some(127));
+        assert_eq!(<i8 as TvInteger>::from_f64(128.0), None);
+        assert_eq!(<i8 as TvInteger>::from_f64(-129.0), None);
+        assert_eq!(<u8 as TvInteger>::from_f64(-0.5), Some(0));
+        assert_eq!(<u8 as TvInteger>::from_f64(-1.0), None);
+        assert_eq!(<i64 as TvInteger>::from_f64(i64::MIN as f64), Some(i64::MIN));
+        assert_eq!(<i64 as TvInteger>::from_f64(i64::MAX as f64), None);
+        assert_eq!(<u64 as TvInteger>::from_f64(u64::MAX as f64), None);
+        assert_eq!(<i32 as TvInteger>::from_f64(f64::NAN), None);
+    }
+
+    #[test]
+    fn scalar_test_from_usize() {
+        assert_eq!(<i8 as TvScalar>::from_usize(127), Some(127));
+        assert_eq!(<i8 as TvScalar>::from_usize(128), None);
+        assert_eq!(<f32 as TvScalar>::from_usize(1 << 30), Some((1 << 30) as f32));
+    }
+
+    #[test]
+    fn scalar_test_narrow_integers() {
+        assert_eq!((-128i8).div_count(200), 0);
+        assert_eq!(100i8.div_count(7), 14);
+        assert_eq!(<i8 as TvScalar>::mean(&[100; 300]), 100);
+        assert_eq!(<i8 as TvScalar>::mean(&[-128, -127, 127]), -42);
+        assert_eq!(<f64 as TvScalar>::mean(&[1.0, 2.0]), 1.5);
+    }
+
+    #[test]
+    fn scalar_test_try_add() {
+        assert_eq!(100i8.try_add(27), Some(127));
+        assert_eq!(100i8.try_add(28), None);
+        assert_eq!((-100i8).try_sub(29), None);
+        assert_eq!(f32::MAX.try_add(f32::MAX), Some(f32::INFINITY));
+    }
+
+    #[cfg(all(feature = "ordered-float", feature = "std"))]
+    #[test]
+    fn ordered_float_test_matches_f64() {
+        use {condat, tautstring};
+
+        let input = vec![101.25, 101.5, 101.0, 104.75, 105.0, 104.5];
+        let not_nan: Vec<NotNan<f64>> = input.iter().map(|&value| NotNan::new(value).unwrap()).collect();
+        let output = condat(&not_nan, NotNan::new(0.5).unwrap());
+        let expected = condat(&input, 0.5);
+        assert_eq!(output.iter().map(|value| value.into_inner()).collect::<Vec<f64>>(), expected);
+
+        let ordered: Vec<OrderedFloat<f64>> = input.iter().map(|&value| OrderedFloat(value)).collect();
+        let output = tautstring(&ordered, OrderedFloat(0.5));
+        assert_eq!(output.iter().map(|value| value.0).collect::<Vec<f64>>(), tautstring(&input, 0.5));
+    }
+
+    #[cfg(all(feature = "ordered-float", feature = "std"))]
+    #[test]
+    #[should_panic]
+    fn ordered_float_test_nan_lambda() {
+        use condat;
+
+        condat(&[OrderedFloat(1.0), OrderedFloat(2.0)], OrderedFloat(f64::NAN));
+    }
+}
@@ -0,0 +1,218 @@
+use condat;
+use interleaved::condat_strided;
+use scalar::{TvFloat, TvScalar};
+use utils;
+
+/// The result of
+/// [`tv1d::seasonal_decompose`](../tv1d/fn.seasonal_decompose.html),
+/// whose three components add up to the input.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SeasonalDecomposition<T> {
+    /// The piecewise constant trend.
+    pub trend: Vec<T>,
+    /// The seasonal component, which repeats every period and sums to
+    /// `0` over each full period.
+    pub seasonal: Vec<T>,
+    /// What is left of the input.
+    pub remainder: Vec<T>,
+    /// The number of iterations run.
+    pub iterations: usize,
+}
+
+/// Decomposes the input values into a trend, a seasonal component of
+/// `period` values, and a remainder, like STL, but estimating the trend
+/// by total variation denoising to keep its level shifts sharp.
+///
+/// The components are estimated alternately. The seasonal component is
+/// the mean of the input minus the trend at each position within the
+/// period, centered to sum to `0` over a period, and the trend is the
+/// input minus the seasonal component, denoised with
+/// [`tv1d::condat`](../tv1d/fn.condat.html) and `lambda`. The
+/// iterations start from a trend of `0`, and stop once the seasonal
+/// component changes by less than `1e-9` times the range of the input,
+/// or after `max_iterations`.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `period` is `0`, or
+/// `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // A weekly pattern, with a level shift after three weeks.
+/// let week = [1.0, 2.0, 2.0, 2.0, 2.0, 0.0, -9.0];
+/// let input: Vec<f64> = (0..42).map(|i| week[i % 7] + if i < 21 { 10.0 } else { 20.0 }).collect();
+///
+/// let decomposition = tv1d::seasonal_decompose(&input, 7, 1.0, 100);
+/// assert!((decomposition.trend[21] - decomposition.trend[20] - 10.0).abs() < 1.0);
+/// assert!((decomposition.seasonal[6] - decomposition.seasonal[5] + 9.0).abs() < 1e-6);
+/// ```
+pub fn seasonal_decompose<T>(input: &[T], period: usize, lambda: T, max_iterations: usize) -> SeasonalDecomposition<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(period > 0, "Period must be greater than 0.");
+
+    let (min, max) = input.iter().fold((input[0], input[0]), |(min, max), &value| (min.min(value), max.max(value)));
+    let tolerance = T::from_f64(1e-9).expect("Unable to convert f64 to TvScalar.") * (max - min);
+
+    let mut trend = vec![T::zero(); input.len()];
+    let mut seasonal = vec![T::zero(); input.len()];
+    let mut iterations = 0;
+    while iterations < max_iterations {
+        let detrended: Vec<T> = input.iter().zip(trend.iter()).map(|(&value, &trend)| value - trend).collect();
+        let new_seasonal = periodic_means(&detrended, period);
+        let change = new_seasonal.iter().zip(seasonal.iter()).fold(T::zero(), |change, (&new, &old)| change.max((new - old).abs()));
+        seasonal = new_seasonal;
+
+        let deseasonalized: Vec<T> = input.iter().zip(seasonal.iter()).map(|(&value, &seasonal)| value - seasonal).collect();
+        trend = condat(&deseasonalized, lambda);
+        iterations += 1;
+
+        if change <= tolerance && iterations > 1 {
+            break;
+        }
+    }
+
+    let remainder = input.iter()
+        .zip(trend.iter().zip(seasonal.iter()))
+        .map(|(&value, (&trend, &seasonal))| value - trend - seasonal)
+        .collect();
+    SeasonalDecomposition {
+        trend,
+        seasonal,
+        remainder,
+        iterations,
+    }
+}
+
+// Returns the mean of the values at each position within the period,
+// centered to sum to `0` over a period, repeated over the values.
+fn periodic_means<T>(values: &[T], period: usize) -> Vec<T>
+    where T: TvFloat
+{
+    let period = period.min(values.len());
+    let means: Vec<T> = (0..period)
+        .map(|phase| {
+            let phase_values: Vec<T> = values[phase..].iter().step_by(period).cloned().collect();
+            utils::mean(&phase_values)
+        })
+        .collect();
+    let center = utils::mean(&means);
+    (0..values.len()).map(|i| means[i % period] - center).collect()
+}
+
+/// Denoises the input values penalizing the differences between
+/// values `period` apart, `lambda * sum(abs(x[i] - x[i - period]))`,
+/// instead of between neighboring values, e.g. to denoise a daily KPI
+/// series with a weekly pattern by comparing each day with the same
+/// day of the week before.
+///
+/// The penalty only ties together the values at the same position
+/// within the period, so the output is that of
+/// [`tv1d::condat`](../tv1d/fn.condat.html) on each of the `period`
+/// series of every `period`-th value, denoised independently. With a
+/// `period` of `1`, it is the output of `condat`.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `period` is `0`, or
+/// `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // Weekdays are busy and weekends quiet, with noise on top.
+/// let input = vec![10.0, 11.0, 9.0, 10.0, 11.0, 2.0, 1.0,
+///                  11.0, 10.0, 10.0, 9.0, 10.0, 1.0, 2.0];
+///
+/// let denoised = tv1d::condat_seasonal(&input, 7, 10.0);
+/// assert_eq!(denoised[..7], denoised[7..]);
+/// assert_eq!(denoised[..7], [10.5, 10.5, 9.5, 9.5, 10.5, 1.5, 1.5]);
+/// ```
+pub fn condat_seasonal<T>(input: &[T], period: usize, lambda: T) -> Vec<T>
+    where T: TvScalar
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(period > 0, "Period must be greater than 0.");
+
+    let mut output = input.to_vec();
+    for phase in 0..period.min(input.len()) {
+        let denoised = condat_strided(input, period, phase, lambda);
+        for (value, denoised) in output[phase..].iter_mut().step_by(period).zip(denoised) {
+            *value = denoised;
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seasonal_decompose_test_components_add_up() {
+        let input: Vec<f64> = (0..120)
+            .map(|i| [3.0, -1.0, 0.5, -2.5][i % 4] + if i < 70 { 0.0 } else { 5.0 } + ((i * 7919) % 13) as f64 * 0.05)
+            .collect();
+        let decomposition = seasonal_decompose(&input, 4, 2.0, 100);
+        assert!(decomposition.iterations < 100);
+        for (i, &value) in input.iter().enumerate() {
+            let sum = decomposition.trend[i] + decomposition.seasonal[i] + decomposition.remainder[i];
+            assert!((sum - value).abs() < 1e-9);
+            if i >= 4 {
+                assert!((decomposition.seasonal[i] - decomposition.seasonal[i - 4]).abs() < 1e-12);
+            }
+        }
+        assert!(decomposition.seasonal[..4].iter().sum::<f64>().abs() < 1e-9);
+        // The level shift is kept in the trend, not smoothed over.
+        assert!(decomposition.trend[70] - decomposition.trend[69] > 4.0);
+        for (value, expected) in decomposition.seasonal[..4].iter().zip([3.0, -1.0, 0.5, -2.5].iter()) {
+            assert!((value - expected).abs() < 0.2);
+        }
+    }
+
+    #[test]
+    fn seasonal_decompose_test_period_one() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let decomposition = seasonal_decompose(&input, 1, 2.0, 10);
+        assert_eq!(decomposition.seasonal, vec![0.0; 8]);
+        assert_eq!(decomposition.trend, condat(&input, 2.0));
+    }
+
+    #[test]
+    fn condat_seasonal_test_matches_condat() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        assert_eq!(condat_seasonal(&input, 1, 2.0), condat(&input, 2.0));
+        assert_eq!(condat_seasonal(&input, 8, 2.0), input);
+        assert_eq!(condat_seasonal(&input, 100, 2.0), input);
+
+        // Every other value, denoised on its own.
+        let output = condat_seasonal(&input, 2, 2.0);
+        let even = condat(&[1.0, 5.2, 1.4, 6.2], 2.0);
+        let odd = condat(&[2.1, 8.2, 5.2, 10.1], 2.0);
+        assert_eq!(output, vec![even[0], odd[0], even[1], odd[1], even[2], odd[2], even[3], odd[3]]);
+    }
+
+    #[test]
+    fn condat_seasonal_test_integers() {
+        // Each series of every other value is constant, so the
+        // alternating input is kept whole.
+        let input: Vec<i64> = vec![3, 7, 3, 7, 3, 7];
+        assert_eq!(condat_seasonal(&input, 2, 100), input);
+    }
+
+    #[test]
+    #[should_panic]
+    fn seasonal_decompose_test_zero_period() {
+        seasonal_decompose(&[1.0, 2.0], 0, 1.0, 10);
+    }
+}
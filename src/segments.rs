@@ -0,0 +1,239 @@
+// Condat's algorithm as a state machine that finds one segment at a
+// time, which is the only copy of its loop. `condat_in_place` and the
+// other in-memory solvers run it to the end with `solve`, while the
+// lazy and streaming solvers drive it segment by segment.
+
+use observer::{Bounds, Observer};
+use scalar::TvScalar;
+use utils;
+
+// The additions and subtractions of the algorithm, so that
+// `condat_saturating` can run the same loop with saturating
+// arithmetic.
+pub(crate) trait Arithmetic<T> {
+    fn add(&self, a: T, b: T) -> T;
+    fn sub(&self, a: T, b: T) -> T;
+}
+
+// The arithmetic of `T` itself.
+pub(crate) struct Exact;
+
+impl<T> Arithmetic<T> for Exact
+    where T: TvScalar
+{
+    #[inline]
+    fn add(&self, a: T, b: T) -> T {
+        a + b
+    }
+
+    #[inline]
+    fn sub(&self, a: T, b: T) -> T {
+        a - b
+    }
+}
+
+// The state of Condat's algorithm between segments. All indices are
+// relative to the input passed to `advance` and `finish`, which must
+// start at the same value each time.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub(crate) struct Condat<T> {
+    pub(crate) lambda: T,
+    // The location of the element the algorithm is currently
+    // inspecting.
+    pub(crate) current_input_index: usize,
+    // The index of the beginning of the current segment.
+    pub(crate) segment_start: usize,
+    // Keep track of the values seen since the last adjustment of the
+    // bounds, and decide whether the bounds should be adjusted.
+    pub(crate) umin: T,
+    pub(crate) umax: T,
+    // The bounds of the value of the current segment.
+    pub(crate) segment_lower_bound: T,
+    pub(crate) segment_upper_bound: T,
+    // The last position where `umax = -lambda`.
+    pub(crate) kplus: usize,
+    // The last position where `umin = lambda`.
+    pub(crate) kminus: usize,
+}
+
+impl<T> Condat<T>
+    where T: TvScalar
+{
+    pub(crate) fn new<A: Arithmetic<T>>(first: T, lambda: T, arithmetic: &A) -> Condat<T> {
+        Condat {
+            lambda,
+            current_input_index: 0,
+            segment_start: 0,
+            umin: lambda,
+            umax: -lambda,
+            segment_lower_bound: arithmetic.sub(first, lambda),
+            segment_upper_bound: arithmetic.add(first, lambda),
+            kplus: 0,
+            kminus: 0,
+        }
+    }
+
+    // Ends the current segment before `end`, returning its length, and
+    // restarts the algorithm from `end`.
+    fn end_segment(&mut self, end: usize) -> usize {
+        let len = end - self.segment_start;
+        self.segment_start = end;
+        self.current_input_index = end;
+        len
+    }
+
+    // Moves every index `shift` positions back, after the first `shift`
+    // values of the input have been dropped.
+    #[cfg(feature = "std")]
+    pub(crate) fn rebase(&mut self, shift: usize) {
+        self.current_input_index -= shift;
+        self.segment_start -= shift;
+        self.kplus -= shift;
+        self.kminus -= shift;
+    }
+
+    fn observe<O: Observer<T>>(&self, observer: &mut O) {
+        observer.bounds(Bounds {
+            index: self.current_input_index,
+            segment_start: self.segment_start,
+            kminus: self.kminus,
+            kplus: self.kplus,
+            umin: self.umin,
+            umax: self.umax,
+            lower: self.segment_lower_bound,
+            upper: self.segment_upper_bound,
+        });
+    }
+
+    // Runs the algorithm until it finds the next segment, returning
+    // its length and value, or `None` once it has read every value of
+    // the input. The segments found here do not depend on any value
+    // after the input.
+    pub(crate) fn advance<A, O>(&mut self, input: &[T], arithmetic: &A, observer: &mut O) -> Option<(usize, T)>
+        where A: Arithmetic<T>,
+              O: Observer<T>
+    {
+        let lambda = self.lambda;
+        let minlambda = -lambda;
+        let twolambda = arithmetic.add(lambda, lambda);
+        while self.current_input_index + 1 < input.len() {
+            self.observe(observer);
+            let next = utils::get(input, self.current_input_index + 1);
+            self.umin = arithmetic.add(self.umin, arithmetic.sub(next, self.segment_lower_bound));
+            self.umax = arithmetic.add(self.umax, arithmetic.sub(next, self.segment_upper_bound));
+            if self.umin < minlambda {
+                // The next value is much smaller than
+                // `segment_lower_bound`, so make a negative jump. The
+                // next segment starts at its lower bound.
+                let value = self.segment_lower_bound;
+                let end = self.kminus + 1;
+                let len = self.end_segment(end);
+                self.kminus = end;
+                self.kplus = end;
+                self.segment_lower_bound = utils::get(input, end);
+                self.segment_upper_bound = arithmetic.add(self.segment_lower_bound, twolambda);
+                self.umin = lambda;
+                self.umax = minlambda;
+                return Some((len, value));
+            } else if self.umax > lambda {
+                // The next value is much larger than
+                // `segment_upper_bound`, so make a positive jump.
+                let value = self.segment_upper_bound;
+                let end = self.kplus + 1;
+                let len = self.end_segment(end);
+                self.kminus = end;
+                self.kplus = end;
+                self.segment_upper_bound = utils::get(input, end);
+                self.segment_lower_bound = arithmetic.sub(self.segment_upper_bound, twolambda);
+                self.umin = lambda;
+                self.umax = minlambda;
+                return Some((len, value));
+            }
+
+            // No jump is necessary, but the bounds may have to move
+            // closer to the values seen so far.
+            self.current_input_index += 1;
+            if self.umin >= lambda {
+                self.kminus = self.current_input_index;
+                let excess = arithmetic.sub(self.umin, lambda);
+                self.segment_lower_bound = arithmetic.add(self.segment_lower_bound,
+                                                          excess.div_count(self.kminus - self.segment_start + 1));
+                self.umin = lambda;
+            }
+            if self.umax <= minlambda {
+                self.kplus = self.current_input_index;
+                let excess = arithmetic.add(self.umax, lambda);
+                self.segment_upper_bound = arithmetic.add(self.segment_upper_bound,
+                                                          excess.div_count(self.kplus - self.segment_start + 1));
+                self.umax = minlambda;
+            }
+        }
+        None
+    }
+
+    // Finds the next segment once `advance` has read every value of
+    // the input, which is the last segment if the returned flag is
+    // set. Otherwise the algorithm must `advance` again.
+    pub(crate) fn finish<A, O>(&mut self, input: &[T], arithmetic: &A, observer: &mut O) -> (usize, T, bool)
+        where A: Arithmetic<T>,
+              O: Observer<T>
+    {
+        self.observe(observer);
+        let lambda = self.lambda;
+        if self.umin < T::zero() {
+            // `segment_lower_bound` is too high, so jump down.
+            let value = self.segment_lower_bound;
+            let end = self.kminus + 1;
+            let len = self.end_segment(end);
+            self.kminus = end;
+            self.segment_lower_bound = utils::get(input, end);
+            self.umin = lambda;
+            self.umax = arithmetic.sub(arithmetic.add(self.segment_lower_bound, self.umin), self.segment_upper_bound);
+            (len, value, false)
+        } else if self.umax > T::zero() {
+            // `segment_upper_bound` is too low, so jump up.
+            let value = self.segment_upper_bound;
+            let end = self.kplus + 1;
+            let len = self.end_segment(end);
+            self.kplus = end;
+            self.segment_upper_bound = utils::get(input, end);
+            self.umax = -lambda;
+            self.umin = arithmetic.sub(arithmetic.add(self.segment_upper_bound, self.umax), self.segment_lower_bound);
+            (len, value, false)
+        } else {
+            // The bounds are neither too high nor too low, so the last
+            // segment takes the lower bound, adjusted for the values
+            // seen since it last moved.
+            let adjustment = self.umin.div_count(self.current_input_index - self.segment_start + 1);
+            self.segment_lower_bound = arithmetic.add(self.segment_lower_bound, adjustment);
+            let value = self.segment_lower_bound;
+            let len = self.end_segment(input.len());
+            (len, value, true)
+        }
+    }
+}
+
+// Runs the algorithm over `values` and overwrites them with the
+// output. A segment is only written once the algorithm has moved past
+// it, and the algorithm never reads a value before the current
+// segment, so the values can be denoised in place.
+pub(crate) fn solve<T, A, O>(values: &mut [T], lambda: T, arithmetic: &A, observer: &mut O)
+    where T: TvScalar,
+          A: Arithmetic<T>,
+          O: Observer<T>
+{
+    let mut condat = Condat::new(values[0], lambda, arithmetic);
+    loop {
+        let (len, value, last) = match condat.advance(values, arithmetic, observer) {
+            Some((len, value)) => (len, value, false),
+            None => condat.finish(values, arithmetic, observer),
+        };
+        let end = condat.segment_start;
+        observer.segment(end - len, end, value);
+        utils::fill_range(values, end - len, end, value);
+        if last {
+            return;
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// Explicit SIMD versions of the memory-bound loops, used with the
+// `simd` feature. Each function returns `None`, or `false`, when it
+// does not handle the values, either because `T` is neither `f32`
+// nor `f64` or because the CPU lacks the instructions, and the caller
+// falls back to the scalar loop.
+
+use std::any::TypeId;
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+/// Sets every value to `value`.
+pub fn fill<T: 'static>(values: &mut [T], value: T) -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if TypeId::of::<T>() == TypeId::of::<f64>() && is_x86_feature_detected!("avx") {
+            let (values, value) = unsafe { (cast_mut::<T, f64>(values), *cast_ref::<T, f64>(&value)) };
+            unsafe { fill_f64(values, value) };
+            return true;
+        }
+        if TypeId::of::<T>() == TypeId::of::<f32>() && is_x86_feature_detected!("avx") {
+            let (values, value) = unsafe { (cast_mut::<T, f32>(values), *cast_ref::<T, f32>(&value)) };
+            unsafe { fill_f32(values, value) };
+            return true;
+        }
+    }
+    let _ = (values, value);
+    false
+}
+
+#[cfg(not(feature = "deterministic"))]
+/// Returns the sum of the squared differences between `a` and `b`,
+/// which have the same length.
+pub fn squared_distance<T: Copy + 'static>(a: &[T], b: &[T]) -> Option<T> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if TypeId::of::<T>() == TypeId::of::<f64>() && is_x86_feature_detected!("avx") {
+            let sum = unsafe { squared_distance_f64(cast::<T, f64>(a), cast::<T, f64>(b)) };
+            return Some(unsafe { *cast_ref::<f64, T>(&sum) });
+        }
+        if TypeId::of::<T>() == TypeId::of::<f32>() && is_x86_feature_detected!("avx") {
+            let sum = unsafe { squared_distance_f32(cast::<T, f32>(a), cast::<T, f32>(b)) };
+            return Some(unsafe { *cast_ref::<f32, T>(&sum) });
+        }
+    }
+    let _ = (a, b);
+    None
+}
+
+// The casts are only called once `TypeId` has shown that `T` and `U`
+// are the same type.
+#[cfg(not(feature = "deterministic"))]
+#[cfg(target_arch = "x86_64")]
+unsafe fn cast<T, U>(values: &[T]) -> &[U] {
+    &*(values as *const [T] as *const [U])
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn cast_mut<T, U>(values: &mut [T]) -> &mut [U] {
+    &mut *(values as *mut [T] as *mut [U])
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn cast_ref<T, U>(value: &T) -> &U {
+    &*(value as *const T as *const U)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn fill_f64(values: &mut [f64], value: f64) {
+    let x = _mm256_set1_pd(value);
+    let mut chunks = values.chunks_exact_mut(4);
+    for chunk in &mut chunks {
+        _mm256_storeu_pd(chunk.as_mut_ptr(), x);
+    }
+    for v in chunks.into_remainder() {
+        *v = value;
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn fill_f32(values: &mut [f32], value: f32) {
+    let x = _mm256_set1_ps(value);
+    let mut chunks = values.chunks_exact_mut(8);
+    for chunk in &mut chunks {
+        _mm256_storeu_ps(chunk.as_mut_ptr(), x);
+    }
+    for v in chunks.into_remainder() {
+        *v = value;
+    }
+}
+
+#[cfg(not(feature = "deterministic"))]
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn squared_distance_f64(a: &[f64], b: &[f64]) -> f64 {
+    assert_eq!(a.len(), b.len());
+    let mut sum = _mm256_setzero_pd();
+    let len = a.len() - a.len() % 4;
+    let mut i = 0;
+    while i < len {
+        let difference = _mm256_sub_pd(_mm256_loadu_pd(a.as_ptr().add(i)), _mm256_loadu_pd(b.as_ptr().add(i)));
+        sum = _mm256_add_pd(sum, _mm256_mul_pd(difference, difference));
+        i += 4;
+    }
+    let mut lanes = [0.0; 4];
+    _mm256_storeu_pd(lanes.as_mut_ptr(), sum);
+    let mut sum = lanes.iter().sum::<f64>();
+    for (x, y) in a[len..].iter().zip(b[len..].iter()) {
+        sum += (x - y) * (x - y);
+    }
+    sum
+}
+
+#[cfg(not(feature = "deterministic"))]
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn squared_distance_f32(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len());
+    let mut sum = _mm256_setzero_ps();
+    let len = a.len() - a.len() % 8;
+    let mut i = 0;
+    while i < len {
+        let difference = _mm256_sub_ps(_mm256_loadu_ps(a.as_ptr().add(i)), _mm256_loadu_ps(b.as_ptr().add(i)));
+        sum = _mm256_add_ps(sum, _mm256_mul_ps(difference, difference));
+        i += 8;
+    }
+    let mut lanes = [0.0; 8];
+    _mm256_storeu_ps(lanes.as_mut_ptr(), sum);
+    let mut sum = lanes.iter().sum::<f32>();
+    for (x, y) in a[len..].iter().zip(b[len..].iter()) {
+        sum += (x - y) * (x - y);
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_test_fills_every_value() {
+        for len in 0..20 {
+            let mut values = vec![0.0f64; len];
+            fill(&mut values, 3.5);
+            assert!(values.iter().all(|&value| value == 3.5));
+            let mut values = vec![0.0f32; len];
+            fill(&mut values, 3.5);
+            assert!(values.iter().all(|&value| value == 3.5));
+        }
+    }
+
+    #[cfg(not(feature = "deterministic"))]
+    #[test]
+    fn squared_distance_test_matches_scalar() {
+        for len in 0..20 {
+            let a: Vec<f64> = (0..len).map(|i| (i * 3 % 7) as f64).collect();
+            let b: Vec<f64> = (0..len).map(|i| (i % 4) as f64).collect();
+            let expected: f64 = a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum();
+            if let Some(sum) = squared_distance(&a, &b) {
+                assert_eq!(sum, expected);
+            }
+        }
+    }
+
+    #[cfg(not(feature = "deterministic"))]
+    #[test]
+    fn squared_distance_test_other_types() {
+        assert_eq!(squared_distance(&[1i32], &[2i32]), None);
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::{Duration, Instant};
+
+use scalar::{TvFloat, TvScalar};
+use {condat, tautstring};
+
+/// Statistics of a solve, returned alongside the output by
+/// [`tv1d::condat_with_stats`](../tv1d/fn.condat_with_stats.html) and
+/// [`tv1d::tautstring_with_stats`](../tv1d/fn.tautstring_with_stats.html)
+/// to monitor the denoising without recomputing it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SolveStats<T> {
+    /// The number of constant segments of the output.
+    pub segments: usize,
+    /// The number of jumps of the output to a larger value.
+    pub jumps_up: usize,
+    /// The number of jumps of the output to a smaller value.
+    pub jumps_down: usize,
+    /// The largest absolute difference between an input value and the
+    /// output value at the same position.
+    pub max_residual: T,
+    /// The time taken by the solver, without computing the
+    /// statistics.
+    pub elapsed: Duration,
+}
+
+impl<T> SolveStats<T>
+    where T: TvScalar
+{
+    // Computes the statistics of `output`, the output of a solver on
+    // `input` that took `elapsed`.
+    fn new(input: &[T], output: &[T], elapsed: Duration) -> SolveStats<T> {
+        let mut jumps_up = 0;
+        let mut jumps_down = 0;
+        for window in output.windows(2) {
+            if window[1] > window[0] {
+                jumps_up += 1;
+            } else if window[1] < window[0] {
+                jumps_down += 1;
+            }
+        }
+
+        let max_residual = input.iter().zip(output.iter()).fold(T::zero(), |max, (&value, &denoised)| {
+            let residual = if value < denoised { denoised - value } else { value - denoised };
+            if residual > max { residual } else { max }
+        });
+
+        SolveStats {
+            segments: jumps_up + jumps_down + 1,
+            jumps_up,
+            jumps_down,
+            max_residual,
+            elapsed,
+        }
+    }
+}
+
+/// Denoises the input values with
+/// [`tv1d::condat`](../tv1d/fn.condat.html), and returns the
+/// statistics of the solve alongside the output.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 1.0, 5.0, 5.0, 2.0];
+/// let (output, stats) = tv1d::condat_with_stats(&input, 0.5);
+/// assert_eq!(output, vec![1.25, 1.25, 4.5, 4.5, 2.5]);
+/// assert_eq!((stats.segments, stats.jumps_up, stats.jumps_down), (3, 1, 1));
+/// assert_eq!(stats.max_residual, 0.5);
+/// ```
+pub fn condat_with_stats<T>(input: &[T], lambda: T) -> (Vec<T>, SolveStats<T>)
+    where T: TvScalar
+{
+    let start = Instant::now();
+    let output = condat(input, lambda);
+    let stats = SolveStats::new(input, &output, start.elapsed());
+    (output, stats)
+}
+
+/// Denoises the input values with
+/// [`tv1d::tautstring`](../tv1d/fn.tautstring.html), and returns the
+/// statistics of the solve alongside the output.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// let (output, stats) = tv1d::tautstring_with_stats(&input, 10.0);
+/// assert_eq!(output, vec![3.0; 5]);
+/// assert_eq!(stats.segments, 1);
+/// assert_eq!(stats.max_residual, 2.0);
+/// ```
+pub fn tautstring_with_stats<T>(input: &[T], lambda: T) -> (Vec<T>, SolveStats<T>)
+    where T: TvFloat
+{
+    let start = Instant::now();
+    let output = tautstring(input, lambda);
+    let stats = SolveStats::new(input, &output, start.elapsed());
+    (output, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condat_with_stats_test_counts() {
+        let input: Vec<i32> = vec![111, 422, 145, 248, 871, 675, 436, 310];
+        let (output, stats) = condat_with_stats(&input, 50);
+        assert_eq!(output, condat(&input, 50));
+        let jumps = output.windows(2).filter(|window| window[0] != window[1]).count();
+        assert_eq!(stats.segments, jumps + 1);
+        assert_eq!(stats.jumps_up + stats.jumps_down, jumps);
+        let max_residual = input.iter().zip(output.iter()).map(|(value, denoised)| (value - denoised).abs()).max();
+        assert_eq!(Some(stats.max_residual), max_residual);
+    }
+
+    #[test]
+    fn tautstring_with_stats_test_single_value() {
+        let (output, stats) = tautstring_with_stats(&[4.0], 1.0);
+        assert_eq!(output, vec![4.0]);
+        assert_eq!((stats.segments, stats.jumps_up, stats.jumps_down), (1, 0, 0));
+        assert_eq!(stats.max_residual, 0.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn condat_with_stats_test_serde_round_trip() {
+        use serde_json;
+
+        let (_, stats) = condat_with_stats(&[1.0, 2.1, 5.2, 8.2, 1.4], 1.0);
+        let json = serde_json::to_string(&stats).unwrap();
+        assert_eq!(serde_json::from_str::<SolveStats<f64>>(&json).unwrap(), stats);
+    }
+}
@@ -0,0 +1,271 @@
+use std::cmp;
+
+use dp::dynamic_programming_weighted;
+use scalar::TvFloat;
+use utils;
+
+/// Denoises unevenly spaced samples, given as `(timestamp, value)`
+/// pairs such as event logs or tick data, penalizing the difference
+/// between neighboring values by `lambda` divided by the time between
+/// them.
+///
+/// A jump over a long gap between samples is then cheaper than one
+/// between samples close in time, as the signal had more time to
+/// change. `lambda` is per unit of time, as in
+/// [`Lambda::PerUnitTime`](enum.Lambda.html#variant.PerUnitTime), so
+/// samples taken every `dt` are denoised like
+/// [`tv1d::condat`](../tv1d/fn.condat.html) with `lambda / dt`. The
+/// denoised values are returned in the order of the input, and solved
+/// with
+/// [`tv1d::dynamic_programming_weighted`](../tv1d/fn.dynamic_programming_weighted.html).
+///
+/// # Panics
+/// Panics if input vector's length is `0`, the timestamps are not
+/// strictly increasing, `lambda` is NaN or less than `0`, or `lambda`
+/// divided by the time between two samples overflows.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // Two samples a second apart, a gap of eight seconds, and two more.
+/// let input = vec![(0.0, 1.0), (1.0, 2.0), (9.0, 3.0), (10.0, 4.0)];
+///
+/// // The jump over the gap is penalized by `1.0 / 8.0` only.
+/// let denoised = tv1d::denoise_timestamped(&input, 1.0);
+/// assert_eq!(denoised, vec![1.5625, 1.5625, 3.4375, 3.4375]);
+/// ```
+pub fn denoise_timestamped<T>(input: &[(T, T)], lambda: T) -> Vec<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(!utils::is_nan_lambda(&lambda),
+            "Lambda must not be NaN.");
+
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    assert!(input.windows(2).all(|window| window[0].0 < window[1].0),
+            "Timestamps must be strictly increasing.");
+
+    let values: Vec<T> = input.iter().map(|&(_, value)| value).collect();
+    if utils::is_infinite_lambda(lambda) {
+        return vec![utils::mean(&values); values.len()];
+    }
+
+    let lambdas: Vec<T> = input.windows(2).map(|window| lambda / (window[1].0 - window[0].0)).collect();
+    dynamic_programming_weighted(&values, &lambdas)
+}
+
+/// How [`tv1d::collapse_duplicates`](../tv1d/fn.collapse_duplicates.html)
+/// combines the values of samples sharing a timestamp.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregation<'a, T: 'a> {
+    /// The mean of the values.
+    Mean,
+    /// The median of the values, robust to outliers among them.
+    Median,
+    /// The mean of the values weighted by the weights at the same
+    /// positions, e.g. the number of events each sample summarizes.
+    WeightedMean(&'a [T]),
+}
+
+/// Samples with distinct timestamps, returned by
+/// [`tv1d::collapse_duplicates`](../tv1d/fn.collapse_duplicates.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct CollapsedSamples<T> {
+    /// The `(timestamp, value)` pairs, in strictly increasing order of
+    /// timestamp.
+    pub samples: Vec<(T, T)>,
+    /// For each input sample, the index of the sample in `samples`
+    /// its value was collapsed into.
+    pub mapping: Vec<usize>,
+}
+
+impl<T: Copy> CollapsedSamples<T> {
+    /// Expands values with one value per collapsed sample, such as the
+    /// denoised values, back to one value per input sample.
+    ///
+    /// # Panics
+    /// Panics if `values` does not have one value per collapsed sample.
+    pub fn expand(&self, values: &[T]) -> Vec<T> {
+        assert!(values.len() == self.samples.len(),
+                "Values must have one value per collapsed sample.");
+
+        self.mapping.iter().map(|&index| values[index]).collect()
+    }
+}
+
+/// Collapses the `(timestamp, value)` pairs sharing a timestamp into
+/// one, combining their values according to `aggregation`, to prepare
+/// messy event-sourced data for
+/// [`tv1d::denoise_timestamped`](../tv1d/fn.denoise_timestamped.html).
+///
+/// The input does not need to be sorted. The collapsed samples are
+/// sorted by timestamp, and the mapping from the input samples to the
+/// collapsed ones is returned alongside, to expand the denoised values
+/// back with
+/// [`CollapsedSamples::expand`](struct.CollapsedSamples.html#method.expand).
+///
+/// # Panics
+/// Panics if input vector's length is `0`, a timestamp is NaN, or with
+/// `Aggregation::WeightedMean`, the weights do not have the same length
+/// as the input, a weight is NaN or less than `0`, or the weights of a
+/// timestamp sum to `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+/// use tv1d::Aggregation;
+///
+/// let input = vec![(1.0, 3.0), (0.0, 1.0), (1.0, 5.0), (2.0, 4.0)];
+///
+/// let collapsed = tv1d::collapse_duplicates(&input, Aggregation::Mean);
+/// assert_eq!(collapsed.samples, vec![(0.0, 1.0), (1.0, 4.0), (2.0, 4.0)]);
+/// assert_eq!(collapsed.mapping, vec![1, 0, 1, 2]);
+///
+/// let denoised = tv1d::denoise_timestamped(&collapsed.samples, 0.0);
+/// assert_eq!(collapsed.expand(&denoised), vec![4.0, 1.0, 4.0, 4.0]);
+/// ```
+pub fn collapse_duplicates<T>(input: &[(T, T)], aggregation: Aggregation<T>) -> CollapsedSamples<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!(input.iter().all(|&(timestamp, _)| !timestamp.is_nan()),
+            "Timestamps must not be NaN.");
+
+    if let Aggregation::WeightedMean(weights) = aggregation {
+        assert!(weights.len() == input.len(),
+                "Input and weights must have the same length.");
+
+        assert!(weights.iter().all(|&weight| weight >= T::zero()),
+                "Weights must be greater than or equal to 0.");
+    }
+
+    let mut order: Vec<usize> = (0..input.len()).collect();
+    order.sort_by(|&a, &b| input[a].0.partial_cmp(&input[b].0).unwrap_or(cmp::Ordering::Equal));
+
+    let mut samples = Vec::new();
+    let mut mapping = vec![0; input.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let timestamp = input[order[start]].0;
+        let mut end = start + 1;
+        while end < order.len() && input[order[end]].0 == timestamp {
+            end += 1;
+        }
+
+        let group = &order[start..end];
+        for &i in group {
+            mapping[i] = samples.len();
+        }
+        samples.push((timestamp, aggregate(input, group, aggregation)));
+        start = end;
+    }
+
+    CollapsedSamples { samples, mapping }
+}
+
+// Combines the values of `input` at the indices in `group`.
+fn aggregate<T>(input: &[(T, T)], group: &[usize], aggregation: Aggregation<T>) -> T
+    where T: TvFloat
+{
+    match aggregation {
+        Aggregation::Mean => {
+            let values: Vec<T> = group.iter().map(|&i| input[i].1).collect();
+            utils::mean(&values)
+        }
+        Aggregation::Median => {
+            let values: Vec<T> = group.iter().map(|&i| input[i].1).collect();
+            utils::median(&values)
+        }
+        Aggregation::WeightedMean(weights) => {
+            let mut sum = T::zero();
+            let mut total = T::zero();
+            for &i in group {
+                sum += weights[i] * input[i].1;
+                total += weights[i];
+            }
+            assert!(total > T::zero(),
+                    "The weights of a timestamp must not sum to 0.");
+            sum / total
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    #[test]
+    fn denoise_timestamped_test_evenly_spaced() {
+        let values = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let input: Vec<(f64, f64)> = values.iter().enumerate().map(|(i, &value)| (10.0 + i as f64 * 0.5, value)).collect();
+        for &lambda in &[0.0, 0.5, 2.0, 100.0, f64::INFINITY] {
+            let output = denoise_timestamped(&input, lambda);
+            let expected = condat(&values, lambda / 0.5);
+            for (value, expected) in output.iter().zip(expected.iter()) {
+                assert!((value - expected).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn denoise_timestamped_test_gap() {
+        // The same values, with a long gap in the middle or without.
+        let values = [1.0, 1.2, 0.9, 3.0, 3.1, 2.9];
+        let even: Vec<(f64, f64)> = values.iter().enumerate().map(|(i, &value)| (i as f64, value)).collect();
+        let gap: Vec<(f64, f64)> = values.iter().enumerate().map(|(i, &value)| (i as f64 + if i < 3 { 0.0 } else { 100.0 }, value)).collect();
+
+        // Evenly spaced, the jump is shrunk by `lambda`; over the gap
+        // it is kept nearly whole.
+        let output = denoise_timestamped(&even, 1.5);
+        assert!(output[3] - output[2] < 1.2);
+        let output = denoise_timestamped(&gap, 1.5);
+        assert!(output[3] - output[2] > 1.9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn denoise_timestamped_test_unordered_timestamps() {
+        denoise_timestamped(&[(0.0, 1.0), (2.0, 2.0), (1.0, 3.0)], 1.0);
+    }
+
+    #[test]
+    fn collapse_duplicates_test_aggregations() {
+        let input = vec![(2.0, 10.0), (1.0, 1.0), (2.0, 2.0), (2.0, 3.0), (0.5, 7.0)];
+        let collapsed = collapse_duplicates(&input, Aggregation::Median);
+        assert_eq!(collapsed.samples, vec![(0.5, 7.0), (1.0, 1.0), (2.0, 3.0)]);
+        assert_eq!(collapsed.mapping, vec![2, 1, 2, 2, 0]);
+        assert_eq!(collapse_duplicates(&input, Aggregation::Mean).samples[2], (2.0, 5.0));
+
+        let weights = vec![1.0, 4.0, 0.0, 3.0, 2.0];
+        let collapsed = collapse_duplicates(&input, Aggregation::WeightedMean(&weights));
+        assert_eq!(collapsed.samples, vec![(0.5, 7.0), (1.0, 1.0), (2.0, 4.75)]);
+    }
+
+    #[test]
+    fn collapse_duplicates_test_expand() {
+        let input = vec![(0.0, 1.0), (0.0, 1.2), (1.0, 0.9), (2.0, 5.0), (3.0, 5.1), (3.0, 4.8)];
+        let collapsed = collapse_duplicates(&input, Aggregation::Mean);
+        let denoised = denoise_timestamped(&collapsed.samples, 0.2);
+        let output = collapsed.expand(&denoised);
+        assert_eq!(output.len(), input.len());
+        assert_eq!(output[0], output[1]);
+        assert_eq!(output[4], output[5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn collapse_duplicates_test_zero_weights() {
+        collapse_duplicates(&[(0.0, 1.0), (0.0, 2.0)], Aggregation::WeightedMean(&[0.0, 0.0]));
+    }
+}
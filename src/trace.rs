@@ -0,0 +1,73 @@
+// Instrumentation of the solvers with the `tracing` feature. Without
+// it, the macros expand to nothing and their arguments are not
+// evaluated, so the instrumentation costs nothing.
+
+// Enters a `tracing` span named `$name` with the given fields, until
+// the end of the enclosing block.
+macro_rules! trace_span {
+    ($name:expr, $($fields:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = ::tracing::debug_span!($name, $($fields)*).entered();
+    };
+}
+
+// Emits a `tracing` event with the given fields and message in the
+// current span.
+macro_rules! trace_event {
+    ($($arguments:tt)*) => {
+        #[cfg(feature = "tracing")]
+        ::tracing::debug!($($arguments)*);
+    };
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tracing;
+    use tracing::span;
+
+    use {condat, tautstring};
+
+    // Counts the spans and events it is sent.
+    #[derive(Clone, Default)]
+    struct Counter {
+        spans: Arc<AtomicUsize>,
+        events: Arc<AtomicUsize>,
+    }
+
+    impl tracing::Subscriber for Counter {
+        fn enabled(&self, _: &tracing::Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &span::Attributes) -> span::Id {
+            span::Id::from_u64(self.spans.fetch_add(1, Ordering::Relaxed) as u64 + 1)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, _: &tracing::Event) {
+            self.events.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[test]
+    fn solvers_emit_spans_and_events() {
+        let counter = Counter::default();
+        tracing::subscriber::with_default(counter.clone(), || {
+            condat(&[1.0, 2.0, 3.0], 0.5);
+            tautstring(&[1.0, 2.0, 3.0], 0.0);
+        });
+        assert_eq!(counter.spans.load(Ordering::Relaxed), 2);
+        // A segment count for each solver, and the fallback of
+        // `tautstring` for a `lambda` of `0`.
+        assert_eq!(counter.events.load(Ordering::Relaxed), 3);
+    }
+}
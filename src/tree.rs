@@ -0,0 +1,380 @@
+use std::cmp;
+use std::collections::BinaryHeap;
+use std::mem;
+
+use scalar::TvFloat;
+use utils;
+
+/// Denoises values on the nodes of a tree, such as counts aggregated
+/// along a taxonomy, penalizing the absolute difference between every
+/// node and its parent.
+///
+/// The output minimizes `0.5 * sum((x[i] - input[i])^2) + lambda *
+/// sum(abs(x[i] - x[parents[i]]))`, where the second sum runs over the
+/// nodes with a parent. `parents[i]` is the index of the parent of
+/// node `i`, or `None` for a root, so several roots form a forest whose
+/// trees are denoised independently. A chain, where the parent of
+/// every node is the previous one, is the input of
+/// [`tv1d::dynamic_programming`](../tv1d/fn.dynamic_programming.html).
+///
+/// This generalizes its dynamic programming from chains to trees, as
+/// described by Kolmogorov V., Pock T. and Rolinek M. in 2016 in the
+/// paper ["Total Variation on a Tree"](https://doi.org/10.1137/15M1010257).
+/// The derivative of the cost of each value of a node, given the best
+/// values of its subtree, is a piecewise linear function, passed from
+/// the leaves up to the roots and clipped to `[-lambda, lambda]` on
+/// the way, and the output is then read off from the roots down. The
+/// knots of the children of a node are merged smaller into larger, so
+/// the run time is `O(n log^2 n)` for `n` nodes in the worst case, and
+/// `O(n log n)` on a chain.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `input` and `parents` have
+/// different lengths, the parents do not form a forest, or `lambda`
+/// is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // A root with two leaves, one of which is far from the others.
+/// let input = vec![1.0, 2.0, 9.0];
+/// let parents = vec![None, Some(0), Some(0)];
+///
+/// assert_eq!(tv1d::denoise_tree(&input, &parents, 1.0), vec![2.0, 2.0, 8.0]);
+/// assert_eq!(tv1d::denoise_tree(&input, &parents, 10.0), vec![4.0, 4.0, 4.0]);
+/// ```
+pub fn denoise_tree<T>(input: &[T], parents: &[Option<usize>], lambda: T) -> Vec<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+    assert!(input.len() == parents.len(),
+            "Input and parents must have the same length.");
+    assert!(!utils::is_nan_lambda(&lambda),
+            "Lambda must not be NaN.");
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    let n = input.len();
+    let mut children = vec![Vec::new(); n];
+    let mut order = Vec::with_capacity(n);
+    for (node, &parent) in parents.iter().enumerate() {
+        match parent {
+            Some(parent) => {
+                assert!(parent < n && parent != node,
+                        "Parents must form a forest.");
+                children[parent].push(node);
+            }
+            None => order.push(node),
+        }
+    }
+    // The nodes in breadth-first order from the roots, so that every
+    // parent comes before its children.
+    let mut next = 0;
+    while next < order.len() {
+        let node = order[next];
+        order.extend_from_slice(&children[node]);
+        next += 1;
+    }
+    assert!(order.len() == n,
+            "Parents must form a forest.");
+
+    if utils::is_infinite_lambda(lambda) {
+        return tree_means(input, parents, &order);
+    }
+
+    let mut knots = Knots::new();
+    let mut messages: Vec<Option<Message<T>>> = (0..n).map(|_| None).collect();
+    // The values of each node between which it follows its parent.
+    let mut bounds = vec![(T::zero(), T::zero()); n];
+    let mut output = vec![T::zero(); n];
+
+    for &node in order.iter().rev() {
+        // The derivative of `0.5 * (x - input[node])^2`.
+        let mut message = Message::new(T::one(), -input[node]);
+        for &child in &children[node] {
+            let child_message = messages[child].take().expect("Children are visited before their parents.");
+            message.add(&knots, child_message);
+        }
+
+        match parents[node] {
+            Some(_) => {
+                let low = message.pop_left(&mut knots, -lambda);
+                let high = message.pop_right(&mut knots, lambda);
+                bounds[node] = (low, high);
+                message.clip(&mut knots, low, high, lambda);
+                messages[node] = Some(message);
+            }
+            None => output[node] = message.pop_left(&mut knots, T::zero()),
+        }
+    }
+
+    for &node in &order {
+        if let Some(parent) = parents[node] {
+            let (low, high) = bounds[node];
+            output[node] = output[parent].max(low).min(high);
+        }
+    }
+    output
+}
+
+// Returns the mean of every tree of the forest at each of its nodes.
+fn tree_means<T>(input: &[T], parents: &[Option<usize>], order: &[usize]) -> Vec<T>
+    where T: TvFloat
+{
+    let mut sums = input.to_vec();
+    let mut counts = vec![1; input.len()];
+    for &node in order.iter().rev() {
+        if let Some(parent) = parents[node] {
+            let (sum, count) = (sums[node], counts[node]);
+            sums[parent] += sum;
+            counts[parent] += count;
+        }
+    }
+
+    let mut output = vec![T::zero(); input.len()];
+    for &node in order {
+        output[node] = match parents[node] {
+            Some(parent) => output[parent],
+            None => sums[node] / T::from_usize(counts[node]).expect("Unable to convert usize to TvScalar."),
+        };
+    }
+    output
+}
+
+// The slope changes of the knots of every message, and whether each
+// knot has been popped from either end of its message.
+struct Knots<T> {
+    deltas: Vec<T>,
+    removed: Vec<bool>,
+}
+
+impl<T> Knots<T> {
+    fn new() -> Knots<T> {
+        Knots {
+            deltas: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+}
+
+// A knot of a message, ordered by its position.
+struct Knot<T> {
+    position: T,
+    id: usize,
+}
+
+impl<T: PartialOrd> PartialEq for Knot<T> {
+    fn eq(&self, other: &Knot<T>) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl<T: PartialOrd> Eq for Knot<T> {}
+
+impl<T: PartialOrd> PartialOrd for Knot<T> {
+    fn partial_cmp(&self, other: &Knot<T>) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: PartialOrd> Ord for Knot<T> {
+    fn cmp(&self, other: &Knot<T>) -> cmp::Ordering {
+        self.position
+            .partial_cmp(&other.position)
+            .unwrap_or(cmp::Ordering::Equal)
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+// A continuous, increasing, piecewise linear derivative, given by its
+// leftmost and rightmost linear pieces `slope * x + offset` and the
+// knots in between, each in a min-heap and a max-heap, so that knots
+// can be popped from either end. A knot popped from one heap is marked
+// removed and skipped in the other.
+struct Message<T> {
+    left: (T, T),
+    right: (T, T),
+    lowest: BinaryHeap<cmp::Reverse<Knot<T>>>,
+    highest: BinaryHeap<Knot<T>>,
+}
+
+impl<T> Message<T>
+    where T: TvFloat
+{
+    fn new(slope: T, offset: T) -> Message<T> {
+        Message {
+            left: (slope, offset),
+            right: (slope, offset),
+            lowest: BinaryHeap::new(),
+            highest: BinaryHeap::new(),
+        }
+    }
+
+    // Adds the derivative `other`, merging the smaller set of knots
+    // into the larger and dropping the removed ones on the way.
+    fn add(&mut self, knots: &Knots<T>, mut other: Message<T>) {
+        self.left = (self.left.0 + other.left.0, self.left.1 + other.left.1);
+        self.right = (self.right.0 + other.right.0, self.right.1 + other.right.1);
+        if other.highest.len() > self.highest.len() {
+            mem::swap(&mut self.lowest, &mut other.lowest);
+            mem::swap(&mut self.highest, &mut other.highest);
+        }
+        for knot in other.highest.drain() {
+            if !knots.removed[knot.id] {
+                self.push(knot.position, knot.id);
+            }
+        }
+    }
+
+    fn push(&mut self, position: T, id: usize) {
+        self.lowest.push(cmp::Reverse(Knot { position, id }));
+        self.highest.push(Knot { position, id });
+    }
+
+    fn add_knot(&mut self, knots: &mut Knots<T>, position: T, delta: T) {
+        let id = knots.deltas.len();
+        knots.deltas.push(delta);
+        knots.removed.push(false);
+        self.push(position, id);
+    }
+
+    // Pops the knots left of where the derivative reaches `target`, and
+    // returns that position.
+    fn pop_left(&mut self, knots: &mut Knots<T>, target: T) -> T {
+        while let Some(cmp::Reverse(knot)) = self.lowest.pop() {
+            if knots.removed[knot.id] {
+                continue;
+            }
+            let (slope, offset) = self.left;
+            if slope * knot.position + offset >= target {
+                self.lowest.push(cmp::Reverse(knot));
+                break;
+            }
+            knots.removed[knot.id] = true;
+            let delta = knots.deltas[knot.id];
+            self.left = (slope + delta, offset - delta * knot.position);
+        }
+        let (slope, offset) = self.left;
+        (target - offset) / slope
+    }
+
+    // Pops the knots right of where the derivative reaches `target`,
+    // and returns that position.
+    fn pop_right(&mut self, knots: &mut Knots<T>, target: T) -> T {
+        while let Some(knot) = self.highest.pop() {
+            if knots.removed[knot.id] {
+                continue;
+            }
+            let (slope, offset) = self.right;
+            if slope * knot.position + offset <= target {
+                self.highest.push(knot);
+                break;
+            }
+            knots.removed[knot.id] = true;
+            let delta = knots.deltas[knot.id];
+            self.right = (slope - delta, offset + delta * knot.position);
+        }
+        let (slope, offset) = self.right;
+        (target - offset) / slope
+    }
+
+    // Clips the derivative to `[-lambda, lambda]`, after its knots
+    // outside of `[low, high]` were popped.
+    fn clip(&mut self, knots: &mut Knots<T>, low: T, high: T, lambda: T) {
+        let left_slope = self.left.0;
+        let right_slope = self.right.0;
+        self.add_knot(knots, low, left_slope);
+        self.add_knot(knots, high, -right_slope);
+        self.left = (T::zero(), -lambda);
+        self.right = (T::zero(), lambda);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    fn objective(output: &[f64], input: &[f64], parents: &[Option<usize>], lambda: f64) -> f64 {
+        let mut value = 0.0;
+        for i in 0..output.len() {
+            value += 0.5 * (output[i] - input[i]).powi(2);
+            if let Some(parent) = parents[i] {
+                value += lambda * (output[i] - output[parent]).abs();
+            }
+        }
+        value
+    }
+
+    #[test]
+    fn denoise_tree_test_chain_matches_condat() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let parents: Vec<Option<usize>> = (0..input.len()).map(|i| i.checked_sub(1)).collect();
+        // The same chain, rooted at its last node.
+        let reversed: Vec<Option<usize>> = (0..input.len()).map(|i| if i + 1 < input.len() { Some(i + 1) } else { None }).collect();
+        for &lambda in &[0.0, 0.5, 1.5, 3.0, 100.0] {
+            let expected = condat(&input, lambda);
+            for parents in &[&parents, &reversed] {
+                let output = denoise_tree(&input, parents, lambda);
+                for (value, expected) in output.iter().zip(expected.iter()) {
+                    assert!((value - expected).abs() < 1e-12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn denoise_tree_test_minimizes_objective() {
+        let n = 200;
+        let input: Vec<f64> = (0..n).map(|i| ((i * 7919) % 23) as f64 * 0.5 + (i % 3) as f64 * 4.0).collect();
+        // A random-looking tree, with the parent of every node before it.
+        let parents: Vec<Option<usize>> = (0..n).map(|i| if i == 0 { None } else { Some((i * 104_729) % i) }).collect();
+        for &lambda in &[0.3, 2.0, 10.0] {
+            let output = denoise_tree(&input, &parents, lambda);
+            let minimum = objective(&output, &input, &parents, lambda);
+            for i in 0..n {
+                for &delta in &[-1e-6, 1e-6] {
+                    let mut perturbed = output.clone();
+                    perturbed[i] += delta;
+                    assert!(objective(&perturbed, &input, &parents, lambda) >= minimum - 1e-9);
+                }
+            }
+            // Moving a whole segment, a node and the descendants equal to
+            // it, does not decrease the objective either.
+            for i in 0..n {
+                let mut perturbed = output.clone();
+                for (j, value) in perturbed.iter_mut().enumerate().skip(i) {
+                    let mut ancestor = Some(j);
+                    while let Some(node) = ancestor {
+                        if node == i {
+                            break;
+                        }
+                        ancestor = parents[node].filter(|&parent| output[parent] == output[node]);
+                    }
+                    if ancestor == Some(i) {
+                        *value += 1e-6;
+                    }
+                }
+                assert!(objective(&perturbed, &input, &parents, lambda) >= minimum - 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn denoise_tree_test_forest() {
+        let input = vec![1.0, 3.0, 10.0, 20.0, 2.0];
+        let parents = vec![None, Some(0), None, Some(2), Some(0)];
+        assert_eq!(denoise_tree(&input, &parents, 100.0), vec![2.0, 2.0, 15.0, 15.0, 2.0]);
+        assert_eq!(denoise_tree(&input, &parents, f64::INFINITY), vec![2.0, 2.0, 15.0, 15.0, 2.0]);
+        assert_eq!(denoise_tree(&input, &parents, 0.0), input);
+    }
+
+    #[test]
+    #[should_panic]
+    fn denoise_tree_test_cycle() {
+        denoise_tree(&[1.0, 2.0, 3.0], &[None, Some(2), Some(1)], 1.0);
+    }
+}
@@ -0,0 +1,312 @@
+use condat_in_place;
+use scalar::TvFloat;
+use utils;
+
+/// The result of [`tv1d::condat_2d`](../tv1d/fn.condat_2d.html) and
+/// [`tv1d::condat_2d_accurate`](../tv1d/fn.condat_2d_accurate.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Tv2dOutput<T> {
+    /// The denoised values, row by row.
+    pub output: Vec<T>,
+    /// The number of iterations run.
+    pub iterations: usize,
+    /// An upper bound of the Euclidean distance between the output and
+    /// the exact solution, up to rounding errors.
+    pub radius: T,
+}
+
+/// Denoises a 2D array, such as an image, stored row by row with
+/// `columns` values per row, with anisotropic total variation.
+///
+/// The output minimizes
+/// `0.5 * ||x - input||^2 + lambda * (TV_rows(x) + TV_columns(x))`,
+/// where `TV_rows` is the sum of the total variations of the rows, and
+/// `TV_columns` that of the columns. Each of the two terms alone is
+/// solved exactly by applying [`tv1d::condat`](../tv1d/fn.condat.html)
+/// to every row or column, and the two are combined by Dykstra's
+/// alternating proximal scheme, which denoises the rows and the columns
+/// in turn, carrying over the correction each step made, and converges
+/// to the exact solution. The iterations stop once no value changes by
+/// more than `tolerance` from one iteration to the next, or after
+/// `max_iterations`. A small change does not guarantee a small error,
+/// which
+/// [`tv1d::condat_2d_accurate`](../tv1d/fn.condat_2d_accurate.html)
+/// stops on instead.
+///
+/// # Panics
+/// Panics if input vector's length is `0` or not a multiple of
+/// `columns`, `columns` is `0`, `tolerance` is NaN or less than `0`, or
+/// `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // A 3x4 image of a bright square, with a noisy pixel.
+/// let input: Vec<f64> = vec![0.0, 0.0, 0.0, 1.0,
+///                            0.0, 9.0, 9.0, 0.0,
+///                            0.0, 9.0, 9.0, 0.0];
+///
+/// let denoised = tv1d::condat_2d(&input, 4, 0.5, 1e-9, 1000);
+/// assert!(denoised.iterations < 1000);
+///
+/// // The noisy pixel is flattened into the background, and the square
+/// // is kept.
+/// assert!((denoised.output[3] - denoised.output[0]).abs() < 1e-6);
+/// assert!(denoised.output[5] > 8.0);
+/// ```
+pub fn condat_2d<T>(input: &[T], columns: usize, lambda: T, tolerance: T, max_iterations: usize) -> Tv2dOutput<T>
+    where T: TvFloat
+{
+    assert!(!tolerance.is_nan() && tolerance >= T::zero(),
+            "Tolerance must be greater than or equal to 0.");
+
+    solve(input, columns, lambda, max_iterations, |dykstra| dykstra.step(lambda) <= tolerance)
+}
+
+/// Denoises a 2D array stored row by row with `columns` values per row
+/// like [`tv1d::condat_2d`](../tv1d/fn.condat_2d.html), until the
+/// output is guaranteed to be within `accuracy` of the exact solution.
+///
+/// The objective is strongly convex, so the Euclidean distance between
+/// any output and the exact solution is at most the square root of
+/// twice its duality gap. The corrections of Dykstra's scheme are
+/// subgradients of the row and the column total variations, and form a
+/// feasible dual point, whose gap with the output is computed after
+/// every iteration. The iterations stop once the bound is at most
+/// `accuracy`, or after `max_iterations`, and the bound is returned as
+/// the radius of the output. The gap is computed in the precision of
+/// `T`, so accuracies close to the square root of its machine epsilon,
+/// relative to the magnitude of the input, may not be reached.
+///
+/// # Panics
+/// Panics if input vector's length is `0` or not a multiple of
+/// `columns`, `columns` is `0`, `accuracy` is NaN or less than `0`, or
+/// `lambda` is NaN or less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input: Vec<f64> = vec![0.0, 0.0, 0.0, 1.0,
+///                            0.0, 9.0, 9.0, 0.0,
+///                            0.0, 9.0, 9.0, 0.0];
+///
+/// let denoised = tv1d::condat_2d_accurate(&input, 4, 0.5, 1e-6, 1000);
+/// assert!(denoised.radius <= 1e-6);
+/// assert!(denoised.iterations < 1000);
+/// ```
+pub fn condat_2d_accurate<T>(input: &[T], columns: usize, lambda: T, accuracy: T, max_iterations: usize) -> Tv2dOutput<T>
+    where T: TvFloat
+{
+    assert!(!accuracy.is_nan() && accuracy >= T::zero(),
+            "Accuracy must be greater than or equal to 0.");
+
+    solve(input, columns, lambda, max_iterations, |dykstra| {
+        dykstra.step(lambda);
+        dykstra.radius(lambda) <= accuracy
+    })
+}
+
+// Runs `iterate` on Dykstra's scheme until it returns `true` or
+// `max_iterations` is reached.
+fn solve<T, F>(input: &[T], columns: usize, lambda: T, max_iterations: usize, mut iterate: F) -> Tv2dOutput<T>
+    where T: TvFloat,
+          F: FnMut(&mut Dykstra<T>) -> bool
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+    assert!(columns > 0, "Number of columns must be greater than 0.");
+    assert!(input.len().is_multiple_of(columns),
+            "Input length must be a multiple of the number of columns.");
+    assert!(!lambda.is_nan(),
+            "Lambda must not be NaN.");
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    if utils::is_infinite_lambda(lambda) {
+        return Tv2dOutput {
+            output: vec![utils::mean(input); input.len()],
+            iterations: 0,
+            radius: T::zero(),
+        };
+    }
+
+    let mut dykstra = Dykstra::new(input, columns);
+    let mut iterations = 0;
+    while iterations < max_iterations {
+        iterations += 1;
+        if iterate(&mut dykstra) {
+            break;
+        }
+    }
+
+    Tv2dOutput {
+        radius: dykstra.radius(lambda),
+        output: dykstra.output,
+        iterations,
+    }
+}
+
+// Dykstra's alternating proximal scheme for the row and the column
+// total variations. `output + row_correction + column_correction`
+// equals the input after every step.
+struct Dykstra<T> {
+    columns: usize,
+    output: Vec<T>,
+    row_correction: Vec<T>,
+    column_correction: Vec<T>,
+    denoised_rows: Vec<T>,
+    column: Vec<T>,
+}
+
+impl<T> Dykstra<T>
+    where T: TvFloat
+{
+    fn new(input: &[T], columns: usize) -> Dykstra<T> {
+        Dykstra {
+            columns,
+            output: input.to_vec(),
+            row_correction: vec![T::zero(); input.len()],
+            column_correction: vec![T::zero(); input.len()],
+            denoised_rows: vec![T::zero(); input.len()],
+            column: Vec::with_capacity(input.len() / columns),
+        }
+    }
+
+    // Denoises the rows, then the columns, and returns the largest
+    // change of the output.
+    fn step(&mut self, lambda: T) -> T {
+        let n = self.output.len();
+        for i in 0..n {
+            self.denoised_rows[i] = self.output[i] + self.row_correction[i];
+        }
+        for row in self.denoised_rows.chunks_mut(self.columns) {
+            condat_in_place(row, lambda);
+        }
+        for i in 0..n {
+            self.row_correction[i] += self.output[i] - self.denoised_rows[i];
+        }
+
+        let mut change = T::zero();
+        for offset in 0..self.columns {
+            let denoised_rows = &self.denoised_rows;
+            let column_correction = &self.column_correction;
+            self.column.clear();
+            self.column.extend((offset..n).step_by(self.columns).map(|i| denoised_rows[i] + column_correction[i]));
+            condat_in_place(&mut self.column, lambda);
+            for (i, &value) in (offset..n).step_by(self.columns).zip(self.column.iter()) {
+                self.column_correction[i] += self.denoised_rows[i] - value;
+                change = change.max((value - self.output[i]).abs());
+                self.output[i] = value;
+            }
+        }
+        change
+    }
+
+    // Returns the square root of twice the duality gap of the output.
+    //
+    // The corrections are `D^T u` for dual variables `u` bounded by
+    // `lambda`, where `D` takes the differences along the rows or the
+    // columns, so with `output = input - row_correction -
+    // column_correction`, the gap reduces to `lambda * TV(output) -
+    // <output, row_correction + column_correction>`.
+    fn radius(&self, lambda: T) -> T {
+        let n = self.output.len();
+        let mut gap = T::zero();
+        for i in 0..n {
+            let value = self.output[i];
+            let mut variation = T::zero();
+            if (i + 1) % self.columns != 0 {
+                variation += (self.output[i + 1] - value).abs();
+            }
+            if i + self.columns < n {
+                variation += (self.output[i + self.columns] - value).abs();
+            }
+            gap += lambda * variation - value * (self.row_correction[i] + self.column_correction[i]);
+        }
+        (gap.max(T::zero()) * (T::one() + T::one())).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    fn objective(output: &[f64], input: &[f64], columns: usize, lambda: f64) -> f64 {
+        let mut value = 0.0;
+        for i in 0..output.len() {
+            value += 0.5 * (output[i] - input[i]).powi(2);
+            if (i + 1) % columns != 0 {
+                value += lambda * (output[i + 1] - output[i]).abs();
+            }
+            if i + columns < output.len() {
+                value += lambda * (output[i + columns] - output[i]).abs();
+            }
+        }
+        value
+    }
+
+    #[test]
+    fn condat_2d_test_single_row_or_column() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let expected = condat(&input, 1.5);
+        assert_eq!(condat_2d(&input, input.len(), 1.5, 0.0, 10).output, expected);
+        assert_eq!(condat_2d(&input, 1, 1.5, 0.0, 10).output, expected);
+    }
+
+    #[test]
+    fn condat_2d_test_minimizes_objective() {
+        let input: Vec<f64> = (0..30).map(|i| ((i % 6) / 3 * 4 + (i / 6) / 2 * 3) as f64 + ((i * 7919) % 11) as f64 * 0.2).collect();
+        for &lambda in &[0.1, 0.5, 2.0] {
+            let denoised = condat_2d(&input, 6, lambda, 1e-12, 100_000);
+            assert!(denoised.iterations < 100_000);
+            let minimum = objective(&denoised.output, &input, 6, lambda);
+            for i in 0..input.len() {
+                for &delta in &[-1e-4, 1e-4] {
+                    let mut perturbed = denoised.output.clone();
+                    perturbed[i] += delta;
+                    assert!(objective(&perturbed, &input, 6, lambda) >= minimum - 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn condat_2d_test_extreme_lambda() {
+        let input = vec![1.0, 2.0, 3.0, 4.0, 5.0, 9.0];
+        assert_eq!(condat_2d(&input, 3, 0.0, 0.0, 10).output, input);
+        assert_eq!(condat_2d(&input, 3, f64::INFINITY, 0.0, 10).output, vec![4.0; 6]);
+    }
+
+    #[test]
+    fn condat_2d_accurate_test_radius() {
+        let input: Vec<f64> = (0..30).map(|i| ((i % 6) / 3 * 4 + (i / 6) / 2 * 3) as f64 + ((i * 7919) % 11) as f64 * 0.2).collect();
+        let exact = condat_2d(&input, 6, 0.5, 0.0, 100_000).output;
+        for &accuracy in &[1e-1, 1e-3, 1e-6] {
+            let denoised = condat_2d_accurate(&input, 6, 0.5, accuracy, 100_000);
+            assert!(denoised.radius <= accuracy);
+            let distance = denoised.output.iter().zip(exact.iter()).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt();
+            assert!(distance <= accuracy);
+        }
+    }
+
+    #[test]
+    fn condat_2d_accurate_test_extreme_lambda() {
+        let input = vec![1.0, 2.0, 3.0, 4.0, 5.0, 9.0];
+        let denoised = condat_2d_accurate(&input, 3, 0.0, 0.0, 10);
+        assert_eq!(denoised.output, input);
+        assert_eq!(denoised.iterations, 1);
+        assert_eq!(denoised.radius, 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_2d_test_ragged_input() {
+        condat_2d(&[1.0, 2.0, 3.0], 2, 1.0, 0.0, 10);
+    }
+}
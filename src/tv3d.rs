@@ -0,0 +1,218 @@
+use axis::condat_axis;
+use scalar::TvFloat;
+use utils;
+
+/// The result of [`tv1d::condat_3d`](../tv1d/fn.condat_3d.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Tv3dOutput<T> {
+    /// The denoised values, in the order of the input.
+    pub output: Vec<T>,
+    /// The number of iterations run.
+    pub iterations: usize,
+    /// An upper bound of the Euclidean distance between the output and
+    /// the exact solution, up to rounding errors.
+    pub radius: T,
+}
+
+/// Denoises a 3D array, such as a video or a volumetric scan, with
+/// anisotropic total variation along each of its three axes.
+///
+/// The array has the given `shape`, with the last index varying
+/// fastest, i.e. a video of `shape[0]` frames of `shape[1]` rows of
+/// `shape[2]` pixels is stored frame by frame, each row by row. The
+/// output minimizes `0.5 * ||x - input||^2 + lambda * (TV_0(x) +
+/// TV_1(x) + TV_2(x))`, where `TV_k` is the sum of the total variations
+/// of the lanes along axis `k`.
+///
+/// As in [`tv1d::condat_2d`](../tv1d/fn.condat_2d.html), each term
+/// alone is solved exactly by applying
+/// [`tv1d::condat`](../tv1d/fn.condat.html) to every lane along its
+/// axis. The three are combined by the parallel variant of Dykstra's
+/// scheme, which denoises the lanes along each axis with `3 * lambda`
+/// and averages the three results, carrying over the correction each
+/// axis made, and converges to the exact solution. As in
+/// [`tv1d::condat_2d_accurate`](../tv1d/fn.condat_2d_accurate.html),
+/// the iterations stop once the duality gap guarantees the output to
+/// be within `accuracy` of the exact solution, or after
+/// `max_iterations`. The gap is computed in the precision of `T`, so
+/// accuracies close to the square root of its machine epsilon, relative
+/// to the magnitude of the input, may not be reached.
+///
+/// With the `rayon` feature, the lanes along each axis are denoised in
+/// parallel over rayon's thread pool, as in
+/// [`tv1d::condat_axis`](../tv1d/fn.condat_axis.html).
+///
+/// # Panics
+/// Panics if input vector's length is `0` or not the product of
+/// `shape`, `accuracy` is NaN or less than `0`, or `lambda` is NaN or
+/// less than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// // Two frames of a 2x2 video, with a pixel flickering in the second.
+/// let input: Vec<f64> = vec![1.0, 1.0,
+///                            1.0, 1.0,
+///
+///                            1.0, 1.0,
+///                            1.0, 3.0];
+///
+/// let denoised = tv1d::condat_3d(&input, [2, 2, 2], 1.0, 1e-6, 1000);
+/// assert!(denoised.radius <= 1e-6);
+/// for value in denoised.output {
+///     assert!((value - 1.25).abs() < 1e-6);
+/// }
+/// ```
+pub fn condat_3d<T>(input: &[T], shape: [usize; 3], lambda: T, accuracy: T, max_iterations: usize) -> Tv3dOutput<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+    assert!(shape[0] * shape[1] * shape[2] == input.len(),
+            "Input length must be the product of the shape.");
+    assert!(!accuracy.is_nan() && accuracy >= T::zero(),
+            "Accuracy must be greater than or equal to 0.");
+    assert!(!lambda.is_nan(),
+            "Lambda must not be NaN.");
+    assert!(lambda >= T::zero(),
+            "Lambda must be greater than or equal to 0.");
+
+    if utils::is_infinite_lambda(lambda) {
+        return Tv3dOutput {
+            output: vec![utils::mean(input); input.len()],
+            iterations: 0,
+            radius: T::zero(),
+        };
+    }
+
+    let strides = [shape[1] * shape[2], shape[2], 1];
+    let three = T::from_usize(3).expect("Unable to convert usize to TvScalar.");
+    let n = input.len();
+
+    let mut output = input.to_vec();
+    // The corrections of the three axes.
+    let mut corrections = vec![vec![T::zero(); n]; 3];
+    let mut denoised = vec![T::zero(); n];
+    let mut sum = vec![T::zero(); n];
+
+    let mut iterations = 0;
+    let mut radius = distance_bound(input, &output, shape, &strides, lambda);
+    while iterations < max_iterations && radius > accuracy {
+        for value in sum.iter_mut() {
+            *value = T::zero();
+        }
+        for (axis, correction) in corrections.iter_mut().enumerate() {
+            for i in 0..n {
+                denoised[i] = output[i] + correction[i];
+            }
+            condat_axis(&mut denoised, &shape, &strides, axis, lambda * three);
+            for i in 0..n {
+                correction[i] = output[i] + correction[i] - denoised[i];
+                sum[i] += denoised[i];
+            }
+        }
+        for i in 0..n {
+            output[i] = sum[i] / three;
+        }
+
+        iterations += 1;
+        radius = distance_bound(input, &output, shape, &strides, lambda);
+    }
+
+    Tv3dOutput {
+        output,
+        iterations,
+        radius,
+    }
+}
+
+// Returns the square root of twice the duality gap of `output`.
+//
+// The averaged corrections of the three axes sum to `input - output`,
+// and each is `D^T u` for dual variables `u` bounded by `lambda`, where
+// `D` takes the differences along its axis, so the gap reduces to
+// `lambda * TV(output) - <output, input - output>`.
+fn distance_bound<T>(input: &[T], output: &[T], shape: [usize; 3], strides: &[usize; 3], lambda: T) -> T
+    where T: TvFloat
+{
+    let mut gap = T::zero();
+    for (i, &value) in output.iter().enumerate() {
+        let mut variation = T::zero();
+        for axis in 0..3 {
+            if (i / strides[axis]) % shape[axis] + 1 < shape[axis] {
+                variation += (output[i + strides[axis]] - value).abs();
+            }
+        }
+        gap += lambda * variation - value * (input[i] - value);
+    }
+    (gap.max(T::zero()) * (T::one() + T::one())).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tv2d::condat_2d_accurate;
+
+    #[test]
+    fn condat_3d_test_matches_2d() {
+        let input: Vec<f64> = (0..30).map(|i| ((i % 6) / 3 * 4 + (i / 6) / 2 * 3) as f64 + ((i * 7919) % 11) as f64 * 0.2).collect();
+        let expected = condat_2d_accurate(&input, 6, 0.5, 1e-6, 100_000);
+        for &shape in &[[1, 5, 6], [5, 1, 6], [5, 6, 1]] {
+            let denoised = condat_3d(&input, shape, 0.5, 1e-6, 100_000);
+            assert!(denoised.radius <= 1e-6);
+            for (value, expected) in denoised.output.iter().zip(expected.output.iter()) {
+                assert!((value - expected).abs() < 2e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn condat_3d_test_minimizes_objective() {
+        let shape = [3, 4, 5];
+        let input: Vec<f64> = (0..60).map(|i| ((i % 5) / 3 * 4 + (i / 20) * 3) as f64 + ((i * 7919) % 11) as f64 * 0.2).collect();
+        let objective = |output: &[f64]| {
+            let mut value = 0.0;
+            for i in 0..60 {
+                value += 0.5 * (output[i] - input[i]).powi(2);
+                if i % 5 < 4 {
+                    value += 0.5 * (output[i + 1] - output[i]).abs();
+                }
+                if (i / 5) % 4 < 3 {
+                    value += 0.5 * (output[i + 5] - output[i]).abs();
+                }
+                if i / 20 < 2 {
+                    value += 0.5 * (output[i + 20] - output[i]).abs();
+                }
+            }
+            value
+        };
+        let denoised = condat_3d(&input, shape, 0.5, 1e-6, 100_000);
+        assert!(denoised.radius <= 1e-6);
+        let minimum = objective(&denoised.output);
+        for i in 0..60 {
+            for &delta in &[-1e-4, 1e-4] {
+                let mut perturbed = denoised.output.clone();
+                perturbed[i] += delta;
+                assert!(objective(&perturbed) >= minimum - 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn condat_3d_test_extreme_lambda() {
+        let input = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 12.0];
+        let denoised = condat_3d(&input, [2, 2, 2], 0.0, 0.0, 10);
+        assert_eq!(denoised.output, input);
+        assert_eq!(denoised.iterations, 0);
+        assert_eq!(condat_3d(&input, [2, 2, 2], f64::INFINITY, 0.0, 10).output, vec![5.0; 8]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_3d_test_shape_mismatch() {
+        condat_3d(&[1.0, 2.0, 3.0], [1, 2, 2], 1.0, 0.0, 10);
+    }
+}
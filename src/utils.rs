@@ -1,6 +1,272 @@
-/// TODO
-pub fn sync_values(anchor_value: usize, values: &mut [&mut usize]) -> () {
-    for value in values {
-        **value = anchor_value;
+#[cfg(feature = "std")]
+use std::cmp;
+#[cfg(feature = "std")]
+use std::f64;
+
+use scalar::{TvFloat, TvScalar};
+#[cfg(feature = "simd")]
+use simd;
+
+/// Returns the arithmetic mean of the values with `TvScalar::mean`,
+/// which sums the integers in a wider type.
+pub fn mean<T>(values: &[T]) -> T
+    where T: TvScalar
+{
+    T::mean(values)
+}
+
+/// Returns whether `lambda` is positive infinity, for which total
+/// variation denoising returns the mean of the input. Only infinity is
+/// positive and left unchanged by doubling, which overflows the
+/// integers instead.
+pub fn is_infinite_lambda<T>(lambda: T) -> bool
+    where T: TvScalar
+{
+    lambda > T::zero() && lambda.try_add(lambda) == Some(lambda)
+}
+
+/// Returns whether `lambda` is NaN. NaN is unordered with respect to
+/// every value, except in scalars such as `OrderedFloat` that order
+/// it, which are checked through `f64`.
+pub fn is_nan_lambda<T: TvScalar>(lambda: &T) -> bool {
+    lambda.partial_cmp(lambda).is_none() || lambda.to_f64().is_some_and(f64::is_nan)
+}
+
+/// Returns whether every value equals the first, in which case total
+/// variation denoising leaves them unchanged.
+pub fn is_constant<T: PartialEq>(values: &[T]) -> bool {
+    values.iter().all(|value| *value == values[0])
+}
+
+/// Returns the denoised output of `values` for a positive `lambda` if
+/// it is constant, without running a solver: the first value if the
+/// values are constant, and the mean, computed by `mean`, if `lambda`
+/// is at least `lambda_max`. Returns `None` otherwise, or if the
+/// residuals or their running sum overflow an integer type.
+///
+/// The residuals from the constant output are at most `2 * lambda` in
+/// magnitude, so the scan stops as soon as the range of the values
+/// exceeds `4 * lambda`, which makes this cheap unless `lambda` is
+/// large.
+pub fn constant_solution<T, F>(values: &[T], lambda: T, mean: F) -> Option<T>
+    where T: TvScalar,
+          F: FnOnce(&[T]) -> T
+{
+    // If `4 * lambda` overflows, so may the range of the values, and
+    // only the running sums below are checked.
+    let four_lambda = lambda.try_add(lambda).and_then(|two_lambda| two_lambda.try_add(two_lambda));
+    let (mut min, mut max) = (values[0], values[0]);
+    for &value in values {
+        if value < min {
+            min = value;
+        } else if value > max {
+            max = value;
+        }
+        if let Some(four_lambda) = four_lambda {
+            // A range that overflows exceeds `4 * lambda`.
+            if max.try_sub(min).is_none_or(|range| range > four_lambda) {
+                return None;
+            }
+        }
+    }
+    if min == max {
+        return Some(values[0]);
+    }
+
+    // The dual variable of the constant solution is the running sum
+    // of the residuals, like in `lambda_max`.
+    let mean = mean(values);
+    let mut running_sum = T::zero();
+    for &value in &values[..values.len() - 1] {
+        running_sum = running_sum.try_add(value)?.try_sub(mean)?;
+        if running_sum > lambda || T::zero().try_sub(running_sum).is_none_or(|negated| negated > lambda) {
+            return None;
+        }
+    }
+    Some(mean)
+}
+
+/// Returns the number of constant runs in the values.
+#[cfg(feature = "std")]
+pub fn count_segments<T: PartialEq>(values: &[T]) -> usize {
+    if values.is_empty() {
+        return 0;
+    }
+    1 + values.windows(2).filter(|pair| pair[0] != pair[1]).count()
+}
+
+/// Returns the median of the values. NaN values are ordered as
+/// equal to everything.
+#[cfg(feature = "std")]
+pub fn median<T>(values: &[T]) -> T
+    where T: TvFloat
+{
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(cmp::Ordering::Equal));
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        let two = T::one() + T::one();
+        (sorted[middle - 1] + sorted[middle]) / two
+    } else {
+        sorted[middle]
+    }
+}
+
+/// Estimates the standard deviation of white Gaussian noise in the
+/// values from the median absolute first difference, which is
+/// robust to the jumps of a piecewise constant signal.
+#[cfg(feature = "std")]
+pub fn estimate_noise_sigma<T>(values: &[T]) -> T
+    where T: TvFloat
+{
+    if values.len() < 2 {
+        return T::zero();
+    }
+    let differences: Vec<T> = values.windows(2).map(|pair| (pair[1] - pair[0]).abs()).collect();
+    // The first differences have standard deviation `sqrt(2) * sigma`,
+    // and `0.6745` is the median of the absolute standard normal.
+    let scale = T::from_f64(0.6745 * f64::consts::SQRT_2)
+        .expect("Unable to convert f64 to TvScalar.");
+    median(&differences) / scale
+}
+
+/// Returns the linearly interpolated quantile at `fraction` of values
+/// sorted in increasing order.
+#[cfg(feature = "std")]
+pub fn quantile<T>(sorted: &[T], fraction: f64) -> T
+    where T: TvFloat
+{
+    let position = fraction * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = cmp::min(below + 1, sorted.len() - 1);
+    let weight = T::from_f64(position - below as f64)
+        .expect("Unable to convert f64 to TvScalar.");
+    sorted[below] + (sorted[above] - sorted[below]) * weight
+}
+
+/// Spreads `values`, known at the increasing `positions`, over `width`
+/// positions, interpolating linearly in between and repeating the end
+/// values outside.
+#[cfg(feature = "std")]
+pub fn interpolate<T>(width: usize, positions: &[usize], values: &[T]) -> Vec<T>
+    where T: TvFloat
+{
+    let mut output = Vec::with_capacity(width);
+    let mut next = 0;
+    for i in 0..width {
+        while next < positions.len() && positions[next] < i {
+            next += 1;
+        }
+        let value = if next == positions.len() {
+            values[positions.len() - 1]
+        } else if positions[next] == i || next == 0 {
+            values[next]
+        } else {
+            let (left, right) = (positions[next - 1], positions[next]);
+            let weight = T::from_usize(i - left).expect("Unable to convert usize to TvScalar.") /
+                         T::from_usize(right - left).expect("Unable to convert usize to TvScalar.");
+            values[next - 1] + (values[next] - values[next - 1]) * weight
+        };
+        output.push(value);
+    }
+    output
+}
+
+/// Returns the rounded sum of `a` and `b` together with its rounding
+/// error, i.e. Knuth's TwoSum, so that the sum plus the error is
+/// exactly `a + b`.
+pub fn two_sum<T: TvFloat>(a: T, b: T) -> (T, T) {
+    let sum = a + b;
+    let b_rounded = sum - a;
+    let error = (a - (sum - b_rounded)) + (b - b_rounded);
+    (sum, error)
+}
+
+/// Returns the arithmetic mean of the values like `mean`, carrying the
+/// rounding error of the sum along with `two_sum`.
+pub fn compensated_mean<T>(values: &[T]) -> T
+    where T: TvFloat
+{
+    let (mut sum, mut error) = (T::zero(), T::zero());
+    for &value in values {
+        let (next, rounding) = two_sum(sum, value);
+        sum = next;
+        error += rounding;
+    }
+    (sum + error) / T::from_usize(values.len()).expect("Unable to convert usize to TvScalar.")
+}
+
+/// Returns the smallest power of two by which the values and a finite
+/// `lambda` can be divided for any sum of consecutive values and
+/// `lambda`, and twice the sum, to be finite. Dividing by a power of
+/// two is exact unless the quotient is subnormal. NaN and infinite
+/// values are ignored.
+pub fn overflow_scale<T>(values: &[T], lambda: T) -> T
+    where T: TvFloat
+{
+    let bound = values.iter().filter(|value| value.is_finite()).fold(T::zero(), |bound, &value| bound.max(value.abs()));
+    let lambda = if is_infinite_lambda(lambda) { T::zero() } else { lambda };
+    let len = T::from_usize(values.len()).expect("Unable to convert usize to TvScalar.");
+    let two = T::one() + T::one();
+    let limit = T::max_value() / (two + two);
+    let mut scale = T::one();
+    while bound / scale * len + lambda / scale > limit {
+        scale = scale * two;
+    }
+    scale
+}
+
+/// Returns `values[index]`. With the `unsafe-fast` feature, the bounds
+/// check is only made in debug builds.
+#[inline(always)]
+pub fn get<T: Copy>(values: &[T], index: usize) -> T {
+    #[cfg(feature = "unsafe-fast")]
+    {
+        debug_assert!(index < values.len());
+        unsafe { *values.get_unchecked(index) }
+    }
+    #[cfg(not(feature = "unsafe-fast"))]
+    {
+        values[index]
+    }
+}
+
+/// Sets `values[start..end]` to `value`. With the `unsafe-fast`
+/// feature, the bounds check is only made in debug builds.
+#[inline(always)]
+pub fn fill_range<T: Copy>(values: &mut [T], start: usize, end: usize, value: T) {
+    #[cfg(feature = "unsafe-fast")]
+    {
+        debug_assert!(start <= end && end <= values.len());
+        unsafe { values.get_unchecked_mut(start..end) }.fill(value);
+    }
+    #[cfg(not(feature = "unsafe-fast"))]
+    {
+        values[start..end].fill(value);
+    }
+}
+
+/// Sets every value to `value`.
+pub fn fill<T: Copy + 'static>(values: &mut [T], value: T) {
+    #[cfg(feature = "simd")]
+    {
+        if simd::fill(values, value) {
+            return;
+        }
+    }
+    values.fill(value);
+}
+
+/// Returns the sum of the squared differences between `a` and `b`,
+/// which have the same length. With the `deterministic` feature, the
+/// squares are always summed in order.
+#[cfg(feature = "std")]
+pub fn squared_distance<T: TvFloat>(a: &[T], b: &[T]) -> T {
+    #[cfg(all(feature = "simd", not(feature = "deterministic")))]
+    {
+        if let Some(sum) = simd::squared_distance(a, b) {
+            return sum;
+        }
     }
+    a.iter().zip(b.iter()).fold(T::zero(), |sum, (&x, &y)| sum + (x - y) * (x - y))
 }
@@ -0,0 +1,203 @@
+use lazy::CondatState;
+use path::{regularization_path, RegularizationPath};
+use scalar::TvFloat;
+
+/// A solver that keeps the segment structure of its input between
+/// solves, so that re-solving with a different `lambda` does not
+/// start from scratch.
+///
+/// The first solve computes the [regularization
+/// path](../tv1d/fn.regularization_path.html) of the input, after
+/// which every solve is a single linear pass over the retained
+/// segments. This suits interactive tuning, where the same input is
+/// re-solved every time `lambda` changes slightly.
+///
+/// Appending data with
+/// [`extend`](struct.WarmStartSolver.html#method.extend) after a solve
+/// keeps the state of [`tv1d::condat`](../tv1d/fn.condat.html) for the
+/// `lambda` of that solve. The regularization path does not hold that
+/// state, so the first append runs `condat` once over the input so
+/// far. Later appends only process the appended values and the last
+/// segments, as the segments finalized for `lambda` do not change with
+/// the appended values, and re-solving with the same `lambda` returns
+/// without another pass. Solving with another `lambda` after appending
+/// recomputes the regularization path of the whole input.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let mut solver = tv1d::WarmStartSolver::new(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+/// assert_eq!(solver.solve(10.0), vec![3.0, 3.0, 3.0, 3.0, 3.0]);
+/// assert_eq!(solver.solve(1.0), vec![2.0, 2.0, 3.0, 4.0, 4.0]);
+///
+/// solver.extend(&[6.0]);
+/// assert_eq!(solver.solve(0.0), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct WarmStartSolver<T> {
+    input: Vec<T>,
+    path: Option<RegularizationPath<T>>,
+    // The `lambda` of the last solve.
+    lambda: Option<T>,
+    // The state of `condat` on the input for `lambda`, created by the
+    // first append after a solve, which runs `condat` over the input
+    // so far.
+    appended: Option<Appended<T>>,
+}
+
+#[derive(Clone, Debug)]
+struct Appended<T> {
+    lambda: T,
+    state: CondatState<T>,
+    // The values of the segments finalized so far.
+    output: Vec<T>,
+}
+
+impl<T> Appended<T>
+    where T: TvFloat
+{
+    fn new(lambda: T, input: &[T]) -> Appended<T> {
+        let mut appended = Appended {
+            lambda,
+            state: CondatState::new(lambda),
+            output: Vec::with_capacity(input.len()),
+        };
+        appended.feed(input);
+        appended
+    }
+
+    fn feed(&mut self, values: &[T]) {
+        self.output.extend(self.state.feed(values));
+    }
+
+    fn solution(&self) -> Vec<T> {
+        let mut output = self.output.clone();
+        output.extend(self.state.clone().finish());
+        output
+    }
+}
+
+impl<T> WarmStartSolver<T>
+    where T: TvFloat
+{
+    /// Creates a solver for the input values.
+    pub fn new(input: &[T]) -> WarmStartSolver<T> {
+        WarmStartSolver {
+            input: input.to_vec(),
+            path: None,
+            lambda: None,
+            appended: None,
+        }
+    }
+
+    /// Returns the input values the solver denoises.
+    pub fn input(&self) -> &[T] {
+        &self.input
+    }
+
+    /// Appends values to the input, feeding them to the state of
+    /// `condat` for the `lambda` of the last solve, which the first
+    /// append after a solve creates from the whole input.
+    pub fn extend(&mut self, values: &[T]) {
+        if values.is_empty() {
+            return;
+        }
+        if let Some(lambda) = self.lambda {
+            let input = &self.input;
+            self.appended.get_or_insert_with(|| Appended::new(lambda, input)).feed(values);
+        }
+        self.input.extend_from_slice(values);
+        self.path = None;
+    }
+
+    /// Denoises the input values with `lambda`, reusing the segments
+    /// retained from previous solves.
+    ///
+    /// # Panics
+    /// Panics if input vector's length is `0` or `lambda` is less than `0`.
+    pub fn solve(&mut self, lambda: T) -> Vec<T> {
+        if let Some(ref appended) = self.appended {
+            if appended.lambda == lambda {
+                return appended.solution();
+            }
+        }
+        self.appended = None;
+        self.lambda = Some(lambda);
+
+        if self.path.is_none() {
+            self.path = Some(regularization_path(&self.input));
+        }
+        self.path.as_ref().expect("Regularization path was just computed.").solution(lambda)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    #[test]
+    fn warm_start_solver_test_matches_condat() {
+        let input: Vec<f64> = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let mut solver = WarmStartSolver::new(&input);
+        for &lambda in &[3.0, 3.1, 2.9, 0.0, 100.0] {
+            let output = solver.solve(lambda);
+            let output_expected = condat(&input, lambda);
+            for i in 0..input.len() {
+                assert!((output[i] - output_expected[i]).abs() <= 0.000001);
+            }
+        }
+    }
+
+    #[test]
+    fn warm_start_solver_test_extend() {
+        let input: Vec<f64> = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let mut solver = WarmStartSolver::new(&input[..4]);
+        solver.solve(1.0);
+        solver.extend(&input[4..]);
+        assert_eq!(solver.input(), &input[..]);
+
+        let output = solver.solve(1.0);
+        let output_expected = condat(&input, 1.0);
+        for i in 0..input.len() {
+            assert!((output[i] - output_expected[i]).abs() <= 0.000001);
+        }
+    }
+
+    #[test]
+    fn warm_start_solver_test_extend_resumes() {
+        let input: Vec<f64> = (0..300).map(|i| ((i / 40) % 3) as f64 + ((i * 7) % 5) as f64 * 0.1).collect();
+        let mut solver = WarmStartSolver::new(&input[..100]);
+        solver.solve(0.5);
+        for chunk in input[100..].chunks(50) {
+            solver.extend(chunk);
+            // Only the values after the finalized segments are pending.
+            let appended = solver.appended.as_ref().expect("Appending after a solve keeps its state.");
+            assert!(appended.state.pending() < 100);
+
+            let end = solver.input().len();
+            let output = solver.solve(0.5);
+            let output_expected = condat(&input[..end], 0.5);
+            for i in 0..end {
+                assert!((output[i] - output_expected[i]).abs() <= 0.000001);
+            }
+        }
+
+        // Another `lambda` drops the appended state.
+        let output = solver.solve(2.0);
+        assert!(solver.appended.is_none());
+        let output_expected = condat(&input, 2.0);
+        for i in 0..input.len() {
+            assert!((output[i] - output_expected[i]).abs() <= 0.000001);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn warm_start_solver_test_empty_input() {
+        let input: Vec<f64> = vec![];
+        WarmStartSolver::new(&input).solve(1.0);
+    }
+}
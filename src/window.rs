@@ -0,0 +1,199 @@
+use condat_in_place;
+use scalar::TvFloat;
+
+/// A denoiser for soft real-time use, which denoises fixed-size
+/// windows of a stream with [`tv1d::condat`](../tv1d/fn.condat.html)
+/// and cross-fades their overlaps.
+///
+/// A window is denoised every `hop` samples, once its last sample has
+/// been pushed. The denoised windows are blended with linear weights
+/// that ramp up over the first `window - hop` samples of each window
+/// and down over its last ones, which hides the jumps between
+/// windows. Every [`push`](struct.WindowedTv.html#method.push) that
+/// completes a window yields `hop` samples, so the latency is bounded
+/// by `window` samples, unlike [`OnlineTv`](struct.OnlineTv.html),
+/// at the cost of only approximating the output of `condat` on the
+/// whole stream.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let mut windowed = tv1d::WindowedTv::new(1.0, 8, 4);
+/// let mut output = Vec::new();
+/// for i in 0..20 {
+///     output.extend(windowed.push(if i < 10 { 0.0 } else { 10.0 }));
+///     assert!(windowed.pending() < 8);
+/// }
+/// output.extend(windowed.finish());
+/// assert_eq!(output.len(), 20);
+/// assert!(output[0] < 1.0 && output[19] > 9.0);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct WindowedTv<T> {
+    lambda: T,
+    window: usize,
+    hop: usize,
+    // The samples from the start of the next window on, with the
+    // weighted sums of their denoised values and the sums of the
+    // weights over the windows denoised so far.
+    buffer: Vec<T>,
+    sums: Vec<T>,
+    weights: Vec<T>,
+    // Scratch storage for denoising a window.
+    scratch: Vec<T>,
+    // The samples yielded by a `push`.
+    output: Vec<T>,
+}
+
+impl<T> WindowedTv<T>
+    where T: TvFloat
+{
+    /// Creates a windowed denoiser for `lambda`, denoising windows of
+    /// `window` samples every `hop` samples.
+    ///
+    /// # Panics
+    /// Panics if `lambda` is NaN or less than `0`, `hop` is `0`, or
+    /// `hop` is greater than `window`.
+    pub fn new(lambda: T, window: usize, hop: usize) -> WindowedTv<T> {
+        assert!(!lambda.is_nan(),
+                "Lambda must not be NaN.");
+
+        assert!(lambda >= T::zero(),
+                "Lambda must be greater than or equal to 0.");
+
+        assert!(hop > 0 && hop <= window,
+                "Hop must be greater than 0 and at most the window size.");
+
+        WindowedTv {
+            lambda,
+            window,
+            hop,
+            buffer: Vec::with_capacity(window),
+            sums: Vec::with_capacity(window),
+            weights: Vec::with_capacity(window),
+            scratch: Vec::with_capacity(window),
+            output: Vec::with_capacity(hop),
+        }
+    }
+
+    /// Returns the number of samples pushed but not yet yielded.
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Adds a sample to the stream, and returns the `hop` oldest
+    /// pending samples, denoised, if the sample completes a window.
+    pub fn push(&mut self, sample: T) -> impl Iterator<Item = T> + '_ {
+        self.buffer.push(sample);
+        self.sums.push(T::zero());
+        self.weights.push(T::zero());
+        if self.buffer.len() == self.window {
+            self.denoise_buffer(false);
+            let hop = self.hop;
+            self.yield_samples(hop);
+        }
+        self.output.drain(..)
+    }
+
+    /// Ends the stream, and returns the pending samples, denoised.
+    ///
+    /// The pending samples are shorter than a window, and are denoised
+    /// as one final, shorter window.
+    pub fn finish(mut self) -> impl Iterator<Item = T> {
+        if !self.buffer.is_empty() {
+            self.denoise_buffer(true);
+            let len = self.buffer.len();
+            self.yield_samples(len);
+        }
+        self.output.into_iter()
+    }
+
+    // Denoises the buffered samples as one window and adds them to
+    // the weighted sums. The last window does not fade out.
+    fn denoise_buffer(&mut self, last: bool) {
+        self.scratch.clear();
+        self.scratch.extend_from_slice(&self.buffer);
+        condat_in_place(&mut self.scratch, self.lambda);
+
+        let ramp = self.window - self.hop + 1;
+        let steps = T::from_usize(ramp).expect("Unable to convert usize to TvScalar.");
+        let len = self.buffer.len();
+        for j in 0..len {
+            let fade_out = if last { ramp } else { len - j };
+            let step = T::from_usize((j + 1).min(fade_out).min(ramp))
+                .expect("Unable to convert usize to TvScalar.");
+            let weight = step / steps;
+            self.sums[j] += weight * self.scratch[j];
+            self.weights[j] += weight;
+        }
+    }
+
+    // Moves the first `len` samples, normalized by their weights, to
+    // the output.
+    fn yield_samples(&mut self, len: usize) {
+        for j in 0..len {
+            self.output.push(self.sums[j] / self.weights[j]);
+        }
+        self.buffer.drain(..len);
+        self.sums.drain(..len);
+        self.weights.drain(..len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condat;
+
+    fn run(input: &[f64], lambda: f64, window: usize, hop: usize) -> Vec<f64> {
+        let mut windowed = WindowedTv::new(lambda, window, hop);
+        let mut output = Vec::new();
+        for &sample in input {
+            output.extend(windowed.push(sample));
+        }
+        output.extend(windowed.finish());
+        output
+    }
+
+    #[test]
+    fn windowed_tv_test_single_window() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let output = run(&input, 3.0, 8, 8);
+        let output_expected = condat(&input, 3.0);
+        for i in 0..input.len() {
+            assert!((output[i] - output_expected[i]).abs() <= 0.000001);
+        }
+    }
+
+    #[test]
+    fn windowed_tv_test_constant_input() {
+        let input = vec![4.0; 37];
+        for &(window, hop) in &[(8, 4), (8, 1), (5, 5), (10, 3)] {
+            let output = run(&input, 2.0, window, hop);
+            assert_eq!(output.len(), input.len());
+            for &value in &output {
+                assert!((value - 4.0).abs() <= 0.000001);
+            }
+        }
+    }
+
+    #[test]
+    fn windowed_tv_test_bounded_latency() {
+        let mut windowed = WindowedTv::new(1.0, 16, 4);
+        let mut yielded = 0;
+        for i in 0..100 {
+            yielded += windowed.push(i as f64).count();
+            assert!(windowed.pending() < 16);
+        }
+        assert_eq!(yielded + windowed.finish().count(), 100);
+    }
+
+    #[test]
+    #[should_panic]
+    fn windowed_tv_test_hop_larger_than_window() {
+        WindowedTv::new(1.0, 4, 5);
+    }
+}
@@ -0,0 +1,119 @@
+use std::cmp;
+
+use condat;
+use scalar::TvFloat;
+use utils;
+
+/// The result of
+/// [`tv1d::condat_winsorized`](../tv1d/fn.condat_winsorized.html).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct WinsorizedOutput<T> {
+    /// The denoised output of the clipped input.
+    pub output: Vec<T>,
+    /// The value the input was clipped at from below.
+    pub lower: T,
+    /// The value the input was clipped at from above.
+    pub upper: T,
+    /// The indices of the input values that were clipped, in
+    /// increasing order.
+    pub clipped: Vec<usize>,
+}
+
+/// Denoises the input values with
+/// [`tv1d::condat`](../tv1d/fn.condat.html) after clipping them to
+/// their quantiles at `lower_quantile` and `upper_quantile`, i.e.
+/// winsorizing them, and reports which values were clipped.
+///
+/// A rare extreme glitch pulls the whole segment it falls in towards
+/// it, or splits off a segment of its own. Clipping the input first
+/// bounds its influence, e.g. with quantiles of `0.01` and `0.99`. The
+/// quantiles are linearly interpolated between the sorted input
+/// values, and quantiles of `0` and `1` clip nothing.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, `lambda` is NaN or less
+/// than `0`, or the quantiles are not within `[0, 1]` with
+/// `lower_quantile` not greater than `upper_quantile`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let input = vec![1.0, 1.0, 1.0, 1000.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+///
+/// let denoised = tv1d::condat_winsorized(&input, 1.0, 0.0, 0.8);
+/// assert_eq!(denoised.clipped, vec![3]);
+/// assert_eq!(denoised.upper, 1.0);
+/// assert_eq!(denoised.output, vec![1.0; 10]);
+/// ```
+pub fn condat_winsorized<T>(input: &[T], lambda: T, lower_quantile: f64, upper_quantile: f64) -> WinsorizedOutput<T>
+    where T: TvFloat
+{
+    assert!(!input.is_empty(),
+            "Input list should have at least one value.");
+
+    assert!((0.0..=1.0).contains(&lower_quantile) && (0.0..=1.0).contains(&upper_quantile),
+            "Quantiles must be within [0, 1].");
+
+    assert!(lower_quantile <= upper_quantile,
+            "Lower quantile must not be greater than upper quantile.");
+
+    let mut sorted = input.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(cmp::Ordering::Equal));
+    let lower = utils::quantile(&sorted, lower_quantile);
+    let upper = utils::quantile(&sorted, upper_quantile);
+
+    let mut clipped = Vec::new();
+    let values: Vec<T> = input.iter()
+        .enumerate()
+        .map(|(i, &value)| if value < lower {
+            clipped.push(i);
+            lower
+        } else if value > upper {
+            clipped.push(i);
+            upper
+        } else {
+            value
+        })
+        .collect();
+
+    WinsorizedOutput {
+        output: condat(&values, lambda),
+        lower,
+        upper,
+        clipped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condat_winsorized_test_no_clipping() {
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        let denoised = condat_winsorized(&input, 2.0, 0.0, 1.0);
+        assert_eq!(denoised.output, condat(&input, 2.0));
+        assert_eq!((denoised.lower, denoised.upper), (1.0, 10.1));
+        assert!(denoised.clipped.is_empty());
+    }
+
+    #[test]
+    fn condat_winsorized_test_glitches() {
+        let mut input: Vec<f64> = (0..100).map(|i| ((i / 50) as f64) * 2.0 + ((i * 7919) % 13) as f64 * 0.01).collect();
+        input[20] = 500.0;
+        input[70] = -500.0;
+        let denoised = condat_winsorized(&input, 1.0, 0.02, 0.98);
+        assert_eq!(denoised.clipped, vec![20, 70]);
+        // The glitches no longer drag their segments along.
+        assert!(denoised.output[10] < 0.2 && denoised.output[90] > 1.9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn condat_winsorized_test_inverted_quantiles() {
+        condat_winsorized(&[1.0, 2.0], 1.0, 0.9, 0.1);
+    }
+}
@@ -0,0 +1,143 @@
+use std::mem;
+
+use hull::{Buffers, Compensated, TautstringStorage, Vertex};
+use scalar::TvFloat;
+use tautstring_in_place_with;
+
+/// Scratch storage for
+/// [`tv1d::tautstring_with`](../tv1d/fn.tautstring_with.html), which
+/// keeps the auxiliary arrays of the taut string algorithm between
+/// calls.
+///
+/// A workspace grows to fit the longest input it has been used with,
+/// after which calls on inputs of that length or shorter perform no
+/// allocations.
+#[derive(Clone, Debug)]
+pub struct TautstringWorkspace<T> {
+    pub(crate) low: Vec<Vertex<Compensated<T>, T>>,
+    pub(crate) up: Vec<Vertex<Compensated<T>, T>>,
+    pub(crate) knots: Vec<(usize, T)>,
+    output: Vec<T>,
+}
+
+impl<T: TvFloat> TautstringWorkspace<T> {
+    /// Creates an empty workspace.
+    pub fn new() -> TautstringWorkspace<T> {
+        TautstringWorkspace {
+            low: Vec::new(),
+            up: Vec::new(),
+            knots: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    /// Creates a workspace for inputs of up to `len` values.
+    pub fn with_capacity(len: usize) -> TautstringWorkspace<T> {
+        let mut workspace = TautstringWorkspace::new();
+        workspace.reset(len + 1);
+        workspace.output.reserve(len);
+        workspace
+    }
+
+    // Sets every auxiliary array to `width` zeros, allocating only if
+    // the arrays are shorter than `width`. The fields of each vertex
+    // and knot are stored together, as the algorithm reads them
+    // together.
+    pub(crate) fn reset(&mut self, width: usize) {
+        for vertices in &mut [&mut self.low, &mut self.up] {
+            vertices.clear();
+            vertices.resize(width, Vertex::new(0, Compensated::new(T::zero())));
+        }
+        self.knots.clear();
+        self.knots.resize(width, (0, T::zero()));
+    }
+}
+
+impl<T: TvFloat> TautstringStorage<T> for TautstringWorkspace<T> {
+    fn buffers(&mut self, width: usize) -> Buffers<'_, T> {
+        self.reset(width);
+        (&mut self.low, &mut self.up, &mut self.knots)
+    }
+}
+
+impl<T: TvFloat> Default for TautstringWorkspace<T> {
+    fn default() -> TautstringWorkspace<T> {
+        TautstringWorkspace::new()
+    }
+}
+
+/// Denoises the input values with the same algorithm as
+/// [`tv1d::tautstring`](../tv1d/fn.tautstring.html), using the
+/// scratch storage of `workspace` instead of allocating new auxiliary
+/// arrays, and returns the denoised output, which is also kept in
+/// `workspace`.
+///
+/// # Panics
+/// Panics if input vector's length is `0`, or `lambda` is NaN or less
+/// than `0`.
+///
+/// # Examples
+///
+/// ```
+/// use tv1d;
+///
+/// let mut workspace = tv1d::TautstringWorkspace::with_capacity(5);
+///
+/// let input = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(tv1d::tautstring_with(&mut workspace, &input, 10.0), &[3.0, 3.0, 3.0, 3.0, 3.0]);
+///
+/// let input = vec![100.0, 200.0, 300.0, 400.0, 500.0];
+/// assert_eq!(tv1d::tautstring_with(&mut workspace, &input, 10.0),
+///            &[110.0, 200.0, 300.0, 400.0, 490.0]);
+/// ```
+pub fn tautstring_with<'a, T>(workspace: &'a mut TautstringWorkspace<T>,
+                              input: &[T],
+                              lambda: T)
+                              -> &'a [T]
+    where T: TvFloat
+{
+    let mut output = mem::take(&mut workspace.output);
+    output.clear();
+    output.extend_from_slice(input);
+    tautstring_in_place_with(&mut output, lambda, workspace);
+    workspace.output = output;
+    &workspace.output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tautstring;
+
+    #[test]
+    fn tautstring_with_test_matches_tautstring() {
+        let mut workspace = TautstringWorkspace::new();
+        let inputs = vec![vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1],
+                          vec![111.0, 422.1, 145.2],
+                          vec![111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1]];
+        for input in &inputs {
+            for &lambda in &[0.0, 1.0, 5.0, 100.0] {
+                let output_expected = tautstring(input, lambda);
+                assert_eq!(tautstring_with(&mut workspace, input, lambda), &output_expected[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn tautstring_with_test_no_reallocation() {
+        let mut workspace = TautstringWorkspace::with_capacity(8);
+        let capacity = workspace.low.capacity();
+        let input = vec![1.0, 2.1, 5.2, 8.2, 1.4, 5.2, 6.2, 10.1];
+        tautstring_with(&mut workspace, &input, 1.0);
+        tautstring_with(&mut workspace, &input[..4], 1.0);
+        assert_eq!(workspace.low.capacity(), capacity);
+    }
+
+    #[test]
+    #[should_panic]
+    fn tautstring_with_test_empty_input() {
+        let mut workspace = TautstringWorkspace::new();
+        let input: Vec<f64> = vec![];
+        tautstring_with(&mut workspace, &input, 1.0);
+    }
+}
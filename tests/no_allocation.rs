@@ -0,0 +1,57 @@
+// Checks that `try_condat_into` never allocates, with a global
+// allocator counting the heap allocations. The allocator replaces the
+// allocator of the whole test binary, so these tests live in their
+// own binary.
+//
+// The cross-check allocates the input of its reference, so these
+// tests do not run with it.
+#![cfg(all(feature = "std", not(all(feature = "cross-check", debug_assertions))))]
+
+extern crate tv1d;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// Counts the heap allocations made by each thread, so that tests
+// running in parallel do not interfere.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocations<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.with(|count| count.get());
+    f();
+    ALLOCATIONS.with(|count| count.get()) - before
+}
+
+#[test]
+fn try_condat_into_test_no_allocation() {
+    let input = [111.0, 422.1, 145.2, 248.2, 871.4, 675.2, 436.2, 310.1];
+    let mut output = [0.0; 8];
+    for &lambda in &[0.0, 1.0, 100.0, f64::INFINITY, -1.0, f64::NAN] {
+        let allocations = count_allocations(|| {
+            let _ = tv1d::try_condat_into(&input, lambda, &mut output);
+        });
+        assert_eq!(allocations, 0);
+    }
+    let allocations = count_allocations(|| {
+        let _ = tv1d::try_condat_into(&input, 1.0, &mut output[..4]);
+    });
+    assert_eq!(allocations, 0);
+}